@@ -0,0 +1,40 @@
+/*
+ * The C API of dawm, exported by its cdylib when it is built with the `capi`
+ * feature. See the `capi` module of the crate for the details.
+ */
+
+#ifndef DAWM_H
+#define DAWM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A buffer of UTF-8 bytes owned by the caller, freed with `dawm_free`. */
+typedef struct DawmBuffer {
+  /* The bytes, or NULL for an empty buffer. */
+  uint8_t *data;
+  /* The number of bytes. */
+  size_t len;
+  /* Whether the bytes are an error message rather than the result. */
+  bool error;
+} DawmBuffer;
+
+/*
+ * Parses the `len` bytes at `input` as an HTML document, with the default
+ * options, and returns its wire format as JSON.
+ */
+DawmBuffer dawm_parse_html(const uint8_t *input, size_t len);
+
+/* Frees a buffer returned by one of the other functions. */
+void dawm_free(DawmBuffer buffer);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* DAWM_H */
@@ -0,0 +1,156 @@
+//! The `dawm-wasi` command, which parses a document read from stdin and
+//! writes its wire format to stdout as JSON. Built for `wasm32-wasip1`, it
+//! runs in any WASI runtime without the JS glue:
+//!
+//! ```sh
+//! wasmtime dawm-wasi.wasm -- --collectErrors < page.html > page.json
+//! wasmtime dawm-wasi.wasm -- image/svg+xml --profile=scrape < icon.svg
+//! ```
+//!
+//! The first argument that is not an option is the MIME type of the input,
+//! `text/html` by default. Options are given as `--name=value`, by their JS
+//! names, and `--name` alone sets a boolean option to `true`; options that
+//! take arrays or objects cannot be given. The input is decoded like
+//! [`dawm::parse_wire_bytes`] decodes it, and the output is written with
+//! [`dawm::json::to_string`]. Errors are reported on stderr, with an exit
+//! status of 1.
+
+use std::collections::BTreeMap;
+use std::io::Read;
+use std::io::Write;
+use std::process::ExitCode;
+
+use dawm::ParseOptions;
+use dawm::Profile;
+use serde::Deserialize;
+use serde::de::IntoDeserializer;
+use serde::de::Visitor;
+use serde::de::value::Error;
+use serde::de::value::MapDeserializer;
+use serde::forward_to_deserialize_any;
+
+fn main() -> ExitCode {
+  match run() {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(message) => {
+      eprintln!("dawm-wasi: {message}");
+      ExitCode::FAILURE
+    }
+  }
+}
+
+fn run() -> Result<(), String> {
+  let args: Vec<String> = std::env::args().skip(1).collect();
+  let mut mime = None;
+  let mut options = BTreeMap::new();
+  for arg in &args {
+    match arg.strip_prefix("--") {
+      Some(option) => {
+        let (name, value) = option.split_once('=').unwrap_or((option, "true"));
+        options.insert(name, OptionValue(value));
+      }
+      None if mime.is_none() => mime = Some(arg.as_str()),
+      None => return Err(format!("unexpected argument: {arg}")),
+    }
+  }
+
+  // the options of a profile are the defaults of those given with it, like
+  // `profile::apply_profile` does for JS options
+  if let Some(OptionValue(name)) = options.get("profile") {
+    let profile = Profile::from_name(name)
+      .ok_or_else(|| format!("unknown profile: {name}"))?;
+    for (name, value) in profile.options() {
+      let value = match value {
+        true => "true",
+        false => "false",
+      };
+      options.entry(name).or_insert(OptionValue(value));
+    }
+  }
+  let options =
+    ParseOptions::deserialize(MapDeserializer::new(options.into_iter()))
+      .map_err(|error| format!("invalid options: {error}"))?;
+
+  let mut input = Vec::new();
+  std::io::stdin()
+    .read_to_end(&mut input)
+    .map_err(|error| format!("cannot read stdin: {error}"))?;
+  let mime = mime.unwrap_or("text/html");
+  let doc = dawm::parse_wire_bytes(&input, mime, &options)
+    .map_err(|error| error.to_string())?;
+  let json = dawm::json::to_string(&doc).map_err(|error| error.to_string())?;
+
+  let mut stdout = std::io::stdout().lock();
+  writeln!(stdout, "{json}")
+    .and_then(|()| stdout.flush())
+    .map_err(|error| format!("cannot write stdout: {error}"))
+}
+
+// The value of an option given on the command line. It is a boolean or a
+// number where the option takes one, and the text as is everywhere else.
+struct OptionValue<'de>(&'de str);
+
+impl<'de> IntoDeserializer<'de, Error> for OptionValue<'de> {
+  type Deserializer = Self;
+
+  fn into_deserializer(self) -> Self {
+    self
+  }
+}
+
+impl<'de> serde::Deserializer<'de> for OptionValue<'de> {
+  type Error = Error;
+
+  fn deserialize_any<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    match self.0 {
+      "true" => visitor.visit_bool(true),
+      "false" => visitor.visit_bool(false),
+      text => match text.parse::<u64>() {
+        Ok(number) => visitor.visit_u64(number),
+        Err(_) => visitor.visit_borrowed_str(text),
+      },
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_some(self)
+  }
+
+  fn deserialize_str<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_borrowed_str(self.0)
+  }
+
+  fn deserialize_string<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_borrowed_str(self.0)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    name: &'static str,
+    variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    self
+      .0
+      .into_deserializer()
+      .deserialize_enum(name, variants, visitor)
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char bytes byte_buf
+    unit unit_struct newtype_struct seq tuple tuple_struct map struct
+    identifier ignored_any
+  }
+}
@@ -0,0 +1,303 @@
+//! Building trees of an [`RcDom`] in Rust, without parsing markup.
+//!
+//! [`el`] starts an element, which takes attributes and children through
+//! chained calls, and [`text`], [`comment`] and [`doctype`] make the other
+//! nodes. [`ElementBuilder::build`] turns an element into a [`Handle`], and
+//! [`document`] puts nodes into a new [`RcDom`]:
+//!
+//! ```
+//! use dawm::build::*;
+//!
+//! let dom = document([
+//!   doctype("html"),
+//!   el("html")
+//!     .child(el("head").child(el("title").text("Hi")))
+//!     .child(
+//!       el("body")
+//!         .child(el("p").attr("class", "x").text("hi"))
+//!         .child(el("svg").attr("viewBox", "0 0 1 1").child(el("rect"))),
+//!     )
+//!     .into(),
+//! ]);
+//! assert_eq!(
+//!   dom.to_string(),
+//!   "<!DOCTYPE html><html><head><title>Hi</title></head><body>\
+//!    <p class=\"x\">hi</p><svg viewBox=\"0 0 1 1\"><rect></rect></svg>\
+//!    </body></html>"
+//! );
+//! ```
+//!
+//! Elements are put in the namespace the HTML parser would put them in:
+//! `<svg>` and `<math>` start the SVG and MathML namespaces, the other
+//! elements take the namespace of their parent, and the children of the
+//! HTML integration points (like `<foreignObject>`) are HTML again.
+//! [`el_ns`] makes an element in a given namespace instead. Attributes are
+//! in no namespace, except for the `xlink:`, `xml:` and `xmlns` ones. The
+//! children of a `<template>` go into its template contents, and adjacent
+//! text nodes are merged into one.
+//!
+//! Names are taken as they are, without being checked or lowercased, and
+//! text is never parsed, so values from anywhere can be put into a tree
+//! without the risk of injecting markup. Fragments are built from JS with
+//! the `FragmentBuilder` of the [`dom`](crate::dom) module instead.
+
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::Namespace;
+use html5ever::Prefix;
+use html5ever::QualName;
+use html5ever::ns;
+
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::RcDom;
+use crate::dom::create::new_element;
+use crate::rcdom::push_merging_text;
+use crate::traverse::index_in_parent;
+
+/// A node to be built, as the child of an element or of a [`document`].
+#[derive(Debug, Clone)]
+pub enum NodeBuilder {
+  /// An element.
+  Element(ElementBuilder),
+  /// A text node.
+  Text(String),
+  /// A comment.
+  Comment(String),
+  /// A doctype with the given name.
+  Doctype(String),
+  /// An existing node, which is taken out of its parent.
+  Node(Handle),
+}
+
+impl From<ElementBuilder> for NodeBuilder {
+  fn from(element: ElementBuilder) -> Self {
+    NodeBuilder::Element(element)
+  }
+}
+
+impl From<&str> for NodeBuilder {
+  fn from(text: &str) -> Self {
+    NodeBuilder::Text(text.into())
+  }
+}
+
+impl From<String> for NodeBuilder {
+  fn from(text: String) -> Self {
+    NodeBuilder::Text(text)
+  }
+}
+
+impl From<Handle> for NodeBuilder {
+  fn from(node: Handle) -> Self {
+    NodeBuilder::Node(node)
+  }
+}
+
+/// An element to be built. See the [module level
+/// documentation](crate::build) for more details.
+#[derive(Debug, Clone)]
+pub struct ElementBuilder {
+  // `None` for the namespace the element gets from where it is put
+  ns:       Option<Namespace>,
+  prefix:   Option<Prefix>,
+  local:    LocalName,
+  attrs:    Vec<Attribute>,
+  children: Vec<NodeBuilder>,
+}
+
+/// Starts an element with the local name `name`, in the namespace it gets
+/// from where it is put.
+pub fn el(name: &str) -> ElementBuilder {
+  ElementBuilder {
+    ns:       None,
+    prefix:   None,
+    local:    name.into(),
+    attrs:    Vec::new(),
+    children: Vec::new(),
+  }
+}
+
+/// Starts an element in the namespace `ns`, with the qualified name `name`,
+/// e.g. `svg:rect`.
+pub fn el_ns(ns: Namespace, name: &str) -> ElementBuilder {
+  let (prefix, local) = match name.split_once(':') {
+    Some((prefix, local)) => (Some(prefix.into()), local),
+    None => (None, name),
+  };
+  ElementBuilder {
+    ns: Some(ns),
+    prefix,
+    ..el(local)
+  }
+}
+
+/// Makes a text node.
+pub fn text(text: &str) -> NodeBuilder {
+  NodeBuilder::Text(text.into())
+}
+
+/// Makes a comment.
+pub fn comment(text: &str) -> NodeBuilder {
+  NodeBuilder::Comment(text.into())
+}
+
+/// Makes a doctype with the name `name`, and no public or system id.
+pub fn doctype(name: &str) -> NodeBuilder {
+  NodeBuilder::Doctype(name.into())
+}
+
+/// Builds a document with the children `nodes`.
+pub fn document<I>(nodes: I) -> RcDom
+where
+  I: IntoIterator,
+  I::Item: Into<NodeBuilder>,
+{
+  let dom = RcDom::default();
+  let nodes = nodes.into_iter().map(Into::into).collect();
+  append_all(&dom.document, nodes, &ns!(html));
+  dom
+}
+
+impl ElementBuilder {
+  /// Sets the attribute with the qualified name `name` to `value`.
+  pub fn attr(mut self, name: &str, value: &str) -> Self {
+    let name = attribute_name(name);
+    self.attrs.retain(|attr| attr.name != name);
+    self.attrs.push(Attribute {
+      name,
+      value: value.into(),
+    });
+    self
+  }
+
+  /// Appends `node` to the children of the element.
+  pub fn child(mut self, node: impl Into<NodeBuilder>) -> Self {
+    self.children.push(node.into());
+    self
+  }
+
+  /// Appends `nodes` to the children of the element.
+  pub fn children<I>(mut self, nodes: I) -> Self
+  where
+    I: IntoIterator,
+    I::Item: Into<NodeBuilder>,
+  {
+    self.children.extend(nodes.into_iter().map(Into::into));
+    self
+  }
+
+  /// Appends a text node to the children of the element.
+  pub fn text(self, text: &str) -> Self {
+    self.child(NodeBuilder::Text(text.into()))
+  }
+
+  /// Builds the element, as if it were put into an HTML element.
+  pub fn build(self) -> Handle {
+    self.build_in(&ns!(html))
+  }
+
+  // Builds the element, as if it were put into an element in the namespace
+  // `parent`.
+  fn build_in(self, parent: &Namespace) -> Handle {
+    let ns = self
+      .ns
+      .unwrap_or_else(|| element_namespace(&self.local, parent));
+    let children_ns = children_namespace(&ns, &self.local);
+    let element = new_element(QualName::new(self.prefix, ns, self.local));
+    let NodeData::Element {
+      attrs,
+      template_contents,
+      ..
+    } = &element.data
+    else {
+      unreachable!("new_element always makes an element");
+    };
+    *attrs.borrow_mut() = self.attrs;
+    let container = template_contents.as_ref().unwrap_or(&element);
+    append_all(container, self.children, &children_ns);
+    element
+  }
+}
+
+// The namespace the HTML parser puts an element with the local name `local`
+// in, as a child of an element in (or a document of) the namespace `parent`.
+pub(crate) fn element_namespace(local: &str, parent: &Namespace) -> Namespace {
+  match local {
+    "svg" => ns!(svg),
+    "math" => ns!(mathml),
+    _ => parent.clone(),
+  }
+}
+
+// The namespace the children of an element in the namespace `ns` go into,
+// which is HTML again for the HTML integration points.
+pub(crate) fn children_namespace(ns: &Namespace, local: &str) -> Namespace {
+  let is_integration_point = match *ns {
+    ns!(svg) => matches!(local, "foreignObject" | "desc" | "title"),
+    ns!(mathml) => matches!(local, "mi" | "mo" | "mn" | "ms" | "mtext"),
+    _ => false,
+  };
+  match is_integration_point {
+    true => ns!(html),
+    false => ns.clone(),
+  }
+}
+
+// Builds `nodes` and appends them to `parent`, an element in or a document
+// of the namespace `ns`.
+fn append_all(parent: &Handle, nodes: Vec<NodeBuilder>, ns: &Namespace) {
+  let mut children = parent.children.take();
+  for node in nodes {
+    let node = match node {
+      NodeBuilder::Element(element) => element.build_in(ns),
+      NodeBuilder::Text(text) => Node::new(NodeData::Text {
+        contents: RefCell::new(text.into()),
+      }),
+      NodeBuilder::Comment(text) => Node::new(NodeData::Comment {
+        contents: text.into(),
+      }),
+      NodeBuilder::Doctype(name) => Node::new(NodeData::Doctype {
+        name:      name.into(),
+        public_id: Default::default(),
+        system_id: Default::default(),
+      }),
+      NodeBuilder::Node(node) => {
+        if let Some((parent, index)) = index_in_parent(&node) {
+          parent.children.borrow_mut().remove(index);
+        }
+        node.parent.set(None);
+        node
+      }
+    };
+    push_merging_text(&mut children, node);
+  }
+  for child in &children {
+    child.parent.set(Some(Rc::downgrade(parent)));
+  }
+  *parent.children.borrow_mut() = children;
+}
+
+// The name of the attribute with the qualified name `name`, in the
+// namespace the HTML parser puts it in.
+fn attribute_name(name: &str) -> QualName {
+  match name.split_once(':') {
+    None if name == "xmlns" => QualName::new(None, ns!(xmlns), name.into()),
+    Some((prefix @ "xlink", local)) => {
+      QualName::new(Some(prefix.into()), ns!(xlink), local.into())
+    }
+    Some((prefix @ "xml", local)) => {
+      QualName::new(Some(prefix.into()), ns!(xml), local.into())
+    }
+    Some((prefix @ "xmlns", local)) => {
+      QualName::new(Some(prefix.into()), ns!(xmlns), local.into())
+    }
+    _ => QualName::new(None, ns!(), name.into()),
+  }
+}
@@ -0,0 +1,170 @@
+//! Feature detection for JS wrappers.
+//!
+//! Which exports and options are available depends on the version of the
+//! crate and on the Cargo features it was compiled with, e.g. `parse_xml`
+//! only exists with the `xml` feature. Rather than probing for exports (or
+//! catching the errors of calling missing ones), wrappers can call
+//! `capabilities()` once and inspect the returned [`Capabilities`].
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use crate::Encoding;
+use crate::Profile;
+use crate::SanitizePreset;
+use crate::encoding;
+use crate::wire::WIRE_VERSION;
+
+/// What this build of the crate supports.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Capabilities {
+  /// The version of the crate.
+  pub version:          &'static str,
+  /// The Cargo features the crate was compiled with.
+  pub features:         Vec<&'static str>,
+  /// The names of the exported functions and classes.
+  pub exports:          Vec<&'static str>,
+  /// The versions of the wire format that can be produced.
+  pub wire_versions:    &'static [u32],
+  /// The names accepted by the `profile` option.
+  pub profiles:         Vec<&'static str>,
+  /// The names of the sanitizer presets.
+  pub sanitize_presets: Vec<&'static str>,
+  /// The (canonical names of the) encodings `parse_bytes` can decode.
+  pub encodings:        Vec<&'static str>,
+  /// The hard limits of the implementation.
+  pub limits:           Limits,
+  /// Exports and options that still work, but are going away.
+  pub deprecations:     &'static [Deprecation],
+}
+
+/// The hard limits of the implementation.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Limits {
+  /// The maximum number of nodes in a document (node ids are `u32`).
+  pub max_nodes:          u32,
+  /// The maximum number of entries in a string table.
+  pub max_strings:        u32,
+  /// The maximum byte offset that is reported in `lineStarts`.
+  pub max_line_offset:    u32,
+  /// The number of bytes searched for a declared encoding.
+  pub encoding_prescan:   u32,
+  /// The number of bytes looked at by `detectEncoding`.
+  pub encoding_detection: u32,
+}
+
+/// An export or option that is deprecated.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Deprecation {
+  /// The name of the export, or the (JS) name of the option.
+  pub name:        &'static str,
+  /// `"export"` or `"option"`.
+  pub kind:        &'static str,
+  /// The version the deprecation was introduced in.
+  pub since:       &'static str,
+  /// What to use instead, if anything.
+  pub replacement: Option<&'static str>,
+}
+
+/// The deprecated exports and options of this version.
+pub const DEPRECATIONS: &[Deprecation] = &[];
+
+/// Returns the [`Capabilities`] of this build.
+pub fn capabilities() -> Capabilities {
+  let mut features = Vec::new();
+  if cfg!(feature = "serde") {
+    features.push("serde");
+  }
+  if cfg!(feature = "tree") {
+    features.push("tree");
+  }
+  if cfg!(feature = "xml") {
+    features.push("xml");
+  }
+
+  let mut exports = vec![
+    "apply_patch",
+    "capabilities",
+    "collapse_whitespace",
+    "diff_docs",
+    "discover_feeds",
+    "extract_article",
+    "extract_canonical",
+    "extract_forms",
+    "extract_http_equiv",
+    "extract_icons",
+    "extract_images",
+    "extract_languages",
+    "extract_links",
+    "extract_metadata",
+    "extract_outline",
+    "extract_scripts",
+    "extract_styles",
+    "filter_urls",
+    "inject_attributes",
+    "inject_nonce",
+    "inline_svg_uses",
+    "merge_docs",
+    "modernize_presentation",
+    "node_path",
+    "nodes_equal",
+    "parse_bytes",
+    "parse_doc",
+    "parse_frag",
+    "parse_html",
+    "parse_sizes",
+    "parse_srcset",
+    "parse_stats",
+    "prepare_email",
+    "remove_empty",
+    "resolve_base",
+    "rewrite_attributes",
+    "rewrite_urls",
+    "sanitize_doc",
+    "sanitize_html",
+    "sanitize_html_with_report",
+    "scan_attributes",
+    "scope_names",
+    "strip_comments",
+    "text_stats",
+    "Document",
+    "FragmentBuilder",
+    "HtmlRewriter",
+    "NodeRef",
+    "NodeType",
+    "ParseSession",
+    "RewriterElement",
+    "TemplateInstance",
+  ];
+  if cfg!(feature = "xml") {
+    exports.push("parse_feed");
+    exports.push("parse_xml");
+  }
+  exports.sort_unstable();
+
+  Capabilities {
+    version: env!("CARGO_PKG_VERSION"),
+    features,
+    exports,
+    wire_versions: &[WIRE_VERSION],
+    profiles: Profile::ALL.iter().map(Profile::as_str).collect(),
+    sanitize_presets: SanitizePreset::ALL
+      .iter()
+      .map(SanitizePreset::as_str)
+      .collect(),
+    encodings: Encoding::ALL.iter().map(Encoding::as_str).collect(),
+    limits: Limits {
+      max_nodes:          u32::MAX,
+      max_strings:        u32::MAX,
+      max_line_offset:    u32::MAX,
+      encoding_prescan:   encoding::PRESCAN_LENGTH as u32,
+      encoding_detection: encoding::DETECT_LENGTH as u32,
+    },
+    deprecations: DEPRECATIONS,
+  }
+}
@@ -0,0 +1,105 @@
+//! A C API, for native hosts (C, C++, Swift, Go through cgo, ...) that embed
+//! the parser without going through WASM.
+//!
+//! With the `capi` feature, the `cdylib` exports these functions, declared
+//! for C in `include/dawm.h`:
+//!
+//! ```c
+//! DawmBuffer dawm_parse_html(const uint8_t *input, size_t len);
+//! void dawm_free(DawmBuffer buffer);
+//! ```
+//!
+//! [`dawm_parse_html`] parses the bytes of an HTML document (decoded like
+//! [`parse_wire_bytes`] decodes them), and returns its wire format as JSON,
+//! written with [`json::to_string`]. If parsing
+//! fails, or panics, the buffer holds the error message instead, and its
+//! `error` is set. Every buffer is owned by the caller, and must be given
+//! back to [`dawm_free`] exactly once.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::string::ToString;
+use core::ptr;
+use core::slice;
+use std::panic;
+
+use crate::ParseOptions;
+use crate::json;
+use crate::parse_wire_bytes;
+
+/// A buffer of bytes returned to C, which is freed with [`dawm_free`].
+#[repr(C)]
+#[derive(Debug)]
+pub struct DawmBuffer {
+  /// The bytes, which are UTF-8. Null for an empty buffer.
+  pub data:  *mut u8,
+  /// The number of bytes.
+  pub len:   usize,
+  /// Whether the bytes are an error message rather than the result.
+  pub error: bool,
+}
+
+impl DawmBuffer {
+  fn new(text: String, error: bool) -> Self {
+    if text.is_empty() {
+      return DawmBuffer {
+        data: ptr::null_mut(),
+        len: 0,
+        error,
+      };
+    }
+    let bytes = text.into_bytes().into_boxed_slice();
+    let len = bytes.len();
+    DawmBuffer {
+      data: Box::into_raw(bytes).cast(),
+      len,
+      error,
+    }
+  }
+}
+
+/// Parses the `len` bytes at `input` as an HTML document, with the default
+/// options, and returns its wire format as JSON.
+///
+/// # Safety
+///
+/// `input` must point to `len` bytes that are not written to until this
+/// returns, or be null (for no input) if `len` is zero.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dawm_parse_html(
+  input: *const u8,
+  len: usize,
+) -> DawmBuffer {
+  let input = match input.is_null() {
+    true => &[][..],
+    // SAFETY: the caller guarantees that `input` points to `len` bytes.
+    false => unsafe { slice::from_raw_parts(input, len) },
+  };
+  let result = panic::catch_unwind(|| {
+    let doc = parse_wire_bytes(input, "text/html", &ParseOptions::default())
+      .map_err(|error| error.to_string())?;
+    json::to_string(&doc).map_err(|error| error.to_string())
+  });
+  match result {
+    Ok(Ok(json)) => DawmBuffer::new(json, false),
+    Ok(Err(message)) => DawmBuffer::new(message, true),
+    Err(_) => DawmBuffer::new("the parser panicked".into(), true),
+  }
+}
+
+/// Frees a buffer returned by one of the other functions.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library, and not been freed
+/// before. Its fields must not have been changed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn dawm_free(buffer: DawmBuffer) {
+  if buffer.data.is_null() {
+    return;
+  }
+  let bytes = ptr::slice_from_raw_parts_mut(buffer.data, buffer.len);
+  // SAFETY: the caller guarantees that the buffer came from `DawmBuffer::new`,
+  // which leaked a boxed slice of exactly these bytes.
+  drop(unsafe { Box::from_raw(bytes) });
+}
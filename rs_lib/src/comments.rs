@@ -0,0 +1,77 @@
+//! Stripping comments.
+//!
+//! Most comments are dead weight in the HTML that is served, but some of
+//! them still do something: the conditional comments of old versions of
+//! Internet Explorer (`<!--[if IE]>...<![endif]-->`, and the downlevel
+//! revealed `<![if !IE]>...<![endif]>`), and the markers that frameworks
+//! find their way through server-rendered markup by when hydrating it, like
+//! React's `<!--$-->` and `<!--/$-->`, Vue's `<!--[-->` and `<!--]-->`, or
+//! Knockout's `<!-- ko if: x -->` and `<!-- /ko -->`.
+//!
+//! [`strip_comments`] removes every comment (including the ones in the
+//! contents of templates), except for the conditional comments with
+//! `keepConditional`, and the ones whose text starts with one of the `keep`
+//! prefixes, ignoring the whitespace it starts with:
+//!
+//! ```js
+//! strip_comments(html, { keepConditional: true, keep: ["$", "/$", "ko", "/ko"] });
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+use serde::Deserialize;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::rcdom::remove_nodes;
+
+/// Which comments [`strip_comments`] keeps.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StripComments {
+  /// Whether to keep the conditional comments of Internet Explorer.
+  pub keep_conditional: bool,
+  /// The prefixes of the text of the comments to keep, e.g. `"$"` for the
+  /// markers of React.
+  pub keep:             Vec<String>,
+  /// Whether to merge the text nodes that were only separated by removed
+  /// comments into one, like the `coalesceText` option does.
+  pub coalesce_text:    bool,
+}
+
+impl StripComments {
+  /// Whether the comment with the text `text` is kept.
+  pub fn keeps(&self, text: &str) -> bool {
+    let text = text.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    (self.keep_conditional && is_conditional(text))
+      || self.keep.iter().any(|prefix| text.starts_with(&**prefix))
+  }
+}
+
+/// Removes the comments below `root` that `options` does not keep,
+/// returning how many were removed.
+pub fn strip_comments(root: &Handle, options: &StripComments) -> usize {
+  let removed = Cell::new(0);
+  let remove = |data: &NodeData| match data {
+    NodeData::Comment { contents } if !options.keeps(contents) => {
+      removed.set(removed.get() + 1);
+      true
+    }
+    _ => false,
+  };
+  remove_nodes(root, &remove, options.coalesce_text);
+  removed.get()
+}
+
+// Whether a comment (with its leading whitespace removed) starts or ends a
+// conditional comment: `[if IE]>...<![endif]`, `[if !IE]><!`, `<![endif]`, or
+// the `[if !IE]` and `[endif]` the parser turns downlevel revealed ones into.
+fn is_conditional(text: &str) -> bool {
+  ["[if", "[endif]", "<![endif]"].iter().any(|prefix| {
+    text
+      .get(..prefix.len())
+      .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+  })
+}
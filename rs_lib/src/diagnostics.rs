@@ -0,0 +1,195 @@
+//! Structured parse diagnostics.
+//!
+//! html5ever and xml5ever report errors as free-form strings, whose wording
+//! depends on the `exactErrors` option and may change between releases. To
+//! give downstream tooling (linters, validators, editors) something stable to
+//! filter on, every reported message is classified into an [`ErrorCode`] with
+//! an associated [`Severity`], alongside the original message and its
+//! [`Span`] in the input.
+//!
+//! The codes are serialized in kebab-case (e.g. `"duplicate-attribute"`), and
+//! are guaranteed to remain stable across releases; new codes may be added,
+//! but existing ones will not be renamed or repurposed.
+
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+
+use serde::Serialize;
+
+/// How severe a parse error is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+  /// The input is malformed, and the parser had to recover from it in a way
+  /// which likely changes the resulting tree.
+  Error,
+  /// The input is non-conforming, but the resulting tree is unaffected (e.g.
+  /// a duplicate attribute, which is simply dropped).
+  Warning,
+}
+
+impl Severity {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Severity::Error => "error",
+      Severity::Warning => "warning",
+    }
+  }
+}
+
+/// A stable, machine-readable classification of a parse error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+  /// A token that is not allowed in the current insertion mode.
+  UnexpectedToken,
+  /// A character that is not allowed in the current tokenizer state.
+  UnexpectedCharacter,
+  /// The input ended in the middle of a construct.
+  UnexpectedEof,
+  /// Non-whitespace text directly inside of a table.
+  UnexpectedTableText,
+  /// An attribute that was already present on the same tag.
+  DuplicateAttribute,
+  /// Attributes, or a self-closing flag, on an end tag.
+  EndTagAttributes,
+  /// A self-closing flag on a non-void HTML element.
+  NonVoidSelfClosing,
+  /// A malformed character reference, such as `&#;` or `&bogus;`.
+  InvalidCharacterReference,
+  /// An end tag which does not match the currently open elements.
+  MisnestedTag,
+  /// An element nested in a way that is not allowed (forms, buttons, etc).
+  NestedElement,
+  /// A malformed or misplaced doctype.
+  BadDoctype,
+  /// An invalid XML namespace declaration.
+  NamespaceError,
+  /// Content before or after the root element of an XML document.
+  ContentOutsideRoot,
+  /// Any error that does not fall in one of the other categories.
+  Other,
+}
+
+impl ErrorCode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ErrorCode::UnexpectedToken => "unexpected-token",
+      ErrorCode::UnexpectedCharacter => "unexpected-character",
+      ErrorCode::UnexpectedEof => "unexpected-eof",
+      ErrorCode::UnexpectedTableText => "unexpected-table-text",
+      ErrorCode::DuplicateAttribute => "duplicate-attribute",
+      ErrorCode::EndTagAttributes => "end-tag-attributes",
+      ErrorCode::NonVoidSelfClosing => "non-void-self-closing",
+      ErrorCode::InvalidCharacterReference => "invalid-character-reference",
+      ErrorCode::MisnestedTag => "misnested-tag",
+      ErrorCode::NestedElement => "nested-element",
+      ErrorCode::BadDoctype => "bad-doctype",
+      ErrorCode::NamespaceError => "namespace-error",
+      ErrorCode::ContentOutsideRoot => "content-outside-root",
+      ErrorCode::Other => "other",
+    }
+  }
+
+  /// Returns the default severity of errors with this code.
+  pub fn severity(&self) -> Severity {
+    match self {
+      ErrorCode::DuplicateAttribute
+      | ErrorCode::EndTagAttributes
+      | ErrorCode::NonVoidSelfClosing => Severity::Warning,
+      _ => Severity::Error,
+    }
+  }
+
+  /// Classifies a message reported by html5ever or xml5ever. Both the short
+  /// and the `exactErrors` forms of each message are recognized.
+  pub fn classify(message: &str) -> Self {
+    let m = message;
+    if m.contains("EOF") {
+      ErrorCode::UnexpectedEof
+    } else if m.starts_with("Unexpected token") {
+      ErrorCode::UnexpectedToken
+    } else if m.starts_with("Unexpected characters")
+      || m == "Non-space table text"
+    {
+      ErrorCode::UnexpectedTableText
+    } else if m.starts_with("Bad character") || m.starts_with("Saw ") {
+      ErrorCode::UnexpectedCharacter
+    } else if m == "Duplicate attribute" {
+      ErrorCode::DuplicateAttribute
+    } else if m.starts_with("Attributes on") || m == "Self-closing end tag" {
+      ErrorCode::EndTagAttributes
+    } else if m == "Unacknowledged self-closing tag" {
+      ErrorCode::NonVoidSelfClosing
+    } else if m.contains("character reference") {
+      ErrorCode::InvalidCharacterReference
+    } else if m.contains("DOCTYPE") {
+      ErrorCode::BadDoctype
+    } else if m.contains("amespace") || m.contains("XMLNS") {
+      ErrorCode::NamespaceError
+    } else if m.starts_with("Unexpected element in start phase")
+      || m.starts_with("Unexpected element in end phase")
+    {
+      ErrorCode::ContentOutsideRoot
+    } else if m.starts_with("nested") || m.starts_with("Nested") {
+      ErrorCode::NestedElement
+    } else if m.contains("close")
+      || m.contains("closing")
+      || m.contains("in scope")
+      || m.contains("not open")
+      || m.contains("not current node")
+      || m.contains("doesn't match tag")
+      || m.starts_with("Unexpected open")
+      || m.contains("on </form>")
+    {
+      ErrorCode::MisnestedTag
+    } else {
+      ErrorCode::Other
+    }
+  }
+}
+
+/// The location of a parse error in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub struct Span {
+  /// The 1-based line the parser was on when the error was reported, if the
+  /// parser tracks line numbers (the XML parser does not).
+  pub line:   Option<u64>,
+  /// The 1-based column of `offset` on its line, counted in bytes.
+  pub column: Option<u64>,
+  /// The byte offset in the input the parser had read up to when the error
+  /// was reported, if known.
+  pub offset: Option<usize>,
+}
+
+/// A single error reported by the parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+  /// The stable classification of this error.
+  pub code:     ErrorCode,
+  /// How severe this error is.
+  pub severity: Severity,
+  /// The error message, as reported by html5ever / xml5ever.
+  pub message:  Cow<'static, str>,
+  /// Where in the input the error was reported.
+  pub span:     Span,
+}
+
+impl ParseError {
+  /// Creates a new error from a parser message, classifying it in the
+  /// process.
+  pub fn new(message: Cow<'static, str>, span: Span) -> Self {
+    let code = ErrorCode::classify(&message);
+    Self {
+      code,
+      severity: code.severity(),
+      message,
+      span,
+    }
+  }
+}
+
+/// A callback invoked with every parse error as soon as it is reported, even
+/// if the error is not recorded due to the error budget. Returning `false`
+/// aborts parsing.
+pub type ErrorHook = Rc<dyn Fn(&ParseError) -> bool>;
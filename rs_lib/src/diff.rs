@@ -0,0 +1,575 @@
+//! Structural diffs between documents.
+//!
+//! [`diff_docs`] compares two trees and returns the [`PatchOp`]s that turn
+//! the first one into the second. Nodes are addressed by their path: the
+//! index of every node on the way down from the root among its siblings, so
+//! that `[1, 0]` is the first child of the second child of the root. The
+//! contents of a `<template>` count as its children. Every path refers to the
+//! tree as it is after the operations before it have been applied, so the
+//! operations have to be applied in order.
+//!
+//! The children of two matching nodes are matched up in order, as a longest
+//! common subsequence that prefers identical subtrees over merely similar
+//! ones (nodes of the same kind, and for elements, of the same name and
+//! without conflicting `id`s). Matched nodes are compared recursively, while an
+//! unmatched node that is identical to (or an element of the same name and `id`
+//! as) an unmatched node of the other side is moved rather than removed and
+//! inserted again. The result is not an edit script of minimal length, which
+//! is far too expensive to compute for trees, but it is close for the edits
+//! that documents usually go through.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::hash::Hasher;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::hash::Fnv;
+
+// The largest number of pairs of children that are compared to match them,
+// beyond which only the unchanged children at either end are matched.
+const MAX_MATCH_PAIRS: usize = 1 << 20;
+
+/// An operation on a tree, with its target given by path (see the [module
+/// documentation](self)).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum PatchOp {
+  /// Inserts `node`, along with its descendants, so that it ends up at
+  /// `path`.
+  InsertNode { path: Vec<u32>, node: PatchNode },
+  /// Removes the node at `path`, along with its descendants.
+  RemoveNode { path: Vec<u32> },
+  /// Moves the node at `from` to `to`, which refers to the tree as it is
+  /// after the node has been removed from `from`.
+  MoveNode { from: Vec<u32>, to: Vec<u32> },
+  /// Sets an attribute of the element at `path`, adding it if it is missing.
+  SetAttribute {
+    path:      Vec<u32>,
+    /// The qualified name of the attribute, e.g. `class` or `xlink:href`.
+    name:      String,
+    /// The namespace URL of the attribute, if it has one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    namespace: String,
+    value:     String,
+  },
+  /// Removes an attribute of the element at `path`.
+  RemoveAttribute {
+    path:      Vec<u32>,
+    name:      String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    namespace: String,
+  },
+  /// Sets the text of the text node or comment at `path`, or the data of
+  /// the processing instruction at `path`.
+  SetText { path: Vec<u32>, value: String },
+}
+
+/// A node to insert, along with its descendants.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(
+  tag = "type",
+  rename_all = "camelCase",
+  rename_all_fields = "camelCase"
+)]
+pub enum PatchNode {
+  Element {
+    /// The qualified name of the element, e.g. `div` or `svg:rect`.
+    name:       String,
+    /// The namespace URL of the element, e.g. that of HTML.
+    namespace:  String,
+    #[serde(default)]
+    attributes: Vec<PatchAttribute>,
+    /// The children, or the contents of a `<template>`.
+    #[serde(default)]
+    children:   Vec<PatchNode>,
+  },
+  Text {
+    value: String,
+  },
+  Comment {
+    value: String,
+  },
+  ProcessingInstruction {
+    target: String,
+    data:   String,
+  },
+  Doctype {
+    name:      String,
+    #[serde(default)]
+    public_id: String,
+    #[serde(default)]
+    system_id: String,
+  },
+}
+
+/// An attribute of a [`PatchNode::Element`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchAttribute {
+  /// The qualified name of the attribute, e.g. `class` or `xlink:href`.
+  pub name:      String,
+  /// The namespace URL of the attribute, if it has one.
+  #[serde(default, skip_serializing_if = "String::is_empty")]
+  pub namespace: String,
+  pub value:     String,
+}
+
+impl PatchNode {
+  /// Describes `node` along with its descendants, or returns `None` for
+  /// documents, which cannot be inserted.
+  pub fn from_node(node: &Handle) -> Option<Self> {
+    let patch_node = match &node.data {
+      NodeData::Document => return None,
+      NodeData::Doctype {
+        name,
+        public_id,
+        system_id,
+      } => Self::Doctype {
+        name:      name.to_string(),
+        public_id: public_id.to_string(),
+        system_id: system_id.to_string(),
+      },
+      NodeData::Text { contents } => Self::Text {
+        value: contents.borrow().to_string(),
+      },
+      NodeData::Comment { contents } => Self::Comment {
+        value: contents.to_string(),
+      },
+      NodeData::ProcessingInstruction { target, contents } => {
+        Self::ProcessingInstruction {
+          target: target.to_string(),
+          data:   contents.to_string(),
+        }
+      }
+      NodeData::Element { name, attrs, .. } => Self::Element {
+        name:       qualified_name(name),
+        namespace:  name.ns.to_string(),
+        attributes: attrs
+          .borrow()
+          .iter()
+          .map(|attr| PatchAttribute {
+            name:      qualified_name(&attr.name),
+            namespace: attr.name.ns.to_string(),
+            value:     attr.value.to_string(),
+          })
+          .collect(),
+        children:   child_nodes(node)
+          .iter()
+          .filter_map(Self::from_node)
+          .collect(),
+      },
+    };
+    Some(patch_node)
+  }
+}
+
+/// Returns the operations that turn the tree rooted at `old` into the one
+/// rooted at `new`. The roots themselves are not compared, only their
+/// descendants, so that e.g. a document can be diffed against a fragment.
+pub fn diff_docs(old: &Handle, new: &Handle) -> Vec<PatchOp> {
+  let mut differ = Differ {
+    matcher: Matcher::new(&[old, new]),
+    ops:     Vec::new(),
+  };
+  differ.diff_children(old, new, &mut Vec::new());
+  differ.ops
+}
+
+/// Returns the qualified name of an element or attribute, i.e. its local
+/// name with its prefix, if it has one.
+pub(crate) fn qualified_name(name: &QualName) -> String {
+  match &name.prefix {
+    Some(prefix) => format!("{prefix}:{}", name.local),
+    None => name.local.to_string(),
+  }
+}
+
+/// Returns the node whose children are the children of `node` as far as
+/// paths are concerned, i.e. `node` itself, or for a `<template>`, the
+/// fragment of its contents.
+pub(crate) fn container(node: &Handle) -> Handle {
+  match &node.data {
+    NodeData::Element {
+      template_contents: Some(contents),
+      ..
+    } => contents.clone(),
+    _ => node.clone(),
+  }
+}
+
+/// Returns the children of `node` as far as paths are concerned (see
+/// [`container`]).
+pub(crate) fn child_nodes(node: &Handle) -> Vec<Handle> {
+  container(node).children.borrow().clone()
+}
+
+/// Matches up the children of nodes of several trees.
+pub(crate) struct Matcher {
+  // a hash of every subtree of the trees, so that identical subtrees are
+  // recognized without comparing them node by node
+  fingerprints: BTreeMap<*const Node, u64>,
+}
+
+impl Matcher {
+  /// Creates a matcher for the trees rooted at `roots`.
+  pub(crate) fn new(roots: &[&Handle]) -> Self {
+    let mut matcher = Self {
+      fingerprints: BTreeMap::new(),
+    };
+    for root in roots {
+      matcher.fingerprint(root);
+    }
+    matcher
+  }
+
+  fn fingerprint(&mut self, node: &Handle) -> u64 {
+    let mut hasher = Fnv::default();
+    match &node.data {
+      NodeData::Document => 0u8.hash(&mut hasher),
+      NodeData::Doctype {
+        name,
+        public_id,
+        system_id,
+      } => (1u8, &**name, &**public_id, &**system_id).hash(&mut hasher),
+      NodeData::Text { contents } => {
+        (2u8, &**contents.borrow()).hash(&mut hasher)
+      }
+      NodeData::Comment { contents } => (3u8, &**contents).hash(&mut hasher),
+      NodeData::ProcessingInstruction { target, contents } => {
+        (4u8, &**target, &**contents).hash(&mut hasher);
+      }
+      NodeData::Element { name, attrs, .. } => {
+        (5u8, name).hash(&mut hasher);
+        // the order of attributes does not matter
+        let mut attrs: Vec<u64> = attrs
+          .borrow()
+          .iter()
+          .map(|attr| {
+            let mut hasher = Fnv::default();
+            (&attr.name, &*attr.value).hash(&mut hasher);
+            hasher.finish()
+          })
+          .collect();
+        attrs.sort_unstable();
+        attrs.hash(&mut hasher);
+      }
+    }
+    for child in child_nodes(node) {
+      self.fingerprint(&child).hash(&mut hasher);
+    }
+    let fingerprint = hasher.finish();
+    self.fingerprints.insert(Rc::as_ptr(node), fingerprint);
+    fingerprint
+  }
+
+  /// Whether the subtrees rooted at `old` and `new` are identical.
+  pub(crate) fn same(&self, old: &Handle, new: &Handle) -> bool {
+    self.fingerprints.get(&Rc::as_ptr(old))
+      == self.fingerprints.get(&Rc::as_ptr(new))
+  }
+
+  /// Matches up the old and new children, returning the index of the old
+  /// child matched to each of the new ones.
+  pub(crate) fn match_children(
+    &self,
+    old: &[Handle],
+    new: &[Handle],
+  ) -> Vec<Option<usize>> {
+    let mut pairs = vec![None; new.len()];
+
+    // unchanged children at either end, which is all of them in most cases
+    let mut start = 0;
+    while start < old.len()
+      && start < new.len()
+      && self.same(&old[start], &new[start])
+    {
+      pairs[start] = Some(start);
+      start += 1;
+    }
+    let mut end = 0;
+    while end < old.len() - start
+      && end < new.len() - start
+      && self.same(&old[old.len() - 1 - end], &new[new.len() - 1 - end])
+    {
+      pairs[new.len() - 1 - end] = Some(old.len() - 1 - end);
+      end += 1;
+    }
+    let old_middle = &old[start..old.len() - end];
+    let new_middle = &new[start..new.len() - end];
+    let mut matched = vec![false; old.len()];
+    for &index in pairs.iter().flatten() {
+      matched[index] = true;
+    }
+
+    if old_middle.len() * new_middle.len() <= MAX_MATCH_PAIRS {
+      for (i, j) in self.common_subsequence(old_middle, new_middle) {
+        pairs[start + j] = Some(start + i);
+        matched[start + i] = true;
+      }
+    }
+
+    // the nodes left unmatched may have been moved
+    for prefer_identical in [true, false] {
+      for (j, pair) in pairs.iter_mut().enumerate() {
+        if pair.is_some() {
+          continue;
+        }
+        let found = (0..old.len()).find(|&i| {
+          !matched[i]
+            && match prefer_identical {
+              true => self.same(&old[i], &new[j]),
+              false => keyed(&old[i], &new[j]),
+            }
+        });
+        if let Some(i) = found {
+          *pair = Some(i);
+          matched[i] = true;
+        }
+      }
+    }
+    pairs
+  }
+
+  // The pairs of indices of a common subsequence of `old` and `new` which
+  // has the largest weight, counting identical nodes twice as much as
+  // similar ones.
+  fn common_subsequence(
+    &self,
+    old: &[Handle],
+    new: &[Handle],
+  ) -> Vec<(usize, usize)> {
+    let weight = |i: usize, j: usize| {
+      if self.same(&old[i], &new[j]) {
+        2
+      } else if similar(&old[i], &new[j]) {
+        1
+      } else {
+        0
+      }
+    };
+    let columns = new.len() + 1;
+    let mut table = vec![0u32; (old.len() + 1) * columns];
+    for i in (0..old.len()).rev() {
+      for j in (0..new.len()).rev() {
+        let skip = table[(i + 1) * columns + j].max(table[i * columns + j + 1]);
+        let take = match weight(i, j) {
+          0 => 0,
+          w => w + table[(i + 1) * columns + j + 1],
+        };
+        table[i * columns + j] = skip.max(take);
+      }
+    }
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+      let w = weight(i, j);
+      if w > 0 && table[i * columns + j] == w + table[(i + 1) * columns + j + 1]
+      {
+        pairs.push((i, j));
+        i += 1;
+        j += 1;
+      } else if table[(i + 1) * columns + j] >= table[i * columns + j + 1] {
+        i += 1;
+      } else {
+        j += 1;
+      }
+    }
+    pairs
+  }
+}
+
+struct Differ {
+  matcher: Matcher,
+  ops:     Vec<PatchOp>,
+}
+
+impl Differ {
+  fn diff_node(&mut self, old: &Handle, new: &Handle, path: &mut Vec<u32>) {
+    if self.matcher.same(old, new) {
+      return;
+    }
+    match (&old.data, &new.data) {
+      (NodeData::Text { contents: a }, NodeData::Text { contents: b }) => {
+        if *a.borrow() != *b.borrow() {
+          self.set_text(path, &b.borrow());
+        }
+      }
+      (
+        NodeData::Comment { contents: a },
+        NodeData::Comment { contents: b },
+      )
+      | (
+        NodeData::ProcessingInstruction { contents: a, .. },
+        NodeData::ProcessingInstruction { contents: b, .. },
+      ) => {
+        if a != b {
+          self.set_text(path, b);
+        }
+      }
+      (
+        NodeData::Element { attrs: a, .. },
+        NodeData::Element { attrs: b, .. },
+      ) => {
+        self.diff_attributes(&a.borrow(), &b.borrow(), path);
+        self.diff_children(old, new, path);
+      }
+      _ => {}
+    }
+  }
+
+  fn set_text(&mut self, path: &[u32], value: &str) {
+    self.ops.push(PatchOp::SetText {
+      path:  path.to_vec(),
+      value: value.to_string(),
+    });
+  }
+
+  fn diff_attributes(
+    &mut self,
+    old: &[Attribute],
+    new: &[Attribute],
+    path: &[u32],
+  ) {
+    let find = |attrs: &[Attribute], name: &QualName| {
+      attrs
+        .iter()
+        .find(|attr| attr.name.ns == name.ns && attr.name.local == name.local)
+        .map(|attr| attr.value.clone())
+    };
+    for attr in old {
+      if find(new, &attr.name).is_none() {
+        self.ops.push(PatchOp::RemoveAttribute {
+          path:      path.to_vec(),
+          name:      qualified_name(&attr.name),
+          namespace: attr.name.ns.to_string(),
+        });
+      }
+    }
+    for attr in new {
+      if find(old, &attr.name).as_ref() != Some(&attr.value) {
+        self.ops.push(PatchOp::SetAttribute {
+          path:      path.to_vec(),
+          name:      qualified_name(&attr.name),
+          namespace: attr.name.ns.to_string(),
+          value:     attr.value.to_string(),
+        });
+      }
+    }
+  }
+
+  fn diff_children(&mut self, old: &Handle, new: &Handle, path: &mut Vec<u32>) {
+    let old = child_nodes(old);
+    let new = child_nodes(new);
+    let pairs = self.matcher.match_children(&old, &new);
+
+    // `current` tracks which of the old children is where, as the operations
+    // on the children are applied
+    let mut matched = vec![false; old.len()];
+    for &index in pairs.iter().flatten() {
+      matched[index] = true;
+    }
+    let mut current: Vec<Option<usize>> = (0..old.len()).map(Some).collect();
+    for index in (0..old.len()).rev().filter(|&index| !matched[index]) {
+      current.remove(index);
+      self.ops.push(PatchOp::RemoveNode {
+        path: child_path(path, index),
+      });
+    }
+    for (index, pair) in pairs.iter().enumerate() {
+      match pair {
+        Some(old_index) => {
+          let Some(position) =
+            current.iter().position(|&entry| entry == Some(*old_index))
+          else {
+            continue;
+          };
+          if position != index {
+            current.remove(position);
+            current.insert(index, Some(*old_index));
+            self.ops.push(PatchOp::MoveNode {
+              from: child_path(path, position),
+              to:   child_path(path, index),
+            });
+          }
+        }
+        None => {
+          current.insert(index, None);
+          if let Some(node) = PatchNode::from_node(&new[index]) {
+            self.ops.push(PatchOp::InsertNode {
+              path: child_path(path, index),
+              node,
+            });
+          }
+        }
+      }
+    }
+
+    for (index, pair) in pairs.iter().enumerate() {
+      if let Some(old_index) = *pair {
+        path.push(index as u32);
+        self.diff_node(&old[old_index], &new[index], path);
+        path.pop();
+      }
+    }
+  }
+}
+
+fn child_path(path: &[u32], index: usize) -> Vec<u32> {
+  let mut child_path = path.to_vec();
+  child_path.push(index as u32);
+  child_path
+}
+
+/// Whether two nodes are alike enough to be diffed rather than replaced.
+pub(crate) fn similar(old: &Handle, new: &Handle) -> bool {
+  match (&old.data, &new.data) {
+    (NodeData::Element { name: a, .. }, NodeData::Element { name: b, .. }) => {
+      // elements with different ids are different elements, but an id may
+      // also have been added or removed
+      a == b
+        && match (element_id(old), element_id(new)) {
+          (Some(a), Some(b)) => a == b,
+          _ => true,
+        }
+    }
+    (
+      NodeData::ProcessingInstruction { target: a, .. },
+      NodeData::ProcessingInstruction { target: b, .. },
+    ) => a == b,
+    (NodeData::Text { .. }, NodeData::Text { .. })
+    | (NodeData::Comment { .. }, NodeData::Comment { .. }) => true,
+    _ => false,
+  }
+}
+
+// Whether two nodes are the same element, by name and (non-empty) `id`.
+fn keyed(old: &Handle, new: &Handle) -> bool {
+  let id = element_id(old);
+  similar(old, new)
+    && id.as_deref().is_some_and(|id| !id.is_empty())
+    && id == element_id(new)
+}
+
+fn element_id(node: &Handle) -> Option<String> {
+  let NodeData::Element { attrs, .. } = &node.data else {
+    return None;
+  };
+  attrs
+    .borrow()
+    .iter()
+    .find(|attr| attr.name.ns.is_empty() && &*attr.name.local == "id")
+    .map(|attr| attr.value.to_string())
+}
@@ -0,0 +1,245 @@
+//! Reading and changing the attributes of elements, like the DOM's
+//! `Element.*Attribute*` methods.
+//!
+//! The methods taking a qualified name (`getAttribute`, `setAttribute`, ...)
+//! match the first attribute with that name, lowercased first for HTML
+//! elements in HTML documents, while the `*NS` ones match attributes by
+//! namespace and local name. Attribute values are interned when the document
+//! is serialized, so changes show up in the next wire output like any other
+//! string of the document.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::QualName;
+use html5ever::ns;
+use wasm_bindgen::prelude::*;
+
+use super::NodeRef;
+use super::create::invalid_name;
+use super::create::is_valid_attribute_local_name;
+use super::create::validate_and_extract;
+use crate::DawmError;
+use crate::NodeData;
+use crate::diff::qualified_name;
+
+#[wasm_bindgen]
+impl NodeRef {
+  /// Whether the node is an element with any attributes.
+  #[wasm_bindgen(js_name = hasAttributes)]
+  pub fn has_attributes(&self) -> bool {
+    self.attrs().is_some_and(|attrs| !attrs.borrow().is_empty())
+  }
+
+  /// The qualified names of the attributes of an element, in order.
+  #[wasm_bindgen(js_name = getAttributeNames)]
+  pub fn get_attribute_names(&self) -> Vec<String> {
+    self.attrs().map_or_else(Vec::new, |attrs| {
+      attrs
+        .borrow()
+        .iter()
+        .map(|attr| qualified_name(&attr.name))
+        .collect()
+    })
+  }
+
+  /// The value of the first attribute with the given qualified name, if
+  /// any.
+  #[wasm_bindgen(js_name = getAttribute)]
+  pub fn get_attribute(&self, name: &str) -> Option<String> {
+    let name = self.attribute_name(name);
+    let attrs = self.attrs()?.borrow();
+    attrs
+      .iter()
+      .find(|attr| qualified_name(&attr.name) == name)
+      .map(|attr| attr.value.to_string())
+  }
+
+  /// The value of the attribute with the given namespace URL (`null` or
+  /// `""` for none) and local name, if any.
+  #[wasm_bindgen(js_name = getAttributeNS)]
+  pub fn get_attribute_ns(
+    &self,
+    namespace: Option<String>,
+    local_name: &str,
+  ) -> Option<String> {
+    let attrs = self.attrs()?.borrow();
+    let namespace = namespace.unwrap_or_default();
+    attrs
+      .iter()
+      .find(|attr| {
+        *attr.name.ns == *namespace && *attr.name.local == *local_name
+      })
+      .map(|attr| attr.value.to_string())
+  }
+
+  /// Whether the element has an attribute with the given qualified name.
+  #[wasm_bindgen(js_name = hasAttribute)]
+  pub fn has_attribute(&self, name: &str) -> bool {
+    self.get_attribute(name).is_some()
+  }
+
+  /// Whether the element has an attribute with the given namespace URL and
+  /// local name.
+  #[wasm_bindgen(js_name = hasAttributeNS)]
+  pub fn has_attribute_ns(
+    &self,
+    namespace: Option<String>,
+    local_name: &str,
+  ) -> bool {
+    self.get_attribute_ns(namespace, local_name).is_some()
+  }
+
+  /// Sets the value of the first attribute with the given qualified name,
+  /// adding an attribute without a namespace if there is none.
+  #[wasm_bindgen(js_name = setAttribute)]
+  pub fn set_attribute(&self, name: &str, value: &str) -> Result<(), JsError> {
+    Ok(self.set_attribute_value(name, value)?)
+  }
+
+  /// Sets the value of the attribute with the given namespace URL (`null`
+  /// or `""` for none) and local name, adding it with the given qualified
+  /// name if there is none.
+  #[wasm_bindgen(js_name = setAttributeNS)]
+  pub fn set_attribute_ns(
+    &self,
+    namespace: Option<String>,
+    qualified_name: &str,
+    value: &str,
+  ) -> Result<(), JsError> {
+    let name = validate_and_extract(
+      namespace.as_deref(),
+      qualified_name,
+      is_valid_attribute_local_name,
+    )?;
+    let mut attrs = self.element_attrs()?.borrow_mut();
+    match attrs
+      .iter_mut()
+      .find(|attr| attr.name.ns == name.ns && attr.name.local == name.local)
+    {
+      // like in the DOM, the prefix of an existing attribute is kept
+      Some(attr) => attr.value = value.into(),
+      None => attrs.push(Attribute {
+        name,
+        value: value.into(),
+      }),
+    }
+    Ok(())
+  }
+
+  /// Removes the first attribute with the given qualified name, returning
+  /// whether there was one.
+  #[wasm_bindgen(js_name = removeAttribute)]
+  pub fn remove_attribute(&self, name: &str) -> bool {
+    let name = self.attribute_name(name);
+    let Some(attrs) = self.attrs() else {
+      return false;
+    };
+    let mut attrs = attrs.borrow_mut();
+    let position = attrs
+      .iter()
+      .position(|attr| qualified_name(&attr.name) == name);
+    position.map(|index| attrs.remove(index)).is_some()
+  }
+
+  /// Removes the attribute with the given namespace URL and local name,
+  /// returning whether there was one.
+  #[wasm_bindgen(js_name = removeAttributeNS)]
+  pub fn remove_attribute_ns(
+    &self,
+    namespace: Option<String>,
+    local_name: &str,
+  ) -> bool {
+    let Some(attrs) = self.attrs() else {
+      return false;
+    };
+    let namespace = namespace.unwrap_or_default();
+    let mut attrs = attrs.borrow_mut();
+    let position = attrs.iter().position(|attr| {
+      *attr.name.ns == *namespace && *attr.name.local == *local_name
+    });
+    position.map(|index| attrs.remove(index)).is_some()
+  }
+
+  /// Removes the attribute with the given qualified name if it is there
+  /// (or `force` is `false`), and adds it with an empty value otherwise,
+  /// returning whether it is there afterwards.
+  #[wasm_bindgen(js_name = toggleAttribute)]
+  pub fn toggle_attribute(
+    &self,
+    name: &str,
+    force: Option<bool>,
+  ) -> Result<bool, JsError> {
+    if !is_valid_attribute_local_name(name) {
+      return Err(invalid_name(name).into());
+    }
+    self.element_attrs()?;
+    let present = self.has_attribute(name);
+    match force.unwrap_or(!present) {
+      true if !present => self.set_attribute(name, "")?,
+      false if present => {
+        self.remove_attribute(name);
+      }
+      _ => {}
+    }
+    Ok(force.unwrap_or(!present))
+  }
+}
+
+impl NodeRef {
+  /// Like [`NodeRef::set_attribute`], for use from Rust.
+  pub(crate) fn set_attribute_value(
+    &self,
+    name: &str,
+    value: &str,
+  ) -> Result<(), DawmError> {
+    if !is_valid_attribute_local_name(name) {
+      return Err(invalid_name(name));
+    }
+    let name = self.attribute_name(name);
+    let mut attrs = self.element_attrs()?.borrow_mut();
+    match attrs
+      .iter_mut()
+      .find(|attr| qualified_name(&attr.name) == name)
+    {
+      Some(attr) => attr.value = value.into(),
+      None => attrs.push(Attribute {
+        name:  QualName::new(None, ns!(), LocalName::from(name)),
+        value: value.into(),
+      }),
+    }
+    Ok(())
+  }
+
+  fn attrs(&self) -> Option<&RefCell<Vec<Attribute>>> {
+    match &self.node.data {
+      NodeData::Element { attrs, .. } => Some(attrs),
+      _ => None,
+    }
+  }
+
+  fn element_attrs(&self) -> Result<&RefCell<Vec<Attribute>>, DawmError> {
+    self.attrs().ok_or_else(|| {
+      DawmError::InvalidNodeType(format!(
+        "{} is not an element, and has no attributes",
+        self.node_name()
+      ))
+    })
+  }
+
+  // Attribute names are lowercased for HTML elements in HTML documents.
+  fn attribute_name(&self, name: &str) -> String {
+    match &self.node.data {
+      NodeData::Element { name: element, .. }
+        if element.ns == ns!(html) && self.state.is_html() =>
+      {
+        name.to_ascii_lowercase()
+      }
+      _ => name.into(),
+    }
+  }
+}
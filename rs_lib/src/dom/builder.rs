@@ -0,0 +1,168 @@
+//! Building fragments node by node, without parsing markup.
+//!
+//! A [`FragmentBuilder`] appends nodes to a fragment of its document, in
+//! the order they are written: [`FragmentBuilder::element`] opens an
+//! element that the following nodes go into, until it is closed again with
+//! [`FragmentBuilder::end`], and [`FragmentBuilder::attribute`] sets an
+//! attribute of the element that is open. Text is never parsed, so values
+//! from anywhere can be put into a document without the risk of injecting
+//! markup:
+//!
+//! ```js
+//! const list = doc.fragmentBuilder()
+//!   .element("ul").attribute("class", "results");
+//! for (const result of results) {
+//!   list.element("li").text(result.title).end();
+//! }
+//! doc.body.appendChild(list.build());
+//! ```
+//!
+//! The methods return the builder for chaining, and the builders they
+//! return all share the same fragment. Names are checked and cased like
+//! the `create*` methods of the [`Document`] do.
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use wasm_bindgen::prelude::*;
+
+use super::Document;
+use super::NodeRef;
+use crate::Appendable;
+use crate::DawmError;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::diff::container;
+
+/// Builds a fragment of a [`Document`] node by node.
+///
+/// See the [module level documentation](crate::dom::builder) for more
+/// details.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct FragmentBuilder {
+  document: Document,
+  state:    Rc<RefCell<BuilderState>>,
+}
+
+#[derive(Debug)]
+struct BuilderState {
+  fragment: Handle,
+  // the elements that are open, innermost last
+  open:     Vec<Handle>,
+}
+
+#[wasm_bindgen]
+impl Document {
+  /// Starts building a fragment of the document.
+  #[wasm_bindgen(js_name = fragmentBuilder)]
+  pub fn fragment_builder(&self) -> FragmentBuilder {
+    FragmentBuilder {
+      document: self.clone(),
+      state:    Rc::new(RefCell::new(BuilderState {
+        fragment: Node::new(NodeData::Document),
+        open:     Vec::new(),
+      })),
+    }
+  }
+}
+
+#[wasm_bindgen]
+impl FragmentBuilder {
+  /// Opens an element with the given local name, like
+  /// {@linkcode Document.createElement}.
+  pub fn element(&self, local_name: &str) -> Result<FragmentBuilder, JsError> {
+    let element = self.document.create_element(local_name)?;
+    Ok(self.open(element.node))
+  }
+
+  /// Opens an element with the given namespace URL and qualified name, like
+  /// {@linkcode Document.createElementNS}.
+  #[wasm_bindgen(js_name = elementNS)]
+  pub fn element_ns(
+    &self,
+    namespace: Option<String>,
+    qualified_name: &str,
+  ) -> Result<FragmentBuilder, JsError> {
+    let element = self.document.create_element_ns(namespace, qualified_name)?;
+    Ok(self.open(element.node))
+  }
+
+  /// Sets an attribute of the open element, like
+  /// {@linkcode NodeRef.setAttribute}.
+  pub fn attribute(
+    &self,
+    name: &str,
+    value: &str,
+  ) -> Result<FragmentBuilder, JsError> {
+    let element = self.open_element("set an attribute")?;
+    self
+      .document
+      .wrap(element)
+      .set_attribute_value(name, value)?;
+    Ok(self.clone())
+  }
+
+  /// Appends text, merging it into text that was appended right before.
+  pub fn text(&self, data: &str) -> FragmentBuilder {
+    let parent = self.parent();
+    if !parent.append_to_existing_text(data) {
+      parent.append(self.document.create_text_node(data).node);
+    }
+    self.clone()
+  }
+
+  /// Appends a comment.
+  pub fn comment(&self, data: &str) -> FragmentBuilder {
+    let comment = self.document.create_comment(data);
+    self.parent().append(comment.node);
+    self.clone()
+  }
+
+  /// Closes the open element, continuing in its parent.
+  pub fn end(&self) -> Result<FragmentBuilder, JsError> {
+    self.open_element("be closed")?;
+    self.state.borrow_mut().open.pop();
+    Ok(self.clone())
+  }
+
+  /// Closes the elements that are still open, and returns the fragment
+  /// built so far. The builder starts over with a new fragment.
+  pub fn build(&self) -> NodeRef {
+    let mut state = self.state.borrow_mut();
+    state.open.clear();
+    let fragment =
+      core::mem::replace(&mut state.fragment, Node::new(NodeData::Document));
+    self.document.wrap(fragment)
+  }
+}
+
+impl FragmentBuilder {
+  // Appends `element` and makes it the open element.
+  fn open(&self, element: Handle) -> FragmentBuilder {
+    self.parent().append(element.clone());
+    self.state.borrow_mut().open.push(element);
+    self.clone()
+  }
+
+  // Where nodes are appended: the open element (or for a `<template>`, its
+  // contents), or the fragment itself.
+  fn parent(&self) -> Handle {
+    let state = self.state.borrow();
+    match state.open.last() {
+      Some(element) => container(element),
+      None => state.fragment.clone(),
+    }
+  }
+
+  fn open_element(&self, action: &str) -> Result<Handle, DawmError> {
+    let state = self.state.borrow();
+    state.open.last().cloned().ok_or_else(|| {
+      DawmError::InvalidState(format!("no element is open to {action}"))
+    })
+  }
+}
@@ -0,0 +1,156 @@
+//! Reading and changing the classes of elements, like the DOM's
+//! `Element.classList`.
+//!
+//! The `class` attribute is read as an ordered set of tokens: split on ASCII
+//! whitespace, with later duplicates dropped. Changing the set writes it
+//! back in order, joined by single spaces, so `add` appends new tokens at
+//! the end and `remove` keeps the others where they were. Like in the DOM,
+//! an element without a `class` attribute does not get an empty one when
+//! nothing is there to be written.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use super::NodeRef;
+use crate::DawmError;
+use crate::NodeData;
+
+#[wasm_bindgen]
+impl NodeRef {
+  /// The classes of an element, in order and without duplicates.
+  #[wasm_bindgen(js_name = classList)]
+  pub fn class_list(&self) -> Vec<String> {
+    let class = self.get_attribute("class").unwrap_or_default();
+    class_tokens(&class)
+  }
+
+  /// Whether the element has the given class.
+  #[wasm_bindgen(js_name = containsClass)]
+  pub fn contains_class(&self, token: &str) -> bool {
+    self.class_list().iter().any(|class| class == token)
+  }
+
+  /// Adds the given classes that the element does not have yet, at the end.
+  #[wasm_bindgen(js_name = addClass)]
+  pub fn add_class(&self, tokens: Vec<String>) -> Result<(), JsError> {
+    tokens.iter().try_for_each(|token| validate_token(token))?;
+    self.update_classes(|classes| {
+      for token in tokens {
+        if !classes.contains(&token) {
+          classes.push(token);
+        }
+      }
+    })?;
+    Ok(())
+  }
+
+  /// Removes the given classes from the element.
+  #[wasm_bindgen(js_name = removeClass)]
+  pub fn remove_class(&self, tokens: Vec<String>) -> Result<(), JsError> {
+    tokens.iter().try_for_each(|token| validate_token(token))?;
+    self.update_classes(|classes| {
+      classes.retain(|class| !tokens.contains(class))
+    })?;
+    Ok(())
+  }
+
+  /// Removes the given class if the element has it (or `force` is `false`),
+  /// and adds it otherwise, returning whether the element has it afterwards.
+  #[wasm_bindgen(js_name = toggleClass)]
+  pub fn toggle_class(
+    &self,
+    token: &str,
+    force: Option<bool>,
+  ) -> Result<bool, JsError> {
+    validate_token(token)?;
+    let present = self.contains_class(token);
+    let wanted = force.unwrap_or(!present);
+    if wanted != present {
+      self.update_classes(|classes| match wanted {
+        true => classes.push(token.into()),
+        false => classes.retain(|class| class != token),
+      })?;
+    }
+    Ok(wanted)
+  }
+
+  /// Replaces the given class with `new_token`, returning whether the
+  /// element had it. The new class takes the place of the old one, unless
+  /// the element already has it.
+  #[wasm_bindgen(js_name = replaceClass)]
+  pub fn replace_class(
+    &self,
+    token: &str,
+    new_token: &str,
+  ) -> Result<bool, JsError> {
+    validate_token(token)?;
+    validate_token(new_token)?;
+    if !self.contains_class(token) {
+      return Ok(false);
+    }
+    self.update_classes(|classes| {
+      let mut seen = false;
+      classes.retain_mut(|class| {
+        if class == token || class == new_token {
+          let first = !seen;
+          seen = true;
+          *class = new_token.into();
+          first
+        } else {
+          true
+        }
+      });
+    })?;
+    Ok(true)
+  }
+}
+
+impl NodeRef {
+  // Applies `update` to the classes of an element, and writes them back.
+  fn update_classes(
+    &self,
+    update: impl FnOnce(&mut Vec<String>),
+  ) -> Result<(), DawmError> {
+    if !matches!(self.node.data, NodeData::Element { .. }) {
+      return Err(DawmError::InvalidNodeType(format!(
+        "{} is not an element, and has no classes",
+        self.node_name()
+      )));
+    }
+    let previous = self.get_attribute("class");
+    let mut classes = class_tokens(previous.as_deref().unwrap_or_default());
+    update(&mut classes);
+    if previous.is_some() || !classes.is_empty() {
+      self.set_attribute_value("class", &classes.join(" "))?;
+    }
+    Ok(())
+  }
+}
+
+/// Splits the value of a `class` attribute into its tokens, in order and
+/// without duplicates.
+pub fn class_tokens(value: &str) -> Vec<String> {
+  let mut tokens: Vec<String> = Vec::new();
+  for token in value.split_ascii_whitespace() {
+    if !tokens.iter().any(|seen| seen == token) {
+      tokens.push(token.into());
+    }
+  }
+  tokens
+}
+
+// Like in the DOM, a token cannot be empty or contain whitespace.
+fn validate_token(token: &str) -> Result<(), DawmError> {
+  if token.is_empty() {
+    return Err(DawmError::Syntax("a class cannot be empty".into()));
+  }
+  if token.contains(|c: char| c.is_ascii_whitespace()) {
+    return Err(DawmError::InvalidCharacter(format!(
+      "a class cannot contain whitespace: {token:?}"
+    )));
+  }
+  Ok(())
+}
@@ -0,0 +1,218 @@
+//! Creating nodes, like the DOM's `Document.create*` methods.
+//!
+//! The nodes are created without a parent, and belong to the document they
+//! were created by until they are inserted into it. Names are checked like
+//! the DOM does: element names may contain just about anything but
+//! whitespace, `/` and `>` once they start with a letter, and qualified
+//! names have to fit their namespace (only the XML namespace may go with the
+//! `xml` prefix, for example).
+
+use alloc::format;
+#[cfg(feature = "wasm")]
+use alloc::string::String;
+use core::cell::RefCell;
+
+#[cfg(feature = "wasm")]
+use html5ever::LocalName;
+#[cfg(feature = "wasm")]
+use html5ever::Namespace;
+#[cfg(feature = "wasm")]
+use html5ever::Prefix;
+use html5ever::QualName;
+use html5ever::ns;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use super::Document;
+#[cfg(feature = "wasm")]
+use super::NodeRef;
+use crate::DawmError;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl Document {
+  /// Creates an element, in the HTML namespace for HTML (and XHTML)
+  /// documents, with the given local name, lowercased in HTML documents.
+  #[wasm_bindgen(js_name = createElement)]
+  pub fn create_element(&self, local_name: &str) -> Result<NodeRef, JsError> {
+    if !is_valid_element_local_name(local_name) {
+      return Err(invalid_name(local_name).into());
+    }
+    let is_html = self.state.is_html();
+    let local_name = match is_html {
+      true => local_name.to_ascii_lowercase(),
+      false => local_name.into(),
+    };
+    let is_xhtml = self.state.content_type == "application/xhtml+xml";
+    let ns = if is_html || is_xhtml {
+      ns!(html)
+    } else {
+      ns!()
+    };
+    let name = QualName::new(None, ns, LocalName::from(local_name));
+    Ok(self.wrap(new_element(name)))
+  }
+
+  /// Creates an element with the given namespace URL (`null` or `""` for
+  /// none) and qualified name, e.g. `svg:rect`.
+  #[wasm_bindgen(js_name = createElementNS)]
+  pub fn create_element_ns(
+    &self,
+    namespace: Option<String>,
+    qualified_name: &str,
+  ) -> Result<NodeRef, JsError> {
+    let name = validate_and_extract(
+      namespace.as_deref(),
+      qualified_name,
+      is_valid_element_local_name,
+    )?;
+    Ok(self.wrap(new_element(name)))
+  }
+
+  /// Creates a text node.
+  #[wasm_bindgen(js_name = createTextNode)]
+  pub fn create_text_node(&self, data: &str) -> NodeRef {
+    self.wrap(Node::new(NodeData::Text {
+      contents: RefCell::new(data.into()),
+    }))
+  }
+
+  /// Creates a comment.
+  #[wasm_bindgen(js_name = createComment)]
+  pub fn create_comment(&self, data: &str) -> NodeRef {
+    self.wrap(Node::new(NodeData::Comment {
+      contents: data.into(),
+    }))
+  }
+
+  /// Creates a processing instruction. The data may not contain `?>`,
+  /// which would end it early.
+  #[wasm_bindgen(js_name = createProcessingInstruction)]
+  pub fn create_processing_instruction(
+    &self,
+    target: &str,
+    data: &str,
+  ) -> Result<NodeRef, JsError> {
+    if !is_valid_element_local_name(target) {
+      return Err(invalid_name(target).into());
+    }
+    if data.contains("?>") {
+      return Err(
+        DawmError::InvalidCharacter(format!(
+          "the data of a processing instruction cannot contain \"?>\": \
+           {data:?}"
+        ))
+        .into(),
+      );
+    }
+    Ok(self.wrap(Node::new(NodeData::ProcessingInstruction {
+      target:   target.into(),
+      contents: data.into(),
+    })))
+  }
+
+  /// Creates an empty document fragment.
+  #[wasm_bindgen(js_name = createDocumentFragment)]
+  pub fn create_document_fragment(&self) -> NodeRef {
+    self.wrap(Node::new(NodeData::Document))
+  }
+}
+
+/// Creates an element without attributes, along with the fragment for its
+/// contents if it is an HTML `<template>`.
+pub(crate) fn new_element(name: QualName) -> Handle {
+  let is_template = name.ns == ns!(html) && &*name.local == "template";
+  Node::new(NodeData::Element {
+    name,
+    attrs: RefCell::new(Default::default()),
+    template_contents: is_template.then(|| Node::new(NodeData::Document)),
+    mathml_annotation_xml_integration_point: false,
+  })
+}
+
+/// Checks a namespace and qualified name like the DOM does for
+/// `createElementNS` and `setAttributeNS`, returning the name they make up.
+/// The local name is checked with `is_valid_local_name`, as the rules for
+/// elements and attributes differ.
+#[cfg(feature = "wasm")]
+pub(crate) fn validate_and_extract(
+  namespace: Option<&str>,
+  qualified_name: &str,
+  is_valid_local_name: fn(&str) -> bool,
+) -> Result<QualName, DawmError> {
+  let namespace = namespace.filter(|namespace| !namespace.is_empty());
+  let (prefix, local_name) = match qualified_name.split_once(':') {
+    Some((prefix, local_name)) => (Some(prefix), local_name),
+    None => (None, qualified_name),
+  };
+  if prefix.is_some_and(|prefix| !is_valid_namespace_prefix(prefix))
+    || !is_valid_local_name(local_name)
+  {
+    return Err(invalid_name(qualified_name));
+  }
+  let namespace_error =
+    |reason: &str| DawmError::Namespace(format!("{qualified_name:?} {reason}"));
+  let is_xmlns = qualified_name == "xmlns" || prefix == Some("xmlns");
+  if prefix.is_some() && namespace.is_none() {
+    return Err(namespace_error("has a prefix but no namespace"));
+  }
+  if prefix == Some("xml") && namespace != Some(&*ns!(xml)) {
+    return Err(namespace_error("is only allowed in the XML namespace"));
+  }
+  if is_xmlns != (namespace == Some(&*ns!(xmlns))) {
+    return Err(namespace_error(match is_xmlns {
+      true => "is only allowed in the XMLNS namespace",
+      false => "is not allowed in the XMLNS namespace",
+    }));
+  }
+  Ok(QualName::new(
+    prefix.map(Prefix::from),
+    Namespace::from(namespace.unwrap_or_default()),
+    LocalName::from(local_name),
+  ))
+}
+
+/// Whether `name` is a valid local name of an element (which is what
+/// processing instruction targets are held to as well).
+#[cfg(feature = "wasm")]
+pub(crate) fn is_valid_element_local_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    None => false,
+    Some(first) if first.is_ascii_alphabetic() => !name
+      .chars()
+      .any(|c| c.is_ascii_whitespace() || matches!(c, '\0' | '/' | '>')),
+    Some(first) if matches!(first, ':' | '_') || !first.is_ascii() => chars
+      .all(|c| {
+        c.is_ascii_alphanumeric()
+          || matches!(c, '-' | '.' | ':' | '_')
+          || !c.is_ascii()
+      }),
+    Some(_) => false,
+  }
+}
+
+/// Whether `name` is a valid local name of an attribute, which is anything
+/// without whitespace, `/`, `=` and `>`.
+pub(crate) fn is_valid_attribute_local_name(name: &str) -> bool {
+  !name.is_empty()
+    && !name
+      .chars()
+      .any(|c| c.is_ascii_whitespace() || matches!(c, '\0' | '/' | '=' | '>'))
+}
+
+#[cfg(feature = "wasm")]
+fn is_valid_namespace_prefix(prefix: &str) -> bool {
+  !prefix.is_empty()
+    && !prefix
+      .chars()
+      .any(|c| c.is_ascii_whitespace() || matches!(c, '\0' | '/' | '>'))
+}
+
+pub(crate) fn invalid_name(name: &str) -> DawmError {
+  DawmError::InvalidCharacter(format!("{name:?} is not a valid name"))
+}
@@ -0,0 +1,115 @@
+//! Reading and changing the `data-*` attributes of elements, like the DOM's
+//! `HTMLElement.dataset`.
+//!
+//! Names are converted like the DOM does: `data-foo-bar` is read as the key
+//! `fooBar`, and setting the key `fooBar` sets `data-foo-bar`. Attributes
+//! with uppercase letters in their names have no key, and keys with a `-`
+//! followed by a lowercase letter cannot be set, as neither would survive
+//! the conversion back.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::ns;
+use js_sys::Object;
+use js_sys::Reflect;
+use wasm_bindgen::prelude::*;
+
+use super::NodeRef;
+use crate::DawmError;
+use crate::NodeData;
+
+#[wasm_bindgen]
+impl NodeRef {
+  /// The `data-*` attributes of an element, as an object from their keys
+  /// to their values, in the order of the attributes.
+  pub fn dataset(&self) -> Object {
+    let dataset = Object::new();
+    for (key, value) in self.dataset_entries() {
+      let _ = Reflect::set(
+        &dataset,
+        &JsValue::from_str(&key),
+        &JsValue::from_str(&value),
+      );
+    }
+    dataset
+  }
+
+  /// Sets the `data-*` attribute for the given key, e.g. `data-foo-bar` for
+  /// `fooBar`.
+  #[wasm_bindgen(js_name = setDatasetValue)]
+  pub fn set_dataset_value(
+    &self,
+    key: &str,
+    value: &str,
+  ) -> Result<(), JsError> {
+    let name = dataset_attribute_name(key)?;
+    Ok(self.set_attribute_value(&name, value)?)
+  }
+}
+
+impl NodeRef {
+  /// The keys and values of the `data-*` attributes of an element, in
+  /// order.
+  pub fn dataset_entries(&self) -> Vec<(String, String)> {
+    let NodeData::Element { attrs, .. } = &self.node.data else {
+      return Vec::new();
+    };
+    let attrs = attrs.borrow();
+    attrs
+      .iter()
+      .filter(|attr| attr.name.ns == ns!())
+      .filter_map(|attr| {
+        let key = dataset_key(&attr.name.local)?;
+        Some((key, attr.value.to_string()))
+      })
+      .collect()
+  }
+}
+
+/// The dataset key of an attribute name, e.g. `fooBar` for `data-foo-bar`,
+/// if it has one.
+pub fn dataset_key(name: &str) -> Option<String> {
+  let rest = name.strip_prefix("data-")?;
+  if rest.bytes().any(|b| b.is_ascii_uppercase()) {
+    return None;
+  }
+  let mut key = String::with_capacity(rest.len());
+  let mut chars = rest.chars().peekable();
+  while let Some(c) = chars.next() {
+    match chars.peek() {
+      Some(next) if c == '-' && next.is_ascii_lowercase() => {
+        key.push(next.to_ascii_uppercase());
+        chars.next();
+      }
+      _ => key.push(c),
+    }
+  }
+  Some(key)
+}
+
+/// The name of the attribute for a dataset key, e.g. `data-foo-bar` for
+/// `fooBar`.
+pub fn dataset_attribute_name(key: &str) -> Result<String, DawmError> {
+  let bytes = key.as_bytes();
+  if bytes
+    .windows(2)
+    .any(|pair| pair[0] == b'-' && pair[1].is_ascii_lowercase())
+  {
+    return Err(DawmError::Syntax(format!(
+      "a dataset key cannot contain a \"-\" followed by a lowercase letter: \
+       {key:?}"
+    )));
+  }
+  let mut name = String::from("data-");
+  for c in key.chars() {
+    if c.is_ascii_uppercase() {
+      name.push('-');
+      name.push(c.to_ascii_lowercase());
+    } else {
+      name.push(c);
+    }
+  }
+  Ok(name)
+}
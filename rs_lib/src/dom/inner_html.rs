@@ -0,0 +1,176 @@
+//! Replacing the children of elements with parsed markup, like setting the
+//! DOM's `innerHTML`.
+//!
+//! In HTML documents, the markup is parsed with the HTML fragment parsing
+//! algorithm, in the context of the element itself: its name and namespace
+//! decide how the markup is tokenized and where it ends up, so that e.g.
+//! `<td>` cells are kept inside of a `<tr>`, text inside of a `<textarea>` is
+//! not parsed as markup, and elements inside of an `<svg>` end up in the SVG
+//! namespace. In XML documents, the markup is parsed as XML, with the
+//! namespaces that are in scope at the element.
+
+#[cfg(feature = "xml")]
+use alloc::collections::BTreeSet;
+use alloc::format;
+#[cfg(feature = "xml")]
+use alloc::string::String;
+#[cfg(feature = "xml")]
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use html5ever::ParseOpts;
+#[cfg(feature = "xml")]
+use html5ever::ns;
+use html5ever::tokenizer::TokenizerOpts;
+use html5ever::tree_builder::TreeBuilderOpts;
+use wasm_bindgen::prelude::*;
+
+use super::NodeRef;
+#[cfg(feature = "xml")]
+use super::parent;
+use crate::Appendable;
+use crate::DawmError;
+use crate::Handle;
+use crate::NodeData;
+use crate::RcDom;
+use crate::diff::container;
+use crate::feed_in_chunks;
+
+#[wasm_bindgen]
+impl NodeRef {
+  /// Replaces the children of an element (or for a `<template>`, its
+  /// contents) with the nodes parsed from `html`, in the context of the
+  /// element.
+  #[wasm_bindgen(js_name = setInnerHTML)]
+  pub fn set_inner_html(&self, html: &str) -> Result<(), JsError> {
+    if !matches!(self.node.data, NodeData::Element { .. }) {
+      return Err(
+        DawmError::InvalidNodeType(format!(
+          "{} is not an element",
+          self.node_name()
+        ))
+        .into(),
+      );
+    }
+    let nodes = match self.state.is_html() {
+      true => self.parse_html_in_context(html)?,
+      false => self.parse_xml_in_context(html),
+    };
+    let target = container(&self.node);
+    for child in target.children.take() {
+      child.parent.set(None);
+    }
+    for node in nodes {
+      node.parent.set(None);
+      target.append(node);
+    }
+    Ok(())
+  }
+}
+
+impl NodeRef {
+  fn parse_html_in_context(
+    &self,
+    input: &str,
+  ) -> Result<Vec<Handle>, DawmError> {
+    let NodeData::Element { name, attrs, .. } = &self.node.data else {
+      return Ok(Vec::new());
+    };
+    let options = &self.state.options;
+    let mut tree_builder: TreeBuilderOpts = options.clone().into();
+    tree_builder.quirks_mode = self.state.dom.quirks_mode.get();
+    let opts = ParseOpts {
+      tree_builder,
+      tokenizer: TokenizerOpts {
+        exact_errors: options.exact_errors,
+        ..Default::default()
+      },
+    };
+    let parser = html5ever::parse_fragment(
+      RcDom::default(),
+      opts,
+      name.clone(),
+      attrs.borrow().clone(),
+      false,
+    );
+    let dom = feed_in_chunks(parser, input, false)?;
+    // the nodes are parsed into an `<html>` element standing in for the
+    // context element
+    let root = dom.document.children.borrow().first().cloned();
+    Ok(root.map(|root| root.children.take()).unwrap_or_default())
+  }
+
+  #[cfg(feature = "xml")]
+  fn parse_xml_in_context(&self, input: &str) -> Vec<Handle> {
+    // the markup is parsed inside of a root element declaring the
+    // namespaces in scope, the innermost binding of each prefix winning.
+    // The parser does not keep `xmlns` attributes, so the bindings are also
+    // taken from the prefixed names of the element and its ancestors.
+    let mut declarations = String::new();
+    let mut declared = BTreeSet::new();
+    let mut default_namespace = None;
+    let mut declare = |prefix: &str, namespace: &str| {
+      if declared.insert(String::from(prefix)) {
+        let value = escape_attribute(namespace);
+        declarations.push_str(&format!(" xmlns:{prefix}=\"{value}\""));
+      }
+    };
+    let mut node = Some(self.node.clone());
+    while let Some(current) = node {
+      if let NodeData::Element { name, attrs, .. } = &current.data {
+        let attrs = attrs.borrow();
+        for attr in attrs.iter() {
+          match &attr.name.prefix {
+            _ if attr.name.ns == ns!(xmlns) && attr.name.prefix.is_none() => {
+              default_namespace.get_or_insert_with(|| attr.value.to_string());
+            }
+            Some(_) if attr.name.ns == ns!(xmlns) => {
+              declare(&attr.name.local, &attr.value);
+            }
+            Some(prefix) if &**prefix != "xml" => {
+              declare(prefix, &attr.name.ns);
+            }
+            _ => {}
+          }
+        }
+        match &name.prefix {
+          // an unprefixed element is in the default namespace
+          None => {
+            default_namespace.get_or_insert_with(|| name.ns.to_string());
+          }
+          Some(prefix) => declare(prefix, &name.ns),
+        }
+      }
+      node = parent(&current);
+    }
+    if let Some(namespace) = default_namespace {
+      let value = escape_attribute(&namespace);
+      declarations.push_str(&format!(" xmlns=\"{value}\""));
+    }
+
+    let dom =
+      crate::parse_xml_once(&format!("<root{declarations}>{input}</root>"));
+    let root = dom
+      .document
+      .children
+      .borrow()
+      .iter()
+      .find(|child| matches!(child.data, NodeData::Element { .. }))
+      .cloned();
+    root.map(|root| root.children.take()).unwrap_or_default()
+  }
+
+  // Without the XML parser, the markup is parsed as HTML.
+  #[cfg(not(feature = "xml"))]
+  fn parse_xml_in_context(&self, input: &str) -> Vec<Handle> {
+    self.parse_html_in_context(input).unwrap_or_default()
+  }
+}
+
+#[cfg(feature = "xml")]
+fn escape_attribute(value: &str) -> String {
+  value
+    .replace('&', "&amp;")
+    .replace('"', "&quot;")
+    .replace('<', "&lt;")
+}
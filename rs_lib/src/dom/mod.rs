@@ -0,0 +1,626 @@
+//! A live document object model over the parsed tree.
+//!
+//! The `parse_*` functions turn a document into a [`WireDoc`] in one go,
+//! which JS then has to rebuild a tree from. A [`Document`] instead keeps
+//! the parsed tree alive inside of the module, and hands out [`NodeRef`]s
+//! to its nodes, which are navigated (and, through the methods added in the
+//! other modules below, changed) in place. Nothing is serialized until it is
+//! asked for, with [`Document::to_html`] or [`Document::to_wire`].
+//!
+//! The methods are named after their counterparts in the DOM and behave
+//! like them, as far as the tree of an [`RcDom`] goes: the contents of a
+//! `<template>` are a separate fragment, reached through
+//! [`NodeRef::content`], rather than children of the template.
+//!
+//! A [`NodeRef`] keeps its document alive, so nodes can be used after the
+//! [`Document`] they came from has been freed on the JS side. Both have to
+//! be freed (or garbage collected) for the tree to be dropped.
+//!
+//! ## Workers
+//!
+//! The tree is reference-counted, so a [`Document`] and its [`NodeRef`]s are
+//! neither `Send` nor `Sync`: they belong to the WASM instance of the thread
+//! (or worker) that parsed them, and cannot be posted to another one. To
+//! hand a document over, send its markup from [`Document::to_html`] and parse
+//! it again on the receiving side, or send the output of
+//! [`Document::to_wire`], which is plain data, to read it there.
+//!
+//! The classes are only there with the `wasm` feature. Without it, the tree
+//! of an [`RcDom`] is worked with directly, and only the helpers that the
+//! other modules share are left here.
+//!
+//! [`WireDoc`]: crate::WireDoc
+
+use alloc::rc::Rc;
+#[cfg(feature = "wasm")]
+use alloc::string::String;
+#[cfg(feature = "wasm")]
+use alloc::string::ToString;
+#[cfg(feature = "wasm")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "wasm")]
+use html5ever::ns;
+#[cfg(feature = "wasm")]
+use js_sys::Object;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use crate::DawmError;
+use crate::Handle;
+#[cfg(feature = "wasm")]
+use crate::NodeData;
+#[cfg(feature = "wasm")]
+use crate::ParseOptions;
+#[cfg(feature = "wasm")]
+use crate::QuirksMode;
+#[cfg(feature = "wasm")]
+use crate::RcDom;
+#[cfg(feature = "wasm")]
+use crate::SelectorList;
+#[cfg(feature = "wasm")]
+use crate::WireNodeType;
+#[cfg(feature = "wasm")]
+use crate::diff::qualified_name;
+#[cfg(feature = "wasm")]
+use crate::error;
+#[cfg(feature = "wasm")]
+use crate::extract::attribute;
+#[cfg(feature = "wasm")]
+use crate::extract::descendants;
+#[cfg(feature = "wasm")]
+use crate::options_from_js;
+#[cfg(feature = "wasm")]
+use crate::panic_hook;
+#[cfg(feature = "wasm")]
+use crate::parse_by_mime;
+#[cfg(feature = "wasm")]
+use crate::parse_html_document;
+#[cfg(feature = "wasm")]
+use crate::rcdom::clone_node;
+#[cfg(feature = "wasm")]
+use crate::rcdom::inner_html;
+#[cfg(feature = "wasm")]
+use crate::rcdom::is_equal_node;
+#[cfg(feature = "wasm")]
+use crate::rcdom::outer_html;
+#[cfg(feature = "wasm")]
+use crate::selector;
+#[cfg(feature = "wasm")]
+use crate::serialize_dom;
+#[cfg(feature = "wasm")]
+use crate::to_js;
+
+#[cfg(feature = "wasm")]
+pub mod attributes;
+#[cfg(feature = "wasm")]
+pub mod builder;
+#[cfg(feature = "wasm")]
+pub mod class_list;
+pub mod create;
+#[cfg(feature = "wasm")]
+pub mod dataset;
+#[cfg(feature = "wasm")]
+pub mod inner_html;
+pub mod mutation;
+#[cfg(feature = "wasm")]
+pub mod template;
+
+// What the nodes of a document share: the tree along with how it was
+// parsed, which the wire output needs.
+#[cfg(feature = "wasm")]
+#[derive(Debug)]
+struct DocumentState {
+  dom:          RcDom,
+  content_type: &'static str,
+  options:      ParseOptions,
+}
+
+#[cfg(feature = "wasm")]
+impl DocumentState {
+  // Whether the document is an HTML document, rather than an XML one.
+  fn is_html(&self) -> bool {
+    self.content_type == "text/html"
+  }
+}
+
+/// A parsed document, kept alive for its nodes to be worked with in place.
+/// It is confined to the thread that parsed it (see
+/// [Workers](crate::dom#workers)).
+///
+/// See the [module level documentation](crate::dom) for more details.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct Document {
+  state: Rc<DocumentState>,
+}
+
+/// A handle to a node of a [`Document`].
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct NodeRef {
+  node:  Handle,
+  state: Rc<DocumentState>,
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl Document {
+  /// Parses the input as an HTML document, with the same options as
+  /// {@linkcode parse_html}.
+  #[wasm_bindgen(constructor)]
+  pub fn new(
+    input: &str,
+    options: Option<Object>,
+  ) -> Result<Document, JsError> {
+    panic_hook::install();
+    panic_hook::reset();
+
+    let options = parse_options(options)?;
+    let dom = parse_html_document(input, &options)?;
+    Ok(Self::from_dom(dom, "text/html", options))
+  }
+
+  /// Parses the input as a document of the given MIME type, like
+  /// {@linkcode parse_doc}.
+  pub fn parse(
+    input: &str,
+    mime: &str,
+    options: Option<Object>,
+  ) -> Result<Document, JsError> {
+    panic_hook::install();
+    panic_hook::reset();
+
+    let options = parse_options(options)?;
+    let (dom, content_type) = parse_by_mime(input, mime, &options)?;
+    Ok(Self::from_dom(dom, content_type, options))
+  }
+
+  /// The MIME type of the document, e.g. `text/html`.
+  #[wasm_bindgen(js_name = contentType)]
+  pub fn content_type(&self) -> String {
+    self.state.content_type.to_string()
+  }
+
+  /// The quirks mode of the document: `"no-quirks"`, `"limited-quirks"` or
+  /// `"quirks"`.
+  #[wasm_bindgen(getter, js_name = quirksMode)]
+  pub fn quirks_mode(&self) -> String {
+    QuirksMode::from(self.state.dom.quirks_mode.get()).to_string()
+  }
+
+  /// Overrides the quirks mode of the document, which is then what
+  /// {@linkcode Document.toWire} reports, and what selectors match ids and
+  /// class names in (ignoring ASCII case in quirks mode). The tree is left
+  /// as it was built. Takes the same names as the `quirksMode` option, and
+  /// throws for any other string.
+  #[wasm_bindgen(setter, js_name = quirksMode)]
+  pub fn set_quirks_mode(&self, mode: &str) -> Result<(), JsError> {
+    let mode = QuirksMode::try_from_str(mode).map_err(DawmError::QuirksMode)?;
+    self.state.dom.quirks_mode.set(mode.into());
+    Ok(())
+  }
+
+  /// The document node itself.
+  pub fn node(&self) -> NodeRef {
+    self.wrap(self.state.dom.document.clone())
+  }
+
+  /// The root element of the document, if it has one.
+  #[wasm_bindgen(js_name = documentElement)]
+  pub fn document_element(&self) -> Option<NodeRef> {
+    self.document_element_handle().map(|node| self.wrap(node))
+  }
+
+  /// The doctype of the document, if it has one.
+  pub fn doctype(&self) -> Option<NodeRef> {
+    let document = &self.state.dom.document;
+    let children = document.children.borrow();
+    children
+      .iter()
+      .find(|child| matches!(child.data, NodeData::Doctype { .. }))
+      .map(|node| self.wrap(node.clone()))
+  }
+
+  /// The `<head>` element of the document, if it has one.
+  pub fn head(&self) -> Option<NodeRef> {
+    self.html_child("head")
+  }
+
+  /// The `<body>` element of the document, if it has one. Unlike in the
+  /// DOM, a top level `<frameset>` is not considered to be the body.
+  pub fn body(&self) -> Option<NodeRef> {
+    self.html_child("body")
+  }
+
+  /// The first element in tree order whose `id` is `id`, if any.
+  #[wasm_bindgen(js_name = getElementById)]
+  pub fn get_element_by_id(&self, id: &str) -> Option<NodeRef> {
+    if id.is_empty() {
+      return None;
+    }
+    descendants(&self.state.dom.document)
+      .find(|node| attribute(node, "id").is_some_and(|value| &*value == id))
+      .map(|node| self.wrap(node))
+  }
+
+  /// Removes every element matched by `selector` (along with its
+  /// descendants), returning how many were removed. Matches inside of an
+  /// element that is removed are not counted.
+  #[wasm_bindgen(js_name = removeAll)]
+  pub fn remove_all(&self, selector: &str) -> Result<u32, JsError> {
+    let selectors = SelectorList::parse(selector)?
+      .with_quirks_mode(self.state.dom.quirks_mode.get().into());
+    let removed = selector::remove_all(&self.state.dom.document, &selectors);
+    Ok(removed as u32)
+  }
+
+  /// Serializes the document as HTML.
+  #[wasm_bindgen(js_name = toHtml)]
+  pub fn to_html(&self) -> String {
+    inner_html(&self.state.dom.document)
+  }
+
+  /// Serializes the document in its current state like
+  /// {@linkcode parse_doc} does, using the options it was parsed with.
+  #[wasm_bindgen(js_name = toWire)]
+  pub fn to_wire(&self) -> Result<JsValue, JsError> {
+    let state = &self.state;
+    let mut dom = state.dom.clone();
+    // the wire output may drop nodes, which must not change the live tree
+    dom.document = clone_node(&state.dom.document, true);
+    let parsed = serialize_dom(dom, state.content_type, &state.options)?;
+    Ok(to_js(&parsed)?)
+  }
+}
+
+#[cfg(feature = "wasm")]
+impl Document {
+  /// Wraps a parsed tree, along with how it was parsed.
+  pub fn from_dom(
+    dom: RcDom,
+    content_type: &'static str,
+    options: ParseOptions,
+  ) -> Self {
+    Self {
+      state: Rc::new(DocumentState {
+        dom,
+        content_type,
+        options,
+      }),
+    }
+  }
+
+  /// The root of the tree.
+  pub fn handle(&self) -> &Handle {
+    &self.state.dom.document
+  }
+
+  fn wrap(&self, node: Handle) -> NodeRef {
+    NodeRef {
+      node,
+      state: self.state.clone(),
+    }
+  }
+
+  fn document_element_handle(&self) -> Option<Handle> {
+    let document = &self.state.dom.document;
+    let children = document.children.borrow();
+    children
+      .iter()
+      .find(|child| matches!(child.data, NodeData::Element { .. }))
+      .cloned()
+  }
+
+  // The first child of the root `<html>` element with the given name.
+  fn html_child(&self, name: &str) -> Option<NodeRef> {
+    let root = self.document_element_handle()?;
+    if !is_html_element(&root, "html") {
+      return None;
+    }
+    let children = root.children.borrow();
+    children
+      .iter()
+      .find(|child| is_html_element(child, name))
+      .map(|node| self.wrap(node.clone()))
+  }
+}
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl NodeRef {
+  /// The type of the node, as in the DOM's `Node.nodeType`.
+  #[wasm_bindgen(js_name = nodeType)]
+  pub fn node_type(&self) -> WireNodeType {
+    match &self.node.data {
+      NodeData::Document if self.is_document() => WireNodeType::Document,
+      NodeData::Document => WireNodeType::DocumentFragment,
+      NodeData::Doctype { .. } => WireNodeType::DocumentType,
+      NodeData::Text { .. } => WireNodeType::Text,
+      NodeData::Comment { .. } => WireNodeType::Comment,
+      NodeData::ProcessingInstruction { .. } => {
+        WireNodeType::ProcessingInstruction
+      }
+      NodeData::Element { .. } => WireNodeType::Element,
+    }
+  }
+
+  /// The name of the node, as in the DOM: the qualified name of an element
+  /// (uppercased for HTML elements in HTML documents), the name of a
+  /// doctype, the target of a processing instruction, or `#text`,
+  /// `#comment`, `#document` and `#document-fragment`.
+  #[wasm_bindgen(js_name = nodeName)]
+  pub fn node_name(&self) -> String {
+    match &self.node.data {
+      NodeData::Document if self.is_document() => "#document".to_string(),
+      NodeData::Document => "#document-fragment".to_string(),
+      NodeData::Doctype { name, .. } => name.to_string(),
+      NodeData::Text { .. } => "#text".to_string(),
+      NodeData::Comment { .. } => "#comment".to_string(),
+      NodeData::ProcessingInstruction { target, .. } => target.to_string(),
+      NodeData::Element { name, .. } => {
+        let qualified = qualified_name(name);
+        match name.ns == ns!(html) && self.state.is_html() {
+          true => qualified.to_ascii_uppercase(),
+          false => qualified,
+        }
+      }
+    }
+  }
+
+  /// The local name of an element, or `null` for other nodes.
+  #[wasm_bindgen(js_name = localName)]
+  pub fn local_name(&self) -> Option<String> {
+    match &self.node.data {
+      NodeData::Element { name, .. } => Some(name.local.to_string()),
+      _ => None,
+    }
+  }
+
+  /// The namespace URL of an element, or `null` for other nodes and for
+  /// elements in no namespace.
+  #[wasm_bindgen(js_name = namespaceURI)]
+  pub fn namespace_uri(&self) -> Option<String> {
+    match &self.node.data {
+      NodeData::Element { name, .. } if !name.ns.is_empty() => {
+        Some(name.ns.to_string())
+      }
+      _ => None,
+    }
+  }
+
+  /// The namespace prefix of an element, if it has one.
+  pub fn prefix(&self) -> Option<String> {
+    match &self.node.data {
+      NodeData::Element { name, .. } => {
+        name.prefix.as_ref().map(|prefix| prefix.to_string())
+      }
+      _ => None,
+    }
+  }
+
+  /// The text of a text node or comment, or the data of a processing
+  /// instruction, and `null` for other nodes.
+  #[wasm_bindgen(js_name = nodeValue)]
+  pub fn node_value(&self) -> Option<String> {
+    match &self.node.data {
+      NodeData::Text { contents } => Some(contents.borrow().to_string()),
+      NodeData::Comment { contents } => Some(contents.to_string()),
+      NodeData::ProcessingInstruction { contents, .. } => {
+        Some(contents.to_string())
+      }
+      _ => None,
+    }
+  }
+
+  /// The text of the node, as in the DOM: its value for text nodes,
+  /// comments and processing instructions, the concatenated text of all of
+  /// its descendant text nodes for elements and fragments, and `null` for
+  /// documents and doctypes.
+  #[wasm_bindgen(js_name = textContent)]
+  pub fn text_content(&self) -> Option<String> {
+    match &self.node.data {
+      NodeData::Doctype { .. } => None,
+      NodeData::Document if self.is_document() => None,
+      NodeData::Document | NodeData::Element { .. } => {
+        Some(crate::extract::text_content(&self.node))
+      }
+      _ => self.node_value(),
+    }
+  }
+
+  /// The parent of the node, if it has one.
+  #[wasm_bindgen(js_name = parentNode)]
+  pub fn parent_node(&self) -> Option<NodeRef> {
+    parent(&self.node).map(|node| self.wrap(node))
+  }
+
+  /// The parent of the node, if it is an element.
+  #[wasm_bindgen(js_name = parentElement)]
+  pub fn parent_element(&self) -> Option<NodeRef> {
+    parent(&self.node)
+      .filter(|node| matches!(node.data, NodeData::Element { .. }))
+      .map(|node| self.wrap(node))
+  }
+
+  /// The children of the node.
+  #[wasm_bindgen(js_name = childNodes)]
+  pub fn child_nodes(&self) -> Vec<NodeRef> {
+    let children = self.node.children.borrow();
+    children
+      .iter()
+      .map(|node| self.wrap(node.clone()))
+      .collect()
+  }
+
+  /// The children of the node which are elements.
+  pub fn children(&self) -> Vec<NodeRef> {
+    let children = self.node.children.borrow();
+    children
+      .iter()
+      .filter(|node| matches!(node.data, NodeData::Element { .. }))
+      .map(|node| self.wrap(node.clone()))
+      .collect()
+  }
+
+  /// Whether the node has any children.
+  #[wasm_bindgen(js_name = hasChildNodes)]
+  pub fn has_child_nodes(&self) -> bool {
+    !self.node.children.borrow().is_empty()
+  }
+
+  /// The first child of the node, if it has any.
+  #[wasm_bindgen(js_name = firstChild)]
+  pub fn first_child(&self) -> Option<NodeRef> {
+    let first = self.node.children.borrow().first().cloned();
+    first.map(|node| self.wrap(node))
+  }
+
+  /// The last child of the node, if it has any.
+  #[wasm_bindgen(js_name = lastChild)]
+  pub fn last_child(&self) -> Option<NodeRef> {
+    let last = self.node.children.borrow().last().cloned();
+    last.map(|node| self.wrap(node))
+  }
+
+  /// The sibling before the node, if any.
+  #[wasm_bindgen(js_name = previousSibling)]
+  pub fn previous_sibling(&self) -> Option<NodeRef> {
+    let (parent, index) = parent_and_index(&self.node)?;
+    let sibling = parent.children.borrow().get(index.checked_sub(1)?).cloned();
+    sibling.map(|node| self.wrap(node))
+  }
+
+  /// The sibling after the node, if any.
+  #[wasm_bindgen(js_name = nextSibling)]
+  pub fn next_sibling(&self) -> Option<NodeRef> {
+    let (parent, index) = parent_and_index(&self.node)?;
+    let sibling = parent.children.borrow().get(index + 1).cloned();
+    sibling.map(|node| self.wrap(node))
+  }
+
+  /// The fragment holding the contents of a `<template>`, or `null` for
+  /// other nodes.
+  pub fn content(&self) -> Option<NodeRef> {
+    match &self.node.data {
+      NodeData::Element {
+        template_contents: Some(contents),
+        ..
+      } => Some(self.wrap(contents.clone())),
+      _ => None,
+    }
+  }
+
+  /// The document the node belongs to.
+  #[wasm_bindgen(js_name = ownerDocument)]
+  pub fn owner_document(&self) -> Document {
+    Document {
+      state: self.state.clone(),
+    }
+  }
+
+  /// Whether `other` is a handle to the same node.
+  #[wasm_bindgen(js_name = isSameNode)]
+  pub fn is_same_node(&self, other: &NodeRef) -> bool {
+    Rc::ptr_eq(&self.node, &other.node)
+  }
+
+  /// Whether `other` is equal to the node, like the DOM's `isEqualNode`
+  /// (see {@linkcode nodes_equal}).
+  #[wasm_bindgen(js_name = isEqualNode)]
+  pub fn is_equal_node(&self, other: &NodeRef) -> bool {
+    is_equal_node(&self.node, &other.node)
+  }
+
+  /// Whether `other` is the node itself or one of its descendants.
+  pub fn contains(&self, other: &NodeRef) -> bool {
+    let mut node = Some(other.node.clone());
+    while let Some(current) = node {
+      if Rc::ptr_eq(&current, &self.node) {
+        return true;
+      }
+      node = parent(&current);
+    }
+    false
+  }
+
+  /// Serializes the node, including itself, as HTML.
+  #[wasm_bindgen(js_name = outerHTML)]
+  pub fn outer_html(&self) -> String {
+    outer_html(&self.node)
+  }
+
+  /// Serializes the children of the node (or for a `<template>`, its
+  /// contents) as HTML.
+  #[wasm_bindgen(js_name = innerHTML)]
+  pub fn inner_html(&self) -> String {
+    match &self.node.data {
+      NodeData::Element {
+        template_contents: Some(contents),
+        ..
+      } => inner_html(contents),
+      _ => inner_html(&self.node),
+    }
+  }
+}
+
+#[cfg(feature = "wasm")]
+impl NodeRef {
+  /// The node this is a handle to.
+  pub fn handle(&self) -> &Handle {
+    &self.node
+  }
+
+  fn wrap(&self, node: Handle) -> NodeRef {
+    NodeRef {
+      node,
+      state: self.state.clone(),
+    }
+  }
+
+  // Whether this is the document node itself, rather than the contents of
+  // a template.
+  fn is_document(&self) -> bool {
+    Rc::ptr_eq(&self.node, &self.state.dom.document)
+  }
+}
+
+#[cfg(feature = "wasm")]
+fn parse_options(options: Option<Object>) -> error::Result<ParseOptions> {
+  match &options {
+    Some(options) => options_from_js(options),
+    None => Ok(ParseOptions::default()),
+  }
+}
+
+#[cfg(feature = "wasm")]
+fn is_html_element(node: &Handle, name: &str) -> bool {
+  matches!(
+    &node.data,
+    NodeData::Element { name: qualified, .. }
+      if qualified.ns == ns!(html) && &*qualified.local == name
+  )
+}
+
+// The parent of `node`. Unlike `ParentNode::get_parent_and_index`, this
+// does not panic for a node whose parent has been dropped, which happens
+// when JS holds on to a node of a subtree that nothing else refers to.
+pub(crate) fn parent(node: &Handle) -> Option<Handle> {
+  let weak = node.parent.take()?;
+  let parent = weak.upgrade();
+  node.parent.set(Some(weak));
+  parent
+}
+
+fn parent_and_index(node: &Handle) -> Option<(Handle, usize)> {
+  let parent = parent(node)?;
+  let index = parent
+    .children
+    .borrow()
+    .iter()
+    .position(|child| Rc::ptr_eq(child, node))?;
+  Some((parent, index))
+}
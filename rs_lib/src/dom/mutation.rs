@@ -0,0 +1,392 @@
+//! Changing the tree, like the DOM's `Node.appendChild` and friends.
+//!
+//! Insertions are checked like in the DOM before anything is changed: only
+//! documents, fragments and elements can have children, a node cannot be
+//! inserted into itself or one of its descendants (which includes the
+//! contents of the templates below it), and a document can have at most one
+//! doctype and one element, in that order, and no text. A node that is
+//! inserted is removed from where it was first, and inserting a fragment
+//! inserts its children instead, leaving it empty. Wrapping a node and
+//! unwrapping an element are checked the same way.
+
+#[cfg(feature = "wasm")]
+use alloc::format;
+#[cfg(feature = "wasm")]
+use alloc::rc::Rc;
+#[cfg(feature = "wasm")]
+use alloc::string::String;
+#[cfg(feature = "wasm")]
+use alloc::vec;
+#[cfg(feature = "wasm")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "wasm")]
+use wasm_bindgen::prelude::*;
+
+#[cfg(feature = "wasm")]
+use super::NodeRef;
+#[cfg(feature = "wasm")]
+use super::parent;
+use super::parent_and_index;
+#[cfg(feature = "wasm")]
+use crate::DawmError;
+use crate::Handle;
+#[cfg(feature = "wasm")]
+use crate::NodeData;
+#[cfg(feature = "wasm")]
+use crate::diff::container;
+
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+impl NodeRef {
+  /// Appends `node` to the children of this node, returning it.
+  #[wasm_bindgen(js_name = appendChild)]
+  pub fn append_child(&self, node: &NodeRef) -> Result<NodeRef, JsError> {
+    insert(self, node, None)?;
+    Ok(node.clone())
+  }
+
+  /// Inserts `node` before `child`, a child of this node, returning it.
+  /// Unlike in the DOM, `child` cannot be `null`; use
+  /// {@linkcode NodeRef.appendChild} to insert at the end instead.
+  #[wasm_bindgen(js_name = insertBefore)]
+  pub fn insert_before(
+    &self,
+    node: &NodeRef,
+    child: &NodeRef,
+  ) -> Result<NodeRef, JsError> {
+    self.check_child(child)?;
+    insert(self, node, Some(child.node.clone()))?;
+    Ok(node.clone())
+  }
+
+  /// Replaces `child`, a child of this node, with `node`, returning
+  /// `child`.
+  #[wasm_bindgen(js_name = replaceChild)]
+  pub fn replace_child(
+    &self,
+    node: &NodeRef,
+    child: &NodeRef,
+  ) -> Result<NodeRef, JsError> {
+    self.check_child(child)?;
+    if Rc::ptr_eq(&node.node, &child.node) {
+      return Ok(child.clone());
+    }
+    let mut reference = next_sibling(&child.node);
+    if reference
+      .as_ref()
+      .is_some_and(|reference| Rc::ptr_eq(reference, &node.node))
+    {
+      reference = next_sibling(&node.node);
+    }
+    check_insertion(self, node, reference.as_ref(), Some(&child.node))?;
+    detach(&child.node);
+    insert_unchecked(self, node, reference);
+    Ok(child.clone())
+  }
+
+  /// Removes `child`, a child of this node, returning it.
+  #[wasm_bindgen(js_name = removeChild)]
+  pub fn remove_child(&self, child: &NodeRef) -> Result<NodeRef, JsError> {
+    self.check_child(child)?;
+    detach(&child.node);
+    Ok(child.clone())
+  }
+
+  /// Removes the node from its parent, if it has one.
+  pub fn remove(&self) {
+    detach(&self.node);
+  }
+
+  /// Puts `wrapper`, an element, in the place of the node, and appends the
+  /// node to it, returning `wrapper`. E.g. wrapping a text node in a new
+  /// `<p>` element.
+  #[wasm_bindgen(js_name = wrap)]
+  pub fn wrap_in(&self, wrapper: &NodeRef) -> Result<NodeRef, JsError> {
+    let Some(parent) = parent(&self.node) else {
+      return Err(
+        DawmError::HierarchyRequest(format!(
+          "{} has no parent to be wrapped in",
+          self.node_name()
+        ))
+        .into(),
+      );
+    };
+    if !matches!(wrapper.node.data, NodeData::Element { .. }) {
+      return Err(
+        DawmError::InvalidNodeType(format!(
+          "{} is not an element, and cannot wrap a node",
+          wrapper.node_name()
+        ))
+        .into(),
+      );
+    }
+    if Rc::ptr_eq(&self.node, &wrapper.node) {
+      return Err(
+        DawmError::HierarchyRequest(format!(
+          "{} cannot be wrapped in itself",
+          self.node_name()
+        ))
+        .into(),
+      );
+    }
+    let parent = self.wrap(parent);
+    check_insertion(&parent, wrapper, Some(&self.node), Some(&self.node))?;
+    check_insertion(wrapper, self, None, None)?;
+    insert_unchecked(&parent, wrapper, Some(self.node.clone()));
+    insert_unchecked(wrapper, self, None);
+    Ok(wrapper.clone())
+  }
+
+  /// Replaces an element with its children (or for a `<template>`, its
+  /// contents), returning them.
+  pub fn unwrap(&self) -> Result<Vec<NodeRef>, JsError> {
+    if !matches!(self.node.data, NodeData::Element { .. }) {
+      return Err(
+        DawmError::InvalidNodeType(format!(
+          "{} is not an element, and cannot be unwrapped",
+          self.node_name()
+        ))
+        .into(),
+      );
+    }
+    let Some((parent, index)) = parent_and_index(&self.node) else {
+      return Err(
+        DawmError::HierarchyRequest(format!(
+          "{} has no parent to be unwrapped into",
+          self.node_name()
+        ))
+        .into(),
+      );
+    };
+    let source = container(&self.node);
+    if Rc::ptr_eq(&parent, &self.state.dom.document) {
+      let mut children = parent.children.borrow().clone();
+      let moved = source.children.borrow().clone();
+      children.splice(index..=index, moved);
+      check_document_children(&children)?;
+    }
+    let moved = source.children.take();
+    {
+      let mut children = parent.children.borrow_mut();
+      for child in &moved {
+        child.parent.set(Some(Rc::downgrade(&parent)));
+      }
+      children.splice(index..=index, moved.iter().cloned());
+    }
+    self.node.parent.set(None);
+    Ok(moved.into_iter().map(|node| self.wrap(node)).collect())
+  }
+}
+
+#[cfg(feature = "wasm")]
+impl NodeRef {
+  fn check_child(&self, child: &NodeRef) -> Result<(), DawmError> {
+    match parent(&child.node) {
+      Some(parent) if Rc::ptr_eq(&parent, &self.node) => Ok(()),
+      _ => Err(DawmError::NotFound(format!(
+        "{} is not a child of {}",
+        child.node_name(),
+        self.node_name()
+      ))),
+    }
+  }
+}
+
+// Inserts `node` into `parent` before `reference`, a child of `parent` (or
+// at the end).
+#[cfg(feature = "wasm")]
+fn insert(
+  parent: &NodeRef,
+  node: &NodeRef,
+  reference: Option<Handle>,
+) -> Result<(), DawmError> {
+  // inserting a node before itself inserts it before its next sibling
+  let reference = match reference {
+    Some(reference) if Rc::ptr_eq(&reference, &node.node) => {
+      next_sibling(&node.node)
+    }
+    reference => reference,
+  };
+  check_insertion(parent, node, reference.as_ref(), None)?;
+  insert_unchecked(parent, node, reference);
+  Ok(())
+}
+
+#[cfg(feature = "wasm")]
+fn insert_unchecked(
+  parent: &NodeRef,
+  node: &NodeRef,
+  reference: Option<Handle>,
+) {
+  let nodes = match node.node.data {
+    NodeData::Document => {
+      let children = node.node.children.take();
+      for child in &children {
+        child.parent.set(None);
+      }
+      children
+    }
+    _ => {
+      detach(&node.node);
+      vec![node.node.clone()]
+    }
+  };
+  let mut children = parent.node.children.borrow_mut();
+  let index = reference
+    .and_then(|reference| {
+      children
+        .iter()
+        .position(|child| Rc::ptr_eq(child, &reference))
+    })
+    .unwrap_or(children.len());
+  for (offset, child) in nodes.into_iter().enumerate() {
+    child.parent.set(Some(Rc::downgrade(&parent.node)));
+    children.insert(index + offset, child);
+  }
+}
+
+// Checks that `node` can be inserted into `parent` before `reference` (or
+// at the end), in place of `replaced` if given.
+#[cfg(feature = "wasm")]
+fn check_insertion(
+  parent: &NodeRef,
+  node: &NodeRef,
+  reference: Option<&Handle>,
+  replaced: Option<&Handle>,
+) -> Result<(), DawmError> {
+  let error = |reason: String| Err(DawmError::HierarchyRequest(reason));
+  if !matches!(
+    parent.node.data,
+    NodeData::Document | NodeData::Element { .. }
+  ) {
+    return error(format!("{} cannot have children", parent.node_name()));
+  }
+  if node.is_document() {
+    return error("a document cannot be inserted".into());
+  }
+  if is_inclusive_ancestor(&node.node, &parent.node, &parent.state.dom.document)
+  {
+    return error(format!(
+      "{} cannot be inserted into itself or one of its descendants",
+      node.node_name()
+    ));
+  }
+  if !parent.is_document() {
+    return match node.node.data {
+      NodeData::Doctype { .. } => {
+        error("a doctype can only be a child of a document".into())
+      }
+      _ => Ok(()),
+    };
+  }
+
+  // lay out the children of the document as they would end up
+  let is_moved = |child: &Handle| {
+    Rc::ptr_eq(child, &node.node)
+      || replaced.is_some_and(|replaced| Rc::ptr_eq(child, replaced))
+  };
+  let mut children: Vec<Handle> = parent
+    .node
+    .children
+    .borrow()
+    .iter()
+    .filter(|child| !is_moved(child))
+    .cloned()
+    .collect();
+  let inserted = match node.node.data {
+    NodeData::Document => node.node.children.borrow().clone(),
+    _ => vec![node.node.clone()],
+  };
+  let index = reference
+    .and_then(|reference| {
+      children
+        .iter()
+        .position(|child| Rc::ptr_eq(child, reference))
+    })
+    .unwrap_or(children.len());
+  children.splice(index..index, inserted);
+  check_document_children(&children)
+}
+
+// Checks that `children` make sense as the children of a document: at most
+// one doctype and one element, in that order, and no text.
+#[cfg(feature = "wasm")]
+fn check_document_children(children: &[Handle]) -> Result<(), DawmError> {
+  let error = |reason: &str| Err(DawmError::HierarchyRequest(reason.into()));
+  let (mut doctype, mut element) = (false, false);
+  for child in children {
+    match child.data {
+      NodeData::Text { .. } => {
+        return error("a document cannot have text children");
+      }
+      NodeData::Doctype { .. } if doctype || element => {
+        return error(
+          "a document can only have one doctype, before its element",
+        );
+      }
+      NodeData::Element { .. } if element => {
+        return error("a document can only have one element child");
+      }
+      NodeData::Doctype { .. } => doctype = true,
+      NodeData::Element { .. } => element = true,
+      _ => {}
+    }
+  }
+  Ok(())
+}
+
+// Whether `ancestor` is `node` or one of its ancestors, counting templates
+// as the parents of their contents, with `document` being the root of the
+// tree of the document.
+#[cfg(feature = "wasm")]
+fn is_inclusive_ancestor(
+  ancestor: &Handle,
+  node: &Handle,
+  document: &Handle,
+) -> bool {
+  let mut current = node.clone();
+  loop {
+    if Rc::ptr_eq(&current, ancestor) {
+      return true;
+    }
+    match parent(&current) {
+      Some(parent) => current = parent,
+      None => break,
+    }
+  }
+  // the root of a tree other than the document may be the contents of a
+  // template below `ancestor`
+  !Rc::ptr_eq(&current, document)
+    && matches!(current.data, NodeData::Document)
+    && holds_contents(ancestor, &current)
+}
+
+// Whether `contents` are the contents of `node` or of a template below it.
+#[cfg(feature = "wasm")]
+fn holds_contents(node: &Handle, contents: &Handle) -> bool {
+  if let NodeData::Element {
+    template_contents: Some(own),
+    ..
+  } = &node.data
+    && (Rc::ptr_eq(own, contents) || holds_contents(own, contents))
+  {
+    return true;
+  }
+  let children = node.children.borrow();
+  children.iter().any(|child| holds_contents(child, contents))
+}
+
+#[cfg(feature = "wasm")]
+fn next_sibling(node: &Handle) -> Option<Handle> {
+  let (parent, index) = parent_and_index(node)?;
+  parent.children.borrow().get(index + 1).cloned()
+}
+
+// Removes `node` from its parent, if it has one.
+pub(crate) fn detach(node: &Handle) {
+  if let Some((parent, index)) = parent_and_index(node) {
+    parent.children.borrow_mut().remove(index);
+  }
+  node.parent.set(None);
+}
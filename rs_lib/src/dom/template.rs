@@ -0,0 +1,286 @@
+//! Instantiating templates, filling in their placeholders.
+//!
+//! The contents of a `<template>` are cloned, and two kinds of placeholders
+//! in the copy are filled in with the values given by name:
+//!
+//! - the children of an element with a `data-slot="name"` attribute are
+//!   replaced with the value, and the attribute is removed. Without a value,
+//!   the children are kept as the default content of the slot;
+//! - `{{name}}` markers in text and attribute values are replaced with the
+//!   value, or with nothing if there is no value for the name. Whitespace
+//!   around the name is ignored.
+//!
+//! A value is either text, which is never parsed, or a node, of which a
+//! copy is inserted (for a fragment, copies of its children), so one value
+//! can fill any number of placeholders and instances. In attribute values,
+//! a node stands for its text content. Values are not searched for
+//! placeholders themselves, and neither are the contents of the templates
+//! inside of the template, which are left to be instantiated on their own.
+//!
+//! ```js
+//! const card = doc.getElementById("card")
+//!   .templateInstance()
+//!   .text("title", post.title)
+//!   .text("url", post.url)
+//!   .node("body", bodyFragment)
+//!   .build();
+//! ```
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::ns;
+use wasm_bindgen::prelude::*;
+
+use super::NodeRef;
+use super::parent_and_index;
+use crate::Appendable;
+use crate::DawmError;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::extract::text_content;
+use crate::rcdom::clone_node;
+
+/// A value filling the placeholders of a template.
+#[derive(Debug, Clone)]
+pub enum SlotValue {
+  /// Text, inserted as a text node.
+  Text(String),
+  /// A node, of which a copy is inserted (for a fragment, copies of its
+  /// children).
+  Node(Handle),
+}
+
+/// An instance of a `<template>` being filled in, created with
+/// {@linkcode NodeRef.templateInstance}.
+///
+/// See the [module level documentation](crate::dom::template) for more
+/// details.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct TemplateInstance {
+  template: NodeRef,
+  values:   Rc<RefCell<BTreeMap<String, SlotValue>>>,
+}
+
+#[wasm_bindgen]
+impl NodeRef {
+  /// Starts filling in an instance of a `<template>`.
+  #[wasm_bindgen(js_name = templateInstance)]
+  pub fn template_instance(&self) -> Result<TemplateInstance, JsError> {
+    if self.content().is_none() {
+      return Err(
+        DawmError::InvalidNodeType(format!(
+          "{} is not a template",
+          self.node_name()
+        ))
+        .into(),
+      );
+    }
+    Ok(TemplateInstance {
+      template: self.clone(),
+      values:   Rc::default(),
+    })
+  }
+}
+
+#[wasm_bindgen]
+impl TemplateInstance {
+  /// Fills the placeholders called `name` with text.
+  pub fn text(&self, name: &str, value: &str) -> TemplateInstance {
+    self.set(name, SlotValue::Text(value.into()))
+  }
+
+  /// Fills the placeholders called `name` with copies of `node`.
+  pub fn node(&self, name: &str, node: &NodeRef) -> TemplateInstance {
+    self.set(name, SlotValue::Node(node.node.clone()))
+  }
+
+  /// Returns a fragment with a filled in copy of the contents of the
+  /// template. Every call makes a new copy, with the values given so far.
+  pub fn build(&self) -> NodeRef {
+    let contents = self.template.content().map(|contents| contents.node);
+    let fragment = match contents {
+      Some(contents) => instantiate(&contents, &self.values.borrow()),
+      None => Node::new(NodeData::Document),
+    };
+    self.template.wrap(fragment)
+  }
+}
+
+impl TemplateInstance {
+  fn set(&self, name: &str, value: SlotValue) -> TemplateInstance {
+    self.values.borrow_mut().insert(name.into(), value);
+    self.clone()
+  }
+}
+
+/// Copies `contents` (the contents of a template), filling in the
+/// placeholders with `values`, and returns the copy.
+pub fn instantiate(
+  contents: &Handle,
+  values: &BTreeMap<String, SlotValue>,
+) -> Handle {
+  let fragment = clone_node(contents, true);
+  // the placeholders are found before any are filled in, so that the
+  // values themselves are not searched
+  let mut nodes = Vec::new();
+  let mut stack: Vec<Handle> =
+    fragment.children.borrow().iter().rev().cloned().collect();
+  while let Some(node) = stack.pop() {
+    stack.extend(node.children.borrow().iter().rev().cloned());
+    nodes.push(node);
+  }
+  for node in nodes {
+    match &node.data {
+      NodeData::Element { attrs, .. } => {
+        let mut attrs = attrs.borrow_mut();
+        for attr in attrs.iter_mut() {
+          if attr.value.contains("{{") {
+            let value = fill_text(&attr.value, values);
+            attr.value = value.into();
+          }
+        }
+        let slot = attrs.iter().position(|attr| {
+          attr.name.ns == ns!() && &*attr.name.local == "data-slot"
+        });
+        if let Some(index) = slot {
+          let name = attrs.remove(index).value;
+          if let Some(value) = values.get(name.trim()) {
+            for child in node.children.take() {
+              child.parent.set(None);
+            }
+            for child in copies(value) {
+              node.append(child);
+            }
+          }
+        }
+      }
+      NodeData::Text { contents } if contents.borrow().contains("{{") => {
+        let text = contents.borrow().clone();
+        fill_text_node(&node, &text, values);
+      }
+      _ => {}
+    }
+  }
+  fragment
+}
+
+// Replaces a text node with the text and nodes its markers stand for.
+fn fill_text_node(
+  node: &Handle,
+  text: &str,
+  values: &BTreeMap<String, SlotValue>,
+) {
+  let Some((parent, index)) = parent_and_index(node) else {
+    return;
+  };
+  let mut replacement = Vec::new();
+  let mut pending = String::new();
+  for piece in pieces(text) {
+    match piece {
+      Piece::Text(text) => pending.push_str(text),
+      Piece::Marker(name) => match values.get(name) {
+        Some(SlotValue::Text(value)) => pending.push_str(value),
+        Some(value) => {
+          if !pending.is_empty() {
+            replacement.push(text_node(&core::mem::take(&mut pending)));
+          }
+          replacement.extend(copies(value));
+        }
+        None => {}
+      },
+    }
+  }
+  if !pending.is_empty() {
+    replacement.push(text_node(&pending));
+  }
+  node.parent.set(None);
+  for child in &replacement {
+    child.parent.set(Some(Rc::downgrade(&parent)));
+  }
+  parent
+    .children
+    .borrow_mut()
+    .splice(index..=index, replacement);
+}
+
+// Replaces the markers in `text` with the text of their values.
+fn fill_text(text: &str, values: &BTreeMap<String, SlotValue>) -> String {
+  let mut filled = String::new();
+  for piece in pieces(text) {
+    match piece {
+      Piece::Text(text) => filled.push_str(text),
+      Piece::Marker(name) => match values.get(name) {
+        Some(SlotValue::Text(value)) => filled.push_str(value),
+        Some(SlotValue::Node(node)) => match &node.data {
+          NodeData::Text { contents } => filled.push_str(&contents.borrow()),
+          _ => filled.push_str(&text_content(node)),
+        },
+        None => {}
+      },
+    }
+  }
+  filled
+}
+
+enum Piece<'a> {
+  Text(&'a str),
+  Marker(&'a str),
+}
+
+// Splits `text` into plain text and the (trimmed) names of its `{{name}}`
+// markers. Braces that do not make up a marker are plain text.
+fn pieces(mut text: &str) -> Vec<Piece<'_>> {
+  let mut pieces = Vec::new();
+  while let Some(start) = text.find("{{") {
+    let rest = &text[start + 2..];
+    let marker = rest.find("}}").and_then(|end| {
+      let name = rest[..end].trim();
+      let valid = !name.is_empty() && !name.contains(['{', '}']);
+      valid.then_some((name, start + 2 + end + 2))
+    });
+    match marker {
+      Some((name, end)) => {
+        pieces.push(Piece::Text(&text[..start]));
+        pieces.push(Piece::Marker(name));
+        text = &text[end..];
+      }
+      None => {
+        // the first brace is plain text, the second may start a marker
+        pieces.push(Piece::Text(&text[..start + 1]));
+        text = &text[start + 1..];
+      }
+    }
+  }
+  pieces.push(Piece::Text(text));
+  pieces
+}
+
+// Copies of the nodes a value stands for.
+fn copies(value: &SlotValue) -> Vec<Handle> {
+  match value {
+    SlotValue::Text(text) => vec![text_node(text)],
+    SlotValue::Node(node) => match node.data {
+      NodeData::Document => node
+        .children
+        .borrow()
+        .iter()
+        .map(|child| clone_node(child, true))
+        .collect(),
+      _ => vec![clone_node(node, true)],
+    },
+  }
+}
+
+fn text_node(text: &str) -> Handle {
+  Node::new(NodeData::Text {
+    contents: RefCell::new(text.into()),
+  })
+}
@@ -0,0 +1,439 @@
+//! Preparing HTML for email clients.
+//!
+//! Email clients render a small and uneven subset of HTML and CSS: Gmail
+//! drops linked stylesheets and (in some of its apps) `<style>` elements,
+//! Outlook renders with Word and ignores the sectioning elements of HTML5,
+//! and none of them run scripts or load frames. [`prepare_email`] rewrites a
+//! document into what they do render, and reports what is left that they
+//! do not:
+//!
+//! - the rules of `<style>` elements, and of the linked stylesheets whose
+//!   contents are given in `stylesheets` (by their `href` as written), are
+//!   inlined into the `style` attributes of the elements they match, following
+//!   the cascade: `!important` first, then the declarations of the `style`
+//!   attribute itself, then specificity, then order. The rules that cannot be
+//!   inlined, like `@media` queries and the ones with pseudo-classes such as
+//!   `:hover`, stay in a `<style>` element;
+//! - linked stylesheets whose contents are not given, and `@import` rules, are
+//!   removed;
+//! - the sectioning elements (`<article>`, `<section>`, `<header>` and the
+//!   like) become `<div>`s;
+//! - scripts, frames and plugins (`<script>`, `<iframe>`, `<object>`, ...) are
+//!   removed;
+//! - event handlers, and the attributes clients strip or choke on (like
+//!   `srcset`, `loading` or `contenteditable`), are stripped.
+//!
+//! The report lists the removed elements and stylesheets, along with the
+//! elements (`<video>`, `<svg>`, `<form>`, ...) and CSS properties
+//! (`position`, `display: flex`, `var()`, ...) that the major clients do not
+//! render, which are kept as they are.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::QualName;
+use html5ever::local_name;
+use html5ever::ns;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Appendable;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::RcDom;
+use crate::SelectorList;
+use crate::dom::create::new_element;
+use crate::dom::mutation::detach;
+use crate::emit::is_event_handler;
+use crate::extract::attribute;
+use crate::extract::descendants;
+use crate::extract::text_content;
+use crate::rcdom::number_nodes;
+use crate::rcdom::rename_element;
+use crate::rcdom::replace_node;
+use crate::style::Declaration;
+use crate::style::StyleItem;
+use crate::style::parse_declarations;
+use crate::style::parse_stylesheet;
+
+// The elements that are removed, along with their contents.
+const REMOVED: &[&str] = &[
+  "applet", "base", "embed", "frame", "frameset", "iframe", "object", "script",
+];
+
+// The elements that are kept, but which the major clients do not render.
+const UNSUPPORTED: &[&str] = &[
+  "audio", "button", "canvas", "dialog", "form", "input", "math", "select",
+  "svg", "textarea", "video",
+];
+
+// The elements that become `<div>`s.
+const SECTIONING: &[&str] = &[
+  "article",
+  "aside",
+  "figcaption",
+  "figure",
+  "footer",
+  "header",
+  "hgroup",
+  "main",
+  "nav",
+  "search",
+  "section",
+];
+
+// The attributes that are stripped, besides event handlers.
+const STRIPPED: &[&str] = &[
+  "autofocus",
+  "contenteditable",
+  "crossorigin",
+  "decoding",
+  "draggable",
+  "fetchpriority",
+  "integrity",
+  "loading",
+  "nonce",
+  "sizes",
+  "srcset",
+];
+
+// The CSS properties that the major clients do not render.
+const UNSUPPORTED_PROPERTIES: &[&str] =
+  &["animation", "position", "transform", "transition"];
+
+/// How [`prepare_email`] treats stylesheets.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EmailOptions {
+  /// The contents of linked stylesheets, by their `href` as written.
+  pub stylesheets:   BTreeMap<String, String>,
+  /// Whether to inline the rules of stylesheets into `style` attributes.
+  pub inline_styles: bool,
+}
+
+impl Default for EmailOptions {
+  fn default() -> Self {
+    Self {
+      stylesheets:   BTreeMap::new(),
+      inline_styles: true,
+    }
+  }
+}
+
+/// Something that [`prepare_email`] removed, or that email clients do not
+/// render.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailIssue {
+  /// The id of the element, as in the wire output of the document.
+  pub id:     u32,
+  pub tag:    String,
+  /// `"removedElement"`, `"linkedStylesheet"` (removed),
+  /// `"unsupportedElement"` or `"unsupportedCss"`.
+  pub kind:   &'static str,
+  /// The `href` of a stylesheet, or the CSS declaration or rule, if any.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub detail: Option<String>,
+}
+
+/// What [`prepare_email`] changed, and what it could not.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailReport {
+  /// How many elements had styles inlined into them.
+  pub inlined:             u32,
+  /// How many sectioning elements became `<div>`s.
+  pub converted:           u32,
+  /// How many attributes were stripped.
+  pub stripped_attributes: u32,
+  /// The removed and unsupported constructs, in document order.
+  pub issues:              Vec<EmailIssue>,
+}
+
+// What decides which declaration of a property wins, in order: whether it
+// is `!important`, whether it is in a `style` attribute, the specificity of
+// its selector, and its position in the document.
+type Precedence = (bool, bool, (u32, u32, u32), usize);
+
+// A style rule to inline.
+struct Rule {
+  selectors:    SelectorList,
+  declarations: Vec<Declaration>,
+}
+
+/// Rewrites the document of `dom` for email clients, as described in the
+/// [module level documentation](crate::email).
+pub fn prepare_email(dom: &RcDom, options: &EmailOptions) -> EmailReport {
+  let document = &dom.document;
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let id = |node: &Handle| ids.get(&Rc::as_ptr(node)).copied();
+  let mut report = EmailReport::default();
+  let mut issues = Vec::new();
+  let mut issue = |node: &Handle, kind, detail: Option<String>| {
+    issues.push(EmailIssue {
+      id: id(node).unwrap_or(u32::MAX),
+      tag: local(node).to_string(),
+      kind,
+      detail,
+    });
+  };
+
+  let mut rules = Vec::new();
+  let sheets: Vec<Handle> = descendants(document)
+    .filter(|node| is_html(node, "style") || is_stylesheet_link(node))
+    .collect();
+  for sheet in sheets {
+    let css = match is_html(&sheet, "style") {
+      true => text_content(&sheet),
+      false => {
+        let href = attribute(&sheet, "href").unwrap_or_default();
+        let Some(css) = options.stylesheets.get(&*href) else {
+          issue(&sheet, "linkedStylesheet", Some(href.to_string()));
+          detach(&sheet);
+          continue;
+        };
+        css.clone()
+      }
+    };
+    let media = attribute(&sheet, "media");
+    let inlines = options.inline_styles
+      && media.as_deref().is_none_or(|media| {
+        let media = media.trim();
+        media.is_empty()
+          || ["all", "screen"].contains(&&*media.to_ascii_lowercase())
+      });
+    let mut kept = Vec::new();
+    for item in parse_stylesheet(&css) {
+      match item {
+        StyleItem::AtRule(rule)
+          if starts_with_ignore_case(&rule, "@import") =>
+        {
+          issue(&sheet, "unsupportedCss", Some(rule));
+        }
+        StyleItem::Rule {
+          selectors,
+          declarations,
+        } if inlines => match SelectorList::parse(&selectors) {
+          Ok(list) => rules.push(Rule {
+            selectors:    list.with_quirks_mode(dom.quirks_mode.get().into()),
+            declarations: parse_declarations(&declarations),
+          }),
+          Err(_) => kept.push(format!("{selectors} {{ {declarations} }}")),
+        },
+        StyleItem::Rule {
+          selectors,
+          declarations,
+        } => kept.push(format!("{selectors} {{ {declarations} }}")),
+        StyleItem::AtRule(rule) => kept.push(rule),
+      }
+    }
+    if kept.is_empty() {
+      detach(&sheet);
+      continue;
+    }
+    // what is left of a linked stylesheet goes into a `<style>` in its place
+    let style = match is_html(&sheet, "style") {
+      true => sheet.clone(),
+      false => {
+        let style =
+          new_element(QualName::new(None, ns!(html), local_name!("style")));
+        if let NodeData::Element { attrs, .. } = &style.data
+          && let Some(media) = media
+        {
+          attrs.borrow_mut().push(Attribute {
+            name:  QualName::new(None, ns!(), local_name!("media")),
+            value: media,
+          });
+        }
+        replace_node(&sheet, style.clone());
+        style
+      }
+    };
+    for child in style.children.take() {
+      child.parent.set(None);
+    }
+    style.append(Node::new(NodeData::Text {
+      contents: RefCell::new(kept.join("\n").into()),
+    }));
+  }
+
+  let body = descendants(document).find(|node| is_html(node, "body"));
+  if let Some(body) = body
+    && !rules.is_empty()
+  {
+    for element in core::iter::once(body.clone()).chain(descendants(&body)) {
+      report.inlined += u32::from(inline_styles(&element, &rules));
+    }
+  }
+
+  let mut stack = vec![document.clone()];
+  while let Some(node) = stack.pop() {
+    let NodeData::Element { name, attrs, .. } = &node.data else {
+      stack.extend(node.children.borrow().iter().rev().cloned());
+      continue;
+    };
+    let tag = &*name.local;
+    let is_html_element = name.ns == ns!(html);
+    if is_html_element && REMOVED.contains(&tag) {
+      issue(&node, "removedElement", None);
+      detach(&node);
+      continue;
+    }
+    if UNSUPPORTED.contains(&tag) {
+      issue(&node, "unsupportedElement", None);
+    }
+    {
+      let mut attrs = attrs.borrow_mut();
+      let before = attrs.len();
+      attrs.retain(|attr| {
+        let name = &*attr.name.local;
+        attr.name.ns != ns!()
+          || !(is_event_handler(name) || STRIPPED.contains(&name))
+      });
+      report.stripped_attributes += (before - attrs.len()) as u32;
+    }
+    if let Some(style) = attribute(&node, "style") {
+      for declaration in parse_declarations(&style) {
+        if is_unsupported(&declaration) {
+          let detail = format!("{}: {}", declaration.name, declaration.value);
+          issue(&node, "unsupportedCss", Some(detail));
+        }
+      }
+    }
+    let node = match is_html_element && SECTIONING.contains(&tag) {
+      true => {
+        report.converted += 1;
+        rename(&node, "div")
+      }
+      false => node.clone(),
+    };
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  report.issues = issues;
+  report
+}
+
+// Inlines the declarations of the `rules` matching `element` into its
+// `style` attribute, returning whether any did.
+fn inline_styles(element: &Handle, rules: &[Rule]) -> bool {
+  let NodeData::Element { attrs, .. } = &element.data else {
+    return false;
+  };
+  // the winning declaration of each property, with what made it win
+  let mut cascade: Vec<(Declaration, Precedence)> = Vec::new();
+  let mut order = 0;
+  let mut apply = |declaration: Declaration, inline, specificity| {
+    let important = is_important(&declaration.value);
+    let key = (important, inline, specificity, order);
+    order += 1;
+    match cascade.iter_mut().find(|(d, _)| d.name == declaration.name) {
+      Some(winner) if winner.1 <= key => *winner = (declaration, key),
+      Some(_) => {}
+      None => cascade.push((declaration, key)),
+    }
+  };
+  let mut matched = false;
+  for rule in rules {
+    if let Some(specificity) = rule.selectors.specificity_for(element) {
+      matched = true;
+      for declaration in &rule.declarations {
+        apply(declaration.clone(), false, specificity);
+      }
+    }
+  }
+  if !matched {
+    return false;
+  }
+  if let Some(style) = attribute(element, "style") {
+    for declaration in parse_declarations(&style) {
+      apply(declaration, true, (0, 0, 0));
+    }
+  }
+
+  let style: Vec<String> = cascade
+    .iter()
+    .map(|(declaration, _)| {
+      format!("{}: {}", declaration.name, declaration.value)
+    })
+    .collect();
+  let style = style.join("; ");
+  let mut attrs = attrs.borrow_mut();
+  match attrs.iter_mut().find(|attr| {
+    attr.name.ns == ns!() && attr.name.local == local_name!("style")
+  }) {
+    Some(attr) => attr.value = style.into(),
+    None => attrs.push(Attribute {
+      name:  QualName::new(None, ns!(), local_name!("style")),
+      value: style.into(),
+    }),
+  }
+  true
+}
+
+// Whether the major clients do not render a declaration.
+fn is_unsupported(declaration: &Declaration) -> bool {
+  let name = &*declaration.name;
+  let value = declaration.value.to_ascii_lowercase();
+  UNSUPPORTED_PROPERTIES.iter().any(|property| {
+    name == *property || name.starts_with(&format!("{property}-"))
+  }) || (name == "display"
+    && ["flex", "grid", "inline-flex", "inline-grid"]
+      .contains(&value.trim_end_matches("!important").trim()))
+    || value.contains("var(")
+}
+
+fn is_important(value: &str) -> bool {
+  value
+    .trim_end()
+    .strip_suffix("important")
+    .is_some_and(|rest| rest.trim_end().ends_with('!'))
+}
+
+// Replaces an HTML element with one named `local`, with the same
+// attributes and children, and returns it.
+fn rename(element: &Handle, local: &str) -> Handle {
+  rename_element(
+    element,
+    QualName::new(None, ns!(html), LocalName::from(local)),
+  )
+}
+
+fn local(node: &Handle) -> &str {
+  match &node.data {
+    NodeData::Element { name, .. } => &name.local,
+    _ => "",
+  }
+}
+
+fn is_html(node: &Handle, local: &str) -> bool {
+  matches!(&node.data, NodeData::Element { name, .. }
+    if name.ns == ns!(html) && &*name.local == local)
+}
+
+// Whether `node` is a `<link rel=stylesheet>` (but not an alternate one).
+fn is_stylesheet_link(node: &Handle) -> bool {
+  is_html(node, "link")
+    && attribute(node, "rel").is_some_and(|rel| {
+      let mut tokens = rel.split_ascii_whitespace();
+      tokens
+        .clone()
+        .any(|token| token.eq_ignore_ascii_case("stylesheet"))
+        && !tokens.any(|token| token.eq_ignore_ascii_case("alternate"))
+    })
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+  text
+    .get(..prefix.len())
+    .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
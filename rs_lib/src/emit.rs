@@ -0,0 +1,419 @@
+//! Pluggable output formats for parsed documents.
+//!
+//! Converting an [`RcDom`](crate::RcDom) into some output format always
+//! involves the same depth-first traversal, and only differs in what is done
+//! with each node. The [`WireEmitter`] trait captures the per-node part of
+//! that work, while [`emit`] owns the traversal itself, so new output formats
+//! (parse5-shaped trees, hast, binary or struct-of-arrays encodings, ...) are
+//! implemented as emitters instead of forked copies of the traversal.
+//!
+//! The [`WireDocEmitter`] is the emitter behind the default flat [`WireDoc`]
+//! output returned by the `parse_*` functions.
+//!
+//! [`WireDoc`]: crate::WireDoc
+
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::QualName;
+
+use crate::DawmError;
+use crate::Handle;
+use crate::Interner;
+use crate::NodeData;
+use crate::ParseOptions;
+use crate::WireAttr;
+use crate::WireNode;
+use crate::WireNodeType;
+
+/// Receives the nodes of a document, in tree order, as [`emit`] walks it.
+///
+/// Every `visit_*` method is handed the id of the parent node (if any) and
+/// returns the id of the node it emitted, which is then used as the parent of
+/// that node's children. Once a child has been visited, it is linked to its
+/// parent through [`WireEmitter::append_child`].
+pub trait WireEmitter {
+  /// The identifier of an emitted node.
+  type Id: Copy;
+
+  /// Emits a `Document` node.
+  fn visit_document(&mut self, parent: Option<Self::Id>) -> Self::Id;
+
+  /// Emits the `DocumentFragment` node holding a template's contents.
+  fn visit_document_fragment(&mut self) -> Self::Id;
+
+  /// Emits a `DocumentType` node.
+  fn visit_doctype(
+    &mut self,
+    parent: Option<Self::Id>,
+    name: &str,
+    public_id: &str,
+    system_id: &str,
+  ) -> Self::Id;
+
+  /// Emits an `Element` node with its attributes.
+  fn visit_element(
+    &mut self,
+    parent: Option<Self::Id>,
+    name: &QualName,
+    attrs: &[Attribute],
+  ) -> Self::Id;
+
+  /// Emits a `Text` node.
+  fn visit_text(&mut self, parent: Option<Self::Id>, text: &str) -> Self::Id;
+
+  /// Emits a `Comment` node.
+  fn visit_comment(&mut self, parent: Option<Self::Id>, text: &str)
+  -> Self::Id;
+
+  /// Emits a `ProcessingInstruction` node.
+  fn visit_processing_instruction(
+    &mut self,
+    parent: Option<Self::Id>,
+    target: &str,
+    data: &str,
+  ) -> Self::Id;
+
+  /// Links `child` as the next child of `parent`, after `previous` (or as
+  /// the first child, if `previous` is `None`).
+  fn append_child(
+    &mut self,
+    parent: Self::Id,
+    previous: Option<Self::Id>,
+    child: Self::Id,
+  ) {
+    let _ = (parent, previous, child);
+  }
+
+  /// Links a `<template>` element to the fragment holding its contents.
+  fn set_template_content(&mut self, template: Self::Id, fragment: Self::Id) {
+    let _ = (template, fragment);
+  }
+
+  /// Called once all of the children of `node` have been emitted.
+  fn leave(&mut self, node: Self::Id) {
+    let _ = node;
+  }
+}
+
+/// Walks the tree rooted at `handle` depth-first, feeding every node to the
+/// `emitter`. Returns the id of the emitted root.
+pub fn emit<E: WireEmitter>(handle: &Handle, emitter: &mut E) -> E::Id {
+  emit_node(handle, None, emitter)
+}
+
+fn emit_node<E: WireEmitter>(
+  handle: &Handle,
+  parent: Option<E::Id>,
+  emitter: &mut E,
+) -> E::Id {
+  let id = match &handle.data {
+    NodeData::Document => emitter.visit_document(parent),
+    NodeData::Doctype {
+      name,
+      public_id,
+      system_id,
+    } => emitter.visit_doctype(parent, name, public_id, system_id),
+    NodeData::Text { contents } => {
+      emitter.visit_text(parent, &contents.borrow())
+    }
+    NodeData::Comment { contents } => emitter.visit_comment(parent, contents),
+    NodeData::ProcessingInstruction { target, contents } => {
+      emitter.visit_processing_instruction(parent, target, contents)
+    }
+    NodeData::Element { name, attrs, .. } => {
+      emitter.visit_element(parent, name, &attrs.borrow())
+    }
+  };
+
+  let mut previous = None;
+  for child in handle.children.borrow().iter() {
+    let child = emit_node(child, Some(id), emitter);
+    emitter.append_child(id, previous, child);
+    previous = Some(child);
+  }
+
+  // template contents live in a separate fragment, linked from the template
+  if let NodeData::Element {
+    template_contents: Some(contents),
+    ..
+  } = &handle.data
+  {
+    let fragment = emitter.visit_document_fragment();
+    let mut previous = None;
+    for child in contents.children.borrow().iter() {
+      let child = emit_node(child, Some(fragment), emitter);
+      emitter.append_child(fragment, previous, child);
+      previous = Some(child);
+    }
+    emitter.leave(fragment);
+    emitter.set_template_content(id, fragment);
+  }
+
+  emitter.leave(id);
+  id
+}
+
+/// Emits the flat node list of a [`WireDoc`](crate::WireDoc), interning all
+/// of its strings along the way.
+pub struct WireDocEmitter<'a> {
+  pub(crate) options:  &'a ParseOptions,
+  pub(crate) interner: Interner,
+  pub(crate) nodes:    Vec<WireNode>,
+  /// The first error thrown by the predicate of the `attributeFilter`, which
+  /// fails the whole output.
+  pub(crate) error:    Option<DawmError>,
+}
+
+impl<'a> WireDocEmitter<'a> {
+  pub fn new(options: &'a ParseOptions) -> Self {
+    Self::with_interner(options, options.interner_profile.interner())
+  }
+
+  /// Creates an emitter which interns its strings into an existing table,
+  /// such as the one of a [`ParseSession`](crate::ParseSession).
+  pub fn with_interner(options: &'a ParseOptions, interner: Interner) -> Self {
+    Self {
+      options,
+      interner,
+      nodes: Vec::new(),
+      error: None,
+    }
+  }
+
+  fn push(
+    &mut self,
+    node_type: WireNodeType,
+    parent: Option<u32>,
+    name: u32,
+  ) -> u32 {
+    let id = self.nodes.len() as u32;
+    self.nodes.push(WireNode {
+      id,
+      node_type,
+      name: Some(name),
+      parent,
+      ..Default::default()
+    });
+    id
+  }
+
+  // Interns the local name of an element or attribute, in lowercase with the
+  // `foldNameCase` option.
+  fn intern_name(&mut self, name: &LocalName) -> u32 {
+    match self.options.fold_name_case {
+      true => self.interner.intern_atom_folded(name),
+      false => self.interner.intern_atom(name),
+    }
+  }
+
+  fn node_mut(&mut self, id: u32) -> &mut WireNode {
+    &mut self.nodes[id as usize]
+  }
+}
+
+impl WireEmitter for WireDocEmitter<'_> {
+  type Id = u32;
+
+  fn visit_document(&mut self, _parent: Option<u32>) -> u32 {
+    let name = self.interner.intern_static("#document");
+    self.push(WireNodeType::Document, None, name)
+  }
+
+  fn visit_document_fragment(&mut self) -> u32 {
+    let name = self.interner.intern_static("#document-fragment");
+    self.push(WireNodeType::DocumentFragment, None, name)
+  }
+
+  fn visit_doctype(
+    &mut self,
+    parent: Option<u32>,
+    name: &str,
+    public_id: &str,
+    system_id: &str,
+  ) -> u32 {
+    let interner = &mut self.interner;
+    let name_idx = interner.intern(name);
+    // the value is the doctype as it is serialized, without the `<!DOCTYPE`
+    // and `>`, e.g. `html PUBLIC "-//W3C//DTD HTML 4.01//EN" "..."`
+    let mut val = name.to_string();
+    match (public_id.is_empty(), system_id.is_empty()) {
+      (false, true) => val.push_str(&format!(r#" PUBLIC "{public_id}""#)),
+      (false, false) => {
+        val.push_str(&format!(r#" PUBLIC "{public_id}" "{system_id}""#))
+      }
+      (true, false) => val.push_str(&format!(r#" SYSTEM "{system_id}""#)),
+      (true, true) => {}
+    }
+
+    let mut attributes = Vec::new();
+    attributes.push(WireAttr {
+      ns:    None,
+      name:  interner.intern_static("name"),
+      value: interner.intern(name),
+    });
+    if !public_id.is_empty() {
+      attributes.push(WireAttr {
+        ns:    None,
+        name:  interner.intern_static("publicId"),
+        value: interner.intern(public_id),
+      });
+    }
+    if !system_id.is_empty() {
+      attributes.push(WireAttr {
+        ns:    None,
+        name:  interner.intern_static("systemId"),
+        value: interner.intern(system_id),
+      });
+    }
+    let value = interner.intern(&val);
+    let id = self.push(WireNodeType::DocumentType, parent, name_idx);
+    let node = self.node_mut(id);
+    node.value = Some(value);
+    node.attrs = Some(attributes);
+    id
+  }
+
+  fn visit_element(
+    &mut self,
+    parent: Option<u32>,
+    name: &QualName,
+    attrs: &[Attribute],
+  ) -> u32 {
+    let fold = self.options.fold_name_case;
+    let namespace =
+      (!name.ns.is_empty()).then(|| self.interner.intern_atom(&name.ns));
+    let name_idx = intern_qual(name, &mut self.interner, fold);
+    let filter = &self.options.attribute_filter;
+    let mut wa = Vec::with_capacity(attrs.len());
+    let mut stripped = Vec::new();
+    for a in attrs {
+      if self.options.strip_event_handlers && is_event_handler(&a.name.local) {
+        if self.options.report_stripped_handlers {
+          stripped.push(self.intern_name(&a.name.local));
+        }
+        continue;
+      }
+      if !filter.is_all() && self.error.is_none() {
+        match filter.accepts(&a.name.local, &a.value, &name.local) {
+          Ok(true) => {}
+          Ok(false) => continue,
+          // the output is discarded, so the predicate is not called again
+          Err(error) => self.error = Some(error),
+        }
+      }
+      let ns = if a.name.ns.is_empty() {
+        None
+      } else {
+        Some(self.interner.intern_atom(&a.name.ns))
+      };
+      wa.push(WireAttr {
+        name: self.intern_name(&a.name.local),
+        ns,
+        value: self.interner.intern(a.value.as_ref()),
+      });
+    }
+    let id = self.push(WireNodeType::Element, parent, name_idx);
+    self.node_mut(id).namespace = namespace;
+    if !wa.is_empty() {
+      self.node_mut(id).attrs = Some(wa);
+    }
+    if !stripped.is_empty() {
+      self.node_mut(id).stripped_handlers = Some(stripped);
+    }
+    id
+  }
+
+  fn visit_text(&mut self, parent: Option<u32>, text: &str) -> u32 {
+    let name = self.interner.intern_static("#text");
+    let (text, full_length) = match self.options.max_text_length {
+      Some(max) if text.len() > max as usize => (
+        truncate_at_char_boundary(text, max as usize),
+        Some(text.len() as u32),
+      ),
+      _ => (text, None),
+    };
+    let value = self.interner.intern(text);
+    let id = self.push(WireNodeType::Text, parent, name);
+    let node = self.node_mut(id);
+    node.value = Some(value);
+    node.truncated = full_length.is_some();
+    node.full_length = full_length;
+    id
+  }
+
+  fn visit_comment(&mut self, parent: Option<u32>, text: &str) -> u32 {
+    let name = self.interner.intern_static("#comment");
+    let value = self.interner.intern(text);
+    let id = self.push(WireNodeType::Comment, parent, name);
+    self.node_mut(id).value = Some(value);
+    id
+  }
+
+  fn visit_processing_instruction(
+    &mut self,
+    parent: Option<u32>,
+    target: &str,
+    data: &str,
+  ) -> u32 {
+    let name = self.interner.intern(target);
+    let value = self.interner.intern(data);
+    let id = self.push(WireNodeType::ProcessingInstruction, parent, name);
+    self.node_mut(id).value = Some(value);
+    id
+  }
+
+  fn append_child(&mut self, parent: u32, previous: Option<u32>, child: u32) {
+    let index = match previous {
+      None => {
+        self.node_mut(parent).first_child = Some(child);
+        0
+      }
+      Some(previous) => {
+        self.node_mut(previous).next_sibling = Some(child);
+        self.node_mut(previous).index.map_or(0, |index| index + 1)
+      }
+    };
+    self.node_mut(child).index = Some(index);
+  }
+
+  fn set_template_content(&mut self, template: u32, fragment: u32) {
+    self.node_mut(template).template_content = Some(fragment);
+  }
+}
+
+/// Returns `true` if the attribute `name` is an inline event handler, i.e.
+/// any attribute starting with `on` (such as `onclick` or `onload`).
+pub fn is_event_handler(name: &str) -> bool {
+  name.len() > 2 && name.as_bytes()[..2].eq_ignore_ascii_case(b"on")
+}
+
+fn truncate_at_char_boundary(s: &str, max: usize) -> &str {
+  let mut end = max.min(s.len());
+  while !s.is_char_boundary(end) {
+    end -= 1;
+  }
+  &s[..end]
+}
+
+fn intern_qual(q: &QualName, interner: &mut Interner, fold: bool) -> u32 {
+  let prefix = q.prefix.as_ref().map(|p| p.as_ref()).unwrap_or("");
+  let local = q.local.as_ref();
+  let local_idx = match fold {
+    true => interner.intern_atom_folded(&q.local),
+    false => interner.intern_atom(&q.local),
+  };
+  if !prefix.is_empty() {
+    // Combine namespace and local name into single string for simplicity
+    let name = format!("{prefix}:{local}");
+    match fold {
+      true => interner.intern_folded(name),
+      false => interner.intern(name),
+    }
+  } else {
+    local_idx
+  }
+}
@@ -0,0 +1,134 @@
+//! Removing empty elements.
+//!
+//! Markup that went through a WYSIWYG editor, a CMS or a conversion from
+//! another format is littered with elements that hold nothing: the
+//! `<p></p>` of an extra return, the `<span> </span>` and `<b></b>` left by
+//! formatting toggled on and off again. [`remove_empty`] removes the
+//! elements without attributes whose contents are nothing but whitespace,
+//! from the innermost ones out, so that a `<div><span></span></div>` goes
+//! away entirely.
+//!
+//! Elements are kept, even when empty, if their tag is one of the `keep`
+//! tags, which default to [`DEFAULT_KEEP`]: the void elements like `<br>`
+//! and `<img>`, which are always empty, and the ones whose emptiness still
+//! means something, like table cells, form controls and media. With `only`,
+//! only the elements with one of those tags are removed. The `<html>`,
+//! `<head>` and `<body>` elements are always kept, as are the elements
+//! holding comments, which may be markers that scripts look for.
+//!
+//! ```js
+//! remove_empty(html, { keep: ["br", "img", "td"], coalesceText: true });
+//! ```
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::Deserialize;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::rcdom::push_merging_text;
+
+/// The tags of the elements [`remove_empty`] keeps by default.
+pub const DEFAULT_KEEP: &[&str] = &[
+  "area", "audio", "base", "br", "canvas", "col", "embed", "hr", "iframe",
+  "img", "input", "link", "meta", "object", "output", "script", "select",
+  "source", "style", "td", "template", "textarea", "th", "track", "video",
+  "wbr",
+];
+
+/// Which empty elements [`remove_empty`] removes.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct RemoveEmpty {
+  /// The tags of the elements to keep even when empty, or `None` for
+  /// [`DEFAULT_KEEP`].
+  pub keep:          Option<Vec<String>>,
+  /// The tags of the only elements to remove, or `None` for all of them.
+  pub only:          Option<Vec<String>>,
+  /// Whether to merge the text nodes that were only separated by removed
+  /// elements into one, like the `coalesceText` option does.
+  pub coalesce_text: bool,
+}
+
+impl RemoveEmpty {
+  /// Whether an (empty) element with the local name `name` is removed.
+  pub fn removes(&self, name: &str) -> bool {
+    let is_in =
+      |tags: &[String]| tags.iter().any(|tag| tag.eq_ignore_ascii_case(name));
+    let is_kept = match &self.keep {
+      Some(keep) => is_in(keep),
+      None => DEFAULT_KEEP
+        .iter()
+        .any(|tag| tag.eq_ignore_ascii_case(name)),
+    };
+    let is_structural = ["html", "head", "body"]
+      .iter()
+      .any(|tag| tag.eq_ignore_ascii_case(name));
+    !is_structural && !is_kept && self.only.as_deref().is_none_or(is_in)
+  }
+}
+
+/// Removes the elements below `root` (including the contents of templates)
+/// that have no attributes and nothing but whitespace in them, and that
+/// `options` does not keep, returning how many were removed.
+pub fn remove_empty(root: &Handle, options: &RemoveEmpty) -> usize {
+  let mut removed = 0;
+  prune(root, options, &mut removed);
+  removed
+}
+
+fn prune(parent: &Handle, options: &RemoveEmpty, removed: &mut usize) {
+  let children = parent.children.take();
+  let mut kept: Vec<Handle> = Vec::with_capacity(children.len());
+  for child in children {
+    if let NodeData::Element {
+      template_contents, ..
+    } = &child.data
+    {
+      prune(&child, options, removed);
+      if let Some(contents) = template_contents {
+        prune(contents, options, removed);
+      }
+    }
+    if is_empty(&child, options) {
+      child.parent.set(None);
+      *removed += 1;
+      continue;
+    }
+    match options.coalesce_text {
+      true => push_merging_text(&mut kept, child),
+      false => kept.push(child),
+    }
+  }
+  *parent.children.borrow_mut() = kept;
+}
+
+// Whether `node` is an element to remove.
+fn is_empty(node: &Handle, options: &RemoveEmpty) -> bool {
+  let NodeData::Element {
+    name,
+    attrs,
+    template_contents,
+    ..
+  } = &node.data
+  else {
+    return false;
+  };
+  let is_blank = |node: &Handle| {
+    node
+      .children
+      .borrow()
+      .iter()
+      .all(|child| match &child.data {
+        NodeData::Text { contents } => {
+          contents.borrow().chars().all(|c| c.is_ascii_whitespace())
+        }
+        _ => false,
+      })
+  };
+  attrs.borrow().is_empty()
+    && options.removes(&name.local)
+    && is_blank(node)
+    && template_contents.as_ref().is_none_or(is_blank)
+}
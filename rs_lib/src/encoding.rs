@@ -0,0 +1,334 @@
+//! Character encoding sniffing for byte input.
+//!
+//! Documents fetched as raw bytes have to be decoded before parsing. The
+//! [`sniff`] function determines their encoding the way browsers do, in order
+//! of precedence:
+//!
+//! 1. a byte order mark;
+//! 2. the transport-level hint (e.g. the `charset` of a `Content-Type` header),
+//!    given as the `encoding` option;
+//! 3. a `<meta charset>` (or `http-equiv`) declaration within the first 1024
+//!    bytes of HTML, or the `encoding` of an XML declaration;
+//! 4. if the `detectEncoding` option is set, a statistical guess based on the
+//!    byte patterns of the input;
+//! 5. UTF-8.
+//!
+//! The result is reported with a `confidence` (in percent), which is only
+//! below 100 for a statistical guess.
+//!
+//! Only the encodings that can be decoded without any lookup tables beyond a
+//! single page are supported: UTF-8, UTF-16 (LE and BE), and windows-1252
+//! (which the [Encoding Standard] also uses for `iso-8859-1` and `ascii`).
+//! Labels of any other encoding are ignored, as if they were absent.
+//!
+//! [Encoding Standard]: https://encoding.spec.whatwg.org/
+
+use alloc::string::String;
+
+/// The encodings supported by [`decode`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Encoding {
+  #[default]
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+  Windows1252,
+}
+
+impl Encoding {
+  /// Every supported encoding.
+  pub const ALL: &[Self] = &[
+    Encoding::Utf8,
+    Encoding::Utf16Le,
+    Encoding::Utf16Be,
+    Encoding::Windows1252,
+  ];
+
+  /// Returns the canonical name of this encoding.
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Encoding::Utf8 => "utf-8",
+      Encoding::Utf16Le => "utf-16le",
+      Encoding::Utf16Be => "utf-16be",
+      Encoding::Windows1252 => "windows-1252",
+    }
+  }
+
+  /// Looks up an encoding by one of its labels, as listed in the Encoding
+  /// Standard. Returns `None` for unknown and unsupported encodings.
+  pub fn for_label(label: &str) -> Option<Self> {
+    let label = label
+      .trim_matches(|c: char| c.is_ascii_whitespace())
+      .to_ascii_lowercase();
+    let encoding = match label.as_str() {
+      "unicode-1-1-utf-8" | "unicode11utf8" | "unicode20utf8" | "utf-8"
+      | "utf8" | "x-unicode20utf8" => Encoding::Utf8,
+      "csunicode" | "iso-10646-ucs-2" | "ucs-2" | "unicode" | "unicodefeff"
+      | "utf-16" | "utf-16le" => Encoding::Utf16Le,
+      "unicodefffe" | "utf-16be" => Encoding::Utf16Be,
+      "ansi_x3.4-1968" | "ascii" | "cp1252" | "cp819" | "csisolatin1"
+      | "ibm819" | "iso-8859-1" | "iso-ir-100" | "iso8859-1" | "iso88591"
+      | "iso_8859-1" | "iso_8859-1:1987" | "l1" | "latin1" | "us-ascii"
+      | "windows-1252" | "x-cp1252" => Encoding::Windows1252,
+      _ => return None,
+    };
+    Some(encoding)
+  }
+}
+
+/// Where the encoding reported by [`sniff`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EncodingSource {
+  Bom,
+  Transport,
+  Meta,
+  Detected,
+  Default,
+}
+
+impl EncodingSource {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      EncodingSource::Bom => "bom",
+      EncodingSource::Transport => "transport",
+      EncodingSource::Meta => "meta",
+      EncodingSource::Detected => "detected",
+      EncodingSource::Default => "default",
+    }
+  }
+}
+
+/// The encoding of some input, as determined by [`sniff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SniffedEncoding {
+  pub encoding:   Encoding,
+  pub source:     EncodingSource,
+  /// How certain the guess is, from `0` to `100`.
+  pub confidence: u8,
+}
+
+impl SniffedEncoding {
+  fn certain(encoding: Encoding, source: EncodingSource) -> Self {
+    Self {
+      encoding,
+      source,
+      confidence: 100,
+    }
+  }
+}
+
+// The code points of the bytes 0x80..=0x9F in windows-1252; the remaining
+// bytes map to the code point of the same value.
+const WINDOWS_1252_HIGH: [u16; 32] = [
+  0x20AC, 0x0081, 0x201A, 0x0192, 0x201E, 0x2026, 0x2020, 0x2021, 0x02C6,
+  0x2030, 0x0160, 0x2039, 0x0152, 0x008D, 0x017D, 0x008F, 0x0090, 0x2018,
+  0x2019, 0x201C, 0x201D, 0x2022, 0x2013, 0x2014, 0x02DC, 0x2122, 0x0161,
+  0x203A, 0x0153, 0x009D, 0x017E, 0x0178,
+];
+
+// How many bytes of input the meta prescan and the detector look at.
+pub(crate) const PRESCAN_LENGTH: usize = 1024;
+pub(crate) const DETECT_LENGTH: usize = 64 * 1024;
+
+/// Determines the encoding of `bytes`. See the [module level documentation]
+/// for the order in which the sources are consulted.
+///
+/// [module level documentation]: crate::encoding
+pub fn sniff(
+  bytes: &[u8],
+  hint: Option<&str>,
+  html: bool,
+  detect: bool,
+) -> SniffedEncoding {
+  if let Some((encoding, _)) = bom(bytes) {
+    return SniffedEncoding::certain(encoding, EncodingSource::Bom);
+  }
+  if let Some(encoding) = hint.and_then(Encoding::for_label) {
+    return SniffedEncoding::certain(encoding, EncodingSource::Transport);
+  }
+  let prescan = bytes.get(..PRESCAN_LENGTH).unwrap_or(bytes);
+  let declared = match html {
+    true => meta_charset(prescan),
+    false => xml_encoding(prescan),
+  };
+  if let Some(encoding) = declared.and_then(Encoding::for_label) {
+    // an ASCII-compatible declaration cannot describe UTF-16 input
+    let encoding = match encoding {
+      Encoding::Utf16Le | Encoding::Utf16Be => Encoding::Utf8,
+      encoding => encoding,
+    };
+    return SniffedEncoding::certain(encoding, EncodingSource::Meta);
+  }
+  if detect {
+    return detect_encoding(bytes.get(..DETECT_LENGTH).unwrap_or(bytes));
+  }
+  SniffedEncoding::certain(Encoding::Utf8, EncodingSource::Default)
+}
+
+// Returns the encoding indicated by a byte order mark, and the mark's length.
+fn bom(bytes: &[u8]) -> Option<(Encoding, usize)> {
+  match bytes {
+    [0xEF, 0xBB, 0xBF, ..] => Some((Encoding::Utf8, 3)),
+    [0xFF, 0xFE, ..] => Some((Encoding::Utf16Le, 2)),
+    [0xFE, 0xFF, ..] => Some((Encoding::Utf16Be, 2)),
+    _ => None,
+  }
+}
+
+/// Decodes `bytes` as `encoding`, skipping a leading byte order mark and
+/// replacing malformed sequences with U+FFFD.
+pub fn decode(bytes: &[u8], encoding: Encoding) -> String {
+  let bytes = match bom(bytes) {
+    Some((bom, len)) if bom == encoding => &bytes[len..],
+    _ => bytes,
+  };
+  match encoding {
+    Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+    Encoding::Utf16Le | Encoding::Utf16Be => {
+      let units = bytes.chunks(2).map(|pair| match (pair, encoding) {
+        ([lo, hi], Encoding::Utf16Le) => u16::from_le_bytes([*lo, *hi]),
+        ([hi, lo], _) => u16::from_be_bytes([*hi, *lo]),
+        // a trailing odd byte is malformed
+        _ => 0xFFFD,
+      });
+      char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+    }
+    Encoding::Windows1252 => bytes
+      .iter()
+      .map(|&b| match b {
+        0x80..=0x9F => WINDOWS_1252_HIGH[(b - 0x80) as usize],
+        _ => b as u16,
+      })
+      .map(|c| char::from_u32(c as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+      .collect(),
+  }
+}
+
+// A simplified version of the Encoding Standard's prescan: finds the first
+// `<meta>` tag with a `charset=` (either as its own attribute, or within a
+// `content` attribute) outside of comments.
+fn meta_charset(bytes: &[u8]) -> Option<&str> {
+  let mut pos = 0;
+  while let Some(i) = bytes.get(pos..)?.iter().position(|&b| b == b'<') {
+    let start = pos + i;
+    let rest = &bytes[start + 1..];
+    if rest.starts_with(b"!--") {
+      pos = find(bytes, start + 4, b"-->").map_or(bytes.len(), |end| end + 3);
+      continue;
+    }
+    let end = find(bytes, start, b">").unwrap_or(bytes.len());
+    let tag = &bytes[start + 1..end];
+    if tag.len() > 4
+      && tag[..4].eq_ignore_ascii_case(b"meta")
+      && (tag[4].is_ascii_whitespace() || tag[4] == b'/')
+      && let Some(label) = attribute_label(tag, b"charset")
+    {
+      return Some(label);
+    }
+    pos = end;
+  }
+  None
+}
+
+// Finds the `encoding` of an XML declaration at the very start of the input.
+fn xml_encoding(bytes: &[u8]) -> Option<&str> {
+  if !bytes.starts_with(b"<?xml") {
+    return None;
+  }
+  let end = find(bytes, 0, b"?>")?;
+  attribute_label(&bytes[..end], b"encoding")
+}
+
+// Extracts the value following the first `name=` in `tag`, with optional
+// quotes around it.
+fn attribute_label<'a>(tag: &'a [u8], name: &[u8]) -> Option<&'a str> {
+  let mut pos = 0;
+  while let Some(i) = tag
+    .get(pos..)?
+    .windows(name.len())
+    .position(|w| w.eq_ignore_ascii_case(name))
+  {
+    pos += i + name.len();
+    let Some(rest) = tag[pos..].trim_ascii_start().strip_prefix(b"=") else {
+      continue;
+    };
+    let rest = rest.trim_ascii_start();
+    let rest = rest
+      .strip_prefix(b"\"")
+      .or_else(|| rest.strip_prefix(b"'"))
+      .unwrap_or(rest);
+    let len = rest
+      .iter()
+      .position(|&b| matches!(b, b'"' | b'\'' | b';' | b'/') || b <= b' ')
+      .unwrap_or(rest.len());
+    return core::str::from_utf8(&rest[..len])
+      .ok()
+      .filter(|label| !label.is_empty());
+  }
+  None
+}
+
+// Guesses the encoding of a sample of the input from its byte patterns.
+fn detect_encoding(sample: &[u8]) -> SniffedEncoding {
+  let detected = |encoding, confidence: usize| SniffedEncoding {
+    encoding,
+    source: EncodingSource::Detected,
+    confidence: confidence.min(100) as u8,
+  };
+
+  // mostly-ASCII UTF-16 has a zero in every other byte
+  let pairs = sample.len() / 2;
+  if pairs >= 2 {
+    let zeros = |offset| {
+      sample
+        .iter()
+        .skip(offset)
+        .step_by(2)
+        .filter(|&&b| b == 0)
+        .count()
+    };
+    let (even, odd) = (zeros(0), zeros(1));
+    if even * 2 > pairs && odd * 10 < pairs {
+      return detected(Encoding::Utf16Be, even * 100 / pairs);
+    }
+    if odd * 2 > pairs && even * 10 < pairs {
+      return detected(Encoding::Utf16Le, odd * 100 / pairs);
+    }
+  }
+
+  let non_ascii = sample.iter().filter(|b| !b.is_ascii()).count();
+  if non_ascii == 0 {
+    // pure ASCII decodes the same in every supported single-byte encoding
+    return detected(Encoding::Utf8, 100);
+  }
+  let valid_utf8 = match core::str::from_utf8(sample) {
+    Ok(_) => true,
+    // the sample may end in the middle of a sequence
+    Err(error) => error.error_len().is_none(),
+  };
+  if valid_utf8 {
+    // multi-byte sequences are unlikely to be valid UTF-8 by chance, so each
+    // one makes the guess more certain
+    let sequences = sample.iter().filter(|&&b| b >= 0xC0).count();
+    return detected(Encoding::Utf8, 80 + sequences.min(19));
+  }
+  // bytes which are unassigned in windows-1252 make it a less likely fit
+  let unassigned = sample
+    .iter()
+    .filter(|b| matches!(b, 0x81 | 0x8D | 0x8F | 0x90 | 0x9D))
+    .count();
+  detected(
+    Encoding::Windows1252,
+    90usize.saturating_sub(unassigned * 100 / non_ascii),
+  )
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+  bytes
+    .get(from..)?
+    .windows(needle.len())
+    .position(|w| w == needle)
+    .map(|i| from + i)
+}
@@ -0,0 +1,145 @@
+//! Error types surfaced by the dawm parsing engine.
+//!
+//! Every fallible operation exposed to JavaScript returns a [`DawmError`],
+//! which is converted into a thrown `Error` object on the JS side (through
+//! wasm-bindgen's `JsError`) rather than aborting the entire WASM instance
+//! with an opaque `unreachable` trap. Without the `wasm` feature, the same
+//! errors are returned by the Rust API, minus those about JS values.
+
+use alloc::string::String;
+
+use alloc::format;
+
+use derive_more::with_trait::Display;
+use derive_more::with_trait::Error;
+
+use crate::diagnostics::ParseError;
+use crate::quirks_mode::UnknownQuirksMode;
+
+/// The error type returned by the fallible parsing entry points.
+#[derive(Debug, Display, Error)]
+pub enum DawmError {
+  /// The parser reported an error while the `failFast` option was set.
+  #[display("{}", fail_fast_message(_0))]
+  ParseFailed(#[error(not(source))] ParseError),
+  /// The `onError` callback requested parsing to be aborted (by returning
+  /// `false` or throwing) when it was invoked with the given error.
+  #[display("parsing aborted by onError callback: {}", fail_fast_message(_0))]
+  Aborted(#[error(not(source))] ParseError),
+  /// An XML document had more than one top-level element (or stray text at
+  /// the top level), and the `multiRoot` option was set to `"error"`.
+  #[display(
+    "XML document has {_0} top-level nodes, but only one root is allowed"
+  )]
+  MultipleRoots(#[error(not(source))] usize),
+  /// A name or piece of data passed to a DOM method is not allowed there,
+  /// like the DOM's `InvalidCharacterError`.
+  #[display("invalid character: {_0}")]
+  InvalidCharacter(#[error(not(source))] String),
+  /// A string passed to a DOM method cannot be used there at all, e.g. an
+  /// empty class name, like the DOM's `SyntaxError`.
+  #[display("syntax error: {_0}")]
+  Syntax(#[error(not(source))] String),
+  /// A qualified name does not fit the namespace it was given with, like
+  /// the DOM's `NamespaceError`.
+  #[display("namespace error: {_0}")]
+  Namespace(#[error(not(source))] String),
+  /// A node was to be inserted where it cannot go, e.g. into one of its own
+  /// descendants or into a text node, like the DOM's `HierarchyRequestError`.
+  #[display("hierarchy request error: {_0}")]
+  HierarchyRequest(#[error(not(source))] String),
+  /// A DOM method was called on a node of the wrong type, e.g. an attribute
+  /// was to be set on a text node, like the DOM's `InvalidNodeTypeError`.
+  #[display("invalid node type: {_0}")]
+  InvalidNodeType(#[error(not(source))] String),
+  /// An object is not in a state the method can be called in, e.g. a
+  /// fragment builder without an open element was asked to close it, like
+  /// the DOM's `InvalidStateError`.
+  #[display("invalid state: {_0}")]
+  InvalidState(#[error(not(source))] String),
+  /// The content type can only be parsed with a Cargo feature the crate was
+  /// compiled without, e.g. XML without the `xml` feature.
+  #[display("cannot parse {mime:?} without the `{feature}` feature")]
+  UnsupportedMime {
+    mime:    String,
+    feature: &'static str,
+  },
+  /// The node a DOM method refers to is not where it is expected, e.g. the
+  /// child to insert before is not a child of the parent, like the DOM's
+  /// `NotFoundError`.
+  #[display("not found: {_0}")]
+  NotFound(#[error(not(source))] String),
+  /// An element handler of an [`HtmlRewriter`](crate::HtmlRewriter) threw
+  /// an error for an element matched by its selector, or the predicate of an
+  /// [`AttributeFilter`](crate::AttributeFilter) threw (with a `selector` of
+  /// `"attributeFilter"`).
+  #[display("handler for {selector:?} failed: {message}")]
+  Handler {
+    selector: String,
+    message:  String,
+  },
+  /// A stored [`InternerDictionary`](crate::InternerDictionary) cannot be
+  /// loaded, because it is of another version or its hash does not match.
+  #[display("invalid dictionary: {_0}")]
+  Dictionary(#[error(not(source))] String),
+  /// A string given as a quirks mode is not the name of one.
+  #[display("{_0}")]
+  QuirksMode(UnknownQuirksMode),
+  /// No node of the document has the given (wire) id.
+  #[display("no node with id {_0}")]
+  NodeNotFound(#[error(not(source))] u32),
+  /// A [`WireDoc`](crate::WireDoc) does not pass
+  /// [`validate_wire`](crate::validate_wire), e.g. because its links form
+  /// a cycle.
+  #[display("invalid wire document: {_0}")]
+  InvalidWire(#[error(not(source))] String),
+  /// The patch operations passed from JavaScript could not be deserialized.
+  #[cfg(feature = "wasm")]
+  #[display("invalid patch: {_0}")]
+  Patch(serde_wasm_bindgen::Error),
+  /// The options passed from JavaScript could not be deserialized.
+  #[cfg(feature = "wasm")]
+  #[display("invalid options: {_0}")]
+  Options(serde_wasm_bindgen::Error),
+  /// The parsed document could not be converted into a JS value. The
+  /// `context` describes which part of the document failed to serialize
+  /// (e.g. `"node 12"` or `"string 40"`).
+  #[cfg(feature = "wasm")]
+  #[display("failed to serialize the parsed document ({context}): {source}")]
+  Serialize {
+    context: String,
+    source:  serde_wasm_bindgen::Error,
+  },
+}
+
+fn fail_fast_message(error: &ParseError) -> String {
+  let ParseError {
+    code,
+    message,
+    span,
+    ..
+  } = error;
+  let code = code.as_str();
+  match (span.line, span.column) {
+    (Some(line), Some(column)) => {
+      format!("parse error at line {line}, column {column} ({code}): {message}")
+    }
+    (Some(line), None) => {
+      format!("parse error at line {line} ({code}): {message}")
+    }
+    _ => format!("parse error ({code}): {message}"),
+  }
+}
+
+// Extracts the message of a value thrown by a JS callback.
+#[cfg(feature = "wasm")]
+pub(crate) fn error_message(thrown: &wasm_bindgen::JsValue) -> String {
+  use wasm_bindgen::JsCast;
+  match thrown.dyn_ref::<js_sys::Error>() {
+    Some(error) => error.message().into(),
+    None => thrown.as_string().unwrap_or_else(|| format!("{thrown:?}")),
+  }
+}
+
+/// Shorthand for a [`Result`](core::result::Result) with a [`DawmError`].
+pub type Result<T, E = DawmError> = core::result::Result<T, E>;
@@ -0,0 +1,491 @@
+//! Main content ("reader mode") extraction, after Mozilla's Readability.
+//!
+//! Boilerplate like scripts, forms and navigation is ignored, as are
+//! elements whose `class` or `id` marks them as unlikely to be content
+//! (comments, sidebars, footers, ...). Every paragraph of at least 25
+//! characters then adds to the score of its ancestors, based on its length
+//! and number of commas: in full to its parent, half to its grandparent, and
+//! less the further up an ancestor is. Ancestors start out with a score
+//! based on their tag name, and on whether their `class` and `id` sound like
+//! content (`article`, `post`, ...) or not (`sidebar`, `promo`, ...). The
+//! scores are discounted by the link density of the ancestors, the share of
+//! their text that is in links, and the best scoring ancestor is the main
+//! content, along with those of its siblings that score well enough or look
+//! like paragraphs of their own.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::block_text;
+use super::collapse_whitespace;
+use super::descendants;
+use super::is_element;
+use super::is_hidden;
+use super::local_name;
+use super::metadata::extract_metadata;
+use super::text_content;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::ParentNode;
+use crate::rcdom::number_nodes;
+use crate::rcdom::outer_html;
+use crate::rcdom::remove_nodes;
+
+/// Elements which are never part of the content.
+const NOISE: &[&str] = &[
+  "button", "embed", "form", "iframe", "input", "link", "meta", "noscript",
+  "object", "script", "select", "style", "template", "textarea",
+];
+
+/// Elements which are skipped (with their descendants) when scoring.
+const SKIPPED: &[&str] = &["aside", "footer", "header", "nav"];
+
+/// `class` and `id` fragments of elements which are unlikely to be content,
+/// unless they also match [`MAYBE_CANDIDATE`].
+const UNLIKELY_CANDIDATE: &[&str] = &[
+  "-ad-",
+  "ad-break",
+  "agegate",
+  "banner",
+  "breadcrumbs",
+  "combx",
+  "comment",
+  "community",
+  "cover-wrap",
+  "disqus",
+  "extra",
+  "foot",
+  "gdpr",
+  "header",
+  "legends",
+  "menu",
+  "modal",
+  "pager",
+  "pagination",
+  "popup",
+  "related",
+  "remark",
+  "replies",
+  "rss",
+  "share",
+  "shoutbox",
+  "sidebar",
+  "skyscraper",
+  "social",
+  "sponsor",
+  "supplemental",
+  "yom-remote",
+];
+
+const MAYBE_CANDIDATE: &[&str] = &[
+  "and", "article", "body", "column", "content", "main", "shadow",
+];
+
+const POSITIVE: &[&str] = &[
+  "article",
+  "blog",
+  "body",
+  "content",
+  "entry",
+  "hentry",
+  "h-entry",
+  "main",
+  "page",
+  "pagination",
+  "post",
+  "story",
+  "text",
+];
+
+const NEGATIVE: &[&str] = &[
+  "-ad-",
+  "hidden",
+  "banner",
+  "combx",
+  "comment",
+  "com-",
+  "contact",
+  "foot",
+  "footer",
+  "footnote",
+  "gdpr",
+  "masthead",
+  "media",
+  "meta",
+  "outbrain",
+  "promo",
+  "related",
+  "scroll",
+  "share",
+  "shoutbox",
+  "sidebar",
+  "skyscraper",
+  "sponsor",
+  "shopping",
+  "tags",
+  "tool",
+  "widget",
+];
+
+const BYLINE: &[&str] = &["byline", "author", "dateline", "writtenby"];
+
+// Elements whose presence makes a `<div>` a container rather than a
+// paragraph of its own.
+const BLOCKS: &[&str] = &[
+  "address",
+  "article",
+  "aside",
+  "blockquote",
+  "dd",
+  "div",
+  "dl",
+  "dt",
+  "fieldset",
+  "figure",
+  "footer",
+  "form",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "header",
+  "hr",
+  "li",
+  "main",
+  "nav",
+  "ol",
+  "p",
+  "pre",
+  "section",
+  "table",
+  "ul",
+];
+
+/// The main content of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Article {
+  /// The id of the element holding the content, as in the wire output of
+  /// the document.
+  pub id:      u32,
+  /// The `og:title` of the document, or else its `<title>` without the name
+  /// of the site.
+  pub title:   Option<String>,
+  /// The author, from `<meta name="author">` or a byline in the document.
+  pub byline:  Option<String>,
+  /// The description of the document, or else the first paragraph of the
+  /// content which is long enough to be scored.
+  pub excerpt: Option<String>,
+  /// The content, as HTML. When siblings of the content element were
+  /// included, they are all wrapped in a `<div>`.
+  pub content: String,
+  /// The content as plain text, with a blank line between paragraphs.
+  pub text:    String,
+  /// The length of `text`, in characters.
+  pub length:  usize,
+}
+
+/// Extracts the main content of the HTML document rooted at `document`, or
+/// `None` if it has no `<body>`. Boilerplate is removed from the document in
+/// the process.
+pub fn extract_article(document: &Handle) -> Option<Article> {
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let metadata = extract_metadata(document);
+  remove_nodes(
+    document,
+    &|data| match data {
+      NodeData::Comment { .. } | NodeData::ProcessingInstruction { .. } => true,
+      NodeData::Element { name, .. } => NOISE.contains(&name.local.as_ref()),
+      _ => false,
+    },
+    true,
+  );
+  let body = descendants(document).find(|node| is_element(node, "body"))?;
+
+  let mut scorer = Scorer::default();
+  scorer.walk(&body, &mut alloc::vec![body.clone()]);
+  let top = scorer
+    .candidates
+    .iter()
+    .map(|candidate| {
+      let score = candidate.score * (1.0 - link_density(&candidate.node));
+      (candidate, score)
+    })
+    .max_by(|(_, a), (_, b)| a.total_cmp(b));
+  let (top, top_score) = match top {
+    Some((candidate, score)) => (candidate.node.clone(), score),
+    None => (body.clone(), 0.0),
+  };
+
+  let nodes = with_siblings(&top, top_score, &scorer);
+  let content = match nodes.as_slice() {
+    [node] => outer_html(node),
+    nodes => {
+      let inner: String = nodes.iter().map(outer_html).collect();
+      alloc::format!("<div>{inner}</div>")
+    }
+  };
+  let text = nodes
+    .iter()
+    .map(block_text)
+    .filter(|text| !text.is_empty())
+    .collect::<Vec<_>>()
+    .join("\n\n");
+  let excerpt = metadata.description.clone().or_else(|| {
+    nodes
+      .iter()
+      .flat_map(descendants)
+      .filter(|node| is_element(node, "p"))
+      .map(|p| collapse_whitespace(&text_content(&p)))
+      .find(|text| text.chars().count() >= 25)
+  });
+
+  Some(Article {
+    id: ids.get(&Rc::as_ptr(&top)).copied().unwrap_or(u32::MAX),
+    title: article_title(&metadata),
+    byline: metadata.author.clone().or_else(|| byline(&body)),
+    excerpt,
+    content,
+    length: text.chars().count(),
+    text,
+  })
+}
+
+#[derive(Default)]
+struct Scorer {
+  candidates: Vec<Candidate>,
+  index:      BTreeMap<*const Node, usize>,
+}
+
+struct Candidate {
+  node:  Handle,
+  score: f64,
+}
+
+impl Scorer {
+  fn walk(&mut self, node: &Handle, ancestors: &mut Vec<Handle>) {
+    for child in node.children.borrow().iter() {
+      let Some(tag) = local_name(child) else {
+        continue;
+      };
+      let aria_hidden = attribute(child, "aria-hidden")
+        .is_some_and(|hidden| hidden.trim().eq_ignore_ascii_case("true"));
+      if SKIPPED.contains(&tag)
+        || is_unlikely(child)
+        || is_hidden(child)
+        || aria_hidden
+      {
+        continue;
+      }
+      let paragraph = match tag {
+        "p" | "pre" | "td" => true,
+        "div" => !descendants(child).any(|node| {
+          local_name(&node).is_some_and(|tag| BLOCKS.contains(&tag))
+        }),
+        _ => false,
+      };
+      ancestors.push(child.clone());
+      if paragraph {
+        // the paragraph is the last of the ancestors
+        self.score_paragraph(child, &ancestors[..ancestors.len() - 1]);
+      } else {
+        self.walk(child, ancestors);
+      }
+      ancestors.pop();
+    }
+  }
+
+  fn score_paragraph(&mut self, paragraph: &Handle, ancestors: &[Handle]) {
+    let text = collapse_whitespace(&text_content(paragraph));
+    let length = text.chars().count();
+    if length < 25 {
+      return;
+    }
+    let commas = text.matches([',', '，']).count();
+    let score = 1.0 + commas as f64 + (length as f64 / 100.0).floor().min(3.0);
+    for (level, ancestor) in ancestors.iter().rev().take(5).enumerate() {
+      let divider = match level {
+        0 => 1.0,
+        1 => 2.0,
+        level => level as f64 * 3.0,
+      };
+      self.candidate(ancestor).score += score / divider;
+    }
+  }
+
+  fn candidate(&mut self, node: &Handle) -> &mut Candidate {
+    let index = *self.index.entry(Rc::as_ptr(node)).or_insert_with(|| {
+      self.candidates.push(Candidate {
+        node:  node.clone(),
+        score: initial_score(node),
+      });
+      self.candidates.len() - 1
+    });
+    &mut self.candidates[index]
+  }
+
+  fn score_of(&self, node: &Handle) -> Option<f64> {
+    let index = self.index.get(&Rc::as_ptr(node))?;
+    let candidate = &self.candidates[*index];
+    Some(candidate.score * (1.0 - link_density(node)))
+  }
+}
+
+fn initial_score(node: &Handle) -> f64 {
+  let tag_score = match local_name(node).unwrap_or_default() {
+    "div" | "article" | "main" => 5.0,
+    "pre" | "td" | "blockquote" => 3.0,
+    "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+    "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+    _ => 0.0,
+  };
+  tag_score + class_weight(node)
+}
+
+fn class_weight(node: &Handle) -> f64 {
+  let mut weight = 0.0;
+  for name in ["class", "id"] {
+    let Some(value) = attribute(node, name) else {
+      continue;
+    };
+    let value = value.to_ascii_lowercase();
+    if NEGATIVE.iter().any(|fragment| value.contains(fragment)) {
+      weight -= 25.0;
+    }
+    if POSITIVE.iter().any(|fragment| value.contains(fragment)) {
+      weight += 25.0;
+    }
+  }
+  weight
+}
+
+fn class_and_id(node: &Handle) -> String {
+  let class = attribute(node, "class").unwrap_or_default();
+  let id = attribute(node, "id").unwrap_or_default();
+  alloc::format!("{class} {id}").to_ascii_lowercase()
+}
+
+fn is_unlikely(node: &Handle) -> bool {
+  if is_element(node, "body") || is_element(node, "a") {
+    return false;
+  }
+  let names = class_and_id(node);
+  UNLIKELY_CANDIDATE
+    .iter()
+    .any(|fragment| names.contains(fragment))
+    && !MAYBE_CANDIDATE
+      .iter()
+      .any(|fragment| names.contains(fragment))
+}
+
+// The share of the text of `node` that is inside of links.
+fn link_density(node: &Handle) -> f64 {
+  let length = text_content(node).chars().count();
+  if length == 0 {
+    return 0.0;
+  }
+  let linked: usize = descendants(node)
+    .filter(|node| is_element(node, "a"))
+    .map(|a| text_content(&a).chars().count())
+    .sum();
+  linked as f64 / length as f64
+}
+
+// The top candidate, along with those of its siblings that belong to the
+// content as well, in document order.
+fn with_siblings(top: &Handle, top_score: f64, scorer: &Scorer) -> Vec<Handle> {
+  let Some((parent, _)) = top.get_parent_and_index() else {
+    return alloc::vec![top.clone()];
+  };
+  if is_element(top, "body") {
+    return alloc::vec![top.clone()];
+  }
+  let threshold = (top_score * 0.2).max(10.0);
+  let top_class = attribute(top, "class").filter(|class| !class.is_empty());
+  let siblings = parent.children.borrow().clone();
+  siblings
+    .into_iter()
+    .filter(|sibling| {
+      if Rc::ptr_eq(sibling, top) {
+        return true;
+      }
+      if local_name(sibling).is_none() {
+        return false;
+      }
+      let mut bonus = 0.0;
+      if top_class.is_some() && attribute(sibling, "class") == top_class {
+        bonus += top_score * 0.2;
+      }
+      if scorer
+        .score_of(sibling)
+        .is_some_and(|score| score + bonus >= threshold)
+      {
+        return true;
+      }
+      if !is_element(sibling, "p") {
+        return false;
+      }
+      let text = collapse_whitespace(&text_content(sibling));
+      let length = text.chars().count();
+      let density = link_density(sibling);
+      (length > 80 && density < 0.25)
+        || (length > 0
+          && density == 0.0
+          && (text.ends_with('.') || text.contains(". ")))
+    })
+    .collect()
+}
+
+// The `og:title`, or else the `<title>` without a trailing (or leading) site
+// name, when the part that is left still has a few words.
+fn article_title(metadata: &super::Metadata) -> Option<String> {
+  if let Some(title) =
+    metadata.open_graph.get("og:title").and_then(|t| t.first())
+  {
+    return Some(title.clone());
+  }
+  let title = metadata.title.clone().filter(|title| !title.is_empty())?;
+  for separator in [" | ", " - ", " – ", " — ", " :: ", " / ", " » "] {
+    if let Some((head, tail)) = title.rsplit_once(separator) {
+      let (head, tail) = (head.trim(), tail.trim());
+      let part =
+        if head.split_whitespace().count() >= tail.split_whitespace().count() {
+          head
+        } else {
+          tail
+        };
+      if part.split_whitespace().count() >= 3 {
+        return Some(part.to_string());
+      }
+    }
+  }
+  Some(title)
+}
+
+// An element (or `rel="author"` link) whose `class` or `id` marks it as the
+// byline, with text short enough to be one.
+fn byline(body: &Handle) -> Option<String> {
+  descendants(body)
+    .filter(|node| {
+      attribute(node, "rel")
+        .is_some_and(|rel| rel.eq_ignore_ascii_case("author"))
+        || attribute(node, "itemprop")
+          .is_some_and(|prop| prop.contains("author"))
+        || local_name(node).is_some() && {
+          let names = class_and_id(node);
+          BYLINE.iter().any(|fragment| names.contains(fragment))
+        }
+    })
+    .map(|node| collapse_whitespace(&text_content(&node)))
+    .find(|text| !text.is_empty() && text.chars().count() < 100)
+}
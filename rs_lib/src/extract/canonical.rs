@@ -0,0 +1,116 @@
+//! The links of a document that matter to search engines: its canonical URL,
+//! its alternate versions, its neighbours in a paginated series and its AMP
+//! version.
+//!
+//! Only `<link>` elements are considered, wherever they are in the document.
+//! URLs are resolved against the document's `<base>` and `document_url` as
+//! for [links](super::links); a URL that cannot be resolved (e.g. a relative
+//! one without a base URL) is reported as given, with surrounding whitespace
+//! trimmed. Alternates that are feeds are left out, as those are found by
+//! [`discover_feeds`](super::feed::discover_feeds).
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::descendants;
+use super::feed::FEED_TYPES;
+use super::is_element;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+/// The canonical, alternate, pagination and AMP links of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CanonicalLinks {
+  /// The URL of the first `<link rel="canonical">`.
+  pub canonical:  Option<String>,
+  /// The URL of the first `<link rel="amphtml">`.
+  pub amphtml:    Option<String>,
+  /// The URL of the first `<link rel="prev">` (or `rel="previous"`).
+  pub prev:       Option<String>,
+  /// The URL of the first `<link rel="next">`.
+  pub next:       Option<String>,
+  /// The `<link rel="alternate">`s, e.g. translations and mobile versions,
+  /// in document order.
+  pub alternates: Vec<Alternate>,
+}
+
+/// A `<link rel="alternate">` which is not a feed.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Alternate {
+  /// The id of the `<link>` element, as in the wire output of the document.
+  pub id:           u32,
+  pub url:          String,
+  /// The language of the alternate, e.g. `"de-AT"` or `"x-default"`.
+  pub hreflang:     Option<String>,
+  /// The media query the alternate is meant for, e.g. for mobile versions.
+  pub media:        Option<String>,
+  /// The (lowercase) MIME type of the alternate.
+  #[serde(rename = "type")]
+  pub content_type: Option<String>,
+  pub title:        Option<String>,
+}
+
+/// Extracts the [`CanonicalLinks`] of the document rooted at `document`.
+pub fn extract_canonical(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> CanonicalLinks {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let mut links = CanonicalLinks::default();
+  for node in descendants(document).filter(|node| is_element(node, "link")) {
+    let (Some(rel), Some(href)) =
+      (attribute(&node, "rel"), attribute(&node, "href"))
+    else {
+      continue;
+    };
+    let href = href.trim();
+    let url = url::resolve(base.as_deref().unwrap_or_default(), href)
+      .unwrap_or_else(|| href.to_string());
+    for rel in rel.split_ascii_whitespace() {
+      let slot = match rel.to_ascii_lowercase().as_str() {
+        "canonical" => &mut links.canonical,
+        "amphtml" => &mut links.amphtml,
+        "prev" | "previous" => &mut links.prev,
+        "next" => &mut links.next,
+        "alternate" => {
+          let trimmed = |name| {
+            attribute(&node, name)
+              .map(|value| value.trim().to_string())
+              .filter(|value| !value.is_empty())
+          };
+          let content_type = trimmed("type").map(|t| t.to_ascii_lowercase());
+          if content_type
+            .as_deref()
+            .is_some_and(|t| FEED_TYPES.contains(&t))
+          {
+            continue;
+          }
+          links.alternates.push(Alternate {
+            id: ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX),
+            url: url.clone(),
+            hreflang: trimmed("hreflang"),
+            media: trimmed("media"),
+            content_type,
+            title: trimmed("title"),
+          });
+          continue;
+        }
+        _ => continue,
+      };
+      slot.get_or_insert_with(|| url.clone());
+    }
+  }
+  links
+}
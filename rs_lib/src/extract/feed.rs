@@ -0,0 +1,292 @@
+//! Feeds: autodiscovery in HTML documents, and parsing of RSS and Atom.
+//!
+//! [`discover_feeds`] finds the feeds an HTML document advertises with
+//! `<link rel="alternate">`, and [`parse_feed`] reads an RSS 2.0, RSS 1.0
+//! (RDF) or Atom document into a [`Feed`]. Elements are matched by their
+//! local name, so namespaced extensions like `content:encoded` and
+//! `dc:creator` are understood regardless of their prefix. Dates are reported
+//! as given, since feeds in the wild use many more formats than the ones
+//! their specifications allow.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::descendants;
+use super::is_element;
+use super::local_name;
+use super::text_content;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+/// The MIME types of feeds that are recognized by [`discover_feeds`].
+pub const FEED_TYPES: &[&str] = &[
+  "application/rss+xml",
+  "application/atom+xml",
+  "application/rdf+xml",
+  "application/feed+json",
+];
+
+/// A feed advertised by an HTML document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedLink {
+  /// The id of the `<link>` element, as in the wire output of the document.
+  pub id:           u32,
+  /// The `href` attribute, as given.
+  pub href:         String,
+  /// The absolute URL `href` resolves to, or `None` if there is none.
+  pub url:          Option<String>,
+  /// The (lowercase) MIME type of the feed, one of [`FEED_TYPES`].
+  #[serde(rename = "type")]
+  pub content_type: String,
+  /// The `title` attribute, if any.
+  pub title:        Option<String>,
+}
+
+/// A parsed RSS or Atom feed.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Feed {
+  /// `"rss"`, `"rdf"` (for RSS 1.0) or `"atom"`.
+  pub format:      &'static str,
+  pub id:          Option<String>,
+  pub title:       Option<String>,
+  /// The URL of the website the feed belongs to.
+  pub link:        Option<String>,
+  /// The `description` (RSS) or `subtitle` (Atom).
+  pub description: Option<String>,
+  pub language:    Option<String>,
+  /// The `lastBuildDate` or `pubDate` (RSS), or `updated` (Atom).
+  pub updated:     Option<String>,
+  pub entries:     Vec<FeedEntry>,
+}
+
+/// An RSS `<item>` or Atom `<entry>`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedEntry {
+  /// The `guid` (RSS) or `id` (Atom).
+  pub id:         Option<String>,
+  pub title:      Option<String>,
+  pub link:       Option<String>,
+  /// The `description` (RSS) or `summary` (Atom).
+  pub summary:    Option<String>,
+  /// The `content:encoded` (RSS) or `content` (Atom).
+  pub content:    Option<String>,
+  pub published:  Option<String>,
+  pub updated:    Option<String>,
+  pub authors:    Vec<String>,
+  pub categories: Vec<String>,
+  pub enclosures: Vec<Enclosure>,
+}
+
+/// A media file attached to a [`FeedEntry`].
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Enclosure {
+  pub url:          String,
+  #[serde(rename = "type")]
+  pub content_type: Option<String>,
+  /// The size in bytes, if given (and valid).
+  pub length:       Option<u64>,
+}
+
+/// Finds the feeds advertised by the HTML document rooted at `document`, i.e.
+/// its `<link rel="alternate">` elements whose `type` is one of
+/// [`FEED_TYPES`]. As for [links](super::links), URLs are resolved against
+/// the document's `<base>` and `document_url`.
+pub fn discover_feeds(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> Vec<FeedLink> {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  descendants(document)
+    .filter(|node| is_element(node, "link"))
+    .filter(|node| {
+      attribute(node, "rel").is_some_and(|rel| {
+        rel
+          .split_ascii_whitespace()
+          .any(|rel| rel.eq_ignore_ascii_case("alternate"))
+      })
+    })
+    .filter_map(|node| {
+      let content_type = attribute(&node, "type")?.trim().to_ascii_lowercase();
+      if !FEED_TYPES.contains(&content_type.as_str()) {
+        return None;
+      }
+      let href = attribute(&node, "href")?.to_string();
+      Some(FeedLink {
+        id: ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX),
+        url: url::resolve(base.as_deref().unwrap_or_default(), &href),
+        href,
+        content_type,
+        title: attribute(&node, "title").map(|title| title.trim().to_string()),
+      })
+    })
+    .collect()
+}
+
+/// Reads the feed document rooted at `document`. Returns `None` if its root
+/// element is not an RSS `<rss>` or `<rdf:RDF>`, or an Atom `<feed>`.
+pub fn parse_feed(document: &Handle) -> Option<Feed> {
+  let root = child_elements(document).next()?;
+  match local_name(&root)? {
+    "rss" => {
+      let channel = child_elements(&root).find(|n| is_element(n, "channel"))?;
+      let items = child_elements(&channel).filter(|n| is_element(n, "item"));
+      Some(Feed {
+        entries: items.map(|item| rss_entry(&item)).collect(),
+        ..rss_channel(&channel, "rss")
+      })
+    }
+    // in RSS 1.0, the items are siblings of the channel
+    "RDF" => {
+      let channel = child_elements(&root).find(|n| is_element(n, "channel"));
+      let items = child_elements(&root).filter(|n| is_element(n, "item"));
+      Some(Feed {
+        entries: items.map(|item| rss_entry(&item)).collect(),
+        ..channel.map_or_else(
+          || Feed {
+            format: "rdf",
+            ..Feed::default()
+          },
+          |channel| rss_channel(&channel, "rdf"),
+        )
+      })
+    }
+    "feed" => {
+      let entries = child_elements(&root).filter(|n| is_element(n, "entry"));
+      Some(Feed {
+        format:      "atom",
+        id:          child_text(&root, "id"),
+        title:       child_text(&root, "title"),
+        link:        atom_link(&root, "alternate"),
+        description: child_text(&root, "subtitle"),
+        language:    attribute(&root, "lang").map(|lang| lang.to_string()),
+        updated:     child_text(&root, "updated"),
+        entries:     entries.map(|entry| atom_entry(&entry)).collect(),
+      })
+    }
+    _ => None,
+  }
+}
+
+fn rss_channel(channel: &Handle, format: &'static str) -> Feed {
+  Feed {
+    format,
+    id: None,
+    title: child_text(channel, "title"),
+    link: child_text(channel, "link"),
+    description: child_text(channel, "description"),
+    language: child_text(channel, "language"),
+    updated: child_text(channel, "lastBuildDate")
+      .or_else(|| child_text(channel, "pubDate"))
+      .or_else(|| child_text(channel, "date")),
+    entries: Vec::new(),
+  }
+}
+
+fn rss_entry(item: &Handle) -> FeedEntry {
+  FeedEntry {
+    id:         child_text(item, "guid"),
+    title:      child_text(item, "title"),
+    link:       child_text(item, "link"),
+    summary:    child_text(item, "description"),
+    content:    child_text(item, "encoded"),
+    published:  child_text(item, "pubDate")
+      .or_else(|| child_text(item, "date")),
+    updated:    None,
+    authors:    all_child_texts(item, "author")
+      .chain(all_child_texts(item, "creator"))
+      .collect(),
+    categories: all_child_texts(item, "category").collect(),
+    enclosures: child_elements(item)
+      .filter(|n| is_element(n, "enclosure"))
+      .filter_map(|node| {
+        Some(Enclosure {
+          url:          attribute(&node, "url")?.trim().to_string(),
+          content_type: attribute(&node, "type").map(|t| t.trim().to_string()),
+          length:       attribute(&node, "length")
+            .and_then(|length| length.trim().parse().ok()),
+        })
+      })
+      .collect(),
+  }
+}
+
+fn atom_entry(entry: &Handle) -> FeedEntry {
+  FeedEntry {
+    id:         child_text(entry, "id"),
+    title:      child_text(entry, "title"),
+    link:       atom_link(entry, "alternate"),
+    summary:    child_text(entry, "summary"),
+    content:    child_text(entry, "content"),
+    published:  child_text(entry, "published"),
+    updated:    child_text(entry, "updated"),
+    authors:    child_elements(entry)
+      .filter(|n| is_element(n, "author"))
+      .filter_map(|author| child_text(&author, "name"))
+      .collect(),
+    categories: child_elements(entry)
+      .filter(|n| is_element(n, "category"))
+      .filter_map(|category| attribute(&category, "term"))
+      .map(|term| term.trim().to_string())
+      .filter(|term| !term.is_empty())
+      .collect(),
+    enclosures: atom_links(entry, "enclosure")
+      .filter_map(|link| {
+        Some(Enclosure {
+          url:          attribute(&link, "href")?.trim().to_string(),
+          content_type: attribute(&link, "type").map(|t| t.trim().to_string()),
+          length:       attribute(&link, "length")
+            .and_then(|length| length.trim().parse().ok()),
+        })
+      })
+      .collect(),
+  }
+}
+
+// Atom links without a `rel` are `alternate` links.
+fn atom_links(node: &Handle, rel: &str) -> impl Iterator<Item = Handle> {
+  let rel = rel.to_string();
+  child_elements(node).filter(move |link| {
+    is_element(link, "link")
+      && attribute(link, "rel").map_or(rel == "alternate", |r| r.trim() == rel)
+  })
+}
+
+fn atom_link(node: &Handle, rel: &str) -> Option<String> {
+  atom_links(node, rel)
+    .find_map(|link| attribute(&link, "href"))
+    .map(|href| href.trim().to_string())
+}
+
+fn child_elements(node: &Handle) -> impl Iterator<Item = Handle> {
+  let children = node.children.borrow().clone();
+  children
+    .into_iter()
+    .filter(|child| local_name(child).is_some())
+}
+
+// The trimmed text of every child element named `name`, skipping empty ones.
+fn all_child_texts(node: &Handle, name: &str) -> impl Iterator<Item = String> {
+  let name = name.to_string();
+  child_elements(node)
+    .filter(move |child| is_element(child, &name))
+    .map(|child| text_content(&child).trim().to_string())
+    .filter(|text| !text.is_empty())
+}
+
+fn child_text(node: &Handle, name: &str) -> Option<String> {
+  all_child_texts(node, name).next()
+}
@@ -0,0 +1,330 @@
+//! Forms and their controls.
+//!
+//! A control belongs to the form it is nested in, unless it names another
+//! form by id with its `form` attribute. Orphaned controls, which belong to
+//! no form, are not reported. The reported values are the initial ones, as
+//! given by the markup, since no script has run on the parsed document.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::collapse_whitespace;
+use super::descendants;
+use super::is_element;
+use super::local_name;
+use super::text_content;
+use crate::Handle;
+use crate::Node;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+// The recognized `type`s of `<input>` elements. Anything else is `text`.
+const INPUT_TYPES: &[&str] = &[
+  "button",
+  "checkbox",
+  "color",
+  "date",
+  "datetime-local",
+  "email",
+  "file",
+  "hidden",
+  "image",
+  "month",
+  "number",
+  "password",
+  "radio",
+  "range",
+  "reset",
+  "search",
+  "submit",
+  "tel",
+  "text",
+  "time",
+  "url",
+  "week",
+];
+
+/// A `<form>` element.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Form {
+  /// The id of the element, as in the wire output of the document.
+  pub id:       u32,
+  pub name:     Option<String>,
+  /// The `action` attribute, as given.
+  pub action:   Option<String>,
+  /// The absolute URL the form is submitted to: `action` resolved against
+  /// the base URL of the document, which is the default for a missing (or
+  /// empty) `action`.
+  pub url:      Option<String>,
+  /// The (lowercase) method, `"get"` (the default), `"post"` or `"dialog"`.
+  pub method:   String,
+  /// The (lowercase) encoding type, `"application/x-www-form-urlencoded"`
+  /// (the default), `"multipart/form-data"` or `"text/plain"`.
+  pub enctype:  String,
+  pub controls: Vec<FormControl>,
+}
+
+/// An `<input>`, `<select>`, `<textarea>` or `<button>` of a [`Form`].
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FormControl {
+  /// The id of the element, as in the wire output of the document.
+  pub id:       u32,
+  /// The (lowercase) tag name.
+  pub tag:      String,
+  pub name:     Option<String>,
+  /// The (lowercase) type, as for the `type` property of the element in the
+  /// DOM, e.g. `"text"`, `"checkbox"`, `"select-one"` or `"textarea"`.
+  #[serde(rename = "type")]
+  pub kind:     String,
+  /// The initial value. For checkboxes and radio buttons, this is the value
+  /// submitted when they are checked; for selects, it is the value of the
+  /// (first) selected option.
+  pub value:    Option<String>,
+  /// The options of a `<select>`, including those in an `<optgroup>`.
+  pub options:  Vec<FormOption>,
+  pub required: bool,
+  /// Whether the control is disabled, by itself or by an ancestor
+  /// `<fieldset disabled>`.
+  pub disabled: bool,
+  /// Whether a checkbox or radio button is initially checked.
+  pub checked:  bool,
+  pub multiple: bool,
+}
+
+/// An `<option>` of a `<select>`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FormOption {
+  pub value:    String,
+  pub label:    String,
+  pub selected: bool,
+  pub disabled: bool,
+}
+
+/// Extracts the [`Form`]s of the document rooted at `document`, in document
+/// order. Form actions are resolved against the document's `<base>` and
+/// `document_url`, like [links](super::links).
+pub fn extract_forms(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> Vec<Form> {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let form_nodes: Vec<Handle> = descendants(document)
+    .filter(|node| is_element(node, "form"))
+    .collect();
+  let mut forms: Vec<Form> = form_nodes
+    .iter()
+    .map(|node| form(node, id_of(&ids, node), base.as_deref()))
+    .collect();
+  let mut by_id = BTreeMap::new();
+  for (index, node) in form_nodes.iter().enumerate() {
+    if let Some(id) = attribute(node, "id") {
+      by_id.entry(id.to_string()).or_insert(index);
+    }
+  }
+  let form_index: BTreeMap<*const Node, usize> = form_nodes
+    .iter()
+    .enumerate()
+    .map(|(index, node)| (Rc::as_ptr(node), index))
+    .collect();
+
+  let mut walker = Walker {
+    forms:      &mut forms,
+    form_index: &form_index,
+    by_id:      &by_id,
+    ids:        &ids,
+  };
+  walker.walk(document, None, false);
+  forms
+}
+
+fn id_of(ids: &BTreeMap<*const Node, u32>, node: &Handle) -> u32 {
+  ids.get(&Rc::as_ptr(node)).copied().unwrap_or(u32::MAX)
+}
+
+fn form(node: &Handle, id: u32, base: Option<&str>) -> Form {
+  let action = attribute(node, "action").map(|action| action.to_string());
+  let url = match action.as_deref().map(str::trim) {
+    Some(action) if !action.is_empty() => {
+      url::resolve(base.unwrap_or_default(), action)
+    }
+    _ => base.map(String::from),
+  };
+  let method = attribute(node, "method")
+    .map(|method| method.trim().to_ascii_lowercase())
+    .filter(|method| matches!(method.as_str(), "get" | "post" | "dialog"))
+    .unwrap_or_else(|| "get".into());
+  let enctype = attribute(node, "enctype")
+    .map(|enctype| enctype.trim().to_ascii_lowercase())
+    .filter(|enctype| {
+      matches!(enctype.as_str(), "multipart/form-data" | "text/plain")
+    })
+    .unwrap_or_else(|| "application/x-www-form-urlencoded".into());
+  Form {
+    id,
+    name: attribute(node, "name").map(|name| name.to_string()),
+    action,
+    url,
+    method,
+    enctype,
+    controls: Vec::new(),
+  }
+}
+
+struct Walker<'a> {
+  forms:      &'a mut Vec<Form>,
+  form_index: &'a BTreeMap<*const Node, usize>,
+  by_id:      &'a BTreeMap<String, usize>,
+  ids:        &'a BTreeMap<*const Node, u32>,
+}
+
+impl Walker<'_> {
+  fn walk(&mut self, node: &Handle, form: Option<usize>, disabled: bool) {
+    for child in node.children.borrow().iter() {
+      let Some(tag) = local_name(child) else {
+        continue;
+      };
+      let mut form = form;
+      let mut disabled = disabled;
+      match tag {
+        "form" => form = self.form_index.get(&Rc::as_ptr(child)).copied(),
+        "fieldset" => disabled |= attribute(child, "disabled").is_some(),
+        "input" | "select" | "textarea" | "button" => {
+          // the `form` attribute overrides the nesting, even if it is invalid
+          let owner = match attribute(child, "form") {
+            Some(id) => self.by_id.get(id.as_ref()).copied(),
+            None => form,
+          };
+          if let Some(owner) = owner {
+            let control = control(child, tag, id_of(self.ids, child), disabled);
+            self.forms[owner].controls.push(control);
+          }
+        }
+        _ => {}
+      }
+      self.walk(child, form, disabled);
+    }
+  }
+}
+
+fn control(
+  node: &Handle,
+  tag: &str,
+  id: u32,
+  fieldset_disabled: bool,
+) -> FormControl {
+  let has = |name: &str| attribute(node, name).is_some();
+  let value = attribute(node, "value").map(|value| value.to_string());
+  let mut control = FormControl {
+    id,
+    tag: tag.to_string(),
+    name: attribute(node, "name").map(|name| name.to_string()),
+    required: has("required"),
+    disabled: fieldset_disabled || has("disabled"),
+    multiple: has("multiple"),
+    ..FormControl::default()
+  };
+  match tag {
+    "input" => {
+      let kind = attribute(node, "type")
+        .map(|kind| kind.trim().to_ascii_lowercase())
+        .filter(|kind| INPUT_TYPES.contains(&kind.as_str()))
+        .unwrap_or_else(|| "text".into());
+      if matches!(kind.as_str(), "checkbox" | "radio") {
+        control.checked = has("checked");
+        control.value = value.or_else(|| Some("on".into()));
+      } else {
+        control.value = value;
+      }
+      control.kind = kind;
+    }
+    "button" => {
+      control.kind = attribute(node, "type")
+        .map(|kind| kind.trim().to_ascii_lowercase())
+        .filter(|kind| matches!(kind.as_str(), "submit" | "reset" | "button"))
+        .unwrap_or_else(|| "submit".into());
+      control.value = value;
+    }
+    "textarea" => {
+      control.kind = "textarea".into();
+      control.value = Some(text_content(node));
+    }
+    _ => {
+      control.kind = if control.multiple {
+        "select-multiple"
+      } else {
+        "select-one"
+      }
+      .into();
+      control.options = select_options(node);
+      if !control.multiple
+        && !control.options.iter().any(|option| option.selected)
+      {
+        // a single select shows its first enabled option when none is selected
+        if let Some(option) =
+          control.options.iter_mut().find(|option| !option.disabled)
+        {
+          option.selected = true;
+        }
+      }
+      control.value = control
+        .options
+        .iter()
+        .find(|option| option.selected)
+        .map(|option| option.value.clone());
+    }
+  }
+  control
+}
+
+// The options of a select are its `<option>` children, and those of its
+// `<optgroup>` children.
+fn select_options(select: &Handle) -> Vec<FormOption> {
+  let mut options = Vec::new();
+  for child in select.children.borrow().iter() {
+    if is_element(child, "option") {
+      options.push(option(child, false));
+    } else if is_element(child, "optgroup") {
+      let disabled = attribute(child, "disabled").is_some();
+      for option_node in child.children.borrow().iter() {
+        if is_element(option_node, "option") {
+          options.push(option(option_node, disabled));
+        }
+      }
+    }
+  }
+  // only the last of several selected options stays selected in a single select
+  if attribute(select, "multiple").is_none()
+    && let Some(last) = options.iter().rposition(|option| option.selected)
+  {
+    for option in &mut options[..last] {
+      option.selected = false;
+    }
+  }
+  options
+}
+
+fn option(node: &Handle, group_disabled: bool) -> FormOption {
+  let text = collapse_whitespace(&text_content(node));
+  FormOption {
+    value:    attribute(node, "value")
+      .map_or_else(|| text.clone(), |value| value.to_string()),
+    label:    attribute(node, "label")
+      .map(|label| label.to_string())
+      .filter(|label| !label.is_empty())
+      .unwrap_or(text),
+    selected: attribute(node, "selected").is_some(),
+    disabled: group_disabled || attribute(node, "disabled").is_some(),
+  }
+}
@@ -0,0 +1,184 @@
+//! The `<meta http-equiv>` directives of a document, which stand in for HTTP
+//! headers: refresh redirects, Content Security Policies, `X-UA-Compatible`,
+//! and the like.
+//!
+//! As in browsers, only the first valid refresh directive counts. The other
+//! directives are reported in document order, along with whether they are
+//! in the `<head>`, as a Content Security Policy outside of it is ignored.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::local_name;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+/// The `http-equiv` directives of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpEquiv {
+  /// The first valid refresh directive.
+  pub refresh:                   Option<Refresh>,
+  /// The `Content-Security-Policy` directives.
+  pub content_security_policies: Vec<HttpEquivDirective>,
+  /// The content of the first `X-UA-Compatible` directive, e.g. `"IE=edge"`.
+  pub x_ua_compatible:           Option<String>,
+  /// All of the other directives, e.g. `content-type` or `default-style`.
+  pub other:                     Vec<HttpEquivDirective>,
+}
+
+/// A refresh directive, which reloads the document or redirects to another
+/// one after a delay.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Refresh {
+  /// The id of the `<meta>` element, as in the wire output of the document.
+  pub id:           u32,
+  /// The delay in seconds (fractions are ignored, as by browsers).
+  pub delay:        u32,
+  /// The URL to redirect to, as given, or `None` to reload the document.
+  pub url:          Option<String>,
+  /// The absolute URL `url` resolves to, or `None` if there is none.
+  pub resolved_url: Option<String>,
+}
+
+/// A `<meta http-equiv>` element.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpEquivDirective {
+  /// The id of the `<meta>` element, as in the wire output of the document.
+  pub id:      u32,
+  /// The (lowercase) `http-equiv` attribute, the name of the header.
+  pub name:    String,
+  pub content: String,
+  /// Whether the element is a descendant of the `<head>`.
+  pub in_head: bool,
+}
+
+/// Extracts the [`HttpEquiv`] directives of the document rooted at
+/// `document`. Refresh URLs are resolved against the document's `<base>` and
+/// `document_url` as for [links](super::links).
+pub fn extract_http_equiv(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> HttpEquiv {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let mut directives = HttpEquiv::default();
+  let mut metas = Vec::new();
+  collect_metas(document, false, &mut metas);
+  for (node, in_head) in metas {
+    let (Some(name), Some(content)) =
+      (attribute(&node, "http-equiv"), attribute(&node, "content"))
+    else {
+      continue;
+    };
+    let id = ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX);
+    let name = name.trim().to_ascii_lowercase();
+    match name.as_str() {
+      "refresh" => {
+        if directives.refresh.is_none()
+          && let Some((delay, url)) = parse_refresh(&content)
+        {
+          directives.refresh = Some(Refresh {
+            id,
+            delay,
+            resolved_url: url.as_deref().and_then(|url| {
+              url::resolve(base.as_deref().unwrap_or_default(), url)
+            }),
+            url,
+          });
+        }
+      }
+      "x-ua-compatible" => {
+        directives
+          .x_ua_compatible
+          .get_or_insert_with(|| content.trim().to_string());
+      }
+      _ => {
+        let directive = HttpEquivDirective {
+          id,
+          content: content.trim().to_string(),
+          in_head,
+          name,
+        };
+        if directive.name == "content-security-policy" {
+          directives.content_security_policies.push(directive);
+        } else {
+          directives.other.push(directive);
+        }
+      }
+    }
+  }
+  directives
+}
+
+// The `<meta>` elements below `node` in document order, each along with
+// whether it is in the `<head>`.
+fn collect_metas(
+  node: &Handle,
+  in_head: bool,
+  metas: &mut Vec<(Handle, bool)>,
+) {
+  for child in node.children.borrow().iter() {
+    match local_name(child) {
+      Some("meta") => metas.push((child.clone(), in_head)),
+      Some("head") => collect_metas(child, true, metas),
+      _ => collect_metas(child, in_head, metas),
+    }
+  }
+}
+
+// Parses the content of a refresh directive as browsers do, e.g. `5`,
+// `0; url=/next` or `3, URL='https://example.com/'`, into its delay and URL.
+fn parse_refresh(content: &str) -> Option<(u32, Option<String>)> {
+  let is_space = |c: char| c.is_ascii_whitespace();
+  let content = content.trim_start_matches(is_space);
+  let digits = content.bytes().take_while(u8::is_ascii_digit).count();
+  if digits == 0 && !content.starts_with('.') {
+    return None;
+  }
+  // a delay like `.5` is `0`, and one too large is as long as possible
+  let delay = match digits {
+    0 => 0,
+    _ => content[..digits].parse().unwrap_or(u32::MAX),
+  };
+  let rest = content[digits..]
+    .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+  if rest.is_empty() {
+    return Some((delay, None));
+  }
+  // the delay has to be followed by whitespace, `;` or `,`
+  if !rest.starts_with(|c: char| is_space(c) || c == ';' || c == ',') {
+    return None;
+  }
+  let rest = rest.trim_start_matches(is_space);
+  let mut url = rest
+    .strip_prefix([';', ','])
+    .unwrap_or(rest)
+    .trim_start_matches(is_space);
+  if url.get(..3).is_some_and(|u| u.eq_ignore_ascii_case("url")) {
+    let after = url[3..].trim_start_matches(is_space);
+    url = after
+      .strip_prefix('=')
+      .map_or(after, |after| after.trim_start_matches(is_space));
+  }
+  let url = match url.chars().next() {
+    Some(quote @ ('\'' | '"')) => {
+      let url = &url[1..];
+      url.find(quote).map_or(url, |end| &url[..end])
+    }
+    _ => url,
+  };
+  let url = url.trim_matches(is_space);
+  Some((delay, (!url.is_empty()).then(|| url.to_string())))
+}
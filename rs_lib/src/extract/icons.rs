@@ -0,0 +1,145 @@
+//! Favicons, touch icons and the web app manifest of a document.
+//!
+//! Icons are the `<link>` elements whose `rel` includes `icon` (as in the
+//! legacy `shortcut icon`), `apple-touch-icon`, `apple-touch-icon-precomposed`
+//! or `mask-icon` (Safari's pinned tab icon). URLs are resolved against the
+//! document's `<base>` and `document_url` as for [links](super::links). A
+//! document without icons usually still has one at `/favicon.ico`, which
+//! browsers request by default, but as that is a guess, it is not reported.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::descendants;
+use super::is_element;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+// The icon link types, in the order in which they are looked for in `rel`.
+const ICON_RELS: &[&str] = &[
+  "icon",
+  "apple-touch-icon",
+  "apple-touch-icon-precomposed",
+  "mask-icon",
+];
+
+/// The icons and manifest of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Icons {
+  /// The icons, in document order.
+  pub icons:    Vec<Icon>,
+  /// The URL of the first `<link rel="manifest">`.
+  pub manifest: Option<String>,
+}
+
+/// An icon `<link>`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Icon {
+  /// The id of the `<link>` element, as in the wire output of the document.
+  pub id:           u32,
+  /// The kind of icon, one of `"icon"`, `"apple-touch-icon"`,
+  /// `"apple-touch-icon-precomposed"` or `"mask-icon"`.
+  pub rel:          &'static str,
+  /// The `href` attribute, as given.
+  pub href:         String,
+  /// The absolute URL `href` resolves to, or `None` if there is none.
+  pub url:          Option<String>,
+  /// The sizes of the `sizes` attribute, in the order given. Invalid sizes
+  /// are skipped.
+  pub sizes:        Vec<IconSize>,
+  /// Whether `sizes` includes `any`, i.e. the icon is scalable (like SVGs).
+  pub any_size:     bool,
+  /// The (lowercase) MIME type, e.g. `"image/png"`.
+  #[serde(rename = "type")]
+  pub content_type: Option<String>,
+  /// The media query the icon is meant for, e.g. for dark mode icons.
+  pub media:        Option<String>,
+  /// The `color` of a mask icon.
+  pub color:        Option<String>,
+}
+
+/// A size of an [`Icon`], in pixels.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct IconSize {
+  pub width:  u32,
+  pub height: u32,
+}
+
+/// Extracts the [`Icons`] of the document rooted at `document`.
+pub fn extract_icons(document: &Handle, document_url: Option<&str>) -> Icons {
+  let base = base_url(document, document_url);
+  let base = base.as_deref().unwrap_or_default();
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let mut icons = Icons::default();
+  for node in descendants(document).filter(|node| is_element(node, "link")) {
+    let (Some(rel), Some(href)) =
+      (attribute(&node, "rel"), attribute(&node, "href"))
+    else {
+      continue;
+    };
+    let rels: Vec<String> = rel
+      .split_ascii_whitespace()
+      .map(|rel| rel.to_ascii_lowercase())
+      .collect();
+    if icons.manifest.is_none() && rels.iter().any(|rel| rel == "manifest") {
+      icons.manifest = url::resolve(base, &href);
+    }
+    let Some(&kind) = ICON_RELS
+      .iter()
+      .find(|kind| rels.iter().any(|rel| rel == *kind))
+    else {
+      continue;
+    };
+    let trimmed = |name| {
+      attribute(&node, name)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    };
+    let sizes = attribute(&node, "sizes").unwrap_or_default();
+    icons.icons.push(Icon {
+      id:           ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX),
+      rel:          kind,
+      url:          url::resolve(base, &href),
+      href:         href.to_string(),
+      sizes:        sizes
+        .split_ascii_whitespace()
+        .filter_map(icon_size)
+        .collect(),
+      any_size:     sizes
+        .split_ascii_whitespace()
+        .any(|size| size.eq_ignore_ascii_case("any")),
+      content_type: trimmed("type").map(|t| t.to_ascii_lowercase()),
+      media:        trimmed("media"),
+      color:        trimmed("color"),
+    });
+  }
+  icons
+}
+
+// Parses a size like `32x32` (or `32X32`). As in the HTML standard, numbers
+// with leading zeros are invalid.
+fn icon_size(size: &str) -> Option<IconSize> {
+  let (width, height) = size.split_once(['x', 'X'])?;
+  let number = |n: &str| {
+    let valid = !n.is_empty()
+      && !n.starts_with('0')
+      && n.bytes().all(|b| b.is_ascii_digit());
+    valid.then(|| n.parse().ok()).flatten()
+  };
+  Some(IconSize {
+    width:  number(width)?,
+    height: number(height)?,
+  })
+}
@@ -0,0 +1,180 @@
+//! Images: `<img>` elements, along with the `<source>`s of their `<picture>`.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::descendants;
+use super::is_element;
+use crate::Handle;
+use crate::Node;
+use crate::rcdom::number_nodes;
+use crate::srcset::SourceSize;
+use crate::srcset::SrcsetCandidate;
+use crate::srcset::parse_sizes;
+use crate::srcset::parse_srcset;
+use crate::url;
+
+/// An `<img>` element.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Image {
+  /// The id of the element, as in the wire output of the document.
+  pub id:             u32,
+  /// The `src` attribute, as given.
+  pub src:            Option<String>,
+  /// The absolute URL `src` resolves to, or `None` if there is none.
+  pub url:            Option<String>,
+  pub srcset:         Vec<SrcsetCandidate>,
+  pub sizes:          Vec<SourceSize>,
+  pub alt:            Option<String>,
+  /// The `width` attribute, in pixels.
+  pub width:          Option<u32>,
+  /// The `height` attribute, in pixels.
+  pub height:         Option<u32>,
+  /// `"eager"` (the default) or `"lazy"`.
+  pub loading:        String,
+  /// `"auto"` (the default), `"sync"` or `"async"`.
+  pub decoding:       String,
+  /// `"auto"` (the default), `"high"` or `"low"`.
+  pub fetch_priority: String,
+  /// The id of the `<picture>` element the image is part of, if any.
+  pub picture:        Option<u32>,
+  /// The `<source>` elements of that `<picture>`, in document order.
+  pub sources:        Vec<ImageSource>,
+}
+
+/// A `<source>` element of a `<picture>`.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageSource {
+  /// The id of the element, as in the wire output of the document.
+  pub id:           u32,
+  pub srcset:       Vec<SrcsetCandidate>,
+  pub sizes:        Vec<SourceSize>,
+  pub media:        Option<String>,
+  /// The MIME type of the images, e.g. `image/avif`.
+  #[serde(rename = "type")]
+  pub content_type: Option<String>,
+  pub width:        Option<u32>,
+  pub height:       Option<u32>,
+}
+
+/// Extracts the [`Image`]s of the document rooted at `document`, in document
+/// order. The URLs of `src` and `srcset` are resolved against the document's
+/// `<base>` and `document_url`, like [links](super::links).
+pub fn extract_images(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> Vec<Image> {
+  let base = base_url(document, document_url);
+  let base = base.as_deref().unwrap_or_default();
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+
+  // the `<img>` of a `<picture>` is its first `<img>` child, and its sources
+  // are the `<source>` children before that
+  let mut pictures: BTreeMap<*const Node, (u32, Vec<ImageSource>)> =
+    BTreeMap::new();
+  for picture in descendants(document).filter(|n| is_element(n, "picture")) {
+    let mut sources = Vec::new();
+    for child in picture.children.borrow().iter() {
+      if is_element(child, "source") {
+        sources.push(ImageSource {
+          id:           id_of(&ids, child),
+          srcset:       srcset(child, base),
+          sizes:        sizes(child),
+          media:        trimmed(child, "media"),
+          content_type: trimmed(child, "type"),
+          width:        dimension(child, "width"),
+          height:       dimension(child, "height"),
+        });
+      } else if is_element(child, "img") {
+        let picture_id = id_of(&ids, &picture);
+        pictures.insert(Rc::as_ptr(child), (picture_id, sources));
+        break;
+      }
+    }
+  }
+
+  descendants(document)
+    .filter(|node| is_element(node, "img"))
+    .map(|node| {
+      let src = attribute(&node, "src").map(|src| src.to_string());
+      let (picture, sources) = pictures
+        .remove(&Rc::as_ptr(&node))
+        .map_or((None, Vec::new()), |(id, sources)| (Some(id), sources));
+      Image {
+        id: id_of(&ids, &node),
+        url: src.as_deref().and_then(|src| url::resolve(base, src)),
+        src,
+        srcset: srcset(&node, base),
+        sizes: sizes(&node),
+        alt: attribute(&node, "alt").map(|alt| alt.to_string()),
+        width: dimension(&node, "width"),
+        height: dimension(&node, "height"),
+        loading: keyword(&node, "loading", &["eager", "lazy"]),
+        decoding: keyword(&node, "decoding", &["auto", "sync", "async"]),
+        fetch_priority: keyword(
+          &node,
+          "fetchpriority",
+          &["auto", "high", "low"],
+        ),
+        picture,
+        sources,
+      }
+    })
+    .collect()
+}
+
+fn id_of(ids: &BTreeMap<*const Node, u32>, node: &Handle) -> u32 {
+  ids.get(&Rc::as_ptr(node)).copied().unwrap_or(u32::MAX)
+}
+
+fn srcset(node: &Handle, base: &str) -> Vec<SrcsetCandidate> {
+  let mut candidates = attribute(node, "srcset")
+    .map(|srcset| parse_srcset(&srcset))
+    .unwrap_or_default();
+  for candidate in &mut candidates {
+    candidate.resolved_url = url::resolve(base, &candidate.url);
+  }
+  candidates
+}
+
+fn sizes(node: &Handle) -> Vec<SourceSize> {
+  attribute(node, "sizes")
+    .map(|sizes| parse_sizes(&sizes))
+    .unwrap_or_default()
+}
+
+fn trimmed(node: &Handle, name: &str) -> Option<String> {
+  attribute(node, name)
+    .map(|value| value.trim().to_string())
+    .filter(|value| !value.is_empty())
+}
+
+// Parses a dimension attribute like browsers do, ignoring anything after the
+// leading digits (so `100px` is `100`).
+fn dimension(node: &Handle, name: &str) -> Option<u32> {
+  let value = attribute(node, name)?;
+  let value = value.trim_start_matches(|c: char| c.is_ascii_whitespace());
+  let end = value
+    .find(|c: char| !c.is_ascii_digit())
+    .unwrap_or(value.len());
+  value[..end].parse().ok()
+}
+
+// Returns the (lowercase) value of an enumerated attribute, or the first of
+// its `keywords` for a missing or invalid value.
+fn keyword(node: &Handle, name: &str, keywords: &[&str]) -> String {
+  attribute(node, name)
+    .map(|value| value.trim().to_ascii_lowercase())
+    .filter(|value| keywords.contains(&value.as_str()))
+    .unwrap_or_else(|| keywords[0].to_string())
+}
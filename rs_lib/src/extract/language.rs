@@ -0,0 +1,158 @@
+//! The languages of a document, for segmenting its text by language.
+//!
+//! The language of an element is given by its `xml:lang` attribute (in the
+//! XML namespace, as on SVG and MathML elements), or else by its `lang`
+//! attribute, or else it is the language of its parent. The root element
+//! without either falls back to the `Content-Language` pragma, i.e. a
+//! `<meta http-equiv="content-language">`. An empty language means that the
+//! language is unknown.
+//!
+//! Rather than one entry per element, the languages are reported as the
+//! [`LanguageRegion`]s in which they change. Since node ids are assigned in
+//! pre-order, the nodes below an element have the ids from the element's id
+//! up to its `last_id`, so the language of any node is that of the innermost
+//! region whose id range contains the node's id.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use html5ever::ns;
+use serde::Serialize;
+
+use super::attribute;
+use super::descendants;
+use super::is_element;
+use crate::Handle;
+use crate::NodeData;
+
+/// The languages of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Languages {
+  /// The language of the root element, if known.
+  pub document:         Option<String>,
+  /// The language declared by the `Content-Language` pragma, if any.
+  pub content_language: Option<String>,
+  /// The elements whose language differs from that of their parent (or, for
+  /// the root element, whose language is known), in document order.
+  pub regions:          Vec<LanguageRegion>,
+}
+
+/// An element whose language differs from that of its parent, along with
+/// the nodes below it.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LanguageRegion {
+  /// The id of the element, as in the wire output of the document.
+  pub id:      u32,
+  /// The id of the last node below the element, or the element's own id if
+  /// it is empty.
+  pub last_id: u32,
+  /// The (case-preserved) language tag, e.g. `"en-US"`, or `""` if unknown.
+  pub lang:    String,
+}
+
+/// Computes the [`Languages`] of the document rooted at `document`. Language
+/// tags are compared case-insensitively, so `en-us` inside of `en-US` does
+/// not start a region.
+pub fn extract_languages(document: &Handle) -> Languages {
+  let content_language = content_language(document);
+  let mut walker = Walker {
+    next:     0,
+    fallback: content_language.clone(),
+    document: None,
+    regions:  Vec::new(),
+  };
+  walker.walk(document, "", false);
+  Languages {
+    document: walker.document.filter(|lang| !lang.is_empty()),
+    content_language,
+    regions: walker.regions,
+  }
+}
+
+// Reads the `Content-Language` pragma like browsers do: the first of the
+// `<meta>`s declaring it wins, and a value listing several languages (with
+// commas) declares none.
+fn content_language(document: &Handle) -> Option<String> {
+  let content = descendants(document)
+    .filter(|node| is_element(node, "meta"))
+    .find(|node| {
+      attribute(node, "http-equiv").is_some_and(|equiv| {
+        equiv.trim().eq_ignore_ascii_case("content-language")
+      })
+    })
+    .and_then(|node| attribute(&node, "content"))?;
+  if content.contains(',') {
+    return None;
+  }
+  let lang = content.split_ascii_whitespace().next()?;
+  Some(lang.to_string())
+}
+
+// The declared language of an element; `xml:lang` takes precedence.
+fn declared_lang(node: &Handle) -> Option<String> {
+  let NodeData::Element { attrs, .. } = &node.data else {
+    return None;
+  };
+  let attrs = attrs.borrow();
+  let find = |namespace| {
+    attrs
+      .iter()
+      .find(|attr| attr.name.ns == namespace && &*attr.name.local == "lang")
+  };
+  find(ns!(xml))
+    .or_else(|| find(ns!()))
+    .map(|attr| attr.value.trim().to_string())
+}
+
+// The walk numbers the nodes like the wire output does.
+struct Walker {
+  next:     u32,
+  fallback: Option<String>,
+  document: Option<String>,
+  regions:  Vec<LanguageRegion>,
+}
+
+impl Walker {
+  fn walk(&mut self, node: &Handle, inherited: &str, is_root: bool) {
+    let id = self.next;
+    self.next += 1;
+    let mut declared = declared_lang(node);
+    if is_root {
+      declared = declared.or_else(|| self.fallback.clone());
+      self.document.clone_from(&declared);
+    }
+    let region = declared
+      .filter(|lang| !lang.eq_ignore_ascii_case(inherited))
+      .map(|lang| {
+        self.regions.push(LanguageRegion {
+          id,
+          last_id: id,
+          lang,
+        });
+        self.regions.len() - 1
+      });
+    let lang = match region {
+      Some(index) => self.regions[index].lang.clone(),
+      None => inherited.to_string(),
+    };
+    let is_document = matches!(node.data, NodeData::Document);
+    for child in node.children.borrow().iter() {
+      let is_root =
+        is_document && matches!(child.data, NodeData::Element { .. });
+      self.walk(child, &lang, is_root);
+    }
+    if let NodeData::Element {
+      template_contents: Some(contents),
+      ..
+    } = &node.data
+    {
+      self.walk(contents, &lang, false);
+    }
+    if let Some(index) = region {
+      self.regions[index].last_id = self.next - 1;
+    }
+  }
+}
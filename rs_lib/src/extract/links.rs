@@ -0,0 +1,81 @@
+//! Links: anchors, image map areas and `<link>` elements.
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::collapse_whitespace;
+use super::descendants;
+use super::local_name;
+use super::text_content;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+/// A link of a document, i.e. an `<a>`, `<area>` or `<link>` element with an
+/// `href` attribute.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Link {
+  /// The id of the element, as in the wire output of the document.
+  pub id:   u32,
+  /// The (lowercase) tag name, `"a"`, `"area"` or `"link"`.
+  pub tag:  String,
+  /// The `href` attribute, as given.
+  pub href: String,
+  /// The absolute URL `href` resolves to, or `None` if there is none. Without
+  /// a base URL, only absolute URLs can be resolved.
+  pub url:  Option<String>,
+  /// The (lowercase) tokens of the `rel` attribute.
+  pub rel:  Vec<String>,
+  /// The text content, with whitespace collapsed. For `<area>` elements,
+  /// which have none, this is their `alt` text instead.
+  pub text: String,
+}
+
+/// Extracts the [`Link`]s of the document rooted at `document`, in document
+/// order. URLs are resolved against the document's `<base>`, if it has one,
+/// which is itself resolved against `document_url`.
+pub fn extract_links(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> Vec<Link> {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  descendants(document)
+    .filter_map(|node| {
+      let tag = local_name(&node)
+        .filter(|tag| matches!(*tag, "a" | "area" | "link"))?;
+      let href = attribute(&node, "href")?.to_string();
+      let text = if tag == "area" {
+        attribute(&node, "alt")
+          .map(|alt| collapse_whitespace(&alt))
+          .unwrap_or_default()
+      } else {
+        collapse_whitespace(&text_content(&node))
+      };
+      Some(Link {
+        id: ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX),
+        tag: tag.to_string(),
+        url: url::resolve(base.as_deref().unwrap_or_default(), &href),
+        rel: attribute(&node, "rel")
+          .map(|rel| {
+            rel
+              .split_ascii_whitespace()
+              .map(|rel| rel.to_ascii_lowercase())
+              .collect()
+          })
+          .unwrap_or_default(),
+        href,
+        text,
+      })
+    })
+    .collect()
+}
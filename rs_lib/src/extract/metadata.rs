@@ -0,0 +1,141 @@
+//! Document metadata: title, description, Open Graph, Twitter cards, ...
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::collapse_whitespace;
+use super::descendants;
+use super::local_name;
+use super::text_content;
+use crate::Handle;
+
+/// The metadata of a document, as found in its `<head>` (or anywhere else,
+/// for documents which misplace their metadata).
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+  /// The text of the first `<title>`, with whitespace collapsed.
+  pub title:       Option<String>,
+  /// The `href` of the first `<link rel="canonical">`.
+  pub canonical:   Option<String>,
+  /// The content of `<meta name="description">`.
+  pub description: Option<String>,
+  /// The declared character encoding, from `<meta charset>` or a
+  /// `Content-Type` declared with `http-equiv`.
+  pub charset:     Option<String>,
+  /// The content of `<meta name="keywords">`, split at commas.
+  pub keywords:    Vec<String>,
+  /// The content of `<meta name="author">`.
+  pub author:      Option<String>,
+  /// The (lowercase) directives of `<meta name="robots">`, e.g. `noindex`.
+  pub robots:      Vec<String>,
+  /// All of the `og:` properties, in document order. Properties such as
+  /// `og:image` may be given more than once.
+  pub open_graph:  BTreeMap<String, Vec<String>>,
+  /// All of the `twitter:` properties, in document order.
+  pub twitter:     BTreeMap<String, Vec<String>>,
+}
+
+/// Extracts the [`Metadata`] of the document rooted at `document`, in a
+/// single pass over the tree.
+pub fn extract_metadata(document: &Handle) -> Metadata {
+  let mut metadata = Metadata::default();
+  for node in descendants(document) {
+    match local_name(&node) {
+      Some("title") if metadata.title.is_none() => {
+        metadata.title = Some(collapse_whitespace(&text_content(&node)));
+      }
+      Some("link") if metadata.canonical.is_none() => {
+        let is_canonical = attribute(&node, "rel").is_some_and(|rel| {
+          rel
+            .split_ascii_whitespace()
+            .any(|rel| rel.eq_ignore_ascii_case("canonical"))
+        });
+        if is_canonical && let Some(href) = attribute(&node, "href") {
+          metadata.canonical = Some(href.trim().to_string());
+        }
+      }
+      Some("meta") => collect_meta(&node, &mut metadata),
+      _ => {}
+    }
+  }
+  metadata
+}
+
+fn collect_meta(node: &Handle, metadata: &mut Metadata) {
+  if let Some(charset) = attribute(node, "charset") {
+    metadata
+      .charset
+      .get_or_insert_with(|| charset.trim().to_string());
+  }
+  let Some(content) = attribute(node, "content") else {
+    return;
+  };
+  let content = content.trim();
+  if let Some(http_equiv) = attribute(node, "http-equiv")
+    && http_equiv.eq_ignore_ascii_case("content-type")
+    && let Some(charset) = charset_of_content_type(content)
+  {
+    metadata.charset.get_or_insert_with(|| charset.to_string());
+  }
+
+  // Open Graph uses `property`, Twitter uses `name`, and many pages mix them
+  let key = attribute(node, "property").or_else(|| attribute(node, "name"));
+  let Some(key) = key else {
+    return;
+  };
+  let key = key.trim().to_ascii_lowercase();
+  match key.as_str() {
+    "description" => {
+      metadata
+        .description
+        .get_or_insert_with(|| content.to_string());
+    }
+    "author" => {
+      metadata.author.get_or_insert_with(|| content.to_string());
+    }
+    "keywords" => metadata.keywords.extend(split_list(content, ',')),
+    "robots" => metadata.robots.extend(
+      split_list(content, ',').map(|directive| directive.to_ascii_lowercase()),
+    ),
+    _ if key.starts_with("og:") => {
+      metadata
+        .open_graph
+        .entry(key)
+        .or_default()
+        .push(content.to_string());
+    }
+    _ if key.starts_with("twitter:") => {
+      metadata
+        .twitter
+        .entry(key)
+        .or_default()
+        .push(content.to_string());
+    }
+    _ => {}
+  }
+}
+
+fn split_list(list: &str, separator: char) -> impl Iterator<Item = String> {
+  list
+    .split(separator)
+    .map(str::trim)
+    .filter(|item| !item.is_empty())
+    .map(|item| item.to_string())
+}
+
+// Extracts the `charset` parameter of a `Content-Type` value.
+fn charset_of_content_type(content_type: &str) -> Option<&str> {
+  content_type.split(';').skip(1).find_map(|param| {
+    let (name, value) = param.split_once('=')?;
+    name
+      .trim()
+      .eq_ignore_ascii_case("charset")
+      .then(|| value.trim().trim_matches(['"', '\'']))
+  })
+}
@@ -0,0 +1,212 @@
+//! Structured data extraction.
+//!
+//! Link-preview services, crawlers and feed readers typically parse a page
+//! only to pull a handful of facts out of it. The extractors in this module
+//! compute those facts in Rust, straight from the parsed tree, so that no
+//! selector engine (or wire output) is needed on the JS side.
+//!
+//! The helpers below are shared by all of the extractors.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::tendril::StrTendril;
+
+use crate::Handle;
+use crate::NodeData;
+pub use crate::traverse::Descendants;
+use crate::url;
+
+pub mod article;
+pub use article::Article;
+
+pub mod canonical;
+pub use canonical::CanonicalLinks;
+
+pub mod feed;
+pub use feed::Feed;
+
+pub mod forms;
+pub use forms::Form;
+
+pub mod http_equiv;
+pub use http_equiv::HttpEquiv;
+
+pub mod icons;
+pub use icons::Icons;
+
+pub mod images;
+pub use images::Image;
+
+pub mod language;
+pub use language::Languages;
+
+pub mod links;
+pub use links::Link;
+
+pub mod metadata;
+pub use metadata::Metadata;
+
+pub mod outline;
+pub use outline::OutlineEntry;
+
+pub mod scripts;
+pub use scripts::Script;
+
+pub mod styles;
+pub use styles::Styles;
+
+pub mod text;
+pub use text::TextStats;
+
+/// Returns an iterator over the descendants of `root`, like
+/// [`Traverse::descendants`](crate::traverse::Traverse::descendants).
+pub fn descendants(root: &Handle) -> Descendants {
+  Descendants::new(root)
+}
+
+/// Returns the local name of an element, or `None` for other nodes.
+pub fn local_name(node: &Handle) -> Option<&str> {
+  match &node.data {
+    NodeData::Element { name, .. } => Some(&name.local),
+    _ => None,
+  }
+}
+
+/// Returns `true` if `node` is an element with the given local name.
+pub fn is_element(node: &Handle, name: &str) -> bool {
+  local_name(node) == Some(name)
+}
+
+/// Returns the value of an element's attribute, looked up by local name.
+pub fn attribute(node: &Handle, name: &str) -> Option<StrTendril> {
+  let NodeData::Element { attrs, .. } = &node.data else {
+    return None;
+  };
+  attrs
+    .borrow()
+    .iter()
+    .find(|attr| attr.name.local.as_ref().eq_ignore_ascii_case(name))
+    .map(|attr| attr.value.clone())
+}
+
+/// Returns the concatenated text of all of the text nodes below `node`.
+pub fn text_content(node: &Handle) -> String {
+  let mut text = String::new();
+  for child in descendants(node) {
+    if let NodeData::Text { contents } = &child.data {
+      text.push_str(&contents.borrow());
+    }
+  }
+  text
+}
+
+/// Collapses runs of ASCII whitespace into single spaces, and trims the
+/// result, as browsers do for `document.title`.
+pub fn collapse_whitespace(text: &str) -> String {
+  text.split_ascii_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the base URL of a document: the `href` of its first `<base>`
+/// element with one, resolved against the URL of the document itself, or else
+/// just `document_url`.
+pub fn base_url(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> Option<String> {
+  descendants(document)
+    .filter(|node| is_element(node, "base"))
+    .find_map(|node| attribute(&node, "href"))
+    .and_then(|href| url::resolve(document_url.unwrap_or_default(), &href))
+    .or_else(|| document_url.map(String::from))
+}
+
+/// Returns the CORS setting of the `crossorigin` attribute of `node`: none
+/// without the attribute, `"use-credentials"`, or else `"anonymous"`, which
+/// is also what empty and invalid values mean.
+pub fn cors_setting(node: &Handle) -> Option<&'static str> {
+  let value = attribute(node, "crossorigin")?;
+  if value.trim().eq_ignore_ascii_case("use-credentials") {
+    Some("use-credentials")
+  } else {
+    Some("anonymous")
+  }
+}
+
+/// Returns `true` if `node` is hidden with the `hidden` attribute, or with an
+/// inline `display: none` or `visibility: hidden` style.
+pub fn is_hidden(node: &Handle) -> bool {
+  let style = attribute(node, "style")
+    .map(|style| style.to_ascii_lowercase().replace(' ', ""))
+    .unwrap_or_default();
+  attribute(node, "hidden").is_some()
+    || style.contains("display:none")
+    || style.contains("visibility:hidden")
+}
+
+/// Returns the text of `node` roughly as it reads, like `innerText` does:
+/// whitespace is collapsed, `<br>`s and block elements start a new line, and
+/// paragraphs, headings, lists and the like are separated by a blank line.
+/// Elements that are not rendered, like scripts, styles, the `<head>` and
+/// [hidden](is_hidden) elements, are skipped.
+pub fn block_text(node: &Handle) -> String {
+  let mut raw = String::new();
+  write_block_text(node, &mut raw);
+  let mut text = String::with_capacity(raw.len());
+  let mut blank = false;
+  for line in raw.split('\n') {
+    let line = line.trim();
+    if line.is_empty() {
+      blank = true;
+      continue;
+    }
+    if !text.is_empty() {
+      text.push_str(if blank { "\n\n" } else { "\n" });
+    }
+    text.push_str(line);
+    blank = false;
+  }
+  text
+}
+
+// Elements whose contents are never rendered.
+const NOT_RENDERED: &[&str] =
+  &["head", "noscript", "script", "style", "template", "title"];
+
+fn write_block_text(node: &Handle, out: &mut String) {
+  for child in node.children.borrow().iter() {
+    match &child.data {
+      NodeData::Text { contents } => {
+        for c in contents.borrow().chars() {
+          if !c.is_ascii_whitespace() {
+            out.push(c);
+          } else if !out.ends_with([' ', '\n']) {
+            out.push(' ');
+          }
+        }
+      }
+      NodeData::Element { name, .. } => {
+        if NOT_RENDERED.contains(&name.local.as_ref()) || is_hidden(child) {
+          continue;
+        }
+        let separator = match name.local.as_ref() {
+          "br" => {
+            out.push('\n');
+            continue;
+          }
+          "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote"
+          | "pre" | "ul" | "ol" | "dl" | "table" | "figure" | "hr" => "\n\n",
+          "div" | "li" | "dt" | "dd" | "tr" | "section" | "article"
+          | "header" | "footer" | "main" | "nav" | "aside" | "address"
+          | "figcaption" | "caption" => "\n",
+          "td" | "th" => " ",
+          _ => "",
+        };
+        out.push_str(separator);
+        write_block_text(child, out);
+        out.push_str(separator);
+      }
+      _ => {}
+    }
+  }
+}
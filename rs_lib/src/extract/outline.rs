@@ -0,0 +1,198 @@
+//! The heading outline of a document, for building tables of contents.
+//!
+//! Headings nest by rank (`<h1>` through `<h6>`), and sectioning content
+//! (`<article>`, `<aside>`, `<nav>` and `<section>`) nests as in the HTML
+//! outline algorithm: the first heading of a section is nested below the
+//! heading of the enclosing section, whatever their ranks, and no heading
+//! inside of a section can close a section outside of it. Headings of
+//! sections without one (untitled sections) are not made up; the headings
+//! nested in those are nested in the enclosing section instead. An
+//! `<hgroup>` counts as its highest ranked heading, and the headings of
+//! sectioning roots like `<blockquote>` and `<td>` are not part of the
+//! outline at all.
+
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::collapse_whitespace;
+use super::descendants;
+use super::local_name;
+use super::text_content;
+use crate::Handle;
+use crate::Node;
+use crate::rcdom::number_nodes;
+
+/// A heading of the outline, with the headings nested below it.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineEntry {
+  /// The id of the heading element, as in the wire output of the document.
+  pub id:               u32,
+  /// The rank of the heading, from `1` for `<h1>` to `6` for `<h6>`.
+  pub level:            u8,
+  /// The text content, with whitespace collapsed.
+  pub text:             String,
+  /// The fragment identifier to link to the heading with: its `id`, or else
+  /// a slug of its text which is unique within the document.
+  pub anchor:           String,
+  /// Whether `anchor` is a generated slug, which has yet to be set as the
+  /// `id` of the heading for links to it to work.
+  pub anchor_generated: bool,
+  pub children:         Vec<OutlineEntry>,
+}
+
+/// Returns the outline of the document rooted at `document`, as the list of
+/// its top level headings.
+pub fn extract_outline(document: &Handle) -> Vec<OutlineEntry> {
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let taken = descendants(document)
+    .filter_map(|node| attribute(&node, "id"))
+    .map(|id| id.to_string())
+    .collect();
+  let mut builder = Builder {
+    ids,
+    taken,
+    entries: Vec::new(),
+    roots: Vec::new(),
+    stack: Vec::new(),
+  };
+  builder.walk(document, 0);
+  builder.build()
+}
+
+// The entries are collected into an arena first, with the stack holding the
+// currently open headings along with their ranks.
+struct Builder {
+  ids:     BTreeMap<*const Node, u32>,
+  taken:   BTreeSet<String>,
+  entries: Vec<(OutlineEntry, Vec<usize>)>,
+  roots:   Vec<usize>,
+  stack:   Vec<(usize, u8)>,
+}
+
+impl Builder {
+  // `base` is the depth of the stack at the start of the innermost section,
+  // below which no heading of the section may pop.
+  fn walk(&mut self, node: &Handle, base: usize) {
+    for child in node.children.borrow().iter() {
+      let Some(tag) = local_name(child) else {
+        continue;
+      };
+      match tag {
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+          self.heading(child, rank(tag), base);
+        }
+        "hgroup" => {
+          // the first of the highest ranked headings
+          let highest = descendants(child)
+            .filter_map(|node| {
+              Some((local_name(&node).and_then(heading_rank)?, node))
+            })
+            .min_by_key(|&(rank, _)| rank);
+          if let Some((rank, heading)) = highest {
+            self.heading(&heading, rank, base);
+          }
+        }
+        "article" | "aside" | "nav" | "section" => {
+          let depth = self.stack.len();
+          self.walk(child, depth);
+          self.stack.truncate(depth);
+        }
+        "blockquote" | "details" | "dialog" | "fieldset" | "figure" | "td" => {}
+        _ => self.walk(child, base),
+      }
+    }
+  }
+
+  fn heading(&mut self, node: &Handle, rank: u8, base: usize) {
+    while self.stack.len() > base
+      && self.stack.last().is_some_and(|&(_, open)| open >= rank)
+    {
+      self.stack.pop();
+    }
+    let text = collapse_whitespace(&text_content(node));
+    let id = attribute(node, "id")
+      .map(|id| id.to_string())
+      .filter(|id| !id.is_empty());
+    let anchor_generated = id.is_none();
+    let anchor = id.unwrap_or_else(|| self.unique_slug(&text));
+    let entry = OutlineEntry {
+      id: self.ids.get(&Rc::as_ptr(node)).copied().unwrap_or(u32::MAX),
+      level: rank,
+      text,
+      anchor,
+      anchor_generated,
+      children: Vec::new(),
+    };
+    let index = self.entries.len();
+    self.entries.push((entry, Vec::new()));
+    match self.stack.last() {
+      Some(&(parent, _)) => self.entries[parent].1.push(index),
+      None => self.roots.push(index),
+    }
+    self.stack.push((index, rank));
+  }
+
+  // Suffixes the slug of `text` with `-1`, `-2`, ... until it is unique.
+  fn unique_slug(&mut self, text: &str) -> String {
+    let slug = slugify(text);
+    let mut candidate = slug.clone();
+    let mut n = 0;
+    while self.taken.contains(&candidate) {
+      n += 1;
+      candidate = format!("{slug}-{n}");
+    }
+    self.taken.insert(candidate.clone());
+    candidate
+  }
+
+  fn build(mut self) -> Vec<OutlineEntry> {
+    let roots = core::mem::take(&mut self.roots);
+    roots.into_iter().map(|root| self.take(root)).collect()
+  }
+
+  fn take(&mut self, index: usize) -> OutlineEntry {
+    let (mut entry, children) = core::mem::take(&mut self.entries[index]);
+    entry.children =
+      children.into_iter().map(|child| self.take(child)).collect();
+    entry
+  }
+}
+
+fn heading_rank(tag: &str) -> Option<u8> {
+  matches!(tag, "h1" | "h2" | "h3" | "h4" | "h5" | "h6").then(|| rank(tag))
+}
+
+fn rank(tag: &str) -> u8 {
+  tag.as_bytes()[1] - b'0'
+}
+
+/// Turns `text` into a fragment identifier like those of headings on GitHub:
+/// lowercase, with whitespace replaced by hyphens and all punctuation but
+/// hyphens and underscores removed. Falls back to `"section"` for text
+/// without any letters or digits.
+pub fn slugify(text: &str) -> String {
+  let mut slug = String::with_capacity(text.len());
+  for c in text.trim().chars() {
+    if c.is_alphanumeric() || c == '_' {
+      slug.extend(c.to_lowercase());
+    } else if (c.is_whitespace() || c == '-') && !slug.ends_with('-') {
+      slug.push('-');
+    }
+  }
+  let slug = slug.trim_matches('-');
+  if slug.is_empty() {
+    "section".to_string()
+  } else {
+    slug.to_string()
+  }
+}
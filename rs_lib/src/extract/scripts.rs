@@ -0,0 +1,146 @@
+//! The scripts of a document, for bundle analysis and Content Security
+//! Policies.
+//!
+//! The kind of a script is determined from its `type` attribute (or the
+//! legacy `language` attribute) as browsers do: a missing or empty type, or
+//! a JavaScript MIME type, makes a classic script, and anything but `module`,
+//! `importmap` and `speculationrules` makes a data block, which browsers do not
+//! run. URLs are resolved against the document's `<base>` and `document_url`
+//! as for [links](super::links).
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::cors_setting;
+use super::descendants;
+use super::is_element;
+use super::text_content;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+// The JavaScript MIME type essences, which all denote classic scripts.
+const JAVASCRIPT_TYPES: &[&str] = &[
+  "application/ecmascript",
+  "application/javascript",
+  "application/x-ecmascript",
+  "application/x-javascript",
+  "text/ecmascript",
+  "text/javascript",
+  "text/javascript1.0",
+  "text/javascript1.1",
+  "text/javascript1.2",
+  "text/javascript1.3",
+  "text/javascript1.4",
+  "text/javascript1.5",
+  "text/jscript",
+  "text/livescript",
+  "text/x-ecmascript",
+  "text/x-javascript",
+];
+
+/// A `<script>` element.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Script {
+  /// The id of the element, as in the wire output of the document.
+  pub id:              u32,
+  /// The kind of script: `"classic"`, `"module"`, `"importmap"`,
+  /// `"speculationrules"`, or for data blocks, `"json"` (for JSON MIME types
+  /// like `application/ld+json`) or `"data"`.
+  pub kind:            &'static str,
+  /// The `type` attribute, as given.
+  #[serde(rename = "type")]
+  pub content_type:    Option<String>,
+  /// The `src` attribute, as given.
+  pub src:             Option<String>,
+  /// The absolute URL `src` resolves to, or `None` if there is none.
+  pub url:             Option<String>,
+  #[serde(rename = "async")]
+  pub is_async:        bool,
+  pub defer:           bool,
+  pub nomodule:        bool,
+  pub integrity:       Option<String>,
+  /// The CORS setting, `"anonymous"` or `"use-credentials"`, if any.
+  pub crossorigin:     Option<&'static str>,
+  pub nonce:           Option<String>,
+  /// The `referrerpolicy` attribute, as given.
+  pub referrer_policy: Option<String>,
+  /// The length of the inline content in bytes (of its UTF-8 encoding), for
+  /// scripts without a `src`.
+  pub inline_length:   Option<u32>,
+}
+
+/// Extracts the [`Script`]s of the document rooted at `document`, in
+/// document order. This includes the `<script>` elements of SVG images.
+pub fn extract_scripts(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> Vec<Script> {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  descendants(document)
+    .filter(|node| is_element(node, "script"))
+    .map(|node| {
+      let trimmed = |name| {
+        attribute(&node, name)
+          .map(|value| value.trim().to_string())
+          .filter(|value| !value.is_empty())
+      };
+      let has = |name| attribute(&node, name).is_some();
+      let src = attribute(&node, "src").map(|src| src.to_string());
+      Script {
+        id: ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX),
+        kind: kind(&node),
+        content_type: attribute(&node, "type").map(|t| t.to_string()),
+        url: src.as_deref().and_then(|src| {
+          url::resolve(base.as_deref().unwrap_or_default(), src)
+        }),
+        is_async: has("async"),
+        defer: has("defer"),
+        nomodule: has("nomodule"),
+        integrity: trimmed("integrity"),
+        crossorigin: cors_setting(&node),
+        nonce: trimmed("nonce"),
+        referrer_policy: trimmed("referrerpolicy"),
+        inline_length: src.is_none().then(|| text_content(&node).len() as u32),
+        src,
+      }
+    })
+    .collect()
+}
+
+fn kind(node: &Handle) -> &'static str {
+  let content_type = match attribute(node, "type") {
+    Some(content_type) => content_type.to_string(),
+    None => match attribute(node, "language") {
+      Some(language) if !language.is_empty() => format!("text/{language}"),
+      _ => String::new(),
+    },
+  };
+  let content_type = content_type.trim().to_ascii_lowercase();
+  let essence = content_type
+    .split(';')
+    .next()
+    .unwrap_or_default()
+    .trim_end();
+  match essence {
+    "" => "classic",
+    "module" => "module",
+    "importmap" => "importmap",
+    "speculationrules" => "speculationrules",
+    // parameters make a JavaScript MIME type a data block
+    _ if JAVASCRIPT_TYPES.contains(&content_type.as_str()) => "classic",
+    _ if essence.ends_with("/json") || essence.ends_with("+json") => "json",
+    _ => "data",
+  }
+}
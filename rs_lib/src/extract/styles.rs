@@ -0,0 +1,138 @@
+//! The stylesheets of a document, and the elements with inline styles.
+//!
+//! Stylesheets, i.e. `<link rel="stylesheet">`s and `<style>` elements, are
+//! listed together in document order, which is the order in which they
+//! cascade. URLs are resolved against the document's `<base>` and
+//! `document_url` as for [links](super::links).
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use super::attribute;
+use super::base_url;
+use super::cors_setting;
+use super::descendants;
+use super::local_name;
+use super::text_content;
+use crate::Handle;
+use crate::rcdom::number_nodes;
+use crate::url;
+
+/// The styles of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Styles {
+  /// The stylesheets, in document order.
+  pub stylesheets:      Vec<Stylesheet>,
+  /// The elements with a `style` attribute, in document order.
+  pub style_attributes: Vec<StyleAttribute>,
+}
+
+/// A `<link rel="stylesheet">` or `<style>` element.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Stylesheet {
+  /// The id of the element, as in the wire output of the document.
+  pub id:            u32,
+  /// The (lowercase) tag name, `"link"` or `"style"`.
+  pub tag:           &'static str,
+  /// The `href` attribute of a `<link>`, as given.
+  pub href:          Option<String>,
+  /// The absolute URL `href` resolves to, or `None` if there is none.
+  pub url:           Option<String>,
+  /// The media query the stylesheet applies to, if any.
+  pub media:         Option<String>,
+  /// The `title`, which names the stylesheet set it belongs to.
+  pub title:         Option<String>,
+  /// Whether this is an alternate stylesheet (`rel="alternate stylesheet"`),
+  /// which does not apply unless chosen by the user.
+  pub alternate:     bool,
+  pub disabled:      bool,
+  pub integrity:     Option<String>,
+  /// The CORS setting, `"anonymous"` or `"use-credentials"`, if any.
+  pub crossorigin:   Option<&'static str>,
+  /// The length of the contents of a `<style>` in bytes (of their UTF-8
+  /// encoding).
+  pub inline_length: Option<u32>,
+}
+
+/// An element with a `style` attribute.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StyleAttribute {
+  /// The id of the element, as in the wire output of the document.
+  pub id:     u32,
+  /// The (lowercase) tag name.
+  pub tag:    String,
+  /// The length of the attribute value in bytes (of its UTF-8 encoding).
+  pub length: u32,
+}
+
+/// Extracts the [`Styles`] of the document rooted at `document`.
+pub fn extract_styles(document: &Handle, document_url: Option<&str>) -> Styles {
+  let base = base_url(document, document_url);
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let mut styles = Styles::default();
+  for node in descendants(document) {
+    let Some(tag) = local_name(&node) else {
+      continue;
+    };
+    let id = ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX);
+    if let Some(style) = attribute(&node, "style") {
+      styles.style_attributes.push(StyleAttribute {
+        id,
+        tag: tag.to_string(),
+        length: style.len() as u32,
+      });
+    }
+    let trimmed = |name| {
+      attribute(&node, name)
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+    };
+    let stylesheet = Stylesheet {
+      id,
+      media: trimmed("media"),
+      title: trimmed("title"),
+      disabled: attribute(&node, "disabled").is_some(),
+      ..Stylesheet::default()
+    };
+    match tag {
+      "link" => {
+        let rels: Vec<String> = attribute(&node, "rel")
+          .unwrap_or_default()
+          .split_ascii_whitespace()
+          .map(|rel| rel.to_ascii_lowercase())
+          .collect();
+        if !rels.iter().any(|rel| rel == "stylesheet") {
+          continue;
+        }
+        let href = attribute(&node, "href").map(|href| href.to_string());
+        styles.stylesheets.push(Stylesheet {
+          tag: "link",
+          url: href.as_deref().and_then(|href| {
+            url::resolve(base.as_deref().unwrap_or_default(), href)
+          }),
+          href,
+          alternate: rels.iter().any(|rel| rel == "alternate"),
+          integrity: trimmed("integrity"),
+          crossorigin: cors_setting(&node),
+          ..stylesheet
+        });
+      }
+      "style" => styles.stylesheets.push(Stylesheet {
+        tag: "style",
+        inline_length: Some(text_content(&node).len() as u32),
+        ..stylesheet
+      }),
+      _ => {}
+    }
+  }
+  styles
+}
@@ -0,0 +1,66 @@
+//! Statistics over the visible text of a document.
+//!
+//! The text is taken as it reads (see [`block_text`]), so scripts, styles and
+//! hidden elements are not counted. Paragraphs are the blocks of text that
+//! are separated by a blank line, i.e. by block elements like `<p>`, lists
+//! or headings. Words are runs of non-whitespace characters that contain at
+//! least one letter or digit, and sentences end at a `.`, `!`, `?` or `…`
+//! (or their full-width forms) followed by whitespace, or at the end of a
+//! paragraph. These are the usual heuristics of word processors, which are
+//! good enough for reading time estimates, but not linguistically exact.
+
+use serde::Serialize;
+
+use super::block_text;
+use crate::Handle;
+
+const SENTENCE_ENDS: &[char] = &['.', '!', '?', '…', '。', '！', '？'];
+
+/// Counts over the visible text of a node.
+#[derive(Serialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct TextStats {
+  pub words:                     u32,
+  /// The number of characters, including spaces but not line breaks.
+  pub characters:                u32,
+  pub non_whitespace_characters: u32,
+  pub sentences:                 u32,
+  pub paragraphs:                u32,
+}
+
+/// Computes the [`TextStats`] of the visible text below `node`.
+pub fn text_stats(node: &Handle) -> TextStats {
+  let text = block_text(node);
+  let mut stats = TextStats::default();
+  for paragraph in text.split("\n\n") {
+    stats.paragraphs += 1;
+    let mut sentence_has_words = false;
+    for word in paragraph.split_whitespace() {
+      if !word.chars().any(char::is_alphanumeric) {
+        continue;
+      }
+      stats.words += 1;
+      sentence_has_words = true;
+      // closing quotes and brackets may follow the end of a sentence
+      let word = word.trim_end_matches(['"', '\'', ')', '”', '’']);
+      if word.ends_with(SENTENCE_ENDS) {
+        stats.sentences += 1;
+        sentence_has_words = false;
+      }
+    }
+    // the last sentence of a paragraph may well lack a full stop
+    if sentence_has_words {
+      stats.sentences += 1;
+    }
+    for c in paragraph.chars().filter(|&c| c != '\n') {
+      stats.characters += 1;
+      if !c.is_whitespace() {
+        stats.non_whitespace_characters += 1;
+      }
+    }
+  }
+  if text.is_empty() {
+    stats.paragraphs = 0;
+  }
+  stats
+}
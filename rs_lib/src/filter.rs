@@ -0,0 +1,187 @@
+//! Attribute collection filters.
+//!
+//! Real-world documents are frequently littered with framework-generated
+//! attributes (`data-v-*`, `data-reactid`, `ng-*`, and friends) that most
+//! consumers never look at. Every one of them still has to be interned and
+//! shipped across the WASM boundary, which adds up quickly on large pages.
+//!
+//! The [`AttributeFilter`] type controls which attributes are collected into
+//! the wire output at all. It can be provided via the `attributeFilter` parse
+//! option in one of the following forms:
+//!
+//! - an array of attribute names, e.g. `["href", "src", "id", "class"]`, which
+//!   acts as an allowlist;
+//! - an object of the shape `{ allow?: string[], deny?: string[] }`, where the
+//!   denylist takes precedence over the allowlist;
+//! - a predicate function `(name, value, tagName) => boolean`, which is called
+//!   once for every attribute and keeps it if a truthy value is returned
+//!   (only with the `wasm` feature). If the predicate throws, parsing fails
+//!   with a [`Handler`](crate::DawmError::Handler) error.
+//!
+//! Attribute names are compared ASCII case-insensitively, and a trailing `*`
+//! matches any attribute starting with the given prefix (e.g. `"data-*"`).
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+#[cfg(feature = "wasm")]
+use js_sys::Array;
+#[cfg(feature = "wasm")]
+use js_sys::Function;
+use serde::Deserialize;
+use serde::Deserializer;
+#[cfg(feature = "wasm")]
+use serde_wasm_bindgen::from_value;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsCast;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+#[cfg(feature = "wasm")]
+use crate::DawmError;
+use crate::error::Result;
+#[cfg(feature = "wasm")]
+use crate::error::error_message;
+
+/// Determines which attributes are collected into the wire output.
+///
+/// See the [module level documentation](crate::filter) for more details.
+#[derive(Debug, Clone, Default)]
+pub enum AttributeFilter {
+  /// Collect every attribute (the default).
+  #[default]
+  All,
+  /// Collect only the attributes matching the `allow` list (if any) and not
+  /// matching the `deny` list.
+  List {
+    allow: Option<Vec<String>>,
+    deny:  Vec<String>,
+  },
+  /// Collect only the attributes for which the JS predicate returns a truthy
+  /// value. The predicate receives `(name, value, tagName)`.
+  #[cfg(feature = "wasm")]
+  Predicate(Function),
+}
+
+impl AttributeFilter {
+  /// Returns `true` if this filter keeps every attribute unconditionally.
+  pub fn is_all(&self) -> bool {
+    matches!(self, AttributeFilter::All)
+  }
+
+  /// Returns a short description of the kind of filter, as echoed in the
+  /// `effectiveOptions` block of the output.
+  pub fn kind(&self) -> &'static str {
+    match self {
+      AttributeFilter::All => "all",
+      AttributeFilter::List { .. } => "list",
+      #[cfg(feature = "wasm")]
+      AttributeFilter::Predicate(_) => "predicate",
+    }
+  }
+
+  /// Returns `true` if the attribute named `name` with the given `value`
+  /// (owned by the element `tag`) should be collected, or the error thrown by
+  /// the predicate.
+  #[cfg_attr(not(feature = "wasm"), allow(unused_variables))]
+  pub fn accepts(&self, name: &str, value: &str, tag: &str) -> Result<bool> {
+    match self {
+      AttributeFilter::All => Ok(true),
+      AttributeFilter::List { allow, deny } => {
+        if deny.iter().any(|p| pattern_matches(p, name)) {
+          return Ok(false);
+        }
+        Ok(match allow {
+          Some(allow) => allow.iter().any(|p| pattern_matches(p, name)),
+          None => true,
+        })
+      }
+      #[cfg(feature = "wasm")]
+      AttributeFilter::Predicate(f) => f
+        .call3(
+          &JsValue::NULL,
+          &JsValue::from_str(name),
+          &JsValue::from_str(value),
+          &JsValue::from_str(tag),
+        )
+        .map(|v| v.is_truthy())
+        .map_err(|thrown| DawmError::Handler {
+          selector: "attributeFilter".into(),
+          message:  error_message(&thrown),
+        }),
+    }
+  }
+}
+
+fn pattern_matches(pattern: &str, name: &str) -> bool {
+  match pattern.strip_suffix('*') {
+    Some(prefix) => {
+      name.len() >= prefix.len()
+        && name.as_bytes()[..prefix.len()]
+          .eq_ignore_ascii_case(prefix.as_bytes())
+    }
+    None => pattern.eq_ignore_ascii_case(name),
+  }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct AttributeFilterLists {
+  allow: Option<Vec<String>>,
+  deny:  Vec<String>,
+}
+
+#[cfg(feature = "wasm")]
+impl<'de> Deserialize<'de> for AttributeFilter {
+  fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    let value: JsValue =
+      serde_wasm_bindgen::preserve::deserialize(deserializer)?;
+    if value.is_null() || value.is_undefined() {
+      return Ok(AttributeFilter::All);
+    }
+    if let Some(f) = value.dyn_ref::<Function>() {
+      return Ok(AttributeFilter::Predicate(f.clone()));
+    }
+    if Array::is_array(&value) {
+      let allow: Vec<String> =
+        from_value(value).map_err(serde::de::Error::custom)?;
+      return Ok(AttributeFilter::List {
+        allow: Some(allow),
+        deny:  Vec::new(),
+      });
+    }
+    let AttributeFilterLists { allow, deny } =
+      from_value(value).map_err(serde::de::Error::custom)?;
+    Ok(AttributeFilter::List { allow, deny })
+  }
+}
+
+// Without JS values to inspect, the forms are told apart by their shape. A
+// predicate cannot be given.
+#[cfg(not(feature = "wasm"))]
+impl<'de> Deserialize<'de> for AttributeFilter {
+  fn deserialize<D>(deserializer: D) -> core::result::Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Form {
+      Allow(Vec<String>),
+      Lists(AttributeFilterLists),
+    }
+    Ok(match Option::<Form>::deserialize(deserializer)? {
+      None => AttributeFilter::All,
+      Some(Form::Allow(allow)) => AttributeFilter::List {
+        allow: Some(allow),
+        deny:  Vec::new(),
+      },
+      Some(Form::Lists(AttributeFilterLists { allow, deny })) => {
+        AttributeFilter::List { allow, deny }
+      }
+    })
+  }
+}
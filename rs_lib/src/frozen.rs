@@ -0,0 +1,581 @@
+//! Immutable snapshots of parsed documents.
+//!
+//! An [`RcDom`] is built to be changed: every node is reference counted, and
+//! its children and attributes sit behind `RefCell`s. A [`FrozenDom`] is a
+//! copy of such a tree that can no longer change, laid out for reading
+//! instead. Its nodes are stored contiguously in tree order, link to their
+//! parents and siblings by index, and keep all of their text in a single
+//! buffer, so walking the tree touches no reference counts or borrow flags,
+//! and the snapshot is [`Send`] and [`Sync`] to share between threads.
+//!
+//! The ids of the nodes are their indices, assigned like the ids of the wire
+//! output of the same tree: in tree order, with the contents of a template
+//! following the template's children as a document fragment that is only
+//! reachable through [`FrozenNode::template_contents`]. A [`SelectorList`]
+//! matches against the nodes of a snapshot like it does against an
+//! [`RcDom`], through [`FrozenNode::matches`], [`FrozenNode::select`] and
+//! [`FrozenNode::select_all`].
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ptr;
+
+use html5ever::QualName;
+use markup5ever::interface::QuirksMode;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::RcDom;
+use crate::SelectorList;
+use crate::selector::Element;
+
+/// An immutable snapshot of a tree. See the [module level
+/// documentation](crate::frozen) for more details.
+#[derive(Debug, Clone)]
+pub struct FrozenDom {
+  nodes:       Vec<Slot>,
+  attrs:       Vec<Attr>,
+  // the text of all of the nodes and attributes, which spans point into
+  text:        String,
+  quirks_mode: QuirksMode,
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+  kind:             Kind,
+  parent:           Option<u32>,
+  first_child:      Option<u32>,
+  last_child:       Option<u32>,
+  previous_sibling: Option<u32>,
+  next_sibling:     Option<u32>,
+  // one past the id of the last node of the subtree
+  end:              u32,
+}
+
+#[derive(Debug, Clone)]
+enum Kind {
+  Document,
+  DocumentFragment,
+  Doctype {
+    name:      Span,
+    public_id: Span,
+    system_id: Span,
+  },
+  Text(Span),
+  Comment(Span),
+  Element {
+    name:              QualName,
+    attrs:             Span,
+    template_contents: Option<u32>,
+  },
+  ProcessingInstruction {
+    target: Span,
+    data:   Span,
+  },
+}
+
+#[derive(Debug, Clone)]
+struct Attr {
+  name:  QualName,
+  value: Span,
+}
+
+// A range of `text`, or of `attrs`.
+#[derive(Debug, Clone, Copy)]
+struct Span {
+  start: u32,
+  end:   u32,
+}
+
+/// The data of a [`FrozenNode`], by the type of the node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrozenData<'a> {
+  /// The document, the root of a snapshot of an [`RcDom`].
+  Document,
+  /// The contents of a template.
+  DocumentFragment,
+  /// A doctype with its name, public id and system id.
+  Doctype {
+    name:      &'a str,
+    public_id: &'a str,
+    system_id: &'a str,
+  },
+  /// A text node with its text.
+  Text(&'a str),
+  /// A comment with its text.
+  Comment(&'a str),
+  /// An element with its name.
+  Element(&'a QualName),
+  /// A processing instruction with its target and data.
+  ProcessingInstruction { target: &'a str, data: &'a str },
+}
+
+impl FrozenDom {
+  /// Takes a snapshot of the document of `dom`.
+  pub fn new(dom: &RcDom) -> Self {
+    let mut frozen = Self::from_node(&dom.document);
+    frozen.quirks_mode = dom.quirks_mode.get();
+    frozen
+  }
+
+  /// Takes a snapshot of the tree rooted at `root`, which becomes the node
+  /// with the id `0`.
+  pub fn from_node(root: &Handle) -> Self {
+    let mut frozen = FrozenDom {
+      nodes:       Vec::new(),
+      attrs:       Vec::new(),
+      text:        String::new(),
+      quirks_mode: QuirksMode::NoQuirks,
+    };
+    frozen.freeze(root, None, false);
+    frozen
+  }
+
+  /// The root of the tree.
+  pub fn root(&self) -> FrozenNode<'_> {
+    FrozenNode { dom: self, id: 0 }
+  }
+
+  /// The node with the id `id`.
+  pub fn node(&self, id: u32) -> Option<FrozenNode<'_>> {
+    ((id as usize) < self.nodes.len()).then_some(FrozenNode { dom: self, id })
+  }
+
+  /// All of the nodes, by their ids, including the contents of templates.
+  pub fn nodes(&self) -> impl ExactSizeIterator<Item = FrozenNode<'_>> {
+    (0..self.nodes.len() as u32).map(|id| FrozenNode { dom: self, id })
+  }
+
+  /// The number of nodes.
+  pub fn len(&self) -> usize {
+    self.nodes.len()
+  }
+
+  /// Whether there are no nodes, which is never the case.
+  pub fn is_empty(&self) -> bool {
+    self.nodes.is_empty()
+  }
+
+  /// The quirks mode of the document, which is no-quirks for snapshots of
+  /// something other than an [`RcDom`].
+  pub fn quirks_mode(&self) -> QuirksMode {
+    self.quirks_mode
+  }
+
+  // Copies `node` and its subtree, as a child of `parent`, returning its
+  // id. Template contents are copied as document fragments.
+  fn freeze(
+    &mut self,
+    node: &Handle,
+    parent: Option<u32>,
+    is_template_contents: bool,
+  ) -> u32 {
+    let id = self.nodes.len() as u32;
+    let kind = match &node.data {
+      NodeData::Document if is_template_contents => Kind::DocumentFragment,
+      NodeData::Document => Kind::Document,
+      NodeData::Doctype {
+        name,
+        public_id,
+        system_id,
+      } => Kind::Doctype {
+        name:      self.push_text(name),
+        public_id: self.push_text(public_id),
+        system_id: self.push_text(system_id),
+      },
+      NodeData::Text { contents } => {
+        Kind::Text(self.push_text(&contents.borrow()))
+      }
+      NodeData::Comment { contents } => Kind::Comment(self.push_text(contents)),
+      NodeData::Element { name, attrs, .. } => {
+        let start = self.attrs.len() as u32;
+        for attr in attrs.borrow().iter() {
+          let value = self.push_text(&attr.value);
+          self.attrs.push(Attr {
+            name: attr.name.clone(),
+            value,
+          });
+        }
+        Kind::Element {
+          name:              name.clone(),
+          attrs:             Span {
+            start,
+            end: self.attrs.len() as u32,
+          },
+          template_contents: None,
+        }
+      }
+      NodeData::ProcessingInstruction { target, contents } => {
+        Kind::ProcessingInstruction {
+          target: self.push_text(target),
+          data:   self.push_text(contents),
+        }
+      }
+    };
+    let previous_sibling =
+      parent.and_then(|parent| self.slot(parent).last_child);
+    self.nodes.push(Slot {
+      kind,
+      parent,
+      first_child: None,
+      last_child: None,
+      previous_sibling,
+      next_sibling: None,
+      end: id + 1,
+    });
+    if let Some(parent) = parent {
+      match previous_sibling {
+        Some(previous) => self.slot_mut(previous).next_sibling = Some(id),
+        None => self.slot_mut(parent).first_child = Some(id),
+      }
+      self.slot_mut(parent).last_child = Some(id);
+    }
+
+    for child in node.children.borrow().iter() {
+      self.freeze(child, Some(id), false);
+    }
+    if let NodeData::Element {
+      template_contents: Some(contents),
+      ..
+    } = &node.data
+    {
+      let fragment = self.freeze(contents, None, true);
+      if let Kind::Element {
+        template_contents, ..
+      } = &mut self.slot_mut(id).kind
+      {
+        *template_contents = Some(fragment);
+      }
+    }
+    self.slot_mut(id).end = self.nodes.len() as u32;
+    id
+  }
+
+  fn push_text(&mut self, text: &str) -> Span {
+    let start = self.text.len() as u32;
+    self.text.push_str(text);
+    Span {
+      start,
+      end: self.text.len() as u32,
+    }
+  }
+
+  fn str(&self, span: Span) -> &str {
+    &self.text[span.start as usize..span.end as usize]
+  }
+
+  fn slot(&self, id: u32) -> &Slot {
+    &self.nodes[id as usize]
+  }
+
+  fn slot_mut(&mut self, id: u32) -> &mut Slot {
+    &mut self.nodes[id as usize]
+  }
+}
+
+impl From<&RcDom> for FrozenDom {
+  fn from(dom: &RcDom) -> Self {
+    FrozenDom::new(dom)
+  }
+}
+
+/// A node of a [`FrozenDom`].
+#[derive(Clone, Copy)]
+pub struct FrozenNode<'a> {
+  dom: &'a FrozenDom,
+  id:  u32,
+}
+
+impl<'a> FrozenNode<'a> {
+  /// The id of the node, which is its index in the snapshot.
+  pub fn id(&self) -> u32 {
+    self.id
+  }
+
+  /// The snapshot the node is part of.
+  pub fn dom(&self) -> &'a FrozenDom {
+    self.dom
+  }
+
+  /// The data of the node.
+  pub fn data(&self) -> FrozenData<'a> {
+    let dom = self.dom;
+    match &self.slot().kind {
+      Kind::Document => FrozenData::Document,
+      Kind::DocumentFragment => FrozenData::DocumentFragment,
+      Kind::Doctype {
+        name,
+        public_id,
+        system_id,
+      } => FrozenData::Doctype {
+        name:      dom.str(*name),
+        public_id: dom.str(*public_id),
+        system_id: dom.str(*system_id),
+      },
+      Kind::Text(text) => FrozenData::Text(dom.str(*text)),
+      Kind::Comment(text) => FrozenData::Comment(dom.str(*text)),
+      Kind::Element { name, .. } => FrozenData::Element(name),
+      Kind::ProcessingInstruction { target, data } => {
+        FrozenData::ProcessingInstruction {
+          target: dom.str(*target),
+          data:   dom.str(*data),
+        }
+      }
+    }
+  }
+
+  /// Whether the node is an element.
+  pub fn is_element(&self) -> bool {
+    matches!(self.slot().kind, Kind::Element { .. })
+  }
+
+  /// The qualified name of an element.
+  pub fn name(&self) -> Option<&'a QualName> {
+    match &self.dom.slot(self.id).kind {
+      Kind::Element { name, .. } => Some(name),
+      _ => None,
+    }
+  }
+
+  /// The local name of an element, like `div`.
+  pub fn local_name(&self) -> Option<&'a str> {
+    self.name().map(|name| &*name.local)
+  }
+
+  /// The attributes of an element, by name and value.
+  pub fn attrs(
+    &self,
+  ) -> impl ExactSizeIterator<Item = (&'a QualName, &'a str)> {
+    let dom = self.dom;
+    let attrs = match &self.dom.slot(self.id).kind {
+      Kind::Element { attrs, .. } => {
+        &dom.attrs[attrs.start as usize..attrs.end as usize]
+      }
+      _ => &[],
+    };
+    attrs.iter().map(|attr| (&attr.name, dom.str(attr.value)))
+  }
+
+  /// The value of the attribute of an element with the local name `name`
+  /// and no namespace.
+  pub fn attr(&self, name: &str) -> Option<&'a str> {
+    self
+      .attrs()
+      .find(|(attr, _)| attr.ns.is_empty() && &*attr.local == name)
+      .map(|(_, value)| value)
+  }
+
+  /// The text of a text node or comment, or the data of a processing
+  /// instruction, like the DOM's `nodeValue`.
+  pub fn value(&self) -> Option<&'a str> {
+    match self.data() {
+      FrozenData::Text(text) | FrozenData::Comment(text) => Some(text),
+      FrozenData::ProcessingInstruction { data, .. } => Some(data),
+      _ => None,
+    }
+  }
+
+  /// The concatenated text of all of the text nodes below the node.
+  pub fn text_content(&self) -> String {
+    let mut text = String::new();
+    for node in self.descendants() {
+      if let FrozenData::Text(contents) = node.data() {
+        text.push_str(contents);
+      }
+    }
+    text
+  }
+
+  /// The document fragment holding the contents of a `<template>`.
+  pub fn template_contents(&self) -> Option<FrozenNode<'a>> {
+    match &self.slot().kind {
+      Kind::Element {
+        template_contents, ..
+      } => template_contents.map(|id| self.at(id)),
+      _ => None,
+    }
+  }
+
+  /// The parent of the node.
+  pub fn parent(&self) -> Option<FrozenNode<'a>> {
+    self.slot().parent.map(|id| self.at(id))
+  }
+
+  /// The first child of the node.
+  pub fn first_child(&self) -> Option<FrozenNode<'a>> {
+    self.slot().first_child.map(|id| self.at(id))
+  }
+
+  /// The last child of the node.
+  pub fn last_child(&self) -> Option<FrozenNode<'a>> {
+    self.slot().last_child.map(|id| self.at(id))
+  }
+
+  /// The sibling before the node.
+  pub fn previous_sibling(&self) -> Option<FrozenNode<'a>> {
+    self.slot().previous_sibling.map(|id| self.at(id))
+  }
+
+  /// The sibling after the node.
+  pub fn next_sibling(&self) -> Option<FrozenNode<'a>> {
+    self.slot().next_sibling.map(|id| self.at(id))
+  }
+
+  /// The children of the node, in order.
+  pub fn children(&self) -> impl Iterator<Item = FrozenNode<'a>> + use<'a> {
+    core::iter::successors(self.first_child(), FrozenNode::next_sibling)
+  }
+
+  /// The ancestors of the node, starting with its parent.
+  pub fn ancestors(&self) -> impl Iterator<Item = FrozenNode<'a>> + use<'a> {
+    core::iter::successors(self.parent(), FrozenNode::parent)
+  }
+
+  /// The descendants of the node in document order, not including the node
+  /// itself or the contents of templates.
+  pub fn descendants(&self) -> FrozenDescendants<'a> {
+    FrozenDescendants {
+      dom:  self.dom,
+      next: self.id + 1,
+      end:  self.slot().end,
+    }
+  }
+
+  /// Whether the node is an element matched by `selectors`, in the quirks
+  /// mode of its document.
+  pub fn matches(&self, selectors: &SelectorList) -> bool {
+    selectors.matches_element_in(self, self.dom.quirks_mode.into())
+  }
+
+  /// The first descendant of the node matched by `selectors`, if any.
+  pub fn select(&self, selectors: &SelectorList) -> Option<FrozenNode<'a>> {
+    self.descendants().find(|node| node.matches(selectors))
+  }
+
+  /// The descendants of the node matched by `selectors`, in document order.
+  pub fn select_all(&self, selectors: &SelectorList) -> Vec<FrozenNode<'a>> {
+    self
+      .descendants()
+      .filter(|node| node.matches(selectors))
+      .collect()
+  }
+
+  fn slot(&self) -> &'a Slot {
+    self.dom.slot(self.id)
+  }
+
+  fn at(&self, id: u32) -> FrozenNode<'a> {
+    FrozenNode { dom: self.dom, id }
+  }
+}
+
+impl PartialEq for FrozenNode<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    ptr::eq(self.dom, other.dom) && self.id == other.id
+  }
+}
+
+impl Eq for FrozenNode<'_> {}
+
+impl fmt::Debug for FrozenNode<'_> {
+  fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+    fmt
+      .debug_struct("FrozenNode")
+      .field("id", &self.id)
+      .field("data", &self.data())
+      .finish()
+  }
+}
+
+/// Iterates over the descendants of a [`FrozenNode`] in document order.
+#[derive(Debug, Clone)]
+pub struct FrozenDescendants<'a> {
+  dom:  &'a FrozenDom,
+  next: u32,
+  end:  u32,
+}
+
+impl<'a> Iterator for FrozenDescendants<'a> {
+  type Item = FrozenNode<'a>;
+
+  fn next(&mut self) -> Option<FrozenNode<'a>> {
+    while self.next < self.end {
+      let id = self.next;
+      let slot = self.dom.slot(id);
+      // template contents are not descendants of the template
+      if let Kind::DocumentFragment = slot.kind {
+        self.next = slot.end;
+        continue;
+      }
+      self.next += 1;
+      return Some(FrozenNode { dom: self.dom, id });
+    }
+    None
+  }
+}
+
+impl Element for FrozenNode<'_> {
+  fn name(&self) -> Option<&QualName> {
+    FrozenNode::name(self)
+  }
+
+  fn any_attribute(
+    &self,
+    predicate: &mut dyn FnMut(&QualName, &str) -> bool,
+  ) -> bool {
+    self.attrs().any(|(name, value)| predicate(name, value))
+  }
+
+  fn parent_element(&self) -> Option<Self> {
+    self.parent().filter(FrozenNode::is_element)
+  }
+
+  fn is_root(&self) -> bool {
+    self
+      .parent()
+      .is_some_and(|parent| matches!(parent.slot().kind, Kind::Document))
+  }
+
+  fn is_empty(&self) -> bool {
+    self.children().all(|child| match child.data() {
+      FrozenData::Element(_) => false,
+      FrozenData::Text(text) => text.is_empty(),
+      _ => true,
+    })
+  }
+
+  fn position(&self, of_type: bool, from_end: bool) -> Option<usize> {
+    let name = FrozenNode::name(self)?;
+    let parent = self.parent()?;
+    let same_kind = |sibling: &FrozenNode| match sibling.name() {
+      Some(other) => {
+        !of_type || (other.ns == name.ns && other.local == name.local)
+      }
+      None => false,
+    };
+    match from_end {
+      true => core::iter::successors(
+        parent.last_child(),
+        FrozenNode::previous_sibling,
+      )
+      .filter(same_kind)
+      .position(|sibling| sibling == *self),
+      false => parent
+        .children()
+        .filter(same_kind)
+        .position(|sibling| sibling == *self),
+    }
+  }
+
+  fn previous_elements(&self) -> Vec<Self> {
+    let mut elements: Vec<Self> = core::iter::successors(
+      self.previous_sibling(),
+      FrozenNode::previous_sibling,
+    )
+    .filter(FrozenNode::is_element)
+    .collect();
+    elements.reverse();
+    elements
+  }
+}
@@ -0,0 +1,193 @@
+//! Stable structural hashes of nodes, for change detection and memoization.
+//!
+//! The hash of a node covers what the node is and contains, but not where it
+//! is: the name and attributes of an element (in any order) along with the
+//! hashes of its children and, for a `<template>`, its contents, or the text
+//! of a text node with whitespace collapsed. Comments, processing
+//! instructions and whitespace-only text do not contribute to the hashes of
+//! their ancestors, so reformatting or commenting markup does not change the
+//! hashes of the elements in it.
+//!
+//! The hashes are 64-bit FNV-1a hashes, which are stable across builds and
+//! platforms, so they can be stored and compared between parses. They are
+//! not cryptographic: telling apart subtrees that were crafted to collide is
+//! not what they are for.
+
+use alloc::vec::Vec;
+use core::hash::Hasher;
+
+use crate::Handle;
+use crate::NodeData;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// The bytes that mark the kind of data fed to the hasher, so that e.g. an
+// attribute name cannot be mistaken for a child's text.
+const DOCUMENT: u8 = 0x01;
+const DOCTYPE: u8 = 0x02;
+const TEXT: u8 = 0x03;
+const COMMENT: u8 = 0x04;
+const INSTRUCTION: u8 = 0x05;
+const ELEMENT: u8 = 0x06;
+const ATTRIBUTE: u8 = 0x07;
+const CHILD: u8 = 0x08;
+const STRINGS: u8 = 0x09;
+const END: u8 = 0xff;
+
+// Also a `Hasher`, for the hashes that are only compared within a run.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Fnv(u64);
+
+impl Fnv {
+  fn new(kind: u8) -> Self {
+    let mut fnv = Self(FNV_OFFSET_BASIS);
+    fnv.write(&[kind]);
+    fnv
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.0 ^= u64::from(byte);
+      self.0 = self.0.wrapping_mul(FNV_PRIME);
+    }
+  }
+
+  // Strings are terminated, so that adjacent ones cannot run together.
+  fn write_str(&mut self, s: &str) {
+    self.write(s.as_bytes());
+    self.write(&[END]);
+  }
+}
+
+impl Default for Fnv {
+  fn default() -> Self {
+    Self(FNV_OFFSET_BASIS)
+  }
+}
+
+impl Hasher for Fnv {
+  fn finish(&self) -> u64 {
+    self.0
+  }
+
+  fn write(&mut self, bytes: &[u8]) {
+    Fnv::write(self, bytes);
+  }
+}
+
+/// Returns the structural hash of `node`.
+pub fn node_hash(node: &Handle) -> u64 {
+  hash(node, &mut None)
+}
+
+/// Returns the structural hashes of `root` and all of its descendants, in
+/// the order of their ids in the wire output: pre-order, with the contents
+/// of a template following the template's children.
+pub fn node_hashes(root: &Handle) -> Vec<u64> {
+  let mut hashes = Some(Vec::new());
+  hash(root, &mut hashes);
+  hashes.unwrap_or_default()
+}
+
+// Hashes `node`, recording the hashes of it and its descendants in wire
+// order if `hashes` is given.
+fn hash(node: &Handle, hashes: &mut Option<Vec<u64>>) -> u64 {
+  let slot = hashes.as_mut().map(|hashes| {
+    hashes.push(0);
+    hashes.len() - 1
+  });
+  let mut fnv = match &node.data {
+    NodeData::Document => Fnv::new(DOCUMENT),
+    NodeData::Doctype {
+      name,
+      public_id,
+      system_id,
+    } => {
+      let mut fnv = Fnv::new(DOCTYPE);
+      fnv.write_str(name);
+      fnv.write_str(public_id);
+      fnv.write_str(system_id);
+      fnv
+    }
+    NodeData::Text { contents } => {
+      let mut fnv = Fnv::new(TEXT);
+      for word in contents.borrow().split_ascii_whitespace() {
+        fnv.write_str(word);
+      }
+      fnv
+    }
+    NodeData::Comment { contents } => {
+      let mut fnv = Fnv::new(COMMENT);
+      fnv.write_str(contents);
+      fnv
+    }
+    NodeData::ProcessingInstruction { target, contents } => {
+      let mut fnv = Fnv::new(INSTRUCTION);
+      fnv.write_str(target);
+      fnv.write_str(contents);
+      fnv
+    }
+    NodeData::Element { name, attrs, .. } => {
+      let mut fnv = Fnv::new(ELEMENT);
+      fnv.write_str(&name.ns);
+      fnv.write_str(&name.local);
+      let attrs = attrs.borrow();
+      let mut sorted: Vec<_> = attrs.iter().collect();
+      sorted.sort_by(|a, b| {
+        (&*a.name.ns, &*a.name.local).cmp(&(&*b.name.ns, &*b.name.local))
+      });
+      for attr in sorted {
+        fnv.write(&[ATTRIBUTE]);
+        fnv.write_str(&attr.name.ns);
+        fnv.write_str(&attr.name.local);
+        fnv.write_str(&attr.value);
+      }
+      fnv
+    }
+  };
+
+  let mut children: Vec<Handle> = node.children.borrow().clone();
+  if let NodeData::Element {
+    template_contents: Some(contents),
+    ..
+  } = &node.data
+  {
+    // the fragment of the contents is numbered after the template's children
+    children.push(contents.clone());
+  }
+  for child in &children {
+    let child_hash = hash(child, hashes);
+    if contributes(child) {
+      fnv.write(&[CHILD]);
+      fnv.write(&child_hash.to_le_bytes());
+    }
+  }
+
+  if let (Some(hashes), Some(slot)) = (hashes.as_mut(), slot) {
+    hashes[slot] = fnv.0;
+  }
+  fnv.0
+}
+
+/// Returns the hash of a table of strings, which covers every string and
+/// their order.
+pub(crate) fn strings_hash<'a>(
+  strings: impl IntoIterator<Item = &'a str>,
+) -> u64 {
+  let mut fnv = Fnv::new(STRINGS);
+  for s in strings {
+    fnv.write_str(s);
+  }
+  fnv.0
+}
+
+fn contributes(node: &Handle) -> bool {
+  match &node.data {
+    NodeData::Comment { .. } | NodeData::ProcessingInstruction { .. } => false,
+    NodeData::Text { contents } => {
+      !contents.borrow().trim_ascii_start().is_empty()
+    }
+    _ => true,
+  }
+}
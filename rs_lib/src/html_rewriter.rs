@@ -0,0 +1,1263 @@
+//! Rewriting HTML as it streams through.
+//!
+//! An [`HtmlRewriter`] rewrites HTML without parsing it into a tree: the
+//! input is tokenized chunk by chunk, the handlers registered with
+//! [`HtmlRewriter::on`] are called for every start tag their selector
+//! matches, and the tokens are written back out as soon as they are
+//! complete, the way the handlers left them. Memory use depends on how
+//! deeply the elements are nested rather than on the size of the input,
+//! which suits proxies rewriting responses on the fly:
+//!
+//! ```js
+//! const rewriter = new HtmlRewriter()
+//!   .on("img", (el) => el.setAttribute("loading", "lazy"))
+//!   .on("script[src*=tracker]", (el) => el.remove());
+//! for await (const chunk of body) yield rewriter.write(chunk);
+//! yield rewriter.end();
+//! ```
+//!
+//! A handler is passed a [`RewriterElement`], through which it can change
+//! the attributes of the element, insert text or HTML around or inside of
+//! it, replace its content, or remove it (with or without its content),
+//! until the handler returns. The handlers of an element are called in the
+//! order they were registered, and the first one that throws stops the
+//! rewriter: the chunk it was called for fails with the error, and so do
+//! the ones after it.
+//!
+//! ## Streaming
+//!
+//! Without a tree builder, elements are opened and closed by their tags,
+//! along with the end tags the parser implies for `<p>`, `<li>`, `<dt>`,
+//! `<dd>`, `<option>` and table parts, which matches the tree for all but
+//! the most broken markup. Selectors are matched against the open elements
+//! and, for sibling combinators and structural pseudo-classes, the earlier
+//! siblings of the element, which are only kept for as long as their
+//! parent is open if a selector needs them. Pseudo-classes that depend on
+//! what follows an element (`:empty`, `:only-child`, `:last-child`,
+//! `:nth-last-child()` and their `-of-type` versions) cannot be matched,
+//! and are rejected. Selectors match the input as it was written, not as
+//! the handlers changed it, and the elements inside of removed content are
+//! not passed to handlers.
+//!
+//! The output is the input written back token by token, which is
+//! equivalent to the input, but not identical to it: attribute values are
+//! always quoted, and character references are written as the characters
+//! they stand for, except for the ones that have to be escaped.
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cell::RefCell;
+use core::cell::RefMut;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::Namespace;
+use html5ever::QualName;
+use html5ever::local_name;
+use html5ever::ns;
+use html5ever::tendril::StrTendril;
+use html5ever::tendril::TendrilSink;
+use html5ever::tokenizer::BufferQueue;
+use html5ever::tokenizer::Doctype;
+use html5ever::tokenizer::EndTag;
+use html5ever::tokenizer::StartTag;
+use html5ever::tokenizer::Tag;
+use html5ever::tokenizer::Token;
+use html5ever::tokenizer::TokenSink;
+use html5ever::tokenizer::TokenSinkResult;
+use html5ever::tokenizer::Tokenizer;
+use html5ever::tokenizer::TokenizerOpts;
+use html5ever::tokenizer::states::RawKind;
+use js_sys::Function;
+use js_sys::Object;
+use serde::Deserialize;
+use serde_wasm_bindgen::from_value;
+use wasm_bindgen::prelude::*;
+
+use crate::Appendable;
+use crate::DawmError;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::QuirksMode;
+use crate::RcDom;
+use crate::SelectorList;
+use crate::dom::create::invalid_name;
+use crate::dom::create::is_valid_attribute_local_name;
+use crate::error::error_message;
+
+/// Rewrites HTML as it streams through, calling handlers for the elements
+/// matched by their selectors.
+///
+/// See the [module level documentation](crate::html_rewriter) for more
+/// details.
+#[wasm_bindgen]
+#[derive(Clone)]
+pub struct HtmlRewriter {
+  inner: Rc<Rewriter>,
+}
+
+struct Rewriter {
+  tokenizer: Tokenizer<RewriteSink>,
+  queue:     BufferQueue,
+  // whether a chunk is being rewritten, so that handlers can neither add
+  // handlers nor write to the rewriter they are called from
+  busy:      Cell<bool>,
+  ended:     Cell<bool>,
+  failed:    Cell<bool>,
+}
+
+type ElementHandler = Box<dyn FnMut(&RewriterElement) -> Result<(), DawmError>>;
+
+struct Handler {
+  selectors: SelectorList,
+  callback:  ElementHandler,
+}
+
+/// An element matched by the selector of a handler of an
+/// {@linkcode HtmlRewriter}, which the handler can change until it returns.
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct RewriterElement {
+  state: Rc<RefCell<ElementState>>,
+}
+
+#[derive(Debug)]
+struct ElementState {
+  name:             QualName,
+  attrs:            Vec<Attribute>,
+  self_closing:     bool,
+  can_have_content: bool,
+  // the HTML to write around and inside of the element
+  before:           String,
+  after:            String,
+  prepend:          String,
+  append:           String,
+  // the HTML replacing the content, or with `Removal::Element`, the element
+  content:          Option<String>,
+  removal:          Removal,
+  // whether the handler the element was passed to is still running
+  active:           bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Removal {
+  None,
+  Element,
+  Tags,
+}
+
+/// How the content passed to the methods of a {@linkcode RewriterElement}
+/// is inserted.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ContentOptions {
+  /// Whether the content is HTML, which is inserted as is, rather than text,
+  /// which is escaped.
+  pub html: bool,
+}
+
+struct RewriteSink {
+  handlers:      RefCell<Vec<Handler>>,
+  // whether closed elements are kept as the earlier siblings of the next
+  // ones, for the selectors that look at them
+  keep_siblings: Cell<bool>,
+  // the document the open elements are a (shallow) copy of, for matching
+  // selectors against
+  root:          Handle,
+  // the quirks mode of the document, once its first tokens have told it
+  quirks_mode:   Cell<Option<QuirksMode>>,
+  open:          RefCell<Vec<Frame>>,
+  // how many of the open elements have their content left out
+  suppressed:    Cell<usize>,
+  output:        RefCell<String>,
+  error:         RefCell<Option<DawmError>>,
+}
+
+// An open element.
+struct Frame {
+  node:       Handle,
+  // what is left to write when the element is closed, unless it is inside
+  // of content that is left out
+  pending:    Option<Pending>,
+  // whether the content of the element is left out
+  suppresses: bool,
+}
+
+struct Pending {
+  append:  String,
+  end_tag: bool,
+  after:   String,
+}
+
+impl Default for HtmlRewriter {
+  fn default() -> Self {
+    let sink = RewriteSink {
+      handlers:      RefCell::default(),
+      keep_siblings: Cell::new(false),
+      root:          Node::new(NodeData::Document),
+      quirks_mode:   Cell::new(None),
+      open:          RefCell::default(),
+      suppressed:    Cell::new(0),
+      output:        RefCell::default(),
+      error:         RefCell::default(),
+    };
+    HtmlRewriter {
+      inner: Rc::new(Rewriter {
+        tokenizer: Tokenizer::new(sink, TokenizerOpts::default()),
+        queue:     BufferQueue::default(),
+        busy:      Cell::new(false),
+        ended:     Cell::new(false),
+        failed:    Cell::new(false),
+      }),
+    }
+  }
+}
+
+#[wasm_bindgen]
+impl HtmlRewriter {
+  /// Creates a rewriter without any handlers.
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Registers `handler` to be called with a {@linkcode RewriterElement}
+  /// for every element matched by `selector`, from the next chunk on.
+  pub fn on(
+    &self,
+    selector: &str,
+    handler: Function,
+  ) -> Result<HtmlRewriter, JsError> {
+    let source = selector.to_string();
+    self.on_element(selector, move |element| {
+      handler
+        .call1(&JsValue::NULL, &element.clone().into())
+        .map(drop)
+        .map_err(|thrown| DawmError::Handler {
+          selector: source.clone(),
+          message:  error_message(&thrown),
+        })
+    })?;
+    Ok(self.clone())
+  }
+
+  /// Rewrites the next chunk of the input, returning the output that is
+  /// complete so far.
+  pub fn write(&self, chunk: &str) -> Result<String, JsError> {
+    Ok(self.feed(chunk)?)
+  }
+
+  /// Ends the input, returning the rest of the output.
+  pub fn end(&self) -> Result<String, JsError> {
+    Ok(self.finish()?)
+  }
+}
+
+impl HtmlRewriter {
+  /// Registers `handler` to be called for every element matched by
+  /// `selector`, like {@linkcode HtmlRewriter.on}.
+  pub fn on_element(
+    &self,
+    selector: &str,
+    handler: impl FnMut(&RewriterElement) -> Result<(), DawmError> + 'static,
+  ) -> Result<(), DawmError> {
+    let selectors = SelectorList::parse(selector)?;
+    if selectors.needs_lookahead() {
+      return Err(DawmError::Syntax(format!(
+        "{selector:?} cannot be matched while streaming, as it depends on \
+         what follows the element"
+      )));
+    }
+    if self.inner.busy.get() {
+      return Err(DawmError::InvalidState(
+        "handlers cannot be added while the rewriter is running".into(),
+      ));
+    }
+    let sink = &self.inner.tokenizer.sink;
+    if selectors.uses_siblings() {
+      sink.keep_siblings.set(true);
+    }
+    sink.handlers.borrow_mut().push(Handler {
+      selectors,
+      callback: Box::new(handler),
+    });
+    Ok(())
+  }
+
+  /// Rewrites the next chunk of the input, like
+  /// {@linkcode HtmlRewriter.write}.
+  pub fn feed(&self, chunk: &str) -> Result<String, DawmError> {
+    let rewriter = self.enter()?;
+    rewriter.queue.push_back(StrTendril::from_slice(chunk));
+    let _ = rewriter.tokenizer.feed(&rewriter.queue);
+    self.leave()
+  }
+
+  /// Ends the input, like {@linkcode HtmlRewriter.end}.
+  pub fn finish(&self) -> Result<String, DawmError> {
+    let rewriter = self.enter()?;
+    rewriter.ended.set(true);
+    rewriter.tokenizer.end();
+    let sink = &rewriter.tokenizer.sink;
+    while !sink.open.borrow().is_empty() {
+      sink.close(false);
+    }
+    self.leave()
+  }
+
+  fn enter(&self) -> Result<&Rewriter, DawmError> {
+    let rewriter = &*self.inner;
+    let state = match () {
+      _ if rewriter.busy.get() => "is running",
+      _ if rewriter.failed.get() => "has stopped after a handler failed",
+      _ if rewriter.ended.get() => "has already ended",
+      _ => {
+        rewriter.busy.set(true);
+        return Ok(rewriter);
+      }
+    };
+    Err(DawmError::InvalidState(format!(
+      "cannot write to a rewriter that {state}"
+    )))
+  }
+
+  fn leave(&self) -> Result<String, DawmError> {
+    let rewriter = &*self.inner;
+    rewriter.busy.set(false);
+    let sink = &rewriter.tokenizer.sink;
+    let output = sink.output.take();
+    match sink.error.take() {
+      Some(error) => {
+        rewriter.failed.set(true);
+        Err(error)
+      }
+      None => Ok(output),
+    }
+  }
+}
+
+#[wasm_bindgen]
+impl RewriterElement {
+  /// The local name of the element.
+  #[wasm_bindgen(getter, js_name = tagName)]
+  pub fn tag_name(&self) -> String {
+    self.state.borrow().name.local.to_string()
+  }
+
+  /// The namespace URL of the element.
+  #[wasm_bindgen(getter, js_name = namespaceURI)]
+  pub fn namespace_uri(&self) -> String {
+    self.state.borrow().name.ns.to_string()
+  }
+
+  /// Whether the element can have content, which void elements (like
+  /// `<img>`) and self-closing foreign elements cannot, so nothing can be
+  /// inserted into them.
+  #[wasm_bindgen(getter, js_name = canHaveContent)]
+  pub fn can_have_content(&self) -> bool {
+    self.state.borrow().can_have_content
+  }
+
+  /// Whether the element has been removed (or replaced).
+  #[wasm_bindgen(getter)]
+  pub fn removed(&self) -> bool {
+    self.state.borrow().removal != Removal::None
+  }
+
+  /// The value of the attribute with the given name, if any.
+  #[wasm_bindgen(js_name = getAttribute)]
+  pub fn get_attribute(&self, name: &str) -> Option<String> {
+    let state = self.state.borrow();
+    let index = state.attribute_index(name)?;
+    Some(state.attrs[index].value.to_string())
+  }
+
+  /// Whether the element has an attribute with the given name.
+  #[wasm_bindgen(js_name = hasAttribute)]
+  pub fn has_attribute(&self, name: &str) -> bool {
+    self.state.borrow().attribute_index(name).is_some()
+  }
+
+  /// The names of the attributes of the element, in order.
+  #[wasm_bindgen(js_name = getAttributeNames)]
+  pub fn get_attribute_names(&self) -> Vec<String> {
+    let state = self.state.borrow();
+    state
+      .attrs
+      .iter()
+      .map(|attr| attr.name.local.to_string())
+      .collect()
+  }
+
+  /// Sets the value of the attribute with the given name, adding it if
+  /// there is none.
+  #[wasm_bindgen(js_name = setAttribute)]
+  pub fn set_attribute(&self, name: &str, value: &str) -> Result<(), JsError> {
+    if !is_valid_attribute_local_name(name) {
+      return Err(invalid_name(name).into());
+    }
+    let mut state = self.active()?;
+    match state.attribute_index(name) {
+      Some(index) => state.attrs[index].value = value.into(),
+      None => {
+        let name = match state.name.ns == ns!(html) {
+          true => LocalName::from(name.to_ascii_lowercase()),
+          false => LocalName::from(name),
+        };
+        state.attrs.push(Attribute {
+          name:  QualName::new(None, ns!(), name),
+          value: value.into(),
+        });
+      }
+    }
+    Ok(())
+  }
+
+  /// Removes the attribute with the given name, if there is one.
+  #[wasm_bindgen(js_name = removeAttribute)]
+  pub fn remove_attribute(&self, name: &str) -> Result<(), JsError> {
+    let mut state = self.active()?;
+    if let Some(index) = state.attribute_index(name) {
+      state.attrs.remove(index);
+    }
+    Ok(())
+  }
+
+  /// Inserts content before the element.
+  pub fn before(
+    &self,
+    content: &str,
+    options: Option<Object>,
+  ) -> Result<(), JsError> {
+    let content = content_html(content, options)?;
+    self.active()?.before.push_str(&content);
+    Ok(())
+  }
+
+  /// Inserts content after the element.
+  pub fn after(
+    &self,
+    content: &str,
+    options: Option<Object>,
+  ) -> Result<(), JsError> {
+    let content = content_html(content, options)?;
+    // content inserted later goes closer to the element
+    self.active()?.after.insert_str(0, &content);
+    Ok(())
+  }
+
+  /// Inserts content at the start of the content of the element.
+  pub fn prepend(
+    &self,
+    content: &str,
+    options: Option<Object>,
+  ) -> Result<(), JsError> {
+    let content = content_html(content, options)?;
+    // content inserted later goes closer to the start tag
+    self.active()?.prepend.insert_str(0, &content);
+    Ok(())
+  }
+
+  /// Inserts content at the end of the content of the element.
+  pub fn append(
+    &self,
+    content: &str,
+    options: Option<Object>,
+  ) -> Result<(), JsError> {
+    let content = content_html(content, options)?;
+    self.active()?.append.push_str(&content);
+    Ok(())
+  }
+
+  /// Replaces the content of the element.
+  #[wasm_bindgen(js_name = setInnerContent)]
+  pub fn set_inner_content(
+    &self,
+    content: &str,
+    options: Option<Object>,
+  ) -> Result<(), JsError> {
+    let content = content_html(content, options)?;
+    let mut state = self.active()?;
+    state.prepend.clear();
+    state.append.clear();
+    state.content = Some(content);
+    Ok(())
+  }
+
+  /// Replaces the element, along with its content.
+  pub fn replace(
+    &self,
+    content: &str,
+    options: Option<Object>,
+  ) -> Result<(), JsError> {
+    let content = content_html(content, options)?;
+    let mut state = self.active()?;
+    state.removal = Removal::Element;
+    state.content = Some(content);
+    Ok(())
+  }
+
+  /// Removes the element, along with its content.
+  pub fn remove(&self) -> Result<(), JsError> {
+    let mut state = self.active()?;
+    state.removal = Removal::Element;
+    state.content = None;
+    Ok(())
+  }
+
+  /// Removes the tags of the element, keeping its content.
+  #[wasm_bindgen(js_name = removeAndKeepContent)]
+  pub fn remove_and_keep_content(&self) -> Result<(), JsError> {
+    self.active()?.removal = Removal::Tags;
+    Ok(())
+  }
+}
+
+impl RewriterElement {
+  fn active(&self) -> Result<RefMut<'_, ElementState>, DawmError> {
+    let state = self.state.borrow_mut();
+    match state.active {
+      true => Ok(state),
+      false => Err(DawmError::InvalidState(
+        "an element can only be changed by its handlers, while they run".into(),
+      )),
+    }
+  }
+}
+
+impl ElementState {
+  fn attribute_index(&self, name: &str) -> Option<usize> {
+    let is_html = self.name.ns == ns!(html);
+    self.attrs.iter().position(|attr| match is_html {
+      true => attr.name.local.as_ref().eq_ignore_ascii_case(name),
+      false => *attr.name.local == *name,
+    })
+  }
+}
+
+impl TokenSink for RewriteSink {
+  type Handle = ();
+
+  fn process_token(&self, token: Token, _line: u64) -> TokenSinkResult<()> {
+    if self.error.borrow().is_some() {
+      return TokenSinkResult::Continue;
+    }
+    if self.quirks_mode.get().is_none() {
+      self.quirks_mode.set(initial_quirks_mode(&token));
+    }
+    match token {
+      Token::TagToken(tag) if tag.kind == StartTag => {
+        return self.start_tag(tag);
+      }
+      Token::TagToken(tag) if tag.kind == EndTag => self.end_tag(&tag.name),
+      Token::CharacterTokens(text) => {
+        if self.writing() {
+          let raw = self.open.borrow().last().is_some_and(|frame| {
+            let NodeData::Element { name, .. } = &frame.node.data else {
+              return false;
+            };
+            name.ns == ns!(html) && is_raw_text(&name.local)
+          });
+          let mut output = self.output.borrow_mut();
+          match raw {
+            true => output.push_str(&text),
+            false => escape_into(&mut output, &text, false),
+          }
+        }
+      }
+      Token::CommentToken(text) => {
+        self.write(&format!("<!--{text}-->"));
+      }
+      Token::DoctypeToken(doctype) => self.write(&doctype_html(&doctype)),
+      // the tree builder ignores the null characters the tokenizer reports
+      // (within raw text, they come as U+FFFD characters instead)
+      Token::NullCharacterToken
+      | Token::EOFToken
+      | Token::ParseError(_)
+      | Token::TagToken(_) => {}
+    }
+    TokenSinkResult::Continue
+  }
+
+  fn adjusted_current_node_present_but_not_in_html_namespace(&self) -> bool {
+    self.open.borrow().last().is_some_and(|frame| {
+      matches!(&frame.node.data, NodeData::Element { name, .. } if name.ns != ns!(html))
+    })
+  }
+}
+
+impl RewriteSink {
+  fn start_tag(&self, mut tag: Tag) -> TokenSinkResult<()> {
+    self.close_implied(&tag);
+    let ns = self.child_namespace(&tag.name);
+    if ns == ns!(svg) {
+      tag.name = svg_name(&tag.name, SVG_TAG_NAMES);
+      for attr in &mut tag.attrs {
+        attr.name.local = svg_name(&attr.name.local, SVG_ATTRIBUTE_NAMES);
+      }
+    } else if ns == ns!(mathml) {
+      for attr in &mut tag.attrs {
+        if attr.name.local == local_name!("definitionurl") {
+          attr.name.local = LocalName::from("definitionURL");
+        }
+      }
+    }
+    let name = QualName::new(None, ns, tag.name);
+    let can_have_content = match name.ns {
+      ns!(html) => !is_void(&name.local),
+      _ => !tag.self_closing,
+    };
+    let raw_kind = match name.ns == ns!(html) && can_have_content {
+      true => raw_kind(&name.local),
+      false => TokenSinkResult::Continue,
+    };
+
+    let node = Node::new(NodeData::Element {
+      name: name.clone(),
+      attrs: RefCell::new(tag.attrs.clone()),
+      template_contents: None,
+      mathml_annotation_xml_integration_point: false,
+    });
+    self.current().append(node.clone());
+
+    let mut frame = Frame {
+      node:       node.clone(),
+      pending:    None,
+      suppresses: false,
+    };
+    if self.writing() {
+      let element = RewriterElement {
+        state: Rc::new(RefCell::new(ElementState {
+          name,
+          attrs: tag.attrs,
+          self_closing: tag.self_closing,
+          can_have_content,
+          before: String::new(),
+          after: String::new(),
+          prepend: String::new(),
+          append: String::new(),
+          content: None,
+          removal: Removal::None,
+          active: true,
+        })),
+      };
+      // a start tag settles the quirks mode, if nothing before it did
+      let quirks_mode = self.quirks_mode.get().unwrap_or(QuirksMode::Quirks);
+      for handler in self.handlers.borrow_mut().iter_mut() {
+        if !handler.selectors.matches_in(&node, quirks_mode) {
+          continue;
+        }
+        if let Err(error) = (handler.callback)(&element) {
+          self.error.replace(Some(error));
+          return raw_kind;
+        }
+      }
+      let mut state = element.state.borrow_mut();
+      state.active = false;
+      frame = self.write_start(&mut state, node);
+    }
+
+    match can_have_content {
+      true => {
+        if frame.suppresses {
+          self.suppressed.set(self.suppressed.get() + 1);
+        }
+        self.open.borrow_mut().push(frame);
+      }
+      false => {
+        self.open.borrow_mut().push(frame);
+        self.close(false);
+      }
+    }
+    raw_kind
+  }
+
+  // Writes what comes before the content of an element, returning its
+  // frame with what is left to write after its content.
+  fn write_start(&self, state: &mut ElementState, node: Handle) -> Frame {
+    let mut output = self.output.borrow_mut();
+    output.push_str(&state.before);
+    let content = state.can_have_content;
+    let suppresses = match state.removal {
+      Removal::Element => {
+        output.push_str(state.content.as_deref().unwrap_or_default());
+        true
+      }
+      removal => {
+        if removal == Removal::None {
+          start_tag_into(&mut output, state);
+        }
+        if content {
+          output.push_str(&state.prepend);
+          output.push_str(state.content.as_deref().unwrap_or_default());
+        }
+        content && state.content.is_some()
+      }
+    };
+    let append = match state.removal != Removal::Element && content {
+      true => core::mem::take(&mut state.append),
+      false => String::new(),
+    };
+    Frame {
+      node,
+      pending: Some(Pending {
+        append,
+        end_tag: state.removal == Removal::None,
+        after: core::mem::take(&mut state.after),
+      }),
+      suppresses,
+    }
+  }
+
+  fn end_tag(&self, name: &LocalName) {
+    // end tags are lowercase, while the names of foreign elements may not be
+    let index = self.open.borrow().iter().rposition(|frame| {
+      matches!(&frame.node.data, NodeData::Element { name: open, .. }
+        if open.local.eq_ignore_ascii_case(name))
+    });
+    match index {
+      Some(index) => {
+        while self.open.borrow().len() > index + 1 {
+          self.close(false);
+        }
+        self.close(true);
+      }
+      None => self.write(&format!("</{name}>")),
+    }
+  }
+
+  // Closes the innermost open element, with or without an end tag in the
+  // input.
+  fn close(&self, explicit: bool) {
+    let Some(frame) = self.open.borrow_mut().pop() else {
+      return;
+    };
+    if frame.suppresses {
+      self.suppressed.set(self.suppressed.get() - 1);
+    }
+    if let Some(pending) = frame.pending
+      && self.writing()
+    {
+      let mut output = self.output.borrow_mut();
+      output.push_str(&pending.append);
+      if pending.end_tag
+        && explicit
+        && let NodeData::Element { name, .. } = &frame.node.data
+      {
+        output.push_str(&format!("</{}>", name.local));
+      }
+      output.push_str(&pending.after);
+    }
+    // the content of a closed element is never looked at again, and the
+    // element itself only as an earlier sibling
+    frame.node.children.take();
+    if !self.keep_siblings.get() {
+      self.current().children.borrow_mut().pop();
+    }
+  }
+
+  // Closes the elements the parser would close before `tag` is opened.
+  fn close_implied(&self, tag: &Tag) {
+    if self.in_foreign_content() {
+      if !breaks_out(tag) {
+        return;
+      }
+      while self.in_foreign_content() {
+        self.close(false);
+      }
+    }
+    let name = &*tag.name;
+    const BUTTON_SCOPE: &[&str] = &[
+      "applet", "button", "caption", "html", "marquee", "object", "table",
+      "td", "template", "th",
+    ];
+    if closes_p(name) {
+      self.close_up_to(&["p"], BUTTON_SCOPE);
+    }
+    match name {
+      "li" => self.close_up_to(
+        &["li"],
+        &["html", "menu", "ol", "table", "td", "template", "th", "ul"],
+      ),
+      "dd" | "dt" => self.close_up_to(
+        &["dd", "dt"],
+        &["dl", "html", "table", "td", "template", "th"],
+      ),
+      "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+        self.close_up_to(&["h1", "h2", "h3", "h4", "h5", "h6"], &[])
+      }
+      "option" => self.close_up_to(&["option"], &[]),
+      "optgroup" => {
+        self.close_up_to(&["option"], &[]);
+        self.close_up_to(&["optgroup"], &[]);
+      }
+      "tr" => self.close_up_to(
+        &["tr"],
+        &["html", "table", "tbody", "template", "tfoot", "thead"],
+      ),
+      "td" | "th" => self.close_up_to(
+        &["td", "th"],
+        &["html", "table", "tbody", "template", "tfoot", "thead", "tr"],
+      ),
+      "tbody" | "tfoot" | "thead" => self.close_up_to(
+        &["tbody", "tfoot", "thead"],
+        &["html", "table", "template"],
+      ),
+      _ => {}
+    }
+  }
+
+  // Closes the innermost open HTML element named one of `names`, and the
+  // ones inside of it, unless one named one of `boundaries` comes first.
+  // Without boundaries, only the innermost element is looked at.
+  fn close_up_to(&self, names: &[&str], boundaries: &[&str]) {
+    let index = {
+      let open = self.open.borrow();
+      let mut index = None;
+      for (i, frame) in open.iter().enumerate().rev() {
+        let NodeData::Element { name, .. } = &frame.node.data else {
+          break;
+        };
+        if name.ns == ns!(html) && names.contains(&&*name.local) {
+          index = Some(i);
+          break;
+        }
+        if boundaries.is_empty()
+          || name.ns != ns!(html)
+          || boundaries.contains(&&*name.local)
+        {
+          break;
+        }
+      }
+      index
+    };
+    if let Some(index) = index {
+      while self.open.borrow().len() > index {
+        self.close(false);
+      }
+    }
+  }
+
+  // The namespace of an element named `name` opened at the current
+  // position.
+  fn child_namespace(&self, name: &LocalName) -> Namespace {
+    let parent = self.open.borrow().last().map(|frame| frame.node.clone());
+    let parent = parent.as_ref().and_then(|node| match &node.data {
+      NodeData::Element { name, .. } => Some(name.clone()),
+      _ => None,
+    });
+    match parent {
+      _ if *name == local_name!("svg") => ns!(svg),
+      _ if *name == local_name!("math") => ns!(mathml),
+      Some(parent) if !is_integration_point(&parent) => parent.ns,
+      _ => ns!(html),
+    }
+  }
+
+  // Whether the innermost open element is foreign, and not an integration
+  // point, where HTML elements would go inside of it.
+  fn in_foreign_content(&self) -> bool {
+    self.open.borrow().last().is_some_and(|frame| {
+      matches!(&frame.node.data, NodeData::Element { name, .. }
+        if name.ns != ns!(html) && !is_integration_point(name))
+    })
+  }
+
+  // Where the next element goes in the copy of the open elements.
+  fn current(&self) -> Handle {
+    match self.open.borrow().last() {
+      Some(frame) => frame.node.clone(),
+      None => self.root.clone(),
+    }
+  }
+
+  // Whether output is written at the current position.
+  fn writing(&self) -> bool {
+    self.suppressed.get() == 0
+  }
+
+  fn write(&self, html: &str) {
+    if self.writing() {
+      self.output.borrow_mut().push_str(html);
+    }
+  }
+}
+
+fn start_tag_into(output: &mut String, state: &ElementState) {
+  output.push('<');
+  output.push_str(&state.name.local);
+  for attr in &state.attrs {
+    output.push(' ');
+    output.push_str(&attr.name.local);
+    output.push_str("=\"");
+    escape_into(output, &attr.value, true);
+    output.push('"');
+  }
+  if state.self_closing {
+    output.push('/');
+  }
+  output.push('>');
+}
+
+// The quirks mode of a document starting with `token`, if the token tells:
+// a doctype sets it, and anything but a comment or whitespace before one
+// puts the document in quirks mode, like it does for the tree builder.
+fn initial_quirks_mode(token: &Token) -> Option<QuirksMode> {
+  match token {
+    Token::DoctypeToken(doctype) if doctype.force_quirks => {
+      Some(QuirksMode::Quirks)
+    }
+    // html5ever keeps its tables of legacy doctypes to itself, so it is
+    // asked to parse the doctype on its own
+    Token::DoctypeToken(doctype) => {
+      let dom = html5ever::parse_document(RcDom::default(), Default::default())
+        .one(doctype_html(doctype));
+      Some(dom.quirks_mode.get().into())
+    }
+    Token::CharacterTokens(text)
+      if text
+        .trim_start_matches(['\t', '\n', '\x0c', '\r', ' '])
+        .is_empty() =>
+    {
+      None
+    }
+    Token::CommentToken(_) | Token::ParseError(_) | Token::EOFToken => None,
+    _ => Some(QuirksMode::Quirks),
+  }
+}
+
+fn doctype_html(doctype: &Doctype) -> String {
+  let mut html = String::from("<!DOCTYPE");
+  if let Some(name) = &doctype.name {
+    html.push(' ');
+    html.push_str(name);
+  }
+  match (&doctype.public_id, &doctype.system_id) {
+    (Some(public_id), system_id) => {
+      html.push_str(&format!(" PUBLIC \"{public_id}\""));
+      if let Some(system_id) = system_id {
+        html.push_str(&format!(" \"{system_id}\""));
+      }
+    }
+    (None, Some(system_id)) => {
+      html.push_str(&format!(" SYSTEM \"{system_id}\""));
+    }
+    (None, None) => {}
+  }
+  html.push('>');
+  html
+}
+
+// Escapes text like the HTML serializer does.
+fn escape_into(output: &mut String, text: &str, attribute: bool) {
+  for c in text.chars() {
+    match c {
+      '&' => output.push_str("&amp;"),
+      '\u{a0}' => output.push_str("&nbsp;"),
+      '"' if attribute => output.push_str("&quot;"),
+      '<' if !attribute => output.push_str("&lt;"),
+      '>' if !attribute => output.push_str("&gt;"),
+      c => output.push(c),
+    }
+  }
+}
+
+fn content_html(
+  content: &str,
+  options: Option<Object>,
+) -> Result<String, DawmError> {
+  let ContentOptions { html } = match options {
+    Some(options) => from_value(options.into()).map_err(DawmError::Options)?,
+    None => ContentOptions::default(),
+  };
+  Ok(match html {
+    true => content.into(),
+    false => {
+      let mut escaped = String::with_capacity(content.len());
+      escape_into(&mut escaped, content, false);
+      escaped
+    }
+  })
+}
+
+fn is_void(name: &str) -> bool {
+  matches!(
+    name,
+    "area"
+      | "base"
+      | "basefont"
+      | "bgsound"
+      | "br"
+      | "col"
+      | "embed"
+      | "frame"
+      | "hr"
+      | "img"
+      | "input"
+      | "keygen"
+      | "link"
+      | "meta"
+      | "param"
+      | "source"
+      | "track"
+      | "wbr"
+  )
+}
+
+// Whether the text of an HTML element is written as is.
+fn is_raw_text(name: &str) -> bool {
+  matches!(
+    name,
+    "iframe"
+      | "noembed"
+      | "noframes"
+      | "plaintext"
+      | "script"
+      | "style"
+      | "xmp"
+  )
+}
+
+// The tokenizer state for the content of an HTML element.
+fn raw_kind(name: &str) -> TokenSinkResult<()> {
+  match name {
+    "script" => TokenSinkResult::RawData(RawKind::ScriptData),
+    "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+      TokenSinkResult::RawData(RawKind::Rawtext)
+    }
+    "title" | "textarea" => TokenSinkResult::RawData(RawKind::Rcdata),
+    "plaintext" => TokenSinkResult::Plaintext,
+    _ => TokenSinkResult::Continue,
+  }
+}
+
+// Whether a start tag closes an open `<p>`.
+fn closes_p(name: &str) -> bool {
+  matches!(
+    name,
+    "address"
+      | "article"
+      | "aside"
+      | "blockquote"
+      | "center"
+      | "dd"
+      | "details"
+      | "dialog"
+      | "dir"
+      | "div"
+      | "dl"
+      | "dt"
+      | "fieldset"
+      | "figcaption"
+      | "figure"
+      | "footer"
+      | "form"
+      | "h1"
+      | "h2"
+      | "h3"
+      | "h4"
+      | "h5"
+      | "h6"
+      | "header"
+      | "hgroup"
+      | "hr"
+      | "li"
+      | "listing"
+      | "main"
+      | "menu"
+      | "nav"
+      | "ol"
+      | "p"
+      | "plaintext"
+      | "pre"
+      | "search"
+      | "section"
+      | "summary"
+      | "table"
+      | "ul"
+      | "xmp"
+  )
+}
+
+// Whether a start tag in foreign content closes the foreign elements, like
+// the tree builder does.
+fn breaks_out(tag: &Tag) -> bool {
+  match &*tag.name {
+    "font" => tag
+      .attrs
+      .iter()
+      .any(|attr| matches!(&*attr.name.local, "color" | "face" | "size")),
+    name => matches!(
+      name,
+      "b"
+        | "big"
+        | "blockquote"
+        | "body"
+        | "br"
+        | "center"
+        | "code"
+        | "dd"
+        | "div"
+        | "dl"
+        | "dt"
+        | "em"
+        | "embed"
+        | "h1"
+        | "h2"
+        | "h3"
+        | "h4"
+        | "h5"
+        | "h6"
+        | "head"
+        | "hr"
+        | "i"
+        | "img"
+        | "li"
+        | "listing"
+        | "menu"
+        | "meta"
+        | "nobr"
+        | "ol"
+        | "p"
+        | "pre"
+        | "ruby"
+        | "s"
+        | "small"
+        | "span"
+        | "strike"
+        | "strong"
+        | "sub"
+        | "sup"
+        | "table"
+        | "tt"
+        | "u"
+        | "ul"
+        | "var"
+    ),
+  }
+}
+
+// Whether HTML elements go inside of the foreign element `name`.
+fn is_integration_point(name: &QualName) -> bool {
+  match name.ns {
+    ns!(svg) => matches!(&*name.local, "foreignObject" | "desc" | "title"),
+    ns!(mathml) => matches!(&*name.local, "mi" | "mo" | "mn" | "ms" | "mtext"),
+    _ => false,
+  }
+}
+
+// The names of SVG elements and attributes that are not all lowercase,
+// which the tokenizer lowercases.
+const SVG_TAG_NAMES: &[&str] = &[
+  "altGlyph",
+  "altGlyphDef",
+  "altGlyphItem",
+  "animateColor",
+  "animateMotion",
+  "animateTransform",
+  "clipPath",
+  "feBlend",
+  "feColorMatrix",
+  "feComponentTransfer",
+  "feComposite",
+  "feConvolveMatrix",
+  "feDiffuseLighting",
+  "feDisplacementMap",
+  "feDistantLight",
+  "feDropShadow",
+  "feFlood",
+  "feFuncA",
+  "feFuncB",
+  "feFuncG",
+  "feFuncR",
+  "feGaussianBlur",
+  "feImage",
+  "feMerge",
+  "feMergeNode",
+  "feMorphology",
+  "feOffset",
+  "fePointLight",
+  "feSpecularLighting",
+  "feSpotLight",
+  "feTile",
+  "feTurbulence",
+  "foreignObject",
+  "glyphRef",
+  "linearGradient",
+  "radialGradient",
+  "textPath",
+];
+
+const SVG_ATTRIBUTE_NAMES: &[&str] = &[
+  "attributeName",
+  "attributeType",
+  "baseFrequency",
+  "baseProfile",
+  "calcMode",
+  "clipPathUnits",
+  "diffuseConstant",
+  "edgeMode",
+  "filterUnits",
+  "glyphRef",
+  "gradientTransform",
+  "gradientUnits",
+  "kernelMatrix",
+  "kernelUnitLength",
+  "keyPoints",
+  "keySplines",
+  "keyTimes",
+  "lengthAdjust",
+  "limitingConeAngle",
+  "markerHeight",
+  "markerUnits",
+  "markerWidth",
+  "maskContentUnits",
+  "maskUnits",
+  "numOctaves",
+  "pathLength",
+  "patternContentUnits",
+  "patternTransform",
+  "patternUnits",
+  "pointsAtX",
+  "pointsAtY",
+  "pointsAtZ",
+  "preserveAlpha",
+  "preserveAspectRatio",
+  "primitiveUnits",
+  "refX",
+  "refY",
+  "repeatCount",
+  "repeatDur",
+  "requiredExtensions",
+  "requiredFeatures",
+  "specularConstant",
+  "specularExponent",
+  "spreadMethod",
+  "startOffset",
+  "stdDeviation",
+  "stitchTiles",
+  "surfaceScale",
+  "systemLanguage",
+  "tableValues",
+  "targetX",
+  "targetY",
+  "textLength",
+  "viewBox",
+  "viewTarget",
+  "xChannelSelector",
+  "yChannelSelector",
+  "zoomAndPan",
+];
+
+fn svg_name(name: &LocalName, names: &[&str]) -> LocalName {
+  names
+    .iter()
+    .find(|adjusted| adjusted.eq_ignore_ascii_case(name))
+    .map_or_else(|| name.clone(), |adjusted| LocalName::from(*adjusted))
+}
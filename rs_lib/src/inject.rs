@@ -0,0 +1,228 @@
+//! Declarative attribute injection.
+//!
+//! Many hygiene fixes for HTML come down to adding an attribute to the
+//! elements that lack it: `loading="lazy"` on images, `rel="noopener
+//! noreferrer"` on links opening a new tab, `referrerpolicy` on iframes,
+//! and so on. An [`AttributeInjection`] writes such a fix down as a
+//! selector and the attributes to add to the elements it matches:
+//!
+//! ```js
+//! parse_html(html, {
+//!   injectAttributes: [
+//!     { selector: "img", attributes: { loading: "lazy" } },
+//!     {
+//!       selector: "a[target=_blank]",
+//!       external: true,
+//!       mode: "merge",
+//!       attributes: { rel: "noopener noreferrer" },
+//!     },
+//!   ],
+//! });
+//! ```
+//!
+//! The [`InjectMode`] of an injection decides what happens to attributes
+//! that an element already has. With `external` set, only the elements
+//! linking to another site are matched: the ones whose `href` (or else
+//! `src`, or `action`) has a host other than the one of the document's
+//! base URL, which without a `<base>` means any host at all.
+//!
+//! Injections run as part of the parse pipeline with the `injectAttributes`
+//! option, right before the document is serialized, or on their own with
+//! `inject_attributes`. They are applied in the order they are given, and
+//! each one sees the attributes the ones before it added.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::QualName;
+use html5ever::ns;
+use serde::Deserialize;
+use serde::Deserializer;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::RcDom;
+use crate::SelectorList;
+use crate::dom::create::is_valid_attribute_local_name;
+use crate::extract::attribute;
+use crate::extract::base_url;
+use crate::extract::descendants;
+use crate::url;
+
+/// A set of attributes to add to the elements matched by a selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeInjection {
+  /// The elements the attributes are added to.
+  pub selector:   SelectorList,
+  /// The (lowercase) names of the attributes, with their values.
+  pub attributes: Vec<(String, String)>,
+  /// What happens to the attributes an element already has.
+  pub mode:       InjectMode,
+  /// Whether only the elements linking to another site are matched.
+  pub external:   bool,
+}
+
+/// What an [`AttributeInjection`] does with an attribute that an element
+/// already has.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InjectMode {
+  /// Leave it as it is, i.e. only add missing attributes.
+  #[default]
+  Missing,
+  /// Replace its value.
+  Replace,
+  /// Add the (space-separated) tokens of the value that it is missing,
+  /// compared ignoring ASCII case, like for `rel` or `class`.
+  Merge,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InjectionSpec {
+  selector:   String,
+  attributes: BTreeMap<String, String>,
+  #[serde(default)]
+  mode:       InjectMode,
+  #[serde(default)]
+  external:   bool,
+}
+
+impl<'de> Deserialize<'de> for AttributeInjection {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: Deserializer<'de>,
+  {
+    use serde::de::Error;
+
+    let spec = InjectionSpec::deserialize(deserializer)?;
+    let selector =
+      SelectorList::parse(&spec.selector).map_err(D::Error::custom)?;
+    let attributes = spec
+      .attributes
+      .into_iter()
+      .map(|(name, value)| match is_valid_attribute_local_name(&name) {
+        true => Ok((name.to_ascii_lowercase(), value)),
+        false => Err(D::Error::custom(format!(
+          "{name:?} is not a valid attribute name"
+        ))),
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(AttributeInjection {
+      selector,
+      attributes,
+      mode: spec.mode,
+      external: spec.external,
+    })
+  }
+}
+
+/// Adds the attributes of `injections` to the matching elements of `dom`,
+/// matched in its quirks mode, returning how many elements were changed.
+pub fn inject_attributes(
+  dom: &RcDom,
+  injections: &[AttributeInjection],
+) -> usize {
+  let root = &dom.document;
+  let quirks_mode = dom.quirks_mode.get().into();
+  let base = match injections.iter().any(|injection| injection.external) {
+    true => base_url(root, None),
+    false => None,
+  };
+  let mut changed = 0;
+  for node in descendants(root) {
+    let NodeData::Element { name, attrs, .. } = &node.data else {
+      continue;
+    };
+    let is_html = name.ns == ns!(html);
+    let mut touched = false;
+    for injection in injections {
+      if !injection.selector.matches_in(&node, quirks_mode)
+        || (injection.external && !is_external(&node, base.as_deref()))
+      {
+        continue;
+      }
+      let mut attrs = attrs.borrow_mut();
+      for (name, value) in &injection.attributes {
+        touched |= inject(&mut attrs, name, value, injection.mode, is_html);
+      }
+    }
+    changed += usize::from(touched);
+  }
+  changed
+}
+
+// Adds or updates a single attribute, returning whether anything changed.
+fn inject(
+  attrs: &mut Vec<Attribute>,
+  name: &str,
+  value: &str,
+  mode: InjectMode,
+  is_html: bool,
+) -> bool {
+  let existing = attrs.iter_mut().find(|attr| {
+    attr.name.ns == ns!()
+      && match is_html {
+        true => attr.name.local.as_ref().eq_ignore_ascii_case(name),
+        false => *attr.name.local == *name,
+      }
+  });
+  let Some(attr) = existing else {
+    attrs.push(Attribute {
+      name:  QualName::new(None, ns!(), LocalName::from(name)),
+      value: value.into(),
+    });
+    return true;
+  };
+  let updated = match mode {
+    InjectMode::Missing => return false,
+    InjectMode::Replace => String::from(value),
+    InjectMode::Merge => {
+      let mut missing: Vec<&str> = Vec::new();
+      for token in value.split_ascii_whitespace() {
+        if !attr
+          .value
+          .split_ascii_whitespace()
+          .chain(missing.iter().copied())
+          .any(|other| other.eq_ignore_ascii_case(token))
+        {
+          missing.push(token);
+        }
+      }
+      if missing.is_empty() {
+        return false;
+      }
+      let existing = attr
+        .value
+        .trim_end_matches(|c: char| c.is_ascii_whitespace());
+      match existing.is_empty() {
+        true => missing.join(" "),
+        false => format!("{existing} {}", missing.join(" ")),
+      }
+    }
+  };
+  if *attr.value == *updated {
+    return false;
+  }
+  attr.value = updated.into();
+  true
+}
+
+// Whether the URL of an element points to another host than `base`.
+fn is_external(node: &Handle, base: Option<&str>) -> bool {
+  let Some(link) = ["href", "src", "action"]
+    .into_iter()
+    .find_map(|name| attribute(node, name))
+  else {
+    return false;
+  };
+  let resolved = base.and_then(|base| url::resolve(base, &link));
+  let Some(host) = url::host(resolved.as_deref().unwrap_or(&link)) else {
+    return false;
+  };
+  base.and_then(url::host).is_none_or(|base| base != host)
+}
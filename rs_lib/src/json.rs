@@ -0,0 +1,650 @@
+//! JSON output of the wire format, for hosts without a JS engine.
+//!
+//! [`to_string`] writes any [`Serialize`] value (usually a [`WireDoc`]) as
+//! compact JSON, in the shape the JS entry points hand it to JavaScript:
+//!
+//! - `None` and units are `null`, and so are non-finite floats;
+//! - integers of any width are plain numbers, and bytes arrays of them;
+//! - maps are objects, with string, character, boolean or integer keys;
+//! - enum variants are externally tagged, like `{"Variant": value}`, with unit
+//!   variants as the bare `"Variant"` string.
+//!
+//! [`WireDoc`]: crate::WireDoc
+
+use alloc::string::String;
+use alloc::string::ToString;
+use core::fmt;
+use core::fmt::Write;
+
+use derive_more::with_trait::Display;
+use derive_more::with_trait::Error;
+use serde::Serialize;
+use serde::ser;
+
+/// The error returned when a value cannot be written as JSON.
+#[derive(Debug, Display, Error)]
+#[display("{_0}")]
+pub struct JsonError(#[error(not(source))] String);
+
+impl ser::Error for JsonError {
+  fn custom<T: fmt::Display>(message: T) -> Self {
+    JsonError(message.to_string())
+  }
+}
+
+/// Writes `value` as compact JSON.
+pub fn to_string<T: Serialize + ?Sized>(
+  value: &T,
+) -> Result<String, JsonError> {
+  let mut writer = JsonWriter { out: String::new() };
+  value.serialize(&mut writer)?;
+  Ok(writer.out)
+}
+
+struct JsonWriter {
+  out: String,
+}
+
+impl JsonWriter {
+  // Writing into a `String` cannot fail, so the results of `fmt::Write` are
+  // ignored below.
+  fn write_display(&mut self, value: impl fmt::Display) {
+    let _ = write!(self.out, "{value}");
+  }
+
+  fn write_string(&mut self, s: &str) {
+    self.out.push('"');
+    let mut rest = s;
+    while let Some(index) =
+      rest.find(|c: char| matches!(c, '"' | '\\' | '\0'..='\x1f'))
+    {
+      self.out.push_str(&rest[..index]);
+      let c = rest.as_bytes()[index];
+      match c {
+        b'"' => self.out.push_str("\\\""),
+        b'\\' => self.out.push_str("\\\\"),
+        b'\n' => self.out.push_str("\\n"),
+        b'\r' => self.out.push_str("\\r"),
+        b'\t' => self.out.push_str("\\t"),
+        0x08 => self.out.push_str("\\b"),
+        0x0c => self.out.push_str("\\f"),
+        _ => {
+          let _ = write!(self.out, "\\u{c:04x}");
+        }
+      }
+      rest = &rest[index + 1..];
+    }
+    self.out.push_str(rest);
+    self.out.push('"');
+  }
+
+  // Opens an object with the single key `variant`, for the value of an enum
+  // variant.
+  fn open_variant(&mut self, variant: &str) {
+    self.out.push('{');
+    self.write_string(variant);
+    self.out.push(':');
+  }
+}
+
+impl<'a> ser::Serializer for &'a mut JsonWriter {
+  type Ok = ();
+  type Error = JsonError;
+  type SerializeSeq = Compound<'a>;
+  type SerializeTuple = Compound<'a>;
+  type SerializeTupleStruct = Compound<'a>;
+  type SerializeTupleVariant = Compound<'a>;
+  type SerializeMap = Compound<'a>;
+  type SerializeStruct = Compound<'a>;
+  type SerializeStructVariant = Compound<'a>;
+
+  fn serialize_bool(self, v: bool) -> Result<(), JsonError> {
+    self.write_display(v);
+    Ok(())
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<(), JsonError> {
+    self.serialize_i64(v.into())
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<(), JsonError> {
+    self.serialize_i64(v.into())
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<(), JsonError> {
+    self.serialize_i64(v.into())
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+    self.write_display(v);
+    Ok(())
+  }
+
+  fn serialize_i128(self, v: i128) -> Result<(), JsonError> {
+    self.write_display(v);
+    Ok(())
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<(), JsonError> {
+    self.serialize_u64(v.into())
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<(), JsonError> {
+    self.serialize_u64(v.into())
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<(), JsonError> {
+    self.serialize_u64(v.into())
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+    self.write_display(v);
+    Ok(())
+  }
+
+  fn serialize_u128(self, v: u128) -> Result<(), JsonError> {
+    self.write_display(v);
+    Ok(())
+  }
+
+  fn serialize_f32(self, v: f32) -> Result<(), JsonError> {
+    self.serialize_f64(v.into())
+  }
+
+  fn serialize_f64(self, v: f64) -> Result<(), JsonError> {
+    match v.is_finite() {
+      true => self.write_display(v),
+      false => self.out.push_str("null"),
+    }
+    Ok(())
+  }
+
+  fn serialize_char(self, v: char) -> Result<(), JsonError> {
+    self.write_string(v.encode_utf8(&mut [0; 4]));
+    Ok(())
+  }
+
+  fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+    self.write_string(v);
+    Ok(())
+  }
+
+  fn serialize_bytes(self, v: &[u8]) -> Result<(), JsonError> {
+    ser::Serializer::collect_seq(self, v)
+  }
+
+  fn serialize_none(self) -> Result<(), JsonError> {
+    self.serialize_unit()
+  }
+
+  fn serialize_some<T: Serialize + ?Sized>(
+    self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    value.serialize(self)
+  }
+
+  fn serialize_unit(self) -> Result<(), JsonError> {
+    self.out.push_str("null");
+    Ok(())
+  }
+
+  fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+    self.serialize_unit()
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    variant: &'static str,
+  ) -> Result<(), JsonError> {
+    self.write_string(variant);
+    Ok(())
+  }
+
+  fn serialize_newtype_struct<T: Serialize + ?Sized>(
+    self,
+    _name: &'static str,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: Serialize + ?Sized>(
+    self,
+    _name: &'static str,
+    _index: u32,
+    variant: &'static str,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.open_variant(variant);
+    value.serialize(&mut *self)?;
+    self.out.push('}');
+    Ok(())
+  }
+
+  fn serialize_seq(
+    self,
+    _len: Option<usize>,
+  ) -> Result<Compound<'a>, JsonError> {
+    self.out.push('[');
+    Ok(Compound::new(self, "]"))
+  }
+
+  fn serialize_tuple(self, len: usize) -> Result<Compound<'a>, JsonError> {
+    self.serialize_seq(Some(len))
+  }
+
+  fn serialize_tuple_struct(
+    self,
+    _name: &'static str,
+    len: usize,
+  ) -> Result<Compound<'a>, JsonError> {
+    self.serialize_seq(Some(len))
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Compound<'a>, JsonError> {
+    self.open_variant(variant);
+    self.out.push('[');
+    Ok(Compound::new(self, "]}"))
+  }
+
+  fn serialize_map(
+    self,
+    _len: Option<usize>,
+  ) -> Result<Compound<'a>, JsonError> {
+    self.out.push('{');
+    Ok(Compound::new(self, "}"))
+  }
+
+  fn serialize_struct(
+    self,
+    _name: &'static str,
+    len: usize,
+  ) -> Result<Compound<'a>, JsonError> {
+    self.serialize_map(Some(len))
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    variant: &'static str,
+    _len: usize,
+  ) -> Result<Compound<'a>, JsonError> {
+    self.open_variant(variant);
+    self.out.push('{');
+    Ok(Compound::new(self, "}}"))
+  }
+}
+
+// An array or object being written, along with what closes it.
+struct Compound<'a> {
+  writer: &'a mut JsonWriter,
+  first:  bool,
+  close:  &'static str,
+}
+
+impl<'a> Compound<'a> {
+  fn new(writer: &'a mut JsonWriter, close: &'static str) -> Self {
+    Compound {
+      writer,
+      first: true,
+      close,
+    }
+  }
+
+  // Writes the comma before every element but the first.
+  fn separate(&mut self) {
+    match self.first {
+      true => self.first = false,
+      false => self.writer.out.push(','),
+    }
+  }
+
+  fn element<T: Serialize + ?Sized>(
+    &mut self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.separate();
+    value.serialize(&mut *self.writer)
+  }
+
+  fn key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), JsonError> {
+    self.separate();
+    key.serialize(KeyWriter(self.writer))?;
+    self.writer.out.push(':');
+    Ok(())
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    self.writer.out.push_str(self.close);
+    Ok(())
+  }
+}
+
+impl ser::SerializeSeq for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_element<T: Serialize + ?Sized>(
+    &mut self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.element(value)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+impl ser::SerializeTuple for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_element<T: Serialize + ?Sized>(
+    &mut self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.element(value)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+impl ser::SerializeTupleStruct for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_field<T: Serialize + ?Sized>(
+    &mut self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.element(value)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+impl ser::SerializeTupleVariant for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_field<T: Serialize + ?Sized>(
+    &mut self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.element(value)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+impl ser::SerializeMap for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_key<T: Serialize + ?Sized>(
+    &mut self,
+    key: &T,
+  ) -> Result<(), JsonError> {
+    self.key(key)
+  }
+
+  fn serialize_value<T: Serialize + ?Sized>(
+    &mut self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    value.serialize(&mut *self.writer)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+impl ser::SerializeStruct for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_field<T: Serialize + ?Sized>(
+    &mut self,
+    key: &'static str,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.key(key)?;
+    value.serialize(&mut *self.writer)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+impl ser::SerializeStructVariant for Compound<'_> {
+  type Ok = ();
+  type Error = JsonError;
+
+  fn serialize_field<T: Serialize + ?Sized>(
+    &mut self,
+    key: &'static str,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    self.key(key)?;
+    value.serialize(&mut *self.writer)
+  }
+
+  fn end(self) -> Result<(), JsonError> {
+    Compound::end(self)
+  }
+}
+
+// Writes the key of an object, which JSON only allows to be a string.
+struct KeyWriter<'a>(&'a mut JsonWriter);
+
+impl KeyWriter<'_> {
+  fn write_quoted(self, key: impl fmt::Display) -> Result<(), JsonError> {
+    self.0.write_string(&key.to_string());
+    Ok(())
+  }
+}
+
+// The error for the keys that are not strings, numbers or booleans.
+fn key_error() -> JsonError {
+  JsonError("object keys must be strings, numbers or booleans".into())
+}
+
+impl ser::Serializer for KeyWriter<'_> {
+  type Ok = ();
+  type Error = JsonError;
+  type SerializeSeq = ser::Impossible<(), JsonError>;
+  type SerializeTuple = ser::Impossible<(), JsonError>;
+  type SerializeTupleStruct = ser::Impossible<(), JsonError>;
+  type SerializeTupleVariant = ser::Impossible<(), JsonError>;
+  type SerializeMap = ser::Impossible<(), JsonError>;
+  type SerializeStruct = ser::Impossible<(), JsonError>;
+  type SerializeStructVariant = ser::Impossible<(), JsonError>;
+
+  fn serialize_bool(self, v: bool) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_i8(self, v: i8) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_i16(self, v: i16) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_i32(self, v: i32) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_i128(self, v: i128) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_u8(self, v: u8) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_u16(self, v: u16) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_u32(self, v: u32) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_u128(self, v: u128) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_f32(self, _v: f32) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_f64(self, _v: f64) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_char(self, v: char) -> Result<(), JsonError> {
+    self.write_quoted(v)
+  }
+
+  fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+    self.0.write_string(v);
+    Ok(())
+  }
+
+  fn serialize_bytes(self, _v: &[u8]) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_none(self) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_some<T: Serialize + ?Sized>(
+    self,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    value.serialize(self)
+  }
+
+  fn serialize_unit(self) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_unit_struct(self, _name: &'static str) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_unit_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    variant: &'static str,
+  ) -> Result<(), JsonError> {
+    self.0.write_string(variant);
+    Ok(())
+  }
+
+  fn serialize_newtype_struct<T: Serialize + ?Sized>(
+    self,
+    _name: &'static str,
+    value: &T,
+  ) -> Result<(), JsonError> {
+    value.serialize(self)
+  }
+
+  fn serialize_newtype_variant<T: Serialize + ?Sized>(
+    self,
+    _name: &'static str,
+    _index: u32,
+    _variant: &'static str,
+    _value: &T,
+  ) -> Result<(), JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_seq(
+    self,
+    _len: Option<usize>,
+  ) -> Result<Self::SerializeSeq, JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_tuple(
+    self,
+    _len: usize,
+  ) -> Result<Self::SerializeTuple, JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_tuple_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleStruct, JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_tuple_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    _variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeTupleVariant, JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_map(
+    self,
+    _len: Option<usize>,
+  ) -> Result<Self::SerializeMap, JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_struct(
+    self,
+    _name: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStruct, JsonError> {
+    Err(key_error())
+  }
+
+  fn serialize_struct_variant(
+    self,
+    _name: &'static str,
+    _index: u32,
+    _variant: &'static str,
+    _len: usize,
+  ) -> Result<Self::SerializeStructVariant, JsonError> {
+    Err(key_error())
+  }
+}
@@ -1,7 +1,19 @@
+// Without the `std` feature, the crate itself only uses `core` and `alloc`:
+// serialization writes into `fmt::Write`, and the state tracked for the panic
+// hook (a thread local) is dropped. html5ever and its dependencies still link
+// `std` for now.
+#![cfg_attr(not(feature = "std"), no_std)]
+
 extern crate alloc;
 extern crate core;
 
+use alloc::format;
+use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::Infallible;
 
 use derive_more::with_trait::Debug;
 use derive_more::with_trait::From;
@@ -11,12 +23,19 @@ use html5ever::tendril::*;
 use html5ever::tokenizer::*;
 use html5ever::tree_builder::*;
 use html5ever::*;
+#[cfg(feature = "wasm")]
+use js_sys::Function;
+#[cfg(feature = "wasm")]
 use js_sys::Object;
 use markup5ever::interface::QuirksMode as ServoQuirksMode;
+#[cfg(all(feature = "wasm", feature = "xml"))]
 use moos::CowStr;
 use serde::Deserialize;
+#[cfg(feature = "wasm")]
 use serde_wasm_bindgen::from_value;
+#[cfg(feature = "wasm")]
 use serde_wasm_bindgen::to_value;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 pub mod rcdom;
@@ -31,6 +50,159 @@ pub use interner::*;
 pub mod quirks_mode;
 pub use quirks_mode::QuirksMode;
 
+pub mod filter;
+pub use filter::AttributeFilter;
+
+pub mod error;
+pub use error::DawmError;
+
+pub mod encoding;
+pub use encoding::Encoding;
+
+pub mod capabilities;
+pub use capabilities::Capabilities;
+
+#[cfg(feature = "capi")]
+pub mod capi;
+
+pub mod diagnostics;
+pub use diagnostics::*;
+
+pub mod line_index;
+pub use line_index::LineIndex;
+
+pub mod panic_hook;
+
+pub mod profile;
+pub use profile::Profile;
+
+pub mod scan;
+
+pub mod extract;
+pub use extract::Metadata;
+
+pub mod comments;
+pub use comments::StripComments;
+
+pub mod diff;
+pub use diff::PatchOp;
+
+pub mod dom;
+#[cfg(feature = "wasm")]
+pub use dom::Document;
+#[cfg(feature = "wasm")]
+pub use dom::builder::FragmentBuilder;
+#[cfg(feature = "wasm")]
+pub use dom::template::TemplateInstance;
+#[cfg(feature = "wasm")]
+pub use dom::NodeRef;
+
+pub mod build;
+
+pub mod email;
+pub use email::EmailOptions;
+
+pub mod empty;
+pub use empty::RemoveEmpty;
+
+pub mod frozen;
+pub use frozen::FrozenDom;
+
+pub mod hash;
+
+pub mod inject;
+pub use inject::AttributeInjection;
+
+#[cfg(feature = "wasm")]
+pub mod html_rewriter;
+#[cfg(feature = "wasm")]
+pub use html_rewriter::HtmlRewriter;
+#[cfg(feature = "wasm")]
+pub use html_rewriter::RewriterElement;
+
+pub mod json;
+
+#[cfg(feature = "node")]
+pub mod node;
+
+pub mod merge;
+pub use merge::MergeConflict;
+
+pub mod nonce;
+pub use nonce::NonceTargets;
+
+pub mod patch;
+pub use patch::PatchConflict;
+
+pub mod path;
+pub use path::PathFormat;
+
+pub mod pattern;
+pub use pattern::Pattern;
+
+pub mod presentational;
+pub use presentational::Modernize;
+
+pub mod reload;
+pub use reload::wire_to_dom;
+
+pub mod stats;
+pub use stats::DocumentStats;
+
+pub mod rewrite;
+pub use rewrite::AttributeRule;
+
+pub mod sanitize;
+pub use sanitize::SanitizePolicy;
+pub use sanitize::SanitizePreset;
+
+pub mod scope;
+
+pub mod selector;
+pub use selector::SelectorList;
+
+pub mod srcdoc;
+
+pub mod srcset;
+
+pub mod style;
+
+pub mod svg;
+
+pub mod traverse;
+pub use traverse::Traverse;
+
+pub mod url;
+
+pub mod url_filter;
+pub use url_filter::UrlPolicy;
+
+pub mod url_rewrite;
+pub use url_rewrite::UrlRewrite;
+
+pub mod validate;
+pub use validate::validate_wire;
+
+pub mod visit;
+pub use visit::Visit;
+pub use visit::VisitMut;
+
+pub mod whitespace;
+
+#[cfg(feature = "wasm")]
+pub mod session;
+#[cfg(feature = "wasm")]
+pub use session::ParseSession;
+
+pub mod sink;
+pub use sink::InputCursor;
+pub use sink::ParseSink;
+
+pub mod emit;
+pub use emit::WireEmitter;
+pub use emit::WireDocEmitter;
+pub use emit::emit;
+
 /// Parses a given input string as either an HTML or XML-like document,
 /// returning a serialized representation of the [`RcDom`] structure. The
 /// `mime` parameter determines whether to parse as HTML or XML-like. The
@@ -45,25 +217,121 @@ pub use quirks_mode::QuirksMode;
 ///
 /// As with the strings, node hierarchies are represented using integer indices
 /// that need to be resolved relative to the `.nodes` array.
+///
+/// If the input cannot be read or the result cannot be converted into a JS
+/// value, a descriptive `Error` is thrown instead of returning `null`.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
-pub fn parse_doc(input: &str, mime: &str, options: Option<Object>) -> JsValue {
-  let mime_lower = mime.trim().to_ascii_lowercase();
+pub fn parse_doc(
+  input: &str,
+  mime: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
 
-  let parse_options = options
-    .as_ref()
-    .map_or_else(ParseOptions::default, |o| options_from_js_or_default(o));
+  let parse_options = match &options {
+    Some(options) => options_from_js(options)?,
+    None => ParseOptions::default(),
+  };
 
-  let parsed = if is_html_mime(&mime_lower) {
-    let dom = parse_html_document(input, &parse_options);
-    serialize_dom(dom, "text/html")
-  } else {
-    let dom = parse_xml_like(input, &parse_options);
-    // Distinguish SVG/XML/XHTML by mime; default application/xml
-    let ct = normalized_mime(&mime_lower);
-    serialize_dom(dom, ct)
+  let parsed = parse_wire_doc(input, mime, &parse_options)?;
+  Ok(to_js(&parsed)?)
+}
+
+/// Parses a given input string as either an HTML or XML-like document, like
+/// {@linkcode parse_doc}, returning the [`WireDoc`] it serializes to rather
+/// than a JS value. This is the entry point for Rust callers, and does not
+/// need the `wasm` feature.
+pub fn parse_wire_doc(
+  input: &str,
+  mime: &str,
+  options: &ParseOptions,
+) -> error::Result<WireDoc> {
+  let (dom, content_type) = parse_by_mime(input, mime, options)?;
+  let mut parsed = serialize_dom(dom, content_type, options)?;
+  index_lines(&mut parsed, input, options);
+  Ok(parsed)
+}
+
+/// Parses the given bytes like {@linkcode parse_doc}, after decoding them in
+/// the encoding determined by their byte order mark, the `encoding` option,
+/// an in-document declaration or (with the `detectEncoding` option) their
+/// byte patterns, in that order. The encoding that was used is reported as
+/// the `encoding` of the output, along with where it came from and how
+/// certain it is. See the [`encoding`] module for details.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn parse_bytes(
+  input: &[u8],
+  mime: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let parse_options = match &options {
+    Some(options) => options_from_js(options)?,
+    None => ParseOptions::default(),
   };
 
-  to_value(&parsed).unwrap_or(JsValue::NULL)
+  let parsed = parse_wire_bytes(input, mime, &parse_options)?;
+  Ok(to_js(&parsed)?)
+}
+
+/// Parses the given bytes like {@linkcode parse_bytes}, returning the
+/// [`WireDoc`] it serializes to rather than a JS value. Like
+/// [`parse_wire_doc`], this does not need the `wasm` feature.
+pub fn parse_wire_bytes(
+  input: &[u8],
+  mime: &str,
+  options: &ParseOptions,
+) -> error::Result<WireDoc> {
+  let html = is_html_mime(&mime.trim().to_ascii_lowercase());
+  let sniffed = encoding::sniff(
+    input,
+    options.encoding.as_deref(),
+    html,
+    options.detect_encoding,
+  );
+  let decoded = encoding::decode(input, sniffed.encoding);
+  let (dom, content_type) = parse_by_mime(&decoded, mime, options)?;
+  let mut parsed = serialize_dom(dom, content_type, options)?;
+  index_lines(&mut parsed, &decoded, options);
+  parsed.encoding = Some(WireEncoding::from(&sniffed));
+  Ok(parsed)
+}
+
+// Adds the line-start offsets of the input to the output, if requested.
+fn index_lines(parsed: &mut WireDoc, input: &str, options: &ParseOptions) {
+  if options.line_index {
+    parsed.line_starts = Some(LineIndex::new(input).into_starts());
+  }
+}
+
+// Parses the input with the parser selected by `mime`, returning the DOM along
+// with its normalized content type.
+fn parse_by_mime(
+  input: &str,
+  mime: &str,
+  options: &ParseOptions,
+) -> error::Result<(RcDom, &'static str)> {
+  let mime = mime.trim().to_ascii_lowercase();
+  if is_html_mime(&mime) {
+    return Ok((parse_html_document(input, options)?, "text/html"));
+  }
+  // Distinguish SVG/XML/XHTML by mime; default application/xml
+  #[cfg(feature = "xml")]
+  {
+    Ok((parse_xml_like(input, options)?, normalized_mime(&mime)))
+  }
+  #[cfg(not(feature = "xml"))]
+  {
+    Err(DawmError::UnsupportedMime {
+      mime,
+      feature: "xml",
+    })
+  }
 }
 
 /// Parses a given input string as an HTML document, returning a serialized
@@ -72,16 +340,25 @@ pub fn parse_doc(input: &str, mime: &str, options: Option<Object>) -> JsValue {
 ///
 /// @see {@linkcode parse_frag} for parsing HTML fragments.
 /// @see {@linkcode parse_xml} for parsing XML-like documents.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
-pub fn parse_html(input: &str, options: Option<Object>) -> JsValue {
-  let parse_options = options
-    .as_ref()
-    .map_or_else(ParseOptions::default, |o| options_from_js_or_default(o));
+pub fn parse_html(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let parse_options = match &options {
+    Some(options) => options_from_js(options)?,
+    None => ParseOptions::default(),
+  };
 
-  let dom = parse_html_document(input, &parse_options);
-  let parsed = serialize_dom(dom, "text/html");
+  let dom = parse_html_document(input, &parse_options)?;
+  let mut parsed = serialize_dom(dom, "text/html", &parse_options)?;
+  index_lines(&mut parsed, input, &parse_options);
   // let resolved = resolve_wire_doc(parsed);
-  to_value(&parsed).unwrap_or(JsValue::NULL)
+  Ok(to_js(&parsed)?)
 }
 
 /// Parses a given input string as an XML-like document (XML, SVG, XHTML),
@@ -91,15 +368,18 @@ pub fn parse_html(input: &str, options: Option<Object>) -> JsValue {
 ///
 /// @see {@linkcode parse_html} for parsing HTML documents.
 /// @see {@linkcode parse_frag} for parsing HTML fragments.
-#[cfg(feature = "xml")]
+#[cfg(all(feature = "wasm", feature = "xml"))]
 #[wasm_bindgen]
-pub fn parse_xml(input: &str, options: JsValue) -> JsValue {
+pub fn parse_xml(input: &str, options: JsValue) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
   let mut parse_options = ParseOptions::default();
   let mut mime = CowStr::from("application/xml");
   if options.is_string() {
     mime = options.as_string().map(|s| s.into()).unwrap_or(mime);
   } else if !options.is_undefined() && !options.is_null() {
-    parse_options = from_value(options).unwrap_or_default();
+    parse_options = options_from_js(&options)?;
     mime = parse_options
       .content_type
       .clone()
@@ -109,9 +389,10 @@ pub fn parse_xml(input: &str, options: JsValue) -> JsValue {
   let mime = normalized_mime(&mime.trim().to_ascii_lowercase());
   parse_options.content_type = Some(mime.into());
 
-  let dom = parse_xml_like(input, &parse_options);
-  let serialized = serialize_dom(dom, mime);
-  to_value(&serialized).unwrap_or(JsValue::NULL)
+  let dom = parse_xml_like(input, &parse_options)?;
+  let mut serialized = serialize_dom(dom, mime, &parse_options)?;
+  index_lines(&mut serialized, input, &parse_options);
+  Ok(to_js(&serialized)?)
 }
 
 /// Parses a given input string as an HTML fragment within the context of a
@@ -120,33 +401,1086 @@ pub fn parse_xml(input: &str, options: JsValue) -> JsValue {
 /// tree and resolve string references.
 ///
 /// @see {@linkcode parse_html} for parsing full HTML documents.
+#[cfg(feature = "wasm")]
 #[wasm_bindgen]
-pub fn parse_frag(input: &str, options: JsValue) -> JsValue {
+pub fn parse_frag(input: &str, options: JsValue) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let frag_options = frag_options_from_js(options)?;
+  let dom = parse_html_fragment(input, &frag_options)?;
+  let mut parsed = serialize_dom(dom, "text/html", &frag_options.base)?;
+  index_lines(&mut parsed, input, &frag_options.base);
+  if let Some(effective) = parsed.effective_options.as_mut() {
+    effective.context_element = Some(frag_options.context_element.into());
+    effective.doctype =
+      frag_options.doctype.map(|d| d.name().to_string().into());
+  }
+  // let resolved = resolve_wire_doc(parsed);
+  Ok(to_js(&parsed)?)
+}
+
+// Fragment options can be given as an options object, or as just the name of
+// the context element.
+#[cfg(feature = "wasm")]
+fn frag_options_from_js(
+  options: JsValue,
+) -> error::Result<FragmentParseOptions> {
   let mut frag_options = FragmentParseOptions::default();
   if options.is_string() {
-    frag_options.context_element =
-      options.as_string().map(|s| s.into()).unwrap_or_default();
+    frag_options.context_element = options.as_string().unwrap_or_default();
   } else if !options.is_undefined() && !options.is_null() {
-    frag_options = from_value(options).unwrap_or_default();
+    frag_options = from_value(profile::apply_profile(&options))
+      .map_err(DawmError::Options)?;
   }
+  Ok(frag_options)
+}
 
-  let dom = parse_html_fragment(input, &frag_options);
-  let parsed = serialize_dom(dom, "text/html");
-  // let resolved = resolve_wire_doc(parsed);
-  to_value(&parsed).unwrap_or(JsValue::NULL)
+/// Parses a given input string like {@linkcode parse_doc}, but returns only
+/// the aggregate structure of the document (tag and attribute histograms,
+/// depth distribution, text byte total and a structural fingerprint) instead
+/// of its nodes and strings. See the [`stats`] module for details.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn parse_stats(
+  input: &str,
+  mime: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let parse_options = match &options {
+    Some(options) => options_from_js(options)?,
+    None => ParseOptions::default(),
+  };
+
+  let (dom, _) = parse_by_mime(input, mime, &parse_options)?;
+  let stats = stats::document_stats(&dom.document);
+  Ok(to_js_object(&stats, "stats")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its metadata: the title, canonical URL, description, charset,
+/// keywords, author, robots directives, and all of the Open Graph (`og:`)
+/// and Twitter card (`twitter:`) properties. See the [`extract`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_metadata(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  let metadata = extract::metadata::extract_metadata(&dom.document);
+  Ok(to_js_object(&metadata, "metadata")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns all of its links (`<a>`, `<area>` and `<link>` elements with an
+/// `href`), each with its node id, tag name, `rel` tokens, text content, and
+/// its URL resolved against the document's `<base>`. A `baseUrl` may be given
+/// alongside the parse options, as the URL of the document itself.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_links(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let links = extract::links::extract_links(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&links, "links")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its main content, like the reader mode of browsers: the content
+/// as HTML and as plain text, the id of the element holding it, and the
+/// title, byline and excerpt of the article. Returns `null` for documents
+/// without a body. See the [`extract::article`] module for the heuristics.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_article(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  match extract::article::extract_article(&dom.document) {
+    Some(article) => Ok(to_js_object(&article, "article")?),
+    None => Ok(JsValue::NULL),
+  }
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its canonical URL, its alternates (with their `hreflang`, `media`
+/// and `type`), its `prev` and `next` pagination links and its AMP version,
+/// with URLs resolved like those returned by {@linkcode extract_links}. See
+/// the [`extract::canonical`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_canonical(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let links =
+    extract::canonical::extract_canonical(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&links, "canonical links")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its forms, each with its action (resolved like the URLs returned
+/// by {@linkcode extract_links}), method, encoding type and controls. Every
+/// control is reported with its name, type, initial value, the options of a
+/// select, and its required, disabled and checked state. See the
+/// [`extract::forms`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_forms(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let forms = extract::forms::extract_forms(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&forms, "forms")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its `<meta http-equiv>` directives: the refresh redirect (its
+/// delay and URL, resolved like those returned by {@linkcode extract_links}),
+/// the Content Security Policies, `X-UA-Compatible`, and any others. See the
+/// [`extract::http_equiv`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_http_equiv(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let directives =
+    extract::http_equiv::extract_http_equiv(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&directives, "http-equiv directives")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its icons (favicons, touch icons and mask icons, each with its
+/// declared sizes, type and media) and the URL of its web app manifest, with
+/// URLs resolved like those returned by {@linkcode extract_links}. See the
+/// [`extract::icons`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_icons(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let icons = extract::icons::extract_icons(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&icons, "icons")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its images: every `<img>` with its parsed `srcset` candidates and
+/// `sizes`, alt text, dimensions and loading attributes, along with the
+/// `<source>`s of its `<picture>`. URLs are resolved like those returned by
+/// {@linkcode extract_links}. See the [`srcset`] module for the parsing of
+/// `srcset` and `sizes`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_images(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let images =
+    extract::images::extract_images(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&images, "images")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its heading outline: the tree of its `<h1>` to `<h6>` headings,
+/// nested by rank and by sectioning content, each with its node id, text and
+/// a unique anchor to link to it with. See the [`extract::outline`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_outline(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  let outline = extract::outline::extract_outline(&dom.document);
+  Ok(to_js_object(&outline, "outline")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its languages: the language of the document, the one declared by
+/// its `Content-Language` pragma, and the regions of elements (by node id
+/// range) in which the language changes. See the [`extract::language`]
+/// module for how the language of an element is determined.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_languages(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  let languages = extract::language::extract_languages(&dom.document);
+  Ok(to_js_object(&languages, "languages")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its scripts, each with its kind (classic, module, import map or
+/// data block), its `src` (resolved like the URLs returned by
+/// {@linkcode extract_links}), its loading flags, its `integrity` and
+/// `crossorigin` settings, and the byte length of inline scripts. See the
+/// [`extract::scripts`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_scripts(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let scripts =
+    extract::scripts::extract_scripts(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&scripts, "scripts")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns its stylesheets in cascade order, i.e. its `<link rel=stylesheet>`
+/// elements (with `href` resolved like the URLs returned by
+/// {@linkcode extract_links}) and `<style>` elements with their media and
+/// sizes, along with the elements carrying a `style` attribute. See the
+/// [`extract::styles`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn extract_styles(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let styles =
+    extract::styles::extract_styles(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&styles, "styles")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns the word, character, sentence and paragraph counts of its visible
+/// text, or of the text below the node with the given id (as in the output
+/// of {@linkcode parse_html}). Scripts, styles and hidden elements are not
+/// counted. See the [`extract::text`] module for how the text is split.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn text_stats(
+  input: &str,
+  node_id: Option<u32>,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  let node = match node_id {
+    Some(id) => rcdom::node_by_id(&dom.document, id)
+      .ok_or(DawmError::NodeNotFound(id))?,
+    None => dom.document.clone(),
+  };
+  Ok(to_js_object(&extract::text::text_stats(&node), "text stats")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns the feeds it advertises with `<link rel="alternate">`, each with
+/// its node id, MIME type, title, and URL resolved like those returned by
+/// {@linkcode extract_links}.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn discover_feeds(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let feeds = extract::feed::discover_feeds(&dom.document, base_url.as_deref());
+  Ok(to_js_object(&feeds, "feeds")?)
+}
+
+/// Parses the input as an RSS 2.0, RSS 1.0 or Atom document, and returns it
+/// as a feed with its entries, or `null` if the input is not a feed. See the
+/// [`extract::feed`] module.
+#[cfg(all(feature = "wasm", feature = "xml"))]
+#[wasm_bindgen]
+pub fn parse_feed(input: &str) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_xml_like(input, &ParseOptions::default())?;
+  match extract::feed::parse_feed(&dom.document) {
+    Some(feed) => Ok(to_js_object(&feed, "feed")?),
+    None => Ok(JsValue::NULL),
+  }
+}
+
+/// Parses both inputs as HTML documents like {@linkcode parse_html}, and
+/// returns the list of operations that turn the first document into the
+/// second: `insertNode`, `removeNode`, `moveNode`, `setAttribute`,
+/// `removeAttribute` and `setText`, each targeting nodes by their path of
+/// child indices from the document down. The operations have to be applied
+/// in order, as each path refers to the document as it is after the previous
+/// operations. See the [`diff`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn diff_docs(
+  old: &str,
+  new: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let old = parse_for_extraction(old, options.clone())?;
+  let new = parse_for_extraction(new, options)?;
+  let ops = diff::diff_docs(&old.document, &new.document);
+  Ok(to_js_object(&ops, "patch")?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, applies
+/// the patch operations (as returned by {@linkcode diff_docs}) to it in order,
+/// and returns `{ html, conflicts }` with the serialized result and the list
+/// of operations that could not be applied, e.g. because their target does
+/// not exist. Conflicting operations are skipped. See the [`patch`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn apply_patch(
+  input: &str,
+  ops: JsValue,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let ops: Vec<PatchOp> = from_value(ops).map_err(DawmError::Patch)?;
+  let dom = parse_for_extraction(input, options)?;
+  let conflicts = patch::apply_patch(&dom.document, &ops);
+
+  #[derive(serde::Serialize)]
+  struct Patched {
+    html:      String,
+    conflicts: Vec<PatchConflict>,
+  }
+  let html = inner_html(&dom.document);
+  Ok(to_js_object(&Patched { html, conflicts }, "patch result")?)
+}
+
+/// Parses the inputs as HTML documents like {@linkcode parse_html}, merges
+/// the changes that `ours` and `theirs` made to `base`, and returns
+/// `{ html, conflicts }` with the serialized merged document and the list of
+/// changes that could not be merged. Each conflict holds the path of the
+/// conflicting part in the merged document along with its `base`, `ours` and
+/// `theirs` versions, and the merged document has our version of it. See
+/// the [`merge`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn merge_docs(
+  base: &str,
+  ours: &str,
+  theirs: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let base = parse_for_extraction(base, options.clone())?;
+  let ours = parse_for_extraction(ours, options.clone())?;
+  let theirs = parse_for_extraction(theirs, options)?;
+  let (merged, conflicts) =
+    merge::merge_docs(&base.document, &ours.document, &theirs.document);
+
+  #[derive(serde::Serialize)]
+  struct Merged {
+    html:      String,
+    conflicts: Vec<MergeConflict>,
+  }
+  let html = inner_html(&merged);
+  Ok(to_js_object(&Merged { html, conflicts }, "merge result")?)
+}
+
+/// Parses both inputs as HTML documents like {@linkcode parse_html}, and
+/// compares the node with the id `a_id` of the first document to the node
+/// with the id `b_id` of the second one (ids as in the output of
+/// {@linkcode parse_html}) like the DOM's `isEqualNode` does. See
+/// [`rcdom::is_equal_node`] for what is compared.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn nodes_equal(
+  a: &str,
+  a_id: u32,
+  b: &str,
+  b_id: u32,
+  options: Option<Object>,
+) -> Result<bool, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let a = parse_for_extraction(a, options.clone())?;
+  let b = parse_for_extraction(b, options)?;
+  let a = rcdom::node_by_id(&a.document, a_id)
+    .ok_or(DawmError::NodeNotFound(a_id))?;
+  let b = rcdom::node_by_id(&b.document, b_id)
+    .ok_or(DawmError::NodeNotFound(b_id))?;
+  Ok(rcdom::is_equal_node(&a, &b))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// returns a locator for the node with the id `node_id` (as in the output of
+/// {@linkcode parse_html}), which selects that node (and only that node) by
+/// the names and positions of it and its ancestors. The `format` option is
+/// `"css"` (the default) for a selector like
+/// `html > body > div:nth-of-type(2)`, which only elements have, or
+/// `"xpath"` for an expression like `/html[1]/body[1]/div[2]`. See the
+/// [`path`] module for how paths are computed.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn node_path(
+  input: &str,
+  node_id: u32,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let format = match &options {
+    Some(options) => path_format_from_js(options)?,
+    None => PathFormat::default(),
+  };
+  let parse_options = match &options {
+    Some(options) => options_from_js(options)?,
+    None => ParseOptions::default(),
+  };
+  let dom = parse_html_document(input, &parse_options)?;
+  let doc = serialize_dom(dom, "text/html", &parse_options)?;
+  Ok(path::node_path(&doc, node_id, format)?)
+}
+
+// Reads the `format` of the options of `node_path`.
+#[cfg(feature = "wasm")]
+fn path_format_from_js(options: &Object) -> error::Result<PathFormat> {
+  let format = js_sys::Reflect::get(options, &"format".into())
+    .unwrap_or(JsValue::UNDEFINED);
+  if format.is_undefined() {
+    return Ok(PathFormat::default());
+  }
+  serde_wasm_bindgen::from_value(format).map_err(DawmError::Options)
+}
+
+// Reads the `baseUrl` of an extractor's options, the URL of the document.
+#[cfg(feature = "wasm")]
+fn base_url_from_js(options: Option<&Object>) -> Option<String> {
+  let base_url = js_sys::Reflect::get(options?, &"baseUrl".into()).ok()?;
+  base_url.as_string()
+}
+
+// Parses the input of an extractor as an HTML document.
+#[cfg(feature = "wasm")]
+fn parse_for_extraction(
+  input: &str,
+  options: Option<Object>,
+) -> error::Result<RcDom> {
+  let parse_options = match &options {
+    Some(options) => options_from_js(options)?,
+    None => ParseOptions::default(),
+  };
+  parse_html_document(input, &parse_options)
+}
+
+// Converts a value into JS, with maps as plain objects rather than `Map`s.
+#[cfg(feature = "wasm")]
+fn to_js_object<T: serde::Serialize>(
+  value: &T,
+  context: &str,
+) -> error::Result<JsValue> {
+  let serializer =
+    serde_wasm_bindgen::Serializer::new().serialize_maps_as_objects(true);
+  value
+    .serialize(&serializer)
+    .map_err(|source| DawmError::Serialize {
+      context: context.into(),
+      source,
+    })
+}
+
+/// Scans the input for HTML start tags which carry any of the requested
+/// `attributes`, without building a DOM, returning an array of matches in the
+/// shape `{ tagName, offset, line, attributes: [{ name, value }] }`.
+///
+/// The `attributes` are given in any of the forms accepted by the
+/// `attributeFilter` option (e.g. `["href", "src"]`); only the accepted
+/// attributes are included in each match. See the [`scan`] module for more.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn scan_attributes(
+  input: &str,
+  attributes: JsValue,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let filter: AttributeFilter =
+    from_value(attributes).map_err(DawmError::Options)?;
+  let matches = scan::scan_attributes(input, &filter)?;
+  Ok(to_value(&matches).map_err(|source| DawmError::Serialize {
+    context: "scan matches".into(),
+    source,
+  })?)
+}
+
+/// Parses the input as an HTML fragment and sanitizes it according to the
+/// given `policy` (see the [`sanitize`] module), returning the cleaned HTML.
+/// The policy can also be the name of a [`SanitizePreset`]. Omitting the
+/// policy (or any of its fields) uses the defaults.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn sanitize_html(input: &str, policy: JsValue) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let policy = policy_from_js(policy)?;
+  Ok(sanitize::clean(input, &policy)?)
+}
+
+/// Sanitizes the input like {@linkcode sanitize_html}, returning
+/// `{ html, report }` with the cleaned HTML and a report of every node that
+/// was removed or unwrapped and every attribute that was stripped or
+/// rewritten. The nodes are identified by their ids in the output of
+/// {@linkcode parse_frag} for the same input.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn sanitize_html_with_report(
+  input: &str,
+  policy: JsValue,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let policy = policy_from_js(policy)?;
+  let (html, report) = sanitize::clean_with_report(input, &policy)?;
+
+  #[derive(serde::Serialize)]
+  struct Sanitized {
+    html:   String,
+    report: sanitize::SanitizeReport,
+  }
+  Ok(to_value(&Sanitized { html, report }).map_err(|source| {
+    DawmError::Serialize {
+      context: "sanitize report".into(),
+      source,
+    }
+  })?)
+}
+
+/// Parses the input as an HTML fragment and sanitizes it according to the
+/// given `policy`, returning the serialized representation of the result as
+/// in {@linkcode parse_frag}.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn sanitize_doc(input: &str, policy: JsValue) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let policy = policy_from_js(policy)?;
+  let dom = sanitize::sanitize_fragment(input, &policy)?;
+  let parsed = serialize_dom(dom, "text/html", &ParseOptions::default())?;
+  Ok(to_js(&parsed)?)
+}
+
+/// Parses the input as an HTML fragment and validates its URL-valued
+/// attributes against the given `policy` (see the [`url_filter`] module),
+/// returning `{ html, violations }` with the filtered HTML and a list of the
+/// stripped or rewritten attributes.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn filter_urls(input: &str, policy: JsValue) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let policy: UrlPolicy = if policy.is_undefined() || policy.is_null() {
+    UrlPolicy::default()
+  } else {
+    from_value(policy).map_err(DawmError::Options)?
+  };
+  let dom = parse_html_fragment(input, &FragmentParseOptions::default())?;
+  let root = sanitize::fragment_root(&dom);
+  let (html, violations) = match root {
+    Some(root) => {
+      let violations = url_filter::filter_urls(&root, &policy);
+      (inner_html(&root), violations)
+    }
+    None => (String::new(), Vec::new()),
+  };
+
+  #[derive(serde::Serialize)]
+  struct Filtered {
+    html:       String,
+    violations: Vec<url_filter::UrlViolation>,
+  }
+  Ok(to_value(&Filtered { html, violations }).map_err(|source| {
+    DawmError::Serialize {
+      context: "filtered urls".into(),
+      source,
+    }
+  })?)
+}
+
+/// Parses the input as an HTML fragment and applies the attribute rewriting
+/// `rules` to it (see the [`rewrite`] module), returning the rewritten HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn rewrite_attributes(
+  input: &str,
+  rules: JsValue,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let rules: Vec<AttributeRule> =
+    from_value(rules).map_err(DawmError::Options)?;
+  let dom = parse_html_fragment(input, &FragmentParseOptions::default())?;
+  let Some(root) = sanitize::fragment_root(&dom) else {
+    return Ok(String::new());
+  };
+  rewrite::rewrite_attributes(&root, &rules)?;
+  Ok(inner_html(&root))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// rewrites its URLs with `rewrite`, given as `{ baseUrl, map, prefix }`
+/// (see the [`url_rewrite`] module), returning the rewritten HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn rewrite_urls(
+  input: &str,
+  rewrite: JsValue,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let rewrite: UrlRewrite = if rewrite.is_undefined() || rewrite.is_null() {
+    UrlRewrite::default()
+  } else {
+    from_value(rewrite).map_err(DawmError::Options)?
+  };
+  let dom = parse_for_extraction(input, options)?;
+  url_rewrite::rewrite_urls(&dom.document, &rewrite);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, adds
+/// the attributes of `injections` to the elements they match (see the
+/// [`inject`] module), and returns the resulting HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn inject_attributes(
+  input: &str,
+  injections: JsValue,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let injections: Vec<AttributeInjection> =
+    from_value(injections).map_err(DawmError::Options)?;
+  let dom = parse_for_extraction(input, options)?;
+  inject::inject_attributes(&dom, &injections);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// removes its comments, except for the ones kept with the `keepConditional`
+/// and `keep` options (see the [`comments`] module), returning the HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn strip_comments(
+  input: &str,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let strip: StripComments = match &options {
+    Some(options) => {
+      from_value(options.clone().into()).map_err(DawmError::Options)?
+    }
+    None => StripComments::default(),
+  };
+  let dom = parse_for_extraction(input, options)?;
+  comments::strip_comments(&dom.document, &strip);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// collapses the whitespace in it that does not render (see the
+/// [`whitespace`] module), returning the resulting HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn collapse_whitespace(
+  input: &str,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  whitespace::collapse_whitespace(&dom.document);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// replaces the SVG `<use>` elements referencing an element of the document
+/// with a copy of it (see the [`svg`] module), returning the resulting HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn inline_svg_uses(
+  input: &str,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  svg::inline_uses(&dom.document);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// prefixes its ids and class names (and the references to its ids) with
+/// `prefix` (see the [`scope`] module), returning the resulting HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn scope_names(
+  input: &str,
+  prefix: &str,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let dom = parse_for_extraction(input, options)?;
+  scope::scope_names(&dom.document, prefix);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// removes the elements with nothing but whitespace in them, except for the
+/// ones kept with the `keep` and `only` options (see the [`empty`] module),
+/// returning the resulting HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn remove_empty(
+  input: &str,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let remove: RemoveEmpty = match &options {
+    Some(options) => {
+      from_value(options.clone().into()).map_err(DawmError::Options)?
+    }
+    None => RemoveEmpty::default(),
+  };
+  let dom = parse_for_extraction(input, options)?;
+  empty::remove_empty(&dom.document, &remove);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// adds `nonce` to its inline scripts and styles, unless disabled with the
+/// `scripts` and `styles` options (see the [`nonce`] module). Returns
+/// `{ html, injected, uncovered }` with the resulting HTML, how many elements
+/// got the nonce, and the inline event handlers, `javascript:` URLs and
+/// `style` attributes no nonce covers. The elements are identified by their
+/// ids in the output of {@linkcode parse_html} for the same input.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn inject_nonce(
+  input: &str,
+  nonce: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let targets: NonceTargets = match &options {
+    Some(options) => {
+      from_value(options.clone().into()).map_err(DawmError::Options)?
+    }
+    None => NonceTargets::default(),
+  };
+  let dom = parse_for_extraction(input, options)?;
+  let report = nonce::inject_nonce(&dom.document, nonce, targets)?;
+
+  #[derive(serde::Serialize)]
+  struct Injected {
+    html: String,
+    #[serde(flatten)]
+    report: nonce::NonceReport,
+  }
+  let html = inner_html(&dom.document);
+  Ok(to_value(&Injected { html, report }).map_err(|source| {
+    DawmError::Serialize {
+      context: "nonce report".into(),
+      source,
+    }
+  })?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// rewrites it for email clients, inlining its stylesheets (and the linked
+/// ones given as `stylesheets`) into `style` attributes, and converting or
+/// removing what the clients do not support (see the [`email`] module).
+/// Returns `{ html, inlined, converted, strippedAttributes, issues }`, where
+/// the issues are identified by their ids in the output of
+/// {@linkcode parse_html} for the same input.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn prepare_email(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let email: EmailOptions = match &options {
+    Some(options) => {
+      from_value(options.clone().into()).map_err(DawmError::Options)?
+    }
+    None => EmailOptions::default(),
+  };
+  let dom = parse_for_extraction(input, options)?;
+  let report = email::prepare_email(&dom, &email);
+
+  #[derive(serde::Serialize)]
+  struct Prepared {
+    html: String,
+    #[serde(flatten)]
+    report: email::EmailReport,
+  }
+  let html = inner_html(&dom.document);
+  Ok(to_value(&Prepared { html, report }).map_err(|source| {
+    DawmError::Serialize {
+      context: "email report".into(),
+      source,
+    }
+  })?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// resolves the effect of its `<base>` into its URLs and link targets before
+/// removing it (see the [`url_rewrite`] module), so that it renders the same
+/// wherever it is hosted. A `baseUrl` may be given alongside the parse
+/// options, as the URL of the document itself. Returns
+/// `{ html, baseUrl, resolved, targeted, removed }`.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn resolve_base(
+  input: &str,
+  options: Option<Object>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let document_url = base_url_from_js(options.as_ref());
+  let dom = parse_for_extraction(input, options)?;
+  let resolution =
+    url_rewrite::resolve_base(&dom.document, document_url.as_deref());
+
+  #[derive(serde::Serialize)]
+  struct Resolved {
+    html: String,
+    #[serde(flatten)]
+    resolution: url_rewrite::BaseResolution,
+  }
+  let html = inner_html(&dom.document);
+  Ok(to_value(&Resolved { html, resolution }).map_err(|source| {
+    DawmError::Serialize {
+      context: "base resolution".into(),
+      source,
+    }
+  })?)
+}
+
+/// Parses the input as an HTML document like {@linkcode parse_html}, and
+/// converts its presentational attributes, like `align`, `bgcolor` and
+/// `cellpadding`, and its `<font>` and `<center>` elements to `style`
+/// declarations, or to classes with the `classPrefix` option for the
+/// attributes with keyword values (see the [`presentational`] module),
+/// returning the resulting HTML.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn modernize_presentation(
+  input: &str,
+  options: Option<Object>,
+) -> Result<String, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let modernize: Modernize = match &options {
+    Some(options) => {
+      from_value(options.clone().into()).map_err(DawmError::Options)?
+    }
+    None => Modernize::default(),
+  };
+  let dom = parse_for_extraction(input, options)?;
+  presentational::modernize(&dom.document, &modernize);
+  Ok(inner_html(&dom.document))
+}
+
+/// Parses the value of a `srcset` attribute into its valid image candidates,
+/// each `{ url, width, height, density }` with the descriptors it has, and
+/// with `resolvedUrl` when a `baseUrl` is given to resolve the URL against.
+/// Candidates with invalid or conflicting descriptors are dropped, as
+/// browsers do. See the [`srcset`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn parse_srcset(
+  value: &str,
+  base_url: Option<String>,
+) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  let mut candidates = srcset::parse_srcset(value);
+  if let Some(base_url) = base_url {
+    for candidate in &mut candidates {
+      candidate.resolved_url = url::resolve(&base_url, &candidate.url);
+    }
+  }
+  Ok(to_js_object(&candidates, "srcset")?)
+}
+
+/// Parses the value of a `sizes` attribute into its source sizes, each
+/// `{ media, length }` with the media condition, if any, and the length it
+/// selects. See the [`srcset`] module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn parse_sizes(value: &str) -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  Ok(to_js_object(&srcset::parse_sizes(value), "sizes")?)
+}
+
+/// Returns what this build of the library supports: its version, the Cargo
+/// features it was compiled with, its exports, the wire format versions it
+/// produces, the accepted profile, preset and encoding names, its hard limits
+/// and any deprecations. See the [`capabilities`](mod@capabilities) module.
+#[cfg(feature = "wasm")]
+#[wasm_bindgen]
+pub fn capabilities() -> Result<JsValue, JsError> {
+  panic_hook::install();
+  panic_hook::reset();
+
+  Ok(to_js_object(&capabilities::capabilities(), "capabilities")?)
+}
+
+#[cfg(feature = "wasm")]
+fn policy_from_js(policy: JsValue) -> error::Result<SanitizePolicy> {
+  if policy.is_undefined() || policy.is_null() {
+    return Ok(SanitizePolicy::default());
+  }
+  if policy.is_string() {
+    let preset: SanitizePreset =
+      from_value(policy).map_err(DawmError::Options)?;
+    return Ok(preset.policy());
+  }
+  from_value(policy).map_err(DawmError::Options)
 }
 
 /// Options for configuring the behavior of the dawm parsing engine.
 ///
-/// | Option           | Default       | Description                         |
-/// |------------------|---------------|-------------------------------------|
-/// | `allowScripts`   | `true`        | Enables scripting features.         |
-/// | `contentType`    | `"text/html"` | Controls which parser is used.      |
-/// | `contextElement` | `"div"`       | Context element for HTML fragments. |
-/// | `dropDoctype`    | `false`       | Strips the doctype from the output. |
-/// | `exactErrors`    | `true`        | Enables precise error reporting.    |
-/// | `iframeSrcdoc`   | `false`       | Indicates if parsing iframe srcdoc. |
-/// | `quirksMode`     | `"no-quirks"` | Sets the document's quirks mode.    |
+/// | Option                       | Default       | Description                         |
+/// |------------------------------|---------------|-------------------------------------|
+/// | `allowScripts`               | `true`        | Enables scripting features.         |
+/// | `attributeFilter`            | `null`        | Limits which attributes are kept.   |
+/// | `coalesceText`               | `false`       | Merges text split by dropped nodes. |
+/// | `collapseWhitespace`         | `false`       | Removes whitespace that won't show. |
+/// | `collectErrors`              | `false`       | Emits parse errors in the output.   |
+/// | `contentType`                | `"text/html"` | Controls which parser is used.      |
+/// | `contextElement`             | `"div"`       | Context element for HTML fragments. |
+/// | `detectEncoding`             | `false`       | Guesses the encoding of bytes.      |
+/// | `doctype`                    | `null`        | Doctype prepended to fragments.     |
+/// | `dropComments`               | `false`       | Strips comments from the output.    |
+/// | `dropDoctype`                | `false`       | Strips the doctype from the output. |
+/// | `dropProcessingInstructions` | `false`       | Strips PIs from the output.         |
+/// | `echoOptions`                | `false`       | Emits the resolved options used.    |
+/// | `encoding`                   | `null`        | Transport hint for byte input.      |
+/// | `errorSampleRate`            | `1`           | Records only every Nth parse error. |
+/// | `exactErrors`                | `true`        | Enables precise error reporting.    |
+/// | `failFast`                   | `false`       | Fails on the first parse error.     |
+/// | `foldNameCase`               | `false`       | Lowercases element/attribute names. |
+/// | `iframeSrcdoc`               | `false`       | Indicates if parsing iframe srcdoc. |
+/// | `injectAttributes`           | `[]`          | Adds attributes by selector.        |
+/// | `inlineSvgUses`              | `false`       | Inlines internal SVG `<use>`s.      |
+/// | `internerProfile`            | `"default"`   | Seeds the string table.             |
+/// | `internerStats`              | `false`       | Emits string table statistics.      |
+/// | `lineIndex`                  | `false`       | Emits the line-start offset table.  |
+/// | `maxErrors`                  | `null`        | Caps the number of recorded errors. |
+/// | `maxTextLength`              | `null`        | Caps text node length (in bytes).   |
+/// | `multiRoot`                  | `"siblings"`  | Handling of multi-root XML input.   |
+/// | `modernizePresentation`      | `null`        | Converts presentational attributes. |
+/// | `nodeHashes`                 | `false`       | Emits a structural hash per node.   |
+/// | `onError`                    | `null`        | Callback invoked per parse error.   |
+/// | `parseSrcdoc`                | `false`       | Parses iframe `srcdoc`s as well.    |
+/// | `profile`                    | `null`        | Named set of option defaults.       |
+/// | `quirksMode`                 | `"no-quirks"` | Sets the document's quirks mode.    |
+/// | `removeEmpty`                | `null`        | Removes empty elements.             |
+/// | `reportStrippedHandlers`     | `false`       | Lists stripped handlers per node.   |
+/// | `scopePrefix`                | `null`        | Prefixes ids and class names.       |
+/// | `stripEventHandlers`         | `false`       | Drops all `on*` attributes.         |
 #[derive(Deserialize, Debug, Clone)]
 #[serde(default, rename_all = "camelCase")]
 pub struct ParseOptions {
@@ -157,6 +1491,199 @@ pub struct ParseOptions {
   pub drop_doctype: bool,
   pub quirks_mode: QuirksMode,
   pub content_type: Option<String>,
+  /// Controls which attributes are collected into the wire output. See the
+  /// [`filter`] module for the accepted forms.
+  pub attribute_filter: AttributeFilter,
+  /// Whether to include the errors reported by the parser (along with the
+  /// line on which they occurred) in the `errors` array of the output.
+  pub collect_errors: bool,
+  /// The maximum length (in UTF-8 bytes) of a text node's value. Longer text
+  /// is cut at the nearest preceding character boundary, and the node is
+  /// flagged as `truncated` along with its original `fullLength`.
+  pub max_text_length: Option<u32>,
+  /// Whether to include an `effectiveOptions` block in the output, echoing
+  /// the fully resolved options that were actually used for parsing. This is
+  /// mostly useful for debugging unexpected behavior.
+  pub echo_options: bool,
+  /// How to handle XML documents with multiple top-level elements (or stray
+  /// top-level text), which xml5ever's error recovery happily produces.
+  pub multi_root: MultiRootMode,
+  /// The maximum number of parse errors to record. The total number of
+  /// errors is still reported as `errorCount` when `collectErrors` is set.
+  pub max_errors: Option<u32>,
+  /// Records only every Nth parse error, to get a representative sample of
+  /// the errors in very broken documents. Defaults to `1` (record all).
+  pub error_sample_rate: u32,
+  /// Whether to abort parsing as soon as the first parse error is reported,
+  /// throwing that error (with its position) instead of returning a document.
+  /// Useful for validating user-submitted markup. The error thrown is always
+  /// the first one, even if `maxErrors` or `errorSampleRate` leave it out of
+  /// `errors`. HTML input is fed to the parser in chunks of 4 KiB, and is
+  /// only checked for errors between them, so the parser may get up to that
+  /// far past the first error before it stops.
+  pub fail_fast: bool,
+  /// A callback invoked with each parse error as soon as it is reported, in
+  /// the same shape as the entries of the `errors` array. Returning `false`
+  /// (or throwing) aborts parsing at the end of the 4 KiB chunk of input
+  /// being parsed, like `failFast` does. For XML input, the callback is
+  /// invoked once the document has been parsed, since xml5ever cannot be
+  /// stopped.
+  #[cfg(feature = "wasm")]
+  #[serde(deserialize_with = "deserialize_callback")]
+  pub on_error: Option<Function>,
+  /// Whether to drop all inline event handler attributes (`onclick`,
+  /// `onload`, and any other `on*` attribute) from the output.
+  pub strip_event_handlers: bool,
+  /// Whether to list the names of the event handlers dropped by
+  /// `stripEventHandlers` on each node, as `strippedHandlers`.
+  pub report_stripped_handlers: bool,
+  /// The encoding label given by the transport layer (e.g. the `charset` of
+  /// a `Content-Type` header), used by {@linkcode parse_bytes} when the input
+  /// has no byte order mark.
+  pub encoding: Option<String>,
+  /// Whether {@linkcode parse_bytes} should guess the encoding of input
+  /// without a byte order mark, transport hint or declared encoding, instead
+  /// of assuming UTF-8.
+  pub detect_encoding: bool,
+  /// Whether to include the byte offset at which each line of the input
+  /// starts in the output, as `lineStarts`. See the [`line_index`] module.
+  pub line_index: bool,
+  /// Whether to drop all comments from the output.
+  pub drop_comments: bool,
+  /// Whether to drop all processing instructions from the output.
+  pub drop_processing_instructions: bool,
+  /// Whether to merge the text nodes that were only separated by nodes
+  /// dropped by `dropComments` or `dropProcessingInstructions` into one, as
+  /// if the dropped nodes had never been in the input.
+  pub coalesce_text: bool,
+  /// Whether to collapse the whitespace in text that does not render, and
+  /// remove the text nodes left empty, before the document is serialized.
+  /// See the [`whitespace`] module.
+  pub collapse_whitespace: bool,
+  /// Which elements with nothing but whitespace in them to remove, if any,
+  /// before the document is serialized. See the [`empty`] module.
+  pub remove_empty: Option<RemoveEmpty>,
+  /// Whether to include the structural hash of every node in the output, as
+  /// its `hash` (a string of 16 hex digits). See the [`hash`] module.
+  pub node_hashes: bool,
+  /// Whether to include the statistics of the string table (how many of its
+  /// lookups were hits, and how large it is) in the output, as
+  /// `internerStats`. Useful for measuring how much a seed helps.
+  pub interner_stats: bool,
+  /// Whether to intern the names of elements and attributes with their ASCII
+  /// letters in lowercase, so that `CLASS` and `class` share one string.
+  /// Values are left as they are. Note that this also lowercases the names
+  /// that are case-sensitive, such as SVG's `viewBox` and all XML names.
+  pub fold_name_case: bool,
+  /// Which strings the string table is seeded with before the document's
+  /// own are interned. Sessions ignore it, since their table is seeded once,
+  /// when they are created. See [`InternerProfile`].
+  pub interner_profile: InternerProfile,
+  /// Whether to parse the `srcdoc` of every `<iframe>` as a document of its
+  /// own, emitted into the same output after the nodes of the host. See the
+  /// [`srcdoc`] module.
+  pub parse_srcdoc: bool,
+  /// The attributes to add to the elements matched by selectors, e.g.
+  /// `loading="lazy"` to images, before the document is serialized. See the
+  /// [`inject`] module.
+  pub inject_attributes: Vec<AttributeInjection>,
+  /// Whether to replace the SVG `<use>` elements that reference an element
+  /// of the document with a copy of it, before the document is serialized.
+  /// See the [`svg`] module.
+  pub inline_svg_uses: bool,
+  /// The prefix to add to every id and class name (and to the references to
+  /// those ids), before the document is serialized. See the [`scope`]
+  /// module.
+  pub scope_prefix: Option<String>,
+  /// How to convert the presentational attributes (like `align` and
+  /// `bgcolor`) and elements (like `<font>`) to CSS, if at all, before the
+  /// document is serialized. See the [`presentational`] module.
+  pub modernize_presentation: Option<Modernize>,
+  /// The named [`Profile`] whose options were used as the defaults for this
+  /// set of options, if any.
+  pub profile: Option<Profile>,
+}
+
+impl ParseOptions {
+  /// Returns the bounds on recorded parse errors described by these options.
+  pub fn error_budget(&self) -> ErrorBudget {
+    ErrorBudget {
+      max:         self.max_errors.map(|max| max as usize),
+      sample_rate: self.error_sample_rate.max(1) as usize,
+    }
+  }
+
+  /// Wraps the `onError` callback (if any) in an [`ErrorHook`] for the DOM.
+  /// Without the `wasm` feature, there is never a callback.
+  #[cfg(not(feature = "wasm"))]
+  pub fn error_hook(&self) -> Option<ErrorHook> {
+    None
+  }
+
+  /// Wraps the `onError` callback (if any) in an [`ErrorHook`] for the DOM.
+  #[cfg(feature = "wasm")]
+  pub fn error_hook(&self) -> Option<ErrorHook> {
+    let callback = self.on_error.clone()?;
+    Some(Rc::new(move |error: &diagnostics::ParseError| {
+      let Ok(diagnostic) = to_value(&WireError::from(error)) else {
+        return true;
+      };
+      match callback.call1(&JsValue::NULL, &diagnostic) {
+        Ok(result) => result.as_bool() != Some(false),
+        Err(_) => false,
+      }
+    }))
+  }
+}
+
+#[cfg(feature = "wasm")]
+fn deserialize_callback<'de, D>(
+  deserializer: D,
+) -> Result<Option<Function>, D::Error>
+where
+  D: serde::Deserializer<'de>,
+{
+  let value: JsValue =
+    serde_wasm_bindgen::preserve::deserialize(deserializer)?;
+  if value.is_null() || value.is_undefined() {
+    return Ok(None);
+  }
+  value
+    .dyn_into::<Function>()
+    .map(Some)
+    .map_err(|_| serde::de::Error::custom("expected a function"))
+}
+
+/// Determines how XML documents with more than one root are handled.
+///
+/// - `"siblings"` (default): the roots are emitted as siblings, and the output
+///   is flagged with `multiRoot: true`.
+/// - `"wrap"`: the roots are moved under a synthetic `<root>` element.
+/// - `"error"`: parsing fails with an error.
+///
+/// To keep the other roots, the input is parsed a second time with everything
+/// after its prolog wrapped in a synthetic root element. The errors reported
+/// for the content outside of the first root are kept, ahead of the errors of
+/// the second parse. The synthetic root is inserted without any line breaks,
+/// so it does not shift the lines of the errors (the XML parser reports no
+/// columns).
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MultiRootMode {
+  #[default]
+  Siblings,
+  Wrap,
+  Error,
+}
+
+impl MultiRootMode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      MultiRootMode::Siblings => "siblings",
+      MultiRootMode::Wrap => "wrap",
+      MultiRootMode::Error => "error",
+    }
+  }
 }
 
 impl Default for ParseOptions {
@@ -168,17 +1695,162 @@ impl Default for ParseOptions {
       drop_doctype: false,
       quirks_mode: default_quirks_mode().parse().unwrap_or_default(),
       content_type: Some(default_mime_type()),
+      attribute_filter: AttributeFilter::All,
+      collect_errors: false,
+      max_text_length: None,
+      echo_options: false,
+      multi_root: MultiRootMode::Siblings,
+      max_errors: None,
+      error_sample_rate: 1,
+      fail_fast: false,
+      #[cfg(feature = "wasm")]
+      on_error: None,
+      strip_event_handlers: false,
+      report_stripped_handlers: false,
+      encoding: None,
+      detect_encoding: false,
+      line_index: false,
+      drop_comments: false,
+      drop_processing_instructions: false,
+      coalesce_text: false,
+      collapse_whitespace: false,
+      remove_empty: None,
+      node_hashes: false,
+      interner_stats: false,
+      fold_name_case: false,
+      interner_profile: InternerProfile::Default,
+      parse_srcdoc: false,
+      inject_attributes: Vec::new(),
+      inline_svg_uses: false,
+      scope_prefix: None,
+      modernize_presentation: None,
+      profile: None,
     }
   }
 }
 
 // Extension for fragment parsing that includes context element name
-#[derive(Deserialize, Debug, Clone)]
-#[serde(default, rename_all = "camelCase")]
+#[derive(Debug, Clone)]
 pub struct FragmentParseOptions {
-  #[serde(flatten)]
   pub base: ParseOptions,
   pub context_element: String,
+  /// The doctype to prepend to the fragment. When given, it is included in
+  /// the output even though fragments drop doctypes by default.
+  pub doctype: Option<FragmentDoctype>,
+}
+
+/// A doctype prepended to a parsed fragment, given either as just its name
+/// (e.g. `"html"`) or as an object with its `name`, `publicId` and
+/// `systemId`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FragmentDoctype {
+  Name(String),
+  #[serde(rename_all = "camelCase")]
+  Full {
+    name:      String,
+    #[serde(default)]
+    public_id: String,
+    #[serde(default)]
+    system_id: String,
+  },
+}
+
+impl FragmentDoctype {
+  pub fn name(&self) -> &str {
+    match self {
+      FragmentDoctype::Name(name) | FragmentDoctype::Full { name, .. } => name,
+    }
+  }
+
+  /// Inserts this doctype as the first child of the document.
+  pub fn prepend_to(&self, dom: &RcDom) {
+    let (public_id, system_id) = match self {
+      FragmentDoctype::Name(_) => ("", ""),
+      FragmentDoctype::Full {
+        public_id,
+        system_id,
+        ..
+      } => (public_id.as_str(), system_id.as_str()),
+    };
+    let node = Node::new(NodeData::Doctype {
+      name:      self.name().into(),
+      public_id: public_id.into(),
+      system_id: system_id.into(),
+    });
+    node.parent.set(Some(Rc::downgrade(&dom.document)));
+    dom.document.children.borrow_mut().insert(0, node);
+  }
+}
+
+// The fragment-only keys, plus the ones whose defaults differ for fragments.
+#[derive(Deserialize, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct FragmentOnlyOptions {
+  context_element: Option<String>,
+  drop_doctype:    Option<bool>,
+  doctype:         Option<FragmentDoctype>,
+}
+
+impl FragmentParseOptions {
+  // Combines the base options with the fragment-only keys given alongside.
+  fn from_parts(mut base: ParseOptions, extra: FragmentOnlyOptions) -> Self {
+    let defaults = Self::default();
+    // an explicit doctype is always kept
+    base.drop_doctype = extra.doctype.is_none()
+      && extra.drop_doctype.unwrap_or(defaults.base.drop_doctype);
+    let context_element =
+      extra.context_element.unwrap_or(defaults.context_element);
+    Self {
+      base,
+      context_element,
+      doctype: extra.doctype,
+    }
+  }
+}
+
+// `#[serde(flatten)]` buffers values through serde's internal `Content` type,
+// which cannot hold JS values such as predicate functions. Instead, we grab
+// the raw object and deserialize it twice: once as the base options and once
+// for the fragment-specific keys.
+#[cfg(feature = "wasm")]
+impl<'de> Deserialize<'de> for FragmentParseOptions {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    use serde::de::Error;
+
+    let value: JsValue =
+      serde_wasm_bindgen::preserve::deserialize(deserializer)?;
+    let base: ParseOptions =
+      from_value(value.clone()).map_err(D::Error::custom)?;
+    let extra: FragmentOnlyOptions =
+      from_value(value).map_err(D::Error::custom)?;
+    Ok(Self::from_parts(base, extra))
+  }
+}
+
+// Without JS values, there is nothing that `#[serde(flatten)]` cannot buffer.
+// The fragment-specific keys go first, as flattened fields take the keys
+// they know from the rest.
+#[cfg(not(feature = "wasm"))]
+impl<'de> Deserialize<'de> for FragmentParseOptions {
+  fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+  where
+    D: serde::Deserializer<'de>,
+  {
+    #[derive(Deserialize)]
+    struct Flattened {
+      #[serde(flatten)]
+      extra: FragmentOnlyOptions,
+      #[serde(flatten)]
+      base:  ParseOptions,
+    }
+
+    let Flattened { extra, base } = Flattened::deserialize(deserializer)?;
+    Ok(Self::from_parts(base, extra))
+  }
 }
 
 impl Default for FragmentParseOptions {
@@ -189,7 +1861,8 @@ impl Default for FragmentParseOptions {
         iframe_srcdoc: false,
         ..Default::default()
       },
-      context_element: default_context_element().into(),
+      context_element: default_context_element(),
+      doctype: None,
     }
   }
 }
@@ -217,25 +1890,110 @@ impl From<ParseOptions> for TreeBuilderOpts {
   }
 }
 
-// Helper function to parse options from JS or use defaults
-fn options_from_js_or_default<T: Default + for<'de> Deserialize<'de>>(
+// Parses options from JS, with the defaults for `null` or `undefined`. Invalid
+// options (e.g. a misspelled `quirksMode`) are reported rather than replaced by
+// the defaults, which would silently ignore every other option given too.
+#[cfg(feature = "wasm")]
+fn options_from_js<T: Default + for<'de> Deserialize<'de>>(
   js_value: &JsValue,
-) -> T {
+) -> error::Result<T> {
   if js_value.is_null() || js_value.is_undefined() {
-    T::default()
+    Ok(T::default())
   } else {
-    match from_value(js_value.clone()) {
-      Ok(options) => options,
-      Err(_) => T::default(),
-    }
+    from_value(profile::apply_profile(js_value)).map_err(DawmError::Options)
+  }
+}
+
+// Converts a wire document into a JS value, surfacing any failure as an error
+// rather than silently handing `null` back to the caller.
+#[cfg(feature = "wasm")]
+fn to_js(doc: &WireDoc) -> error::Result<JsValue> {
+  to_value(doc).map_err(|source| DawmError::Serialize {
+    context: locate_serialize_failure(doc),
+    source,
+  })
+}
+
+// Narrows a serialization failure of a whole document down to the first
+// string or node which fails to serialize on its own. This only runs on the
+// (rare) failure path, so the extra work is not a concern.
+#[cfg(feature = "wasm")]
+fn locate_serialize_failure(doc: &WireDoc) -> String {
+  if let Some(i) = doc.strings.iter().position(|s| to_value(s).is_err()) {
+    return format!("string {i}");
+  }
+  if let Some(node) = doc.nodes.iter().find(|n| to_value(n).is_err()) {
+    return format!("node {}", node.id);
+  }
+  if doc.errors.as_ref().is_some_and(|e| to_value(e).is_err()) {
+    return "errors".into();
+  }
+  if doc.effective_options.as_ref().is_some_and(|o| to_value(o).is_err()) {
+    return "effectiveOptions".into();
   }
+  "document".into()
 }
 
-fn serialize_dom(dom: RcDom, content_type: &'static str) -> WireDoc {
-  let mut interner = Interner::default();
+/// Serializes a parsed tree into a [`WireDoc`], applying the transforms and
+/// output options of `options` like the `parse_*` functions do. The
+/// `content_type` is reported as the `contentType` of the output. Fails if
+/// the predicate of the `attributeFilter` throws.
+pub fn serialize_dom(
+  dom: RcDom,
+  content_type: &'static str,
+  options: &ParseOptions,
+) -> error::Result<WireDoc> {
+  let mut interner = options.interner_profile.interner();
+  serialize_dom_with(dom, content_type, options, &mut interner, 0)
+}
+
+// Serializes the DOM with its strings interned into `interner`, shipping only
+// the strings starting at index `shipped` (i.e. the ones the receiving side
+// has not seen yet).
+fn serialize_dom_with(
+  dom: RcDom,
+  content_type: &'static str,
+  options: &ParseOptions,
+  interner: &mut Interner,
+  shipped: usize,
+) -> error::Result<WireDoc> {
+  prepare_dom(&dom, options);
+
+  let mut emitter =
+    WireDocEmitter::with_interner(options, core::mem::take(interner));
+  emit(&dom.document, &mut emitter);
+  let nested = match options.parse_srcdoc {
+    true => srcdoc::emit_nested(&dom.document, 0, options, &mut emitter),
+    false => Vec::new(),
+  };
+  let WireDocEmitter {
+    interner: table,
+    mut nodes,
+    error,
+    ..
+  } = emitter;
+  *interner = table;
+  if let Some(error) = error {
+    return Err(error);
+  }
 
-  let mut nodes = Vec::new();
-  collect(&dom.document, None, &mut interner, &mut nodes);
+  if options.node_hashes {
+    // the hashes are in wire order, i.e. in the order of the nodes, and the
+    // nodes of each nested document follow its document node
+    let documents = core::iter::once((0, &dom))
+      .chain(nested.iter().map(|(document, dom)| (*document, dom)));
+    for (document, dom) in documents {
+      let hashes = hash::node_hashes(&dom.document);
+      for (node, hash) in nodes[document as usize..].iter_mut().zip(hashes) {
+        node.hash = Some(format!("{hash:016x}"));
+      }
+    }
+  }
+  let documents = options.parse_srcdoc.then(|| {
+    core::iter::once(0)
+      .chain(nested.iter().map(|(document, _)| *document))
+      .collect()
+  });
 
   // html5ever exposes quirks mode on the document node via RcDom quirks_mode
   // RcDom quirks_mode: ServoQuirksMode::{NoQuirks, Quirks, LimitedQuirks}
@@ -246,156 +2004,116 @@ fn serialize_dom(dom: RcDom, content_type: &'static str) -> WireDoc {
   }
   .into();
 
-  let strings = interner.into_strings();
+  let strings = interner.get(shipped..).unwrap_or_default().to_vec();
+  let strings_offset = shipped.try_into().unwrap_or(u32::MAX);
+
+  let errors = options.collect_errors.then(|| {
+    dom
+      .errors
+      .borrow()
+      .iter()
+      .map(WireError::from)
+      .collect()
+  });
+
+  let effective_options = options.echo_options.then(|| WireOptions {
+    exact_errors:             options.exact_errors,
+    allow_scripts:            options.scripting_enabled,
+    iframe_srcdoc:            options.iframe_srcdoc,
+    drop_doctype:             options.drop_doctype,
+    quirks_mode:              options.quirks_mode.to_string().into(),
+    content_type:             content_type.into(),
+    context_element:          None,
+    doctype:                  None,
+    attribute_filter:         options.attribute_filter.kind().into(),
+    collect_errors:           options.collect_errors,
+    max_text_length:          options.max_text_length,
+    multi_root:               options.multi_root.as_str().into(),
+    max_errors:               options.max_errors,
+    error_sample_rate:        options.error_sample_rate,
+    fail_fast:                options.fail_fast,
+    on_error:                 options.error_hook().is_some(),
+    strip_event_handlers:     options.strip_event_handlers,
+    report_stripped_handlers: options.report_stripped_handlers,
+    encoding:                 options.encoding.clone().map(Into::into),
+    detect_encoding:          options.detect_encoding,
+    line_index:               options.line_index,
+    drop_comments:            options.drop_comments,
+    drop_instructions:        options.drop_processing_instructions,
+    coalesce_text:            options.coalesce_text,
+    collapse_whitespace:      options.collapse_whitespace,
+    remove_empty:             options.remove_empty.is_some(),
+    node_hashes:              options.node_hashes,
+    interner_stats:           options.interner_stats,
+    fold_name_case:           options.fold_name_case,
+    interner_profile:         options.interner_profile.as_str().into(),
+    parse_srcdoc:             options.parse_srcdoc,
+    inject_attributes:        options
+      .inject_attributes
+      .len()
+      .try_into()
+      .unwrap_or(u32::MAX),
+    inline_svg_uses:          options.inline_svg_uses,
+    scope_prefix:             options.scope_prefix.clone().map(Into::into),
+    modernize_presentation:   options.modernize_presentation.is_some(),
+    profile:                  options.profile.map(|p| p.as_str().into()),
+  });
+
+  let multi_root =
+    content_type != "text/html" && count_roots(&dom.document) > 1;
 
-  WireDoc {
+  let error_count = options
+    .collect_errors
+    .then(|| dom.error_count.get().try_into().unwrap_or(u32::MAX));
+
+  Ok(WireDoc {
     strings,
+    strings_offset,
     nodes,
     content_type: content_type.into(),
     quirks_mode,
-  }
+    errors,
+    error_count,
+    effective_options,
+    multi_root,
+    encoding: None,
+    line_starts: None,
+    documents,
+    interner_stats: options.interner_stats.then(|| interner.stats()),
+  })
 }
 
-fn collect(
-  handle: &Handle,
-  parent: Option<u32>,
-  interner: &mut Interner,
-  nodes: &mut Vec<WireNode>,
-) -> u32 {
-  let id = nodes.len() as u32;
-  let mut wire = WireNode {
-    id,
-    parent,
-    first_child: None,
-    next_sibling: None,
-    node_type: WireNodeType::Document,
-    name: None,
-    value: None,
-    attrs: None,
-  };
-
-  match &handle.data {
-    NodeData::Document => {
-      wire.node_type = WireNodeType::Document;
-      wire.parent = None;
-      wire.name = Some(interner.intern("#document"));
-    }
-    NodeData::Doctype {
-      name,
-      public_id,
-      system_id,
-    } => {
-      wire.node_type = WireNodeType::DocumentType;
-      wire.name = Some(interner.intern(name));
-      // Combine doctype into value: "<name> <public_id> <system_id>"
-      let mut val = format!("{name}");
-
-      let mut attributes = Vec::new();
-      attributes.push(WireAttr {
-        ns: None,
-        name: interner.intern("name"),
-        value: interner.intern(name),
-      });
-
-      if !public_id.is_empty() {
-        val.push_str(&format!(r#"PUBLIC "{public_id}" "#));
-        attributes.push(WireAttr {
-          ns: None,
-          name: interner.intern("publicId"),
-          value: interner.intern(public_id),
-        });
-      }
-      if !system_id.is_empty() {
-        val.push_str(&format!(r#"SYSTEM "{system_id}""#));
-        attributes.push(WireAttr {
-          ns: None,
-          name: interner.intern("systemId"),
-          value: interner.intern(system_id),
-        });
-      }
-      wire.value = Some(interner.intern(val.trim().to_string()));
-    }
-    NodeData::Text { contents } => {
-      wire.node_type = WireNodeType::Text;
-      wire.name = Some(interner.intern("#text"));
-      wire.value = Some(interner.intern(contents.borrow().as_ref()));
-    }
-    NodeData::Comment { contents } => {
-      wire.node_type = WireNodeType::Comment;
-      wire.name = Some(interner.intern("#comment"));
-      wire.value = Some(interner.intern(contents));
-    }
-    NodeData::ProcessingInstruction { target, contents } => {
-      wire.node_type = WireNodeType::ProcessingInstruction;
-      wire.name = Some(interner.intern(target));
-      wire.value = Some(interner.intern(contents));
-    }
-    NodeData::Element { name, attrs, .. } => {
-      wire.node_type = WireNodeType::Element;
-      wire.name = Some(intern_qual(name, interner));
-      let attributes = attrs.borrow();
-      if !attributes.is_empty() {
-        let mut wa = Vec::with_capacity(attributes.len());
-        for a in attributes.iter() {
-          let ns = if a.name.ns.is_empty() {
-            None
-          } else {
-            Some(interner.intern(a.name.ns.as_ref()))
-          };
-          wa.push(WireAttr {
-            name: intern_qual_local(&a.name, interner),
-            ns,
-            value: interner.intern(a.value.as_ref()),
-          });
-        }
-        wire.attrs = Some(wa);
+// Applies the transforms of `options` that change the tree before it is
+// serialized.
+pub(crate) fn prepare_dom(dom: &RcDom, options: &ParseOptions) {
+  if options.drop_comments || options.drop_processing_instructions {
+    let remove = |data: &NodeData| match data {
+      NodeData::Comment { .. } => options.drop_comments,
+      NodeData::ProcessingInstruction { .. } => {
+        options.drop_processing_instructions
       }
-    }
+      _ => false,
+    };
+    remove_nodes(&dom.document, &remove, options.coalesce_text);
   }
-
-  nodes.push(wire);
-
-  // children
-  let kids = handle.children.borrow();
-  let mut last_id: Option<u32> = None;
-  for (i, child) in kids.iter().enumerate() {
-    let cid = collect(child, Some(id), interner, nodes);
-    if i == 0 {
-      // set first_child on current node
-      let node = nodes.get_mut(id as usize).unwrap();
-      node.first_child = Some(cid);
-    } else if let Some(prev) = last_id {
-      // set next_sibling on previous child
-      let prev_node = nodes.get_mut(prev as usize).unwrap();
-      prev_node.next_sibling = Some(cid);
-    }
-    last_id = Some(cid);
+  if let Some(modernize) = &options.modernize_presentation {
+    presentational::modernize(&dom.document, modernize);
   }
-
-  id
-}
-
-fn intern_qual(q: &QualName, interner: &mut Interner) -> u32 {
-  let prefix = q.prefix.as_ref().map(|p| p.as_ref()).unwrap_or("");
-  let local = q.local.as_ref();
-  let ns = q.ns.as_ref();
-  if !ns.is_empty() {
-    interner.intern(ns);
-  };
-  let local_idx = interner.intern(local);
-  if !prefix.is_empty() {
-    // Combine namespace and local name into single string for simplicity
-    interner.intern(format!("{prefix}:{local}"))
-  } else {
-    local_idx
+  if let Some(remove) = &options.remove_empty {
+    empty::remove_empty(&dom.document, remove);
+  }
+  if options.collapse_whitespace {
+    whitespace::collapse_whitespace(&dom.document);
+  }
+  if !options.inject_attributes.is_empty() {
+    inject::inject_attributes(dom, &options.inject_attributes);
+  }
+  if options.inline_svg_uses {
+    svg::inline_uses(&dom.document);
+  }
+  if let Some(prefix) = &options.scope_prefix {
+    scope::scope_names(&dom.document, prefix);
   }
-}
-
-fn intern_qual_local(
-  q: &markup5ever::QualName,
-  interner: &mut Interner,
-) -> u32 {
-  interner.intern(q.local.as_ref())
 }
 
 fn default_mime_type() -> String {
@@ -428,8 +2146,23 @@ fn normalized_mime(m: &str) -> &'static str {
 pub fn parse_html_fragment(
   input: &str,
   options: &FragmentParseOptions,
-) -> RcDom {
-  let sink = RcDom::default();
+) -> error::Result<RcDom> {
+  let sink = RcDom::with_line_tracking()
+    .with_error_budget(options.base.error_budget())
+    .with_error_hook(options.base.error_hook());
+  let dom = parse_html_fragment_with(sink, input, options)?;
+  if let Some(doctype) = &options.doctype {
+    doctype.prepend_to(&dom);
+  }
+  Ok(dom)
+}
+
+/// Parses an HTML fragment into the given [`ParseSink`].
+pub fn parse_html_fragment_with<S: ParseSink>(
+  sink: S,
+  input: &str,
+  options: &FragmentParseOptions,
+) -> error::Result<S> {
   let tree_builder: TreeBuilderOpts = options.base.clone().into();
   let tokenizer = TokenizerOpts {
     exact_errors: options.base.exact_errors,
@@ -439,42 +2172,309 @@ pub fn parse_html_fragment(
     tree_builder,
     tokenizer,
   };
-  html5ever::parse_fragment(
+  let parser = html5ever::parse_fragment(
     sink,
     opts,
     QualName::new(None, ns!(html), options.context_element.clone().into()),
     vec![],
     false,
-  )
-  .from_utf8()
-  .read_from(&mut input.as_bytes())
-  .expect("failed to parse HTML fragment")
+  );
+  feed_in_chunks(parser, input, options.base.fail_fast)
 }
 
-pub fn parse_html_document(input: &str, options: &ParseOptions) -> RcDom {
-  let sink = RcDom::default();
+pub fn parse_html_document(
+  input: &str,
+  options: &ParseOptions,
+) -> error::Result<RcDom> {
+  let sink = RcDom::with_line_tracking()
+    .with_error_budget(options.error_budget())
+    .with_error_hook(options.error_hook());
+  parse_html_document_with(sink, input, options)
+}
+
+/// Parses an HTML document into the given [`ParseSink`].
+pub fn parse_html_document_with<S: ParseSink>(
+  sink: S,
+  input: &str,
+  options: &ParseOptions,
+) -> error::Result<S> {
+  let parser = html5ever::parse_document(sink, html_parse_opts(options));
+  feed_in_chunks(parser, input, options.fail_fast)
+}
+
+/// Parses an HTML document into any [`TreeSink`], returning its output.
+///
+/// The options that configure the parser itself (`allowScripts`,
+/// `iframeSrcdoc`, `dropDoctype`, `quirksMode` and `exactErrors`) are applied
+/// like they are for an [`RcDom`], while those that post-process the tree or
+/// its errors are left to the sink, which gets every error through
+/// [`TreeSink::parse_error`]. Since only a [`ParseSink`] can ask to stop,
+/// the whole input is always parsed.
+pub fn parse_html_with_sink<S: TreeSink>(
+  input: &str,
+  options: &ParseOptions,
+  sink: S,
+) -> S::Output {
+  let parser = html5ever::parse_document(sink, html_parse_opts(options));
+  let cursor = InputCursor::new(input);
+  let Ok(output) =
+    feed_checked(parser, &cursor, input, |_| Ok::<_, Infallible>(()));
+  output
+}
+
+/// Parses an XML-like document into any [`TreeSink`], returning its output.
+///
+/// Unlike [`parse_xml_like`], this does not keep the content outside of the
+/// root element (which xml5ever drops), since that takes parsing the input
+/// again into a new sink: the options of XML documents are all applied to
+/// the [`RcDom`] after parsing.
+#[cfg(feature = "xml")]
+pub fn parse_xml_with_sink<S: TreeSink>(input: &str, sink: S) -> S::Output {
+  let opts: xml5ever::driver::XmlParseOpts = Default::default();
+  xml5ever::driver::parse_document(sink, opts).one(input)
+}
+
+/// Parses a document with the parser selected by `mime`, like
+/// {@linkcode parse_doc}, into any [`TreeSink`]. Returns the output of the
+/// sink along with the normalized content type of the document. See
+/// [`parse_html_with_sink`] and [`parse_xml_with_sink`] for what is left to
+/// the sink.
+#[cfg(feature = "xml")]
+pub fn parse_with_sink<S: TreeSink>(
+  input: &str,
+  mime: &str,
+  options: &ParseOptions,
+  sink: S,
+) -> (S::Output, &'static str) {
+  let mime = mime.trim().to_ascii_lowercase();
+  if is_html_mime(&mime) {
+    (parse_html_with_sink(input, options, sink), "text/html")
+  } else {
+    (parse_xml_with_sink(input, sink), normalized_mime(&mime))
+  }
+}
+
+// The options of the HTML parser, for a document parsed with `options`.
+fn html_parse_opts(options: &ParseOptions) -> ParseOpts {
   let tree_builder: TreeBuilderOpts = options.clone().into();
   let tokenizer = TokenizerOpts {
     exact_errors: options.exact_errors,
     ..Default::default()
   };
-  let opts = ParseOpts {
+  ParseOpts {
     tree_builder,
     tokenizer,
+  }
+}
+
+// The number of bytes fed to the parser at a time.
+const CHUNK_SIZE: usize = 4096;
+
+// Feeds the input to the parser in small chunks, bailing out as soon as
+// parsing was aborted (or, with `fail_fast`, an error shows up) instead of
+// parsing the remainder. The offset of each chunk is recorded for the panic
+// hook, and the sink is handed the input to locate its errors in.
+fn feed_in_chunks<S: ParseSink>(
+  parser: html5ever::driver::Parser<S>,
+  input: &str,
+  fail_fast: bool,
+) -> error::Result<S> {
+  let cursor = Rc::new(InputCursor::new(input));
+  parser.tokenizer.sink.sink.track_input(cursor.clone());
+  let sink = feed_checked(parser, &cursor, input, |sink| {
+    sink.check_stop(fail_fast)
+  })?;
+  sink.check_stop(fail_fast)?;
+  Ok(sink)
+}
+
+// Feeds the input to the parser in chunks like `feed_in_chunks`, through the
+// queue of `cursor`, running `check` over the sink after each of them.
+fn feed_checked<S: TreeSink, E>(
+  parser: html5ever::driver::Parser<S>,
+  cursor: &InputCursor,
+  input: &str,
+  check: impl Fn(&S) -> Result<(), E>,
+) -> Result<S::Output, E> {
+  let mut rest = input;
+  while !rest.is_empty() {
+    let mut end = rest.len().min(CHUNK_SIZE);
+    while !rest.is_char_boundary(end) {
+      end += 1;
+    }
+    let (chunk, tail) = rest.split_at(end);
+    panic_hook::set_byte_offset(input.len() - rest.len());
+    cursor.push(chunk);
+    while let TokenizerResult::Script(_) =
+      parser.tokenizer.feed(cursor.queue())
+    {}
+    rest = tail;
+    check(&parser.tokenizer.sink.sink)?;
+  }
+  Ok(parser.finish())
+}
+
+#[cfg(feature = "xml")]
+pub fn parse_xml_like(
+  input: &str,
+  options: &ParseOptions,
+) -> error::Result<RcDom> {
+  // The error budget is applied after the fact, since the full list of
+  // errors is needed to detect content dropped by xml5ever. For the same
+  // reason, fail-fast mode only checks for errors after parsing, and the
+  // `onError` callback is replayed over the errors of the final document.
+  let dom = parse_xml_once(input);
+  dom.check_stop(options.fail_fast)?;
+  if !dropped_top_level_content(&dom) {
+    replay_error_hook(&dom, options)?;
+    dom.apply_error_budget(options.error_budget());
+    return Ok(dom);
+  }
+
+  // xml5ever discards everything outside of the first root element. To keep
+  // those nodes around, re-parse the input with its body wrapped in a
+  // synthetic root element, which is then either kept or unwrapped. The
+  // wrapped input has no content outside of its root, so the errors about
+  // that are carried over from the first parse.
+  let outside: Vec<diagnostics::ParseError> = dom
+    .errors
+    .borrow()
+    .iter()
+    .filter(|e| e.code == ErrorCode::ContentOutsideRoot)
+    .cloned()
+    .collect();
+  let (prolog, body) = split_xml_prolog(input);
+  let dom = parse_xml_once(&format!("{prolog}<root>{body}</root>"));
+  dom.error_count.set(dom.error_count.get() + outside.len());
+  dom.errors.borrow_mut().splice(0..0, outside);
+  replay_error_hook(&dom, options)?;
+  dom.apply_error_budget(options.error_budget());
+  let Some(root) = dom
+    .document
+    .children
+    .borrow()
+    .iter()
+    .find(|child| matches!(child.data, NodeData::Element { .. }))
+    .cloned()
+  else {
+    return Ok(dom);
   };
-  html5ever::parse_document(sink, opts)
-    .from_utf8()
-    .read_from(&mut input.as_bytes())
-    .expect("failed to parse HTML input")
+
+  let roots = count_roots(&root);
+  match options.multi_root {
+    MultiRootMode::Error => Err(DawmError::MultipleRoots(roots)),
+    MultiRootMode::Wrap => Ok(dom),
+    MultiRootMode::Siblings => {
+      unwrap_root(&dom.document, &root);
+      Ok(dom)
+    }
+  }
 }
 
 #[cfg(feature = "xml")]
-pub fn parse_xml_like(input: &str, _options: &ParseOptions) -> RcDom {
+fn parse_xml_once(input: &str) -> RcDom {
   // xml5ever uses the same TreeSink trait; use RcDom as sink
   // Note: XML parser doesn't use the same options structure currently
   let sink = RcDom::default();
   let opts: xml5ever::driver::XmlParseOpts = Default::default();
-  let p = xml5ever::driver::parse_document(sink, opts);
-  p.input_buffer.push_back(input.into());
-  p.finish()
+  // `one()` feeds the input to the tokenizer before finishing; pushing onto
+  // `input_buffer` directly never runs the tokenizer over it.
+  xml5ever::driver::parse_document(sink, opts).one(input)
+}
+
+// Invokes the `onError` callback for each error recorded in the DOM, failing
+// with the first error for which the callback requested an abort.
+#[cfg(feature = "xml")]
+fn replay_error_hook(dom: &RcDom, options: &ParseOptions) -> error::Result<()> {
+  let Some(hook) = options.error_hook() else {
+    return Ok(());
+  };
+  match dom.errors.borrow().iter().find(|error| !hook(error)) {
+    Some(error) => Err(DawmError::Aborted(error.clone())),
+    None => Ok(()),
+  }
+}
+
+// xml5ever reports (and drops) any content it finds before or after the root
+// element with one of these two errors.
+#[cfg(feature = "xml")]
+fn dropped_top_level_content(dom: &RcDom) -> bool {
+  dom
+    .errors
+    .borrow()
+    .iter()
+    .any(|e| e.code == ErrorCode::ContentOutsideRoot)
+}
+
+// Splits an XML document into its prolog (the XML declaration, processing
+// instructions, comments, doctype and whitespace) and everything after it.
+#[cfg(feature = "xml")]
+fn split_xml_prolog(input: &str) -> (&str, &str) {
+  let mut rest = input;
+  loop {
+    let trimmed = rest.trim_start();
+    let skip = if trimmed.starts_with("<?") {
+      trimmed.find("?>").map(|i| i + 2)
+    } else if trimmed.starts_with("<!--") {
+      trimmed.find("-->").map(|i| i + 3)
+    } else if trimmed.starts_with("<!") {
+      // doctype, possibly with an internal subset in square brackets
+      let mut depth = 0usize;
+      trimmed.char_indices().find_map(|(i, c)| match c {
+        '[' => {
+          depth += 1;
+          None
+        }
+        ']' => {
+          depth = depth.saturating_sub(1);
+          None
+        }
+        '>' if depth == 0 => Some(i + 1),
+        _ => None,
+      })
+    } else {
+      None
+    };
+    match skip {
+      Some(n) => rest = &trimmed[n..],
+      None => break,
+    }
+  }
+  input.split_at(input.len() - rest.len())
+}
+
+// Counts the element and non-whitespace text children of a node.
+fn count_roots(parent: &Handle) -> usize {
+  parent
+    .children
+    .borrow()
+    .iter()
+    .filter(|child| match &child.data {
+      NodeData::Element { .. } => true,
+      NodeData::Text { contents } => !contents.borrow().trim().is_empty(),
+      _ => false,
+    })
+    .count()
+}
+
+// Replaces the synthetic root element with its children, dropping the
+// whitespace-only text nodes which cannot live at the top level.
+#[cfg(feature = "xml")]
+fn unwrap_root(document: &Handle, root: &Handle) {
+  let mut top = document.children.borrow_mut();
+  let Some(index) = top.iter().position(|c| Rc::ptr_eq(c, root)) else {
+    return;
+  };
+  let children = core::mem::take(&mut *root.children.borrow_mut());
+  let children: Vec<_> = children
+    .into_iter()
+    .filter(|child| match &child.data {
+      NodeData::Text { contents } => !contents.borrow().trim().is_empty(),
+      _ => true,
+    })
+    .collect();
+  for child in &children {
+    child.parent.set(Some(Rc::downgrade(document)));
+  }
+  top.splice(index..=index, children);
 }
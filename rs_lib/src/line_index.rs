@@ -0,0 +1,66 @@
+//! Byte offset to line/column mapping.
+//!
+//! A [`LineIndex`] records the byte offset at which each line of the input
+//! starts, so that any byte offset can be mapped to its line and column with
+//! a binary search instead of re-scanning the input. With the `lineIndex`
+//! option, the table is included in the output as `lineStarts`, where the
+//! entry at index `n` is the offset of line `n + 1`.
+//!
+//! Lines are terminated by `\n`, `\r\n` or a lone `\r`, just as the HTML
+//! parser normalizes them.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// The line-start offsets of an input.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LineIndex {
+  starts: Vec<u32>,
+}
+
+impl LineIndex {
+  /// Indexes the line starts of `input`.
+  pub fn new(input: &str) -> Self {
+    let bytes = input.as_bytes();
+    let mut starts = vec![0];
+    for (i, &b) in bytes.iter().enumerate() {
+      let ends_line = match b {
+        b'\n' => true,
+        b'\r' => bytes.get(i + 1) != Some(&b'\n'),
+        _ => false,
+      };
+      if ends_line {
+        starts.push((i + 1).try_into().unwrap_or(u32::MAX));
+      }
+    }
+    Self { starts }
+  }
+
+  /// The number of lines, which is always at least `1`.
+  pub fn line_count(&self) -> usize {
+    self.starts.len()
+  }
+
+  /// The byte offset at which the 1-based `line` starts, if there is one.
+  pub fn line_start(&self, line: u64) -> Option<usize> {
+    let index = usize::try_from(line).ok()?.checked_sub(1)?;
+    self.starts.get(index).map(|&start| start as usize)
+  }
+
+  /// Maps a byte offset to its 1-based line and (byte) column.
+  pub fn locate(&self, offset: usize) -> (u64, u64) {
+    let offset = offset.try_into().unwrap_or(u32::MAX);
+    let line = self.starts.partition_point(|&start| start <= offset);
+    let column = offset - self.starts[line - 1];
+    (line as u64, column as u64 + 1)
+  }
+
+  /// The byte offset at which each line starts.
+  pub fn starts(&self) -> &[u32] {
+    &self.starts
+  }
+
+  pub fn into_starts(self) -> Vec<u32> {
+    self.starts
+  }
+}
@@ -0,0 +1,349 @@
+//! Three-way structural merges of documents.
+//!
+//! [`merge_docs`] merges the changes that two versions of a tree (ours and
+//! theirs) made to a common ancestor (the base), the way version control
+//! merges files, but node by node rather than line by line. The children of
+//! matching nodes are matched up like in the [`diff`](crate::diff) module,
+//! and the children that were matched in all three versions, in the same
+//! order, split the remaining children into runs. A node or run that only
+//! one side changed takes that side's version, one changed the same way on
+//! both sides takes either, and the children and attributes of an element
+//! that both sides changed are merged recursively.
+//!
+//! Changes that cannot be merged, e.g. an attribute that was set to
+//! different values, or a paragraph that one side removed while the other
+//! edited it, are reported as [`MergeConflict`]s, with the merged tree
+//! holding our version of the conflicting part. Every conflict records all
+//! three versions of that part, so that it can be resolved afterwards.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use serde::Serialize;
+
+use crate::Appendable;
+use crate::Handle;
+use crate::NodeData;
+use crate::diff::Matcher;
+use crate::diff::child_nodes;
+use crate::diff::container;
+use crate::diff::qualified_name;
+use crate::diff::similar;
+use crate::rcdom::clone_node;
+use crate::rcdom::outer_html;
+
+/// A part of the tree that both sides changed in ways that cannot be merged.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeConflict {
+  /// The path of the conflicting node in the merged tree, as in the
+  /// [`diff`](crate::diff) module, or for conflicting children, of the
+  /// first node of the run.
+  pub path:   Vec<u32>,
+  pub kind:   MergeConflictKind,
+  /// The qualified name of the conflicting attribute.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub name:   Option<String>,
+  /// The number of nodes of the run of conflicting children in the merged
+  /// tree, i.e. of our version of it.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub count:  Option<u32>,
+  /// The base version of the conflicting part, as HTML for nodes and as
+  /// the value for attributes and text, or `None` if it does not exist.
+  pub base:   Option<String>,
+  /// Our version of the conflicting part, which the merged tree holds.
+  pub ours:   Option<String>,
+  /// Their version of the conflicting part.
+  pub theirs: Option<String>,
+}
+
+/// What kind of change conflicts.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeConflictKind {
+  /// Both sides set an attribute of the same element to different values,
+  /// or one side removed it while the other changed it.
+  Attribute,
+  /// Both sides changed the text of the same text node or comment (or the
+  /// data of the same processing instruction) differently.
+  Text,
+  /// Both sides replaced the same node with different ones, e.g. by
+  /// changing the `id` of an element to different values.
+  Node,
+  /// Both sides changed the same run of children differently, e.g. by
+  /// inserting different nodes at the same place, or one side removed
+  /// nodes that the other changed.
+  Children,
+}
+
+/// Merges the changes that the trees rooted at `ours` and `theirs` made to
+/// the one rooted at `base`, returning the root of the merged tree (a copy
+/// of `ours`, with the merged descendants) along with the conflicts. The
+/// roots themselves are not merged, only their descendants, like in
+/// [`diff_docs`](crate::diff::diff_docs). The input trees are left as they
+/// are.
+pub fn merge_docs(
+  base: &Handle,
+  ours: &Handle,
+  theirs: &Handle,
+) -> (Handle, Vec<MergeConflict>) {
+  let mut merger = Merger {
+    matcher:   Matcher::new(&[base, ours, theirs]),
+    conflicts: Vec::new(),
+  };
+  let merged = clone_node(ours, false);
+  merger.merge_children(base, ours, theirs, &merged, &mut Vec::new());
+  (merged, merger.conflicts)
+}
+
+struct Merger {
+  matcher:   Matcher,
+  conflicts: Vec<MergeConflict>,
+}
+
+impl Merger {
+  // Appends the merged children of `base`, `ours` and `theirs` to `merged`,
+  // which is at `path`.
+  fn merge_children(
+    &mut self,
+    base: &Handle,
+    ours: &Handle,
+    theirs: &Handle,
+    merged: &Handle,
+    path: &mut Vec<u32>,
+  ) {
+    let base = child_nodes(base);
+    let ours = child_nodes(ours);
+    let theirs = child_nodes(theirs);
+    let ours_pairs = self.matcher.match_children(&base, &ours);
+    let mut theirs_of_base = vec![None; base.len()];
+    for (j, pair) in self
+      .matcher
+      .match_children(&base, &theirs)
+      .iter()
+      .enumerate()
+    {
+      if let Some(k) = *pair {
+        theirs_of_base[k] = Some(j);
+      }
+    }
+
+    // the children matched in all three versions, in the same order in all
+    // of them, as (base, ours, theirs) indices
+    let mut anchors = Vec::new();
+    let mut last: Option<(usize, usize)> = None;
+    for (i, pair) in ours_pairs.iter().enumerate() {
+      let Some(k) = *pair else {
+        continue;
+      };
+      let Some(j) = theirs_of_base[k] else {
+        continue;
+      };
+      if last.is_none_or(|(last_k, last_j)| k > last_k && j > last_j) {
+        anchors.push((k, i, j));
+        last = Some((k, j));
+      }
+    }
+
+    let parent = container(merged);
+    let (mut k0, mut i0, mut j0) = (0, 0, 0);
+    let end = (base.len(), ours.len(), theirs.len());
+    for (k, i, j) in anchors.into_iter().chain([end]) {
+      self.merge_run(&base[k0..k], &ours[i0..i], &theirs[j0..j], &parent, path);
+      if k == base.len() {
+        break;
+      }
+      path.push(parent.children.borrow().len() as u32);
+      let child = self.merge_node(&base[k], &ours[i], &theirs[j], path);
+      path.pop();
+      parent.append(child);
+      (k0, i0, j0) = (k + 1, i + 1, j + 1);
+    }
+  }
+
+  // Appends the merged run of children between two anchors to `parent`.
+  fn merge_run(
+    &mut self,
+    base: &[Handle],
+    ours: &[Handle],
+    theirs: &[Handle],
+    parent: &Handle,
+    path: &[u32],
+  ) {
+    let same_run = |a: &[Handle], b: &[Handle]| {
+      a.len() == b.len()
+        && a.iter().zip(b).all(|(a, b)| self.matcher.same(a, b))
+    };
+    let (run, conflicting) = if same_run(ours, base) {
+      (theirs, false)
+    } else if same_run(theirs, base) || same_run(ours, theirs) {
+      (ours, false)
+    } else {
+      (ours, true)
+    };
+    if conflicting {
+      let mut run_path = path.to_vec();
+      run_path.push(parent.children.borrow().len() as u32);
+      self.conflicts.push(MergeConflict {
+        path:   run_path,
+        kind:   MergeConflictKind::Children,
+        name:   None,
+        count:  Some(ours.len() as u32),
+        base:   run_html(base),
+        ours:   run_html(ours),
+        theirs: run_html(theirs),
+      });
+    }
+    for node in run {
+      parent.append(clone_node(node, true));
+    }
+  }
+
+  // Merges three matching nodes into a new one, which is to end up at
+  // `path`.
+  fn merge_node(
+    &mut self,
+    base: &Handle,
+    ours: &Handle,
+    theirs: &Handle,
+    path: &mut Vec<u32>,
+  ) -> Handle {
+    if self.matcher.same(ours, theirs) || self.matcher.same(base, theirs) {
+      return clone_node(ours, true);
+    }
+    if self.matcher.same(base, ours) {
+      return clone_node(theirs, true);
+    }
+    match (&base.data, &ours.data, &theirs.data) {
+      (
+        NodeData::Element {
+          attrs: base_attrs, ..
+        },
+        NodeData::Element {
+          attrs: ours_attrs, ..
+        },
+        NodeData::Element {
+          attrs: theirs_attrs,
+          ..
+        },
+      ) if similar(ours, theirs) => {
+        let attrs = self.merge_attributes(
+          &base_attrs.borrow(),
+          &ours_attrs.borrow(),
+          &theirs_attrs.borrow(),
+          path,
+        );
+        let merged = clone_node(ours, false);
+        if let NodeData::Element {
+          attrs: merged_attrs,
+          ..
+        } = &merged.data
+        {
+          *merged_attrs.borrow_mut() = attrs;
+        }
+        self.merge_children(base, ours, theirs, &merged, path);
+        merged
+      }
+      _ => {
+        // identical nodes have been taken care of, so both sides changed the
+        // node, each in its own way
+        let texts = (text(base), text(ours), text(theirs));
+        let (kind, versions) = match texts {
+          (Some(base), Some(ours_text), Some(theirs_text))
+            if similar(ours, theirs) =>
+          {
+            (MergeConflictKind::Text, (base, ours_text, theirs_text))
+          }
+          _ => (
+            MergeConflictKind::Node,
+            (outer_html(base), outer_html(ours), outer_html(theirs)),
+          ),
+        };
+        self.conflicts.push(MergeConflict {
+          path: path.clone(),
+          kind,
+          name: None,
+          count: None,
+          base: Some(versions.0),
+          ours: Some(versions.1),
+          theirs: Some(versions.2),
+        });
+        clone_node(ours, true)
+      }
+    }
+  }
+
+  // Merges the attributes of three matching elements, keeping the order of
+  // ours, with the attributes only they added at the end.
+  fn merge_attributes(
+    &mut self,
+    base: &[Attribute],
+    ours: &[Attribute],
+    theirs: &[Attribute],
+    path: &[u32],
+  ) -> Vec<Attribute> {
+    let find = |attrs: &[Attribute], name: &QualName| {
+      attrs
+        .iter()
+        .find(|attr| attr.name.ns == name.ns && attr.name.local == name.local)
+        .map(|attr| attr.value.clone())
+    };
+    let names: Vec<&QualName> = ours
+      .iter()
+      .chain(
+        theirs
+          .iter()
+          .filter(|attr| find(ours, &attr.name).is_none()),
+      )
+      .map(|attr| &attr.name)
+      .collect();
+    let mut merged = Vec::with_capacity(names.len());
+    for name in names {
+      let (base, ours, theirs) =
+        (find(base, name), find(ours, name), find(theirs, name));
+      let value = if ours == theirs || base == theirs {
+        ours
+      } else if base == ours {
+        theirs
+      } else {
+        self.conflicts.push(MergeConflict {
+          path:   path.to_vec(),
+          kind:   MergeConflictKind::Attribute,
+          name:   Some(qualified_name(name)),
+          count:  None,
+          base:   base.as_ref().map(|value| value.to_string()),
+          ours:   ours.as_ref().map(|value| value.to_string()),
+          theirs: theirs.as_ref().map(|value| value.to_string()),
+        });
+        ours
+      };
+      if let Some(value) = value {
+        merged.push(Attribute {
+          name: name.clone(),
+          value,
+        });
+      }
+    }
+    merged
+  }
+}
+
+// The text of a text node or comment, or the data of a processing
+// instruction.
+fn text(node: &Handle) -> Option<String> {
+  let text = match &node.data {
+    NodeData::Text { contents } => contents.borrow().to_string(),
+    NodeData::Comment { contents } => contents.to_string(),
+    NodeData::ProcessingInstruction { contents, .. } => contents.to_string(),
+    _ => return None,
+  };
+  Some(text)
+}
+
+fn run_html(run: &[Handle]) -> Option<String> {
+  (!run.is_empty()).then(|| run.iter().map(outer_html).collect())
+}
@@ -0,0 +1,754 @@
+//! A native Node.js addon, for servers that want the wire format without
+//! the startup and serialization costs of the WASM module.
+//!
+//! With the `node` feature, the `cdylib` is a Node-API addon (to be renamed
+//! to `dawm.node` and `require`d) which exports the parse functions of the
+//! WASM module, taking the same arguments and returning the same objects:
+//!
+//! ```js
+//! const dawm = require("./dawm.node");
+//! const doc = dawm.parse_doc("<p>Hi", "text/html", { lineIndex: true });
+//! const page = dawm.parse_bytes(fs.readFileSync("page.html"), "text/html");
+//! ```
+//!
+//! - `parse_doc(input, mime, options?)` parses a string, like
+//!   [`parse_wire_doc`], and `parse_html(input, options?)` parses it as
+//!   `text/html`;
+//! - `parse_bytes(input, mime, options?)` parses the bytes of a `Buffer` (or of
+//!   any `Uint8Array`) where they are, without copying them, decoding them like
+//!   [`parse_wire_bytes`].
+//!
+//! The options are read from the JS object as it is, with its `profile`
+//! applied like the WASM module applies it, and options that cannot be read
+//! (e.g. an unknown `quirksMode`) are reported, like they are there. The
+//! result is made by `JSON.parse`ing the [JSON](crate::json) of the
+//! [`WireDoc`](crate::WireDoc), which V8 does faster than the addon could make
+//! it a value at a time. Errors, panics included, are thrown as JS `Error`s.
+//!
+//! The addon calls version 1 of Node-API directly, so it needs no other
+//! dependencies, and its symbols are resolved from the Node binary that
+//! loads it (on macOS, this needs the `cdylib` to be linked with `-C
+//! link-args=-Wl,-undefined,dynamic_lookup`).
+
+use alloc::ffi::CString;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ffi::CStr;
+use core::ffi::c_char;
+use core::ffi::c_int;
+use core::ffi::c_void;
+use core::fmt;
+use core::ptr;
+use core::slice;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+
+use serde::Deserialize;
+use serde::de;
+use serde::de::IntoDeserializer;
+use serde::de::Visitor;
+use serde::de::value::Error;
+use serde::de::value::MapAccessDeserializer;
+use serde::forward_to_deserialize_any;
+
+use crate::ParseOptions;
+use crate::Profile;
+use crate::json;
+use crate::parse_wire_bytes;
+use crate::parse_wire_doc;
+
+// The opaque handles of Node-API.
+type RawEnv = *mut c_void;
+type RawValue = *mut c_void;
+type RawCallbackInfo = *mut c_void;
+type Callback = unsafe extern "C" fn(RawEnv, RawCallbackInfo) -> RawValue;
+
+// The `napi_status` of a successful call, and of one that left a JS
+// exception pending.
+const OK: c_int = 0;
+const PENDING_EXCEPTION: c_int = 10;
+
+// The `napi_valuetype`s that are read.
+const UNDEFINED: c_int = 0;
+const NULL: c_int = 1;
+const BOOLEAN: c_int = 2;
+const NUMBER: c_int = 3;
+const STRING: c_int = 4;
+const OBJECT: c_int = 6;
+
+// The `napi_typedarray_type`s of the arrays of bytes.
+const INT8_ARRAY: c_int = 0;
+const UINT8_ARRAY: c_int = 1;
+const UINT8_CLAMPED_ARRAY: c_int = 2;
+
+unsafe extern "C" {
+  fn napi_get_cb_info(
+    env: RawEnv,
+    info: RawCallbackInfo,
+    argc: *mut usize,
+    argv: *mut RawValue,
+    this: *mut RawValue,
+    data: *mut *mut c_void,
+  ) -> c_int;
+  fn napi_typeof(env: RawEnv, value: RawValue, result: *mut c_int) -> c_int;
+  fn napi_get_value_bool(
+    env: RawEnv,
+    value: RawValue,
+    result: *mut bool,
+  ) -> c_int;
+  fn napi_get_value_double(
+    env: RawEnv,
+    value: RawValue,
+    result: *mut f64,
+  ) -> c_int;
+  fn napi_get_value_string_utf8(
+    env: RawEnv,
+    value: RawValue,
+    buf: *mut c_char,
+    size: usize,
+    result: *mut usize,
+  ) -> c_int;
+  fn napi_is_array(env: RawEnv, value: RawValue, result: *mut bool) -> c_int;
+  fn napi_get_array_length(
+    env: RawEnv,
+    value: RawValue,
+    result: *mut u32,
+  ) -> c_int;
+  fn napi_get_element(
+    env: RawEnv,
+    object: RawValue,
+    index: u32,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_get_property_names(
+    env: RawEnv,
+    object: RawValue,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_get_property(
+    env: RawEnv,
+    object: RawValue,
+    key: RawValue,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_get_named_property(
+    env: RawEnv,
+    object: RawValue,
+    name: *const c_char,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_set_named_property(
+    env: RawEnv,
+    object: RawValue,
+    name: *const c_char,
+    value: RawValue,
+  ) -> c_int;
+  fn napi_is_typedarray(
+    env: RawEnv,
+    value: RawValue,
+    result: *mut bool,
+  ) -> c_int;
+  fn napi_get_typedarray_info(
+    env: RawEnv,
+    value: RawValue,
+    kind: *mut c_int,
+    length: *mut usize,
+    data: *mut *mut c_void,
+    buffer: *mut RawValue,
+    offset: *mut usize,
+  ) -> c_int;
+  fn napi_get_boolean(env: RawEnv, value: bool, result: *mut RawValue)
+  -> c_int;
+  fn napi_create_string_utf8(
+    env: RawEnv,
+    text: *const c_char,
+    len: usize,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_get_global(env: RawEnv, result: *mut RawValue) -> c_int;
+  fn napi_call_function(
+    env: RawEnv,
+    this: RawValue,
+    function: RawValue,
+    argc: usize,
+    argv: *const RawValue,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_create_function(
+    env: RawEnv,
+    name: *const c_char,
+    len: usize,
+    callback: Callback,
+    data: *mut c_void,
+    result: *mut RawValue,
+  ) -> c_int;
+  fn napi_throw_error(
+    env: RawEnv,
+    code: *const c_char,
+    message: *const c_char,
+  ) -> c_int;
+}
+
+/// Registers the functions of the addon on its `exports`. Node calls this
+/// when the addon is loaded.
+///
+/// # Safety
+///
+/// Only to be called by Node, with the environment and exports of the addon.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn napi_register_module_v1(
+  env: RawEnv,
+  exports: RawValue,
+) -> RawValue {
+  let functions: [(&CStr, Callback); 3] = [
+    (c"parse_doc", parse_doc),
+    (c"parse_html", parse_html),
+    (c"parse_bytes", parse_bytes),
+  ];
+  for (name, callback) in functions {
+    let mut function = ptr::null_mut();
+    // SAFETY: `name` is NUL-terminated, and the rest are handles of `env`.
+    unsafe {
+      napi_create_function(
+        env,
+        name.as_ptr(),
+        name.count_bytes(),
+        callback,
+        ptr::null_mut(),
+        &mut function,
+      );
+      napi_set_named_property(env, exports, name.as_ptr(), function);
+    }
+  }
+  exports
+}
+
+unsafe extern "C" fn parse_doc(env: RawEnv, info: RawCallbackInfo) -> RawValue {
+  call(Env(env), info, |env, [input, mime, options]| {
+    let options = parse_options(env, options)?;
+    let input = env.string(input).map_err(|_| "input must be a string")?;
+    let mime = env.mime(mime)?;
+    let doc = parse_wire_doc(&input, &mime, &options)?;
+    env.wire_doc(&doc)
+  })
+}
+
+unsafe extern "C" fn parse_html(
+  env: RawEnv,
+  info: RawCallbackInfo,
+) -> RawValue {
+  call(Env(env), info, |env, [input, options, _]| {
+    let options = parse_options(env, options)?;
+    let input = env.string(input).map_err(|_| "input must be a string")?;
+    let doc = parse_wire_doc(&input, "text/html", &options)?;
+    env.wire_doc(&doc)
+  })
+}
+
+unsafe extern "C" fn parse_bytes(
+  env: RawEnv,
+  info: RawCallbackInfo,
+) -> RawValue {
+  call(Env(env), info, |env, [input, mime, options]| {
+    // everything that makes JS values comes before the input is borrowed,
+    // so that its bytes cannot be moved by the garbage collector meanwhile
+    let options = parse_options(env, options)?;
+    let mime = env.mime(mime)?;
+    let input = env
+      .bytes(input)
+      .ok_or("input must be a Buffer or a Uint8Array")?;
+    // SAFETY: the bytes are not touched by JS until the call returns, and
+    // nothing below makes JS values before they are parsed.
+    let doc = parse_wire_bytes(unsafe { input.as_slice() }, &mime, &options)?;
+    env.wire_doc(&doc)
+  })
+}
+
+// Runs the body of an exported function with its first three arguments,
+// throwing what it fails with, or panics with, as a JS `Error`.
+fn call<F>(env: Env, info: RawCallbackInfo, body: F) -> RawValue
+where
+  F: FnOnce(Env, [RawValue; 3]) -> Result<RawValue, Failure>,
+{
+  let mut argc = 3;
+  let mut argv = [ptr::null_mut(); 3];
+  // SAFETY: `argv` has room for `argc` values.
+  let status = unsafe {
+    napi_get_cb_info(
+      env.0,
+      info,
+      &mut argc,
+      argv.as_mut_ptr(),
+      ptr::null_mut(),
+      ptr::null_mut(),
+    )
+  };
+  let result = match status {
+    // nothing of the body outlives a panic in it
+    OK => panic::catch_unwind(AssertUnwindSafe(|| body(env, argv)))
+      .unwrap_or_else(|_| Err(Failure::Message("dawm panicked".into()))),
+    status => Err(Failure::Status(status)),
+  };
+  match result {
+    Ok(value) => value,
+    Err(Failure::Status(PENDING_EXCEPTION)) => ptr::null_mut(),
+    Err(failure) => {
+      let message = failure.to_string().replace('\0', "\u{fffd}");
+      let message = CString::new(message).unwrap_or_default();
+      // SAFETY: `message` is NUL-terminated, and the code may be null.
+      unsafe { napi_throw_error(env.0, ptr::null(), message.as_ptr()) };
+      ptr::null_mut()
+    }
+  }
+}
+
+// Why an exported function failed.
+enum Failure {
+  // A call to Node-API failed with this status.
+  Status(c_int),
+  Message(String),
+}
+
+impl fmt::Display for Failure {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Failure::Status(status) => {
+        write!(f, "a Node-API call failed with status {status}")
+      }
+      Failure::Message(message) => f.write_str(message),
+    }
+  }
+}
+
+impl From<&str> for Failure {
+  fn from(message: &str) -> Self {
+    Failure::Message(message.into())
+  }
+}
+
+impl From<crate::error::DawmError> for Failure {
+  fn from(error: crate::error::DawmError) -> Self {
+    Failure::Message(error.to_string())
+  }
+}
+
+// The options given as `value`, with the defaults of their `profile`.
+fn parse_options(env: Env, value: RawValue) -> Result<ParseOptions, Failure> {
+  let read = || -> Result<ParseOptions, Error> {
+    if env.type_of(value).map_err(status_error)? != OBJECT {
+      return Ok(ParseOptions::default());
+    }
+    let mut entries = env.entries(value).map_err(status_error)?;
+    let profile = entries
+      .iter()
+      .find(|(name, _)| name == "profile")
+      .and_then(|(_, value)| env.string(*value).ok())
+      .and_then(|name| Profile::from_name(&name));
+    if let Some(profile) = profile {
+      for &(name, on) in profile.options() {
+        if !entries.iter().any(|(given, _)| given == name) {
+          let on = env.boolean(on).map_err(status_error)?;
+          entries.push((name.to_string(), on));
+        }
+      }
+    }
+    ParseOptions::deserialize(MapAccessDeserializer::new(Entries {
+      env,
+      entries: entries.into_iter(),
+      value: None,
+    }))
+  };
+  read().map_err(|error| Failure::Message(format!("invalid options: {error}")))
+}
+
+fn status_error(status: c_int) -> Error {
+  de::Error::custom(Failure::Status(status))
+}
+
+// A Node-API environment, to make calls through.
+#[derive(Clone, Copy)]
+struct Env(RawEnv);
+
+// The bytes of a typed array, which are only valid until the function that
+// was handed the array returns, and while no JS values are made.
+struct Bytes {
+  data: *const u8,
+  len:  usize,
+}
+
+impl Bytes {
+  // SAFETY: the caller must make sure that the bytes are still valid.
+  unsafe fn as_slice<'a>(&self) -> &'a [u8] {
+    match self.data.is_null() {
+      true => &[],
+      // SAFETY: Node-API handed out `len` bytes at `data`.
+      false => unsafe { slice::from_raw_parts(self.data, self.len) },
+    }
+  }
+}
+
+impl Env {
+  fn check(status: c_int) -> Result<(), c_int> {
+    match status {
+      OK => Ok(()),
+      status => Err(status),
+    }
+  }
+
+  fn type_of(self, value: RawValue) -> Result<c_int, c_int> {
+    let mut kind = 0;
+    // SAFETY: `value` is a handle of this environment.
+    Env::check(unsafe { napi_typeof(self.0, value, &mut kind) })?;
+    Ok(kind)
+  }
+
+  fn bool(self, value: RawValue) -> Result<bool, c_int> {
+    let mut result = false;
+    // SAFETY: `value` is a handle of this environment.
+    Env::check(unsafe { napi_get_value_bool(self.0, value, &mut result) })?;
+    Ok(result)
+  }
+
+  fn number(self, value: RawValue) -> Result<f64, c_int> {
+    let mut result = 0.0;
+    // SAFETY: `value` is a handle of this environment.
+    Env::check(unsafe { napi_get_value_double(self.0, value, &mut result) })?;
+    Ok(result)
+  }
+
+  fn string(self, value: RawValue) -> Result<String, c_int> {
+    let mut len = 0;
+    // SAFETY: a null buffer asks for the length of the string only, which
+    // is then written into a buffer with room for it and its terminator.
+    let bytes = unsafe {
+      Env::check(napi_get_value_string_utf8(
+        self.0,
+        value,
+        ptr::null_mut(),
+        0,
+        &mut len,
+      ))?;
+      let mut bytes = vec![0u8; len + 1];
+      Env::check(napi_get_value_string_utf8(
+        self.0,
+        value,
+        bytes.as_mut_ptr().cast(),
+        bytes.len(),
+        &mut len,
+      ))?;
+      bytes.truncate(len);
+      bytes
+    };
+    Ok(String::from_utf8(bytes).unwrap_or_else(|error| {
+      String::from_utf8_lossy(error.as_bytes()).into_owned()
+    }))
+  }
+
+  // The MIME type given as `value`, which defaults to `text/html`.
+  fn mime(self, value: RawValue) -> Result<String, Failure> {
+    match self.type_of(value).map_err(Failure::Status)? {
+      UNDEFINED | NULL => Ok("text/html".into()),
+      _ => self
+        .string(value)
+        .map_err(|_| "mime must be a string".into()),
+    }
+  }
+
+  fn bytes(self, value: RawValue) -> Option<Bytes> {
+    let mut is_typed_array = false;
+    let mut kind = 0;
+    let mut len = 0;
+    let mut data = ptr::null_mut();
+    // SAFETY: `value` is a handle of this environment, and the buffer and
+    // offset may be null.
+    unsafe {
+      Env::check(napi_is_typedarray(self.0, value, &mut is_typed_array))
+        .ok()?;
+      if !is_typed_array {
+        return None;
+      }
+      Env::check(napi_get_typedarray_info(
+        self.0,
+        value,
+        &mut kind,
+        &mut len,
+        &mut data,
+        ptr::null_mut(),
+        ptr::null_mut(),
+      ))
+      .ok()?;
+    }
+    matches!(kind, INT8_ARRAY | UINT8_ARRAY | UINT8_CLAMPED_ARRAY).then(|| {
+      Bytes {
+        data: data.cast_const().cast(),
+        len,
+      }
+    })
+  }
+
+  fn boolean(self, value: bool) -> Result<RawValue, c_int> {
+    let mut result = ptr::null_mut();
+    // SAFETY: the result is written into `result`.
+    Env::check(unsafe { napi_get_boolean(self.0, value, &mut result) })?;
+    Ok(result)
+  }
+
+  fn is_array(self, value: RawValue) -> Result<bool, c_int> {
+    let mut result = false;
+    // SAFETY: `value` is a handle of this environment.
+    Env::check(unsafe { napi_is_array(self.0, value, &mut result) })?;
+    Ok(result)
+  }
+
+  fn elements(self, array: RawValue) -> Result<Vec<RawValue>, c_int> {
+    let mut len = 0;
+    // SAFETY: `array` is a handle of this environment, and an array.
+    Env::check(unsafe { napi_get_array_length(self.0, array, &mut len) })?;
+    (0..len)
+      .map(|index| {
+        let mut element = ptr::null_mut();
+        // SAFETY: as above.
+        Env::check(unsafe {
+          napi_get_element(self.0, array, index, &mut element)
+        })?;
+        Ok(element)
+      })
+      .collect()
+  }
+
+  // The enumerable properties of `object` that are not `undefined`.
+  fn entries(self, object: RawValue) -> Result<Vec<(String, RawValue)>, c_int> {
+    let mut names = ptr::null_mut();
+    // SAFETY: `object` is a handle of this environment.
+    Env::check(unsafe { napi_get_property_names(self.0, object, &mut names) })?;
+    let mut entries = Vec::new();
+    for key in self.elements(names)? {
+      let mut value = ptr::null_mut();
+      // SAFETY: `key` is one of the property names of `object`.
+      Env::check(unsafe {
+        napi_get_property(self.0, object, key, &mut value)
+      })?;
+      if self.type_of(value)? != UNDEFINED {
+        entries.push((self.string(key)?, value));
+      }
+    }
+    Ok(entries)
+  }
+
+  // Turns `doc` into a JS object, by `JSON.parse`ing its JSON.
+  fn wire_doc(self, doc: &crate::WireDoc) -> Result<RawValue, Failure> {
+    let json = json::to_string(doc)
+      .map_err(|error| Failure::Message(error.to_string()))?;
+    let status = |status| Env::check(status).map_err(Failure::Status);
+    let mut global = ptr::null_mut();
+    let mut json_object = ptr::null_mut();
+    let mut parse = ptr::null_mut();
+    let mut text = ptr::null_mut();
+    let mut result = ptr::null_mut();
+    // SAFETY: the names are NUL-terminated, `json` is `json.len()` bytes of
+    // UTF-8, and the rest are handles of this environment.
+    unsafe {
+      status(napi_get_global(self.0, &mut global))?;
+      status(napi_get_named_property(
+        self.0,
+        global,
+        c"JSON".as_ptr(),
+        &mut json_object,
+      ))?;
+      status(napi_get_named_property(
+        self.0,
+        json_object,
+        c"parse".as_ptr(),
+        &mut parse,
+      ))?;
+      status(napi_create_string_utf8(
+        self.0,
+        json.as_ptr().cast(),
+        json.len(),
+        &mut text,
+      ))?;
+      status(napi_call_function(
+        self.0,
+        json_object,
+        parse,
+        1,
+        &text,
+        &mut result,
+      ))?;
+    }
+    Ok(result)
+  }
+}
+
+// Reads a JS value into Rust, like `serde-wasm-bindgen` does.
+struct Value {
+  env:   Env,
+  value: RawValue,
+}
+
+impl Value {
+  fn new(env: Env, value: RawValue) -> Self {
+    Value { env, value }
+  }
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+  type Error = Error;
+
+  fn deserialize_any<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    let env = self.env;
+    match env.type_of(self.value).map_err(status_error)? {
+      UNDEFINED | NULL => visitor.visit_unit(),
+      BOOLEAN => {
+        visitor.visit_bool(env.bool(self.value).map_err(status_error)?)
+      }
+      NUMBER => {
+        let number = env.number(self.value).map_err(status_error)?;
+        // integers are handed out as such, for the integer fields
+        const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_991.0;
+        match number.fract() == 0.0 && number.abs() <= MAX_SAFE_INTEGER {
+          true if number >= 0.0 => visitor.visit_u64(number as u64),
+          true => visitor.visit_i64(number as i64),
+          false => visitor.visit_f64(number),
+        }
+      }
+      STRING => {
+        visitor.visit_string(env.string(self.value).map_err(status_error)?)
+      }
+      OBJECT if env.is_array(self.value).map_err(status_error)? => {
+        let elements = env.elements(self.value).map_err(status_error)?;
+        visitor.visit_seq(Elements {
+          env,
+          elements: elements.into_iter(),
+        })
+      }
+      OBJECT => {
+        let entries = env.entries(self.value).map_err(status_error)?;
+        visitor.visit_map(Entries {
+          env,
+          entries: entries.into_iter(),
+          value: None,
+        })
+      }
+      _ => Err(de::Error::custom(
+        "cannot read functions, symbols or bigints",
+      )),
+    }
+  }
+
+  fn deserialize_option<V: Visitor<'de>>(
+    self,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    match self.env.type_of(self.value).map_err(status_error)? {
+      UNDEFINED | NULL => visitor.visit_none(),
+      _ => visitor.visit_some(self),
+    }
+  }
+
+  fn deserialize_newtype_struct<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    visitor.visit_newtype_struct(self)
+  }
+
+  fn deserialize_enum<V: Visitor<'de>>(
+    self,
+    _name: &'static str,
+    _variants: &'static [&'static str],
+    visitor: V,
+  ) -> Result<V::Value, Error> {
+    let env = self.env;
+    match env.type_of(self.value).map_err(status_error)? {
+      STRING => {
+        let variant = env.string(self.value).map_err(status_error)?;
+        visitor.visit_enum(variant.into_deserializer())
+      }
+      _ => {
+        let entries = env.entries(self.value).map_err(status_error)?;
+        visitor.visit_enum(MapAccessDeserializer::new(Entries {
+          env,
+          entries: entries.into_iter(),
+          value: None,
+        }))
+      }
+    }
+  }
+
+  forward_to_deserialize_any! {
+    bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+    bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+    identifier ignored_any
+  }
+}
+
+// The elements of a JS array.
+struct Elements {
+  env:      Env,
+  elements: vec::IntoIter<RawValue>,
+}
+
+impl<'de> de::SeqAccess<'de> for Elements {
+  type Error = Error;
+
+  fn next_element_seed<T: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: T,
+  ) -> Result<Option<T::Value>, Error> {
+    match self.elements.next() {
+      Some(element) => {
+        seed.deserialize(Value::new(self.env, element)).map(Some)
+      }
+      None => Ok(None),
+    }
+  }
+
+  fn size_hint(&self) -> Option<usize> {
+    Some(self.elements.len())
+  }
+}
+
+// The properties of a JS object.
+struct Entries {
+  env:     Env,
+  entries: vec::IntoIter<(String, RawValue)>,
+  // the value of the property whose name was read last
+  value:   Option<RawValue>,
+}
+
+impl<'de> de::MapAccess<'de> for Entries {
+  type Error = Error;
+
+  fn next_key_seed<K: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: K,
+  ) -> Result<Option<K::Value>, Error> {
+    let Some((name, value)) = self.entries.next() else {
+      return Ok(None);
+    };
+    self.value = Some(value);
+    seed.deserialize(name.into_deserializer()).map(Some)
+  }
+
+  fn next_value_seed<V: de::DeserializeSeed<'de>>(
+    &mut self,
+    seed: V,
+  ) -> Result<V::Value, Error> {
+    let value = self
+      .value
+      .take()
+      .ok_or_else(|| de::Error::custom("value without a key"))?;
+    seed.deserialize(Value::new(self.env, value))
+  }
+
+  fn size_hint(&self) -> Option<usize> {
+    Some(self.entries.len())
+  }
+}
@@ -0,0 +1,168 @@
+//! Content Security Policy nonces.
+//!
+//! A policy like `script-src 'nonce-r4nd0m'` only runs the inline scripts
+//! whose `nonce` attribute is `r4nd0m`, which a server generates anew for
+//! every response. [`inject_nonce`] adds the nonce to every inline
+//! `<script>` (with `scripts`) and `<style>` (with `styles`) of a document,
+//! replacing any nonce they had, and reports the inline code that no nonce
+//! can cover, which the policy blocks unless it also has `'unsafe-inline'`
+//! (or `'unsafe-hashes'` with a hash of it):
+//!
+//! - inline event handlers, like `onclick="..."`;
+//! - `javascript:` URLs, like `href="javascript:void(0)"`;
+//! - with `styles`, `style` attributes.
+//!
+//! Data blocks, like `<script type="application/ld+json">`, get the nonce
+//! too, as it does no harm. The contents of templates are covered as well,
+//! since their scripts run once they are instantiated.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use html5ever::local_name;
+use html5ever::ns;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::DawmError;
+use crate::Handle;
+use crate::NodeData;
+use crate::emit::is_event_handler;
+use crate::rcdom::number_nodes;
+use crate::sanitize::is_url_attribute;
+use crate::sanitize::url_scheme;
+
+/// Which elements [`inject_nonce`] adds the nonce to.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NonceTargets {
+  /// Whether to add it to the inline `<script>` elements.
+  pub scripts: bool,
+  /// Whether to add it to the `<style>` elements.
+  pub styles:  bool,
+}
+
+impl Default for NonceTargets {
+  fn default() -> Self {
+    Self {
+      scripts: true,
+      styles:  true,
+    }
+  }
+}
+
+/// Inline code that a nonce cannot cover.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UncoveredInline {
+  /// The id of the element, as in the wire output of the document.
+  pub id:        u32,
+  pub tag:       String,
+  pub attribute: String,
+  pub value:     String,
+  /// `"eventHandler"`, `"javascriptUrl"` or `"styleAttribute"`.
+  pub kind:      &'static str,
+}
+
+/// What [`inject_nonce`] did, and what it could not do.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NonceReport {
+  /// How many elements got the nonce.
+  pub injected:  u32,
+  /// The inline code left uncovered, in document order.
+  pub uncovered: Vec<UncoveredInline>,
+}
+
+/// Adds `nonce` to the inline scripts and styles of the document rooted at
+/// `document` that `targets` selects, and reports the inline code that it
+/// does not cover. Fails if `nonce` is not a valid nonce source, i.e. is
+/// empty or has characters other than those of base64 (or base64url).
+pub fn inject_nonce(
+  document: &Handle,
+  nonce: &str,
+  targets: NonceTargets,
+) -> Result<NonceReport, DawmError> {
+  let digits = nonce.trim_end_matches('=');
+  if digits.is_empty()
+    || !digits
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '-' | '_'))
+  {
+    return Err(DawmError::Syntax(format!("{nonce:?} is not a valid nonce")));
+  }
+
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let mut report = NonceReport::default();
+  let mut stack = vec![document.clone()];
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      ..
+    } = &node.data
+    {
+      let mut attrs = attrs.borrow_mut();
+      let has = |attrs: &[Attribute], name: &str| {
+        attrs
+          .iter()
+          .any(|attr| attr.name.ns == ns!() && &*attr.name.local == name)
+      };
+      let is_target = match &*name.local {
+        "script" => targets.scripts && !has(&attrs, "src"),
+        "style" => targets.styles,
+        _ => false,
+      };
+      if is_target {
+        attrs.retain(|attr| {
+          !(attr.name.ns == ns!() && attr.name.local == local_name!("nonce"))
+        });
+        attrs.push(Attribute {
+          name:  QualName::new(None, ns!(), local_name!("nonce")),
+          value: nonce.into(),
+        });
+        report.injected += 1;
+      }
+
+      let id = ids.get(&Rc::as_ptr(&node)).copied().unwrap_or(u32::MAX);
+      for attr in attrs.iter().filter(|attr| attr.name.ns == ns!()) {
+        let attribute = &*attr.name.local;
+        let kind = if is_event_handler(attribute) {
+          "eventHandler"
+        } else if targets.styles && attribute == "style" {
+          "styleAttribute"
+        } else if is_javascript_url(attribute, &attr.value) {
+          "javascriptUrl"
+        } else {
+          continue;
+        };
+        report.uncovered.push(UncoveredInline {
+          id,
+          tag: String::from(&*name.local),
+          attribute: String::from(attribute),
+          value: String::from(&*attr.value),
+          kind,
+        });
+      }
+      if let Some(contents) = template_contents {
+        stack.push(contents.clone());
+      }
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  Ok(report)
+}
+
+// Whether the attribute `name` holds a `javascript:` URL.
+fn is_javascript_url(name: &str, value: &str) -> bool {
+  is_url_attribute(name)
+    && url_scheme(value).is_some_and(|scheme| scheme == "javascript")
+}
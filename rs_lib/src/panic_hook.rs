@@ -0,0 +1,174 @@
+//! Structured reporting of internal panics.
+//!
+//! On `wasm32-unknown-unknown`, a Rust panic aborts with an `unreachable`
+//! trap, which surfaces in JavaScript as an opaque `RuntimeError` with no hint
+//! of what went wrong. The hook installed by [`install`] instead throws a
+//! regular JS `Error` (named `"DawmPanic"`) whose message contains the panic
+//! message and its location, with the following additional properties that
+//! describe the state of the parser at the time of the panic:
+//!
+//! - `panicMessage`: the panic message on its own;
+//! - `location`: the `file:line:column` the panic originated from;
+//! - `byteOffset`: the offset of the input chunk being parsed;
+//! - `line`: the input line being parsed, if known;
+//! - `openElements`: the names of the elements enclosing the most recently
+//!   inserted node, outermost first. This closely mirrors the parser's stack of
+//!   open elements, but may differ after foster parenting or adoption.
+//!
+//! Since the hook throws from within the panicking frame, the WASM instance
+//! must be considered poisoned after a panic and should be re-instantiated.
+//!
+//! Without the `wasm` feature, [`install`] does nothing, leaving the panics
+//! of native builds to the default hook (or the application's own). Without
+//! the `std` feature, which the state is kept in a thread local of, the
+//! parser state is not tracked either.
+
+#[cfg(feature = "wasm")]
+use alloc::boxed::Box;
+#[cfg(feature = "wasm")]
+use alloc::format;
+#[cfg(feature = "std")]
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use alloc::rc::Weak;
+#[cfg(feature = "wasm")]
+use alloc::string::String;
+#[cfg(feature = "wasm")]
+use alloc::string::ToString;
+#[cfg(feature = "wasm")]
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+#[cfg(feature = "wasm")]
+use std::panic::PanicHookInfo;
+#[cfg(feature = "wasm")]
+use std::sync::Once;
+
+#[cfg(feature = "wasm")]
+use js_sys::Array;
+#[cfg(feature = "wasm")]
+use js_sys::Error;
+#[cfg(feature = "wasm")]
+use js_sys::Reflect;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+use crate::Handle;
+#[cfg(feature = "std")]
+use crate::Node;
+#[cfg(feature = "wasm")]
+use crate::NodeData;
+
+#[cfg(feature = "std")]
+#[derive(Default)]
+struct ParserState {
+  byte_offset:     usize,
+  line:            u64,
+  insertion_point: Option<Weak<Node>>,
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+  static STATE: RefCell<ParserState> = RefCell::default();
+}
+
+/// Installs the panic hook. This is idempotent, and is called by all of the
+/// WASM entry points before parsing.
+pub fn install() {
+  #[cfg(feature = "wasm")]
+  static INSTALL: Once = Once::new();
+  #[cfg(feature = "wasm")]
+  INSTALL.call_once(|| std::panic::set_hook(Box::new(hook)));
+}
+
+/// Resets the tracked parser state before a new parse.
+pub fn reset() {
+  #[cfg(feature = "std")]
+  STATE.with(|state| *state.borrow_mut() = ParserState::default());
+}
+
+/// Records the byte offset of the input chunk being fed to the parser.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub fn set_byte_offset(offset: usize) {
+  #[cfg(feature = "std")]
+  STATE.with(|state| state.borrow_mut().byte_offset = offset);
+}
+
+/// Records the input line the parser is currently on.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub fn set_line(line: u64) {
+  #[cfg(feature = "std")]
+  STATE.with(|state| state.borrow_mut().line = line);
+}
+
+/// Records the node that the parser most recently inserted children into.
+#[cfg_attr(not(feature = "std"), allow(unused_variables))]
+pub fn set_insertion_point(parent: &Handle) {
+  #[cfg(feature = "std")]
+  STATE.with(|state| {
+    state.borrow_mut().insertion_point = Some(Rc::downgrade(parent));
+  });
+}
+
+#[cfg(feature = "wasm")]
+fn open_elements(node: Option<Handle>) -> Vec<String> {
+  let mut names = Vec::new();
+  let mut current = node;
+  while let Some(node) = current {
+    if let NodeData::Element { name, .. } = &node.data {
+      names.push(name.local.to_string());
+    }
+    let parent = node.parent.take();
+    node.parent.set(parent.clone());
+    current = parent.and_then(|weak| weak.upgrade());
+  }
+  names.reverse();
+  names
+}
+
+#[cfg(feature = "wasm")]
+fn hook(info: &PanicHookInfo) {
+  let message = info
+    .payload()
+    .downcast_ref::<&str>()
+    .map(|s| s.to_string())
+    .or_else(|| info.payload().downcast_ref::<String>().cloned())
+    .unwrap_or_else(|| "Box<dyn Any>".to_string());
+  let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+
+  let (byte_offset, line, elements) =
+    STATE.with(|state| match state.try_borrow() {
+      Ok(state) => (
+        state.byte_offset,
+        state.line,
+        open_elements(state.insertion_point.as_ref().and_then(Weak::upgrade)),
+      ),
+      Err(_) => (0, 0, Vec::new()),
+    });
+
+  let error = Error::new(&format!("dawm panicked at {location}: {message}"));
+  error.set_name("DawmPanic");
+  let set = |key: &str, value: JsValue| {
+    let _ = Reflect::set(&error, &JsValue::from_str(key), &value);
+  };
+  set("panicMessage", JsValue::from_str(&message));
+  set("location", JsValue::from_str(&location));
+  set("byteOffset", JsValue::from_f64(byte_offset as f64));
+  set(
+    "line",
+    match line {
+      0 => JsValue::NULL,
+      line => JsValue::from_f64(line as f64),
+    },
+  );
+  set(
+    "openElements",
+    elements
+      .iter()
+      .map(|name| JsValue::from_str(name))
+      .collect::<Array>()
+      .into(),
+  );
+
+  wasm_bindgen::throw_val(error.into());
+}
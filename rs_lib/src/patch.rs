@@ -0,0 +1,328 @@
+//! Applying [`PatchOp`]s to a tree, the counterpart of the [`diff`] module.
+//!
+//! [`apply_patch`] applies the operations in order, addressing their targets
+//! by path as described in the [`diff`] module. An operation that does not
+//! fit the tree, e.g. because its path leads nowhere or to the wrong kind of
+//! node, is skipped and reported as a [`PatchConflict`], and the remaining
+//! operations are still applied: for a patch produced by [`diff_docs`] for
+//! another version of the tree, conflicts mean that the trees have diverged.
+//!
+//! [`diff`]: crate::diff
+//! [`diff_docs`]: crate::diff::diff_docs
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::Namespace;
+use html5ever::Prefix;
+use html5ever::QualName;
+use html5ever::ns;
+use serde::Serialize;
+
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::ParentNode;
+use crate::diff::PatchNode;
+use crate::diff::PatchOp;
+use crate::diff::container;
+
+/// An operation of a patch that could not be applied.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct PatchConflict {
+  /// The index of the operation in the patch.
+  pub index:   u32,
+  pub kind:    ConflictKind,
+  pub message: String,
+}
+
+/// Why an operation of a patch could not be applied.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictKind {
+  /// There is no node at the path of the operation (or, for insertions, no
+  /// parent with enough children).
+  MissingTarget,
+  /// The node at the path is of the wrong kind, e.g. an attribute is to be
+  /// set on a text node, or the root is to be removed.
+  InvalidTarget,
+  /// The attribute to remove does not exist.
+  MissingAttribute,
+}
+
+/// Applies the `ops` to the tree rooted at `root`, in order, returning the
+/// operations that could not be applied.
+pub fn apply_patch(root: &Handle, ops: &[PatchOp]) -> Vec<PatchConflict> {
+  let mut conflicts = Vec::new();
+  for (index, op) in ops.iter().enumerate() {
+    if let Err((kind, message)) = apply_op(root, op) {
+      conflicts.push(PatchConflict {
+        index: index as u32,
+        kind,
+        message,
+      });
+    }
+  }
+  conflicts
+}
+
+type Conflict = (ConflictKind, String);
+
+fn apply_op(root: &Handle, op: &PatchOp) -> Result<(), Conflict> {
+  match op {
+    PatchOp::InsertNode { path, node } => {
+      let (parent, index) = resolve_slot(root, path)?;
+      insert_child(&parent, index, build_node(node));
+    }
+    PatchOp::RemoveNode { path } => {
+      let node = resolve_child(root, path)?;
+      node.remove_from_parent();
+    }
+    PatchOp::MoveNode { from, to } => {
+      let node = resolve_child(root, from)?;
+      let (parent, index) =
+        node.get_parent_and_index().ok_or_else(|| missing(from))?;
+      node.remove_from_parent();
+      // the destination is looked up without the node in the tree, so it
+      // cannot be below the node itself
+      match resolve_slot(root, to) {
+        Ok((new_parent, new_index)) => {
+          insert_child(&new_parent, new_index, node)
+        }
+        Err(conflict) => {
+          insert_child(&parent, index, node);
+          return Err(conflict);
+        }
+      }
+    }
+    PatchOp::SetAttribute {
+      path,
+      name,
+      namespace,
+      value,
+    } => {
+      let node = resolve(root, path)?;
+      let NodeData::Element { attrs, .. } = &node.data else {
+        return Err(invalid(path, "is not an element"));
+      };
+      let name = qualify(name, namespace);
+      let mut attrs = attrs.borrow_mut();
+      match attrs.iter_mut().find(|attr| same_name(&attr.name, &name)) {
+        Some(attr) => attr.value = value.as_str().into(),
+        None => attrs.push(Attribute {
+          name,
+          value: value.as_str().into(),
+        }),
+      }
+    }
+    PatchOp::RemoveAttribute {
+      path,
+      name,
+      namespace,
+    } => {
+      let node = resolve(root, path)?;
+      let NodeData::Element { attrs, .. } = &node.data else {
+        return Err(invalid(path, "is not an element"));
+      };
+      let qualified = qualify(name, namespace);
+      let mut attrs = attrs.borrow_mut();
+      let Some(position) = attrs
+        .iter()
+        .position(|attr| same_name(&attr.name, &qualified))
+      else {
+        return Err((
+          ConflictKind::MissingAttribute,
+          format!("the element at {path:?} has no attribute {name:?}"),
+        ));
+      };
+      attrs.remove(position);
+    }
+    PatchOp::SetText { path, value } => {
+      let node = resolve_child(root, path)?;
+      // only the contents of text nodes can change in place
+      let data = match &node.data {
+        NodeData::Text { contents } => {
+          *contents.borrow_mut() = value.as_str().into();
+          return Ok(());
+        }
+        NodeData::Comment { .. } => NodeData::Comment {
+          contents: value.as_str().into(),
+        },
+        NodeData::ProcessingInstruction { target, .. } => {
+          NodeData::ProcessingInstruction {
+            target:   target.clone(),
+            contents: value.as_str().into(),
+          }
+        }
+        _ => return Err(invalid(path, "has no text")),
+      };
+      let (parent, index) =
+        node.get_parent_and_index().ok_or_else(|| missing(path))?;
+      node.remove_from_parent();
+      insert_child(&parent, index, Node::new(data));
+    }
+  }
+  Ok(())
+}
+
+fn missing(path: &[u32]) -> Conflict {
+  (
+    ConflictKind::MissingTarget,
+    format!("there is no node at {path:?}"),
+  )
+}
+
+fn invalid(path: &[u32], what: &str) -> Conflict {
+  (
+    ConflictKind::InvalidTarget,
+    format!("the node at {path:?} {what}"),
+  )
+}
+
+// Finds the node at `path`, which is `root` itself for the empty path.
+fn resolve(root: &Handle, path: &[u32]) -> Result<Handle, Conflict> {
+  let mut node = root.clone();
+  for &index in path {
+    let child = container(&node)
+      .children
+      .borrow()
+      .get(index as usize)
+      .cloned();
+    node = child.ok_or_else(|| missing(path))?;
+  }
+  Ok(node)
+}
+
+// Finds the node at `path`, which must not be the root.
+fn resolve_child(root: &Handle, path: &[u32]) -> Result<Handle, Conflict> {
+  if path.is_empty() {
+    return Err(invalid(path, "is the root"));
+  }
+  resolve(root, path)
+}
+
+// Finds the node whose children a node is inserted among to end up at
+// `path`, along with the index to insert it at.
+fn resolve_slot(
+  root: &Handle,
+  path: &[u32],
+) -> Result<(Handle, usize), Conflict> {
+  let Some((&index, parent_path)) = path.split_last() else {
+    return Err(invalid(path, "is the root"));
+  };
+  let parent = resolve(root, parent_path)?;
+  if matches!(
+    parent.data,
+    NodeData::Text { .. }
+      | NodeData::Comment { .. }
+      | NodeData::Doctype { .. }
+      | NodeData::ProcessingInstruction { .. }
+  ) {
+    return Err(invalid(parent_path, "cannot have children"));
+  }
+  let parent = container(&parent);
+  if index as usize > parent.children.borrow().len() {
+    return Err(missing(path));
+  }
+  Ok((parent, index as usize))
+}
+
+fn insert_child(parent: &Handle, index: usize, child: Handle) {
+  child.parent.set(Some(Rc::downgrade(parent)));
+  parent.children.borrow_mut().insert(index, child);
+}
+
+// Attributes are identified by their namespace and local name, like the
+// DOM's `getAttributeNS` does.
+fn same_name(a: &QualName, b: &QualName) -> bool {
+  a.ns == b.ns && a.local == b.local
+}
+
+// Splits a qualified name into its prefix and local name. Only names in a
+// namespace have a prefix, as the local names of HTML attributes (like that
+// of `xml:lang` on an HTML element) may well contain colons.
+fn qualify(name: &str, namespace: &str) -> QualName {
+  let (prefix, local) = match name.split_once(':') {
+    Some((prefix, local)) if !namespace.is_empty() => (Some(prefix), local),
+    _ => (None, name),
+  };
+  QualName::new(
+    prefix.map(Prefix::from),
+    Namespace::from(namespace),
+    LocalName::from(local),
+  )
+}
+
+fn build_node(patch_node: &PatchNode) -> Handle {
+  let (data, children) = match patch_node {
+    PatchNode::Element {
+      name,
+      namespace,
+      attributes,
+      children,
+    } => {
+      let name = qualify(name, namespace);
+      let is_template = name.ns == ns!(html) && &*name.local == "template";
+      let data = NodeData::Element {
+        attrs: RefCell::new(
+          attributes
+            .iter()
+            .map(|attr| Attribute {
+              name:  qualify(&attr.name, &attr.namespace),
+              value: attr.value.as_str().into(),
+            })
+            .collect(),
+        ),
+        template_contents: is_template.then(|| Node::new(NodeData::Document)),
+        mathml_annotation_xml_integration_point: false,
+        name,
+      };
+      (data, children.as_slice())
+    }
+    PatchNode::Text { value } => (
+      NodeData::Text {
+        contents: RefCell::new(value.as_str().into()),
+      },
+      &[][..],
+    ),
+    PatchNode::Comment { value } => (
+      NodeData::Comment {
+        contents: value.as_str().into(),
+      },
+      &[][..],
+    ),
+    PatchNode::ProcessingInstruction { target, data } => (
+      NodeData::ProcessingInstruction {
+        target:   target.as_str().into(),
+        contents: data.as_str().into(),
+      },
+      &[][..],
+    ),
+    PatchNode::Doctype {
+      name,
+      public_id,
+      system_id,
+    } => (
+      NodeData::Doctype {
+        name:      name.as_str().into(),
+        public_id: public_id.as_str().into(),
+        system_id: system_id.as_str().into(),
+      },
+      &[][..],
+    ),
+  };
+  let node = Node::new(data);
+  let parent = container(&node);
+  for child in children {
+    let child = build_node(child);
+    let index = parent.children.borrow().len();
+    insert_child(&parent, index, child);
+  }
+  node
+}
@@ -0,0 +1,188 @@
+//! Locators for the nodes of a [`WireDoc`].
+//!
+//! [`node_path`] computes a CSS selector or an XPath expression that selects
+//! a node of a parsed document, and only that node, from the names of its
+//! ancestors and their positions among the siblings of the same type:
+//!
+//! - as CSS, `html > body > div:nth-of-type(2) > p`, for elements only. A step
+//!   only has an `:nth-of-type()` when its element has siblings of the same
+//!   type.
+//! - as XPath, `/html[1]/body[1]/div[2]/p[1]/text()[1]`, for every node, with
+//!   `/` for the document itself.
+//!
+//! Paths only depend on the names and order of the nodes, not on their ids
+//! or attributes, so they stay the same when the document is parsed again,
+//! or when the attributes of its nodes change. The paths of nodes in the
+//! contents of a `<template>`, or in a fragment, start at the contents or the
+//! fragment, like those of a document start at the document.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::WireDoc;
+use crate::WireNode;
+use crate::WireNodeType;
+use crate::error::DawmError;
+use crate::error::Result;
+
+/// The language of a path computed by [`node_path`].
+#[derive(
+  Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PathFormat {
+  /// A CSS selector, as accepted by `querySelector`.
+  #[default]
+  Css,
+  /// An XPath 1.0 expression, as accepted by `document.evaluate`.
+  Xpath,
+}
+
+/// Computes the path of the node with the id `id` in `doc`, in the given
+/// `format`. Fails if there is no such node, if its ancestors or their names
+/// are missing from `doc`, or if the node cannot be selected in that format
+/// (like a text node with CSS, or a doctype).
+pub fn node_path(doc: &WireDoc, id: u32, format: PathFormat) -> Result<String> {
+  let node = doc.node(id).ok_or(DawmError::NodeNotFound(id))?;
+  if format == PathFormat::Css && node.node_type != WireNodeType::Element {
+    return Err(DawmError::InvalidNodeType(format!(
+      "node {id} is not an element, which CSS paths can only select"
+    )));
+  }
+  if node.node_type == WireNodeType::DocumentType {
+    return Err(DawmError::InvalidNodeType(format!(
+      "node {id} is a doctype, which paths cannot select"
+    )));
+  }
+
+  // the nodes from the node up to (but not including) its root
+  let mut steps = Vec::new();
+  let mut current = node;
+  while let Some(parent) = current.parent {
+    // a tree has fewer steps than nodes, so this guards against cycles
+    if steps.len() == doc.nodes.len() {
+      return Err(DawmError::InvalidState(format!(
+        "the ancestors of node {id} form a cycle"
+      )));
+    }
+    steps.push(current);
+    current = doc.node(parent).ok_or(DawmError::NodeNotFound(parent))?;
+  }
+  steps.reverse();
+
+  let mut path = String::new();
+  for step in steps {
+    let (position, count) = position(doc, step)?;
+    match format {
+      PathFormat::Css => {
+        if !path.is_empty() {
+          path.push_str(" > ");
+        }
+        push_ident(&mut path, name(doc, step)?);
+        if count > 1 {
+          let _ = write!(path, ":nth-of-type({position})");
+        }
+      }
+      PathFormat::Xpath => {
+        path.push('/');
+        match step.node_type {
+          WireNodeType::Element => path.push_str(name(doc, step)?),
+          WireNodeType::Text | WireNodeType::CData => path.push_str("text()"),
+          WireNodeType::Comment => path.push_str("comment()"),
+          _ => path.push_str("processing-instruction()"),
+        }
+        let _ = write!(path, "[{position}]");
+      }
+    }
+  }
+  if path.is_empty() && format == PathFormat::Xpath {
+    path.push('/');
+  }
+  Ok(path)
+}
+
+// The name of an element, which a step of a path tests for.
+fn name<'a>(doc: &'a WireDoc, node: &WireNode) -> Result<&'a str> {
+  let index = node.name.ok_or_else(|| {
+    DawmError::InvalidState(format!("element {} has no name", node.id))
+  })?;
+  doc
+    .string(index)
+    .ok_or_else(|| DawmError::NotFound(format!("string {index}")))
+}
+
+// The 1-based position of `node` among the siblings that the same step of a
+// path would select, along with the number of those siblings.
+fn position(doc: &WireDoc, node: &WireNode) -> Result<(u32, u32)> {
+  let parent = node.parent.and_then(|parent| doc.node(parent));
+  let mut sibling = parent.and_then(|parent| parent.first_child);
+  let (mut position, mut count, mut seen) = (0, 0, 0);
+  while let Some(id) = sibling {
+    let current = doc.node(id).ok_or(DawmError::NodeNotFound(id))?;
+    seen += 1;
+    if same_step(node, current) {
+      count += 1;
+      if current.id == node.id {
+        position = count;
+      }
+    }
+    if seen > doc.nodes.len() {
+      return Err(DawmError::InvalidState(format!(
+        "the children of node {} form a cycle",
+        current.parent.unwrap_or_default()
+      )));
+    }
+    sibling = current.next_sibling;
+  }
+  match position {
+    0 => Err(DawmError::InvalidState(format!(
+      "node {} is not among the children of its parent",
+      node.id
+    ))),
+    position => Ok((position, count)),
+  }
+}
+
+// Whether a step testing for `node` also selects `other`. Both text and
+// CDATA are text to XPath, and element names are compared by their index,
+// since equal strings share one.
+fn same_step(node: &WireNode, other: &WireNode) -> bool {
+  let is_text = |node: &WireNode| {
+    matches!(node.node_type, WireNodeType::Text | WireNodeType::CData)
+  };
+  match node.node_type {
+    WireNodeType::Element => {
+      other.node_type == WireNodeType::Element && other.name == node.name
+    }
+    WireNodeType::Text | WireNodeType::CData => is_text(other),
+    _ => other.node_type == node.node_type,
+  }
+}
+
+// Serializes a name as a CSS identifier, escaping what a type selector cannot
+// contain as is (like the prefix separator of `svg:rect`).
+fn push_ident(out: &mut String, name: &str) {
+  for (i, ch) in name.chars().enumerate() {
+    match ch {
+      'a'..='z' | 'A'..='Z' | '_' | '-' => out.push(ch),
+      '0'..='9' if i > 0 => out.push(ch),
+      '0'..='9' => {
+        let _ = write!(out, "\\{:x} ", ch as u32);
+      }
+      '\0' => out.push('\u{FFFD}'),
+      ch if ch as u32 >= 0x80 => out.push(ch),
+      ch if ch.is_ascii_control() => {
+        let _ = write!(out, "\\{:x} ", ch as u32);
+      }
+      ch => {
+        out.push('\\');
+        out.push(ch);
+      }
+    }
+  }
+}
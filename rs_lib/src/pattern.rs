@@ -0,0 +1,819 @@
+//! A small regular expression engine, for the patterns of rewriting rules.
+//!
+//! The syntax is the common subset of JavaScript's `RegExp`: literals and
+//! `.`, character classes (`[a-z]`, `[^"]`, `\d`, `\w`, `\s` and their
+//! negations), the anchors `^` and `$` (of the whole input) and the word
+//! boundaries `\b` and `\B`, capturing and non-capturing groups,
+//! alternation, and the greedy and lazy quantifiers `*`, `+`, `?`, `{n}`,
+//! `{n,}` and `{n,m}`. A leading `(?i)` makes the whole pattern ignore case.
+//! Backreferences and lookaround are not supported.
+//!
+//! Patterns are compiled to a program for a Pike VM, which runs every way
+//! the pattern can match side by side instead of backtracking, so matching
+//! takes time linear in the length of the input (times that of the program)
+//! even for nested quantifiers like `(a+)+`. Which match wins, and what its
+//! groups capture, is the same as with backtracking. Counted quantifiers are
+//! compiled to as many copies of what they repeat, and a pattern whose
+//! program would have more than [`MAX_PROGRAM_LEN`] instructions is
+//! rejected.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::mem;
+
+use crate::DawmError;
+
+/// The most instructions the program of a pattern can have.
+pub const MAX_PROGRAM_LEN: usize = 10_000;
+
+/// A compiled pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+  program:     Vec<Inst>,
+  groups:      usize,
+  // the number of slots of a thread: two for each group (including the
+  // whole match), and one for each repetition that could match nothing
+  slots:       usize,
+  ignore_case: bool,
+}
+
+/// Where a pattern matched, with the groups it captured, as char offsets
+/// into the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+  /// The span of the whole match.
+  pub span:   (usize, usize),
+  /// The spans of the capturing groups, in order, `None` for those that
+  /// did not participate in the match.
+  pub groups: Vec<Option<(usize, usize)>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+  Char(char),
+  Any,
+  Class(Class),
+  Start,
+  End,
+  WordBoundary(bool),
+  Group(Box<Node>, Option<usize>),
+  Concat(Vec<Node>),
+  Alternation(Vec<Node>),
+  Repeat {
+    node:   Box<Node>,
+    min:    u32,
+    max:    Option<u32>,
+    greedy: bool,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Class {
+  items:   Vec<ClassItem>,
+  negated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ClassItem {
+  Range(char, char),
+  Digit(bool),
+  Word(bool),
+  Space(bool),
+}
+
+// An instruction of the program of a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+  // consumes a char matched by the node, a `Char`, `Any` or `Class`
+  Single(Node),
+  // goes on if the `Start`, `End` or `WordBoundary` node holds
+  Assert(Node),
+  // goes on at both instructions, preferring the first
+  Split(usize, usize),
+  Jump(usize),
+  // records the position in a slot: slot `2 * n` for where group `n`
+  // starts, `2 * n + 1` for where it ends, and the ones after the groups
+  // for where an optional repetition started
+  Save(usize),
+  // goes on if the repetition that started at the slot matched something,
+  // since an optional repetition must not match nothing (like in JS)
+  Progress(usize),
+  Match,
+}
+
+// Where a thread of the VM is in the program, with the slots it saved.
+struct Thread {
+  pc:    usize,
+  slots: Vec<Option<usize>>,
+}
+
+impl Pattern {
+  /// Compiles a pattern.
+  pub fn new(source: &str) -> Result<Self, DawmError> {
+    let (ignore_case, rest) = match source.strip_prefix("(?i)") {
+      Some(rest) => (true, rest),
+      None => (false, source),
+    };
+    let mut parser = Parser {
+      source,
+      chars: rest.chars().collect(),
+      pos: 0,
+      groups: 0,
+    };
+    let node = parser.alternation()?;
+    if parser.pos < parser.chars.len() {
+      return Err(parser.error("unmatched \")\""));
+    }
+    let mut compiler = Compiler {
+      program: Vec::new(),
+      slots:   2 * parser.groups + 2,
+    };
+    if compiler.node(&node).is_none() {
+      return Err(parser.error(&format!(
+        "it takes more than {MAX_PROGRAM_LEN} instructions"
+      )));
+    }
+    compiler.program.push(Inst::Match);
+    Ok(Pattern {
+      program: compiler.program,
+      groups: parser.groups,
+      slots: compiler.slots,
+      ignore_case,
+    })
+  }
+
+  /// Whether the pattern matches anywhere in `text`.
+  pub fn is_match(&self, text: &str) -> bool {
+    let chars: Vec<char> = text.chars().collect();
+    self.find_at(&chars, 0).is_some()
+  }
+
+  /// Replaces every match in `text` with `replacement`, where `$&` stands
+  /// for the match, `$1` to `$99` for the groups and `$$` for a `$`, like
+  /// JavaScript's `String.prototype.replaceAll`.
+  pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::new();
+    let mut last = 0;
+    let mut start = 0;
+    while start <= chars.len() {
+      let Some(found) = self.find_at(&chars, start) else {
+        break;
+      };
+      let (from, to) = found.span;
+      output.extend(&chars[last..from]);
+      self.expand(&chars, &found, replacement, &mut output);
+      last = to;
+      // an empty match moves on by a char, to not match there again
+      start = if to == from { to + 1 } else { to };
+    }
+    output.extend(&chars[last.min(chars.len())..]);
+    output
+  }
+
+  /// The first match in `chars` starting at or after `start`, if any.
+  pub fn find_at(&self, chars: &[char], start: usize) -> Option<Match> {
+    let vm = Vm {
+      program: &self.program,
+      chars,
+      ignore_case: self.ignore_case,
+      slots: self.slots,
+      repeats: 2 * self.groups + 2,
+    };
+    let (slots, end) = vm.run(start)?;
+    let span = |n: usize| match (slots[2 * n], slots[2 * n + 1]) {
+      (Some(from), Some(to)) => Some((from, to)),
+      _ => None,
+    };
+    Some(Match {
+      span:   (slots[0].unwrap_or(start), end),
+      groups: (1..=self.groups).map(span).collect(),
+    })
+  }
+
+  fn expand(
+    &self,
+    chars: &[char],
+    found: &Match,
+    replacement: &str,
+    output: &mut String,
+  ) {
+    let span = |(from, to): (usize, usize)| &chars[from..to];
+    let mut rest = replacement;
+    while let Some(index) = rest.find('$') {
+      output.push_str(&rest[..index]);
+      rest = &rest[index + 1..];
+      let digits = rest.bytes().take_while(u8::is_ascii_digit).count().min(2);
+      // the longest group number that exists wins, like in JS
+      let group = (1..=digits).rev().find_map(|len| {
+        let number: usize = rest[..len].parse().ok()?;
+        (1..=self.groups).contains(&number).then_some((number, len))
+      });
+      match (rest.chars().next(), group) {
+        (_, Some((number, len))) => {
+          if let Some(group) = found.groups[number - 1] {
+            output.extend(span(group));
+          }
+          rest = &rest[len..];
+        }
+        (Some('&'), _) => {
+          output.extend(span(found.span));
+          rest = &rest[1..];
+        }
+        (Some('$'), _) => {
+          output.push('$');
+          rest = &rest[1..];
+        }
+        _ => output.push('$'),
+      }
+    }
+    output.push_str(rest);
+  }
+}
+
+struct Compiler {
+  program: Vec<Inst>,
+  slots:   usize,
+}
+
+impl Compiler {
+  // Appends the instructions of `node` to the program, or returns `None` if
+  // the program gets too long.
+  fn node(&mut self, node: &Node) -> Option<()> {
+    if self.program.len() >= MAX_PROGRAM_LEN {
+      return None;
+    }
+    match node {
+      Node::Char(_) | Node::Any | Node::Class(_) => {
+        self.program.push(Inst::Single(node.clone()));
+      }
+      Node::Start | Node::End | Node::WordBoundary(_) => {
+        self.program.push(Inst::Assert(node.clone()));
+      }
+      Node::Group(inner, None) => self.node(inner)?,
+      Node::Group(inner, Some(index)) => {
+        self.program.push(Inst::Save(2 * index + 2));
+        self.node(inner)?;
+        self.program.push(Inst::Save(2 * index + 3));
+      }
+      Node::Concat(nodes) => {
+        for node in nodes {
+          self.node(node)?;
+        }
+      }
+      Node::Alternation(alternatives) => {
+        let mut jumps = Vec::new();
+        let (last, rest) = alternatives.split_last()?;
+        for alternative in rest {
+          let split = self.program.len();
+          self.program.push(Inst::Split(split + 1, 0));
+          self.node(alternative)?;
+          jumps.push(self.program.len());
+          self.program.push(Inst::Jump(0));
+          self.program[split] = Inst::Split(split + 1, self.program.len());
+        }
+        self.node(last)?;
+        let end = self.program.len();
+        for jump in jumps {
+          self.program[jump] = Inst::Jump(end);
+        }
+      }
+      Node::Repeat {
+        node,
+        min,
+        max,
+        greedy,
+      } => {
+        for _ in 0..*min {
+          self.node(node)?;
+        }
+        // the optional repetitions start with a split that either goes on
+        // with the repetition or skips to the end, in the order the
+        // quantifier prefers
+        let mut splits = Vec::new();
+        let slot = self.slots;
+        let single =
+          matches!(**node, Node::Char(_) | Node::Any | Node::Class(_));
+        self.slots += usize::from(!single);
+        for _ in *min..max.unwrap_or(*min + 1) {
+          splits.push(self.program.len());
+          self.program.push(Inst::Split(0, 0));
+          if !single {
+            self.program.push(Inst::Save(slot));
+          }
+          self.node(node)?;
+          if !single {
+            self.program.push(Inst::Progress(slot));
+          }
+        }
+        if max.is_none() {
+          self.program.push(Inst::Jump(splits[0]));
+        }
+        let end = self.program.len();
+        for at in splits {
+          self.program[at] = match greedy {
+            true => Inst::Split(at + 1, end),
+            false => Inst::Split(end, at + 1),
+          };
+        }
+      }
+    }
+    (self.program.len() <= MAX_PROGRAM_LEN).then_some(())
+  }
+}
+
+struct Vm<'a> {
+  program:     &'a [Inst],
+  chars:       &'a [char],
+  ignore_case: bool,
+  slots:       usize,
+  // the first slot of a repetition
+  repeats:     usize,
+}
+
+// The states the threads at a position were added in, of which only the
+// first thread goes on: its instruction, and the (slots of the) repetitions
+// it is in that have not matched anything yet, which it may still fail.
+type Seen = BTreeSet<(usize, Vec<usize>)>;
+
+impl Vm<'_> {
+  // Finds the first match starting at or after `start`, returning its slots
+  // and where it ends. Its threads are kept in the order backtracking would
+  // try them in, so the first one to match is the one backtracking finds.
+  fn run(&self, start: usize) -> Option<(Vec<Option<usize>>, usize)> {
+    let mut threads = Vec::new();
+    let mut next = Vec::new();
+    let mut seen = Seen::new();
+    let mut seen_next = Seen::new();
+    let mut found = None;
+    for pos in start..=self.chars.len() {
+      // a match starting here is only wanted if none started earlier
+      if found.is_none() {
+        let mut slots = vec![None; self.slots];
+        slots[0] = Some(pos);
+        self.add(&mut threads, &mut seen, pos, 0, slots);
+      }
+      for thread in threads.drain(..) {
+        match &self.program[thread.pc] {
+          // the threads after this one are less preferred
+          Inst::Match => {
+            found = Some((thread.slots, pos));
+            break;
+          }
+          Inst::Single(node) if self.single(node, pos) => {
+            let (pc, slots) = (thread.pc + 1, thread.slots);
+            self.add(&mut next, &mut seen_next, pos + 1, pc, slots);
+          }
+          _ => {}
+        }
+      }
+      if next.is_empty() && found.is_some() {
+        break;
+      }
+      mem::swap(&mut threads, &mut next);
+      seen = mem::take(&mut seen_next);
+    }
+    found
+  }
+
+  // Adds a thread at `pc` to `threads`, following the instructions that do
+  // not consume a char first, with an explicit stack rather than recursion.
+  fn add(
+    &self,
+    threads: &mut Vec<Thread>,
+    seen: &mut Seen,
+    pos: usize,
+    pc: usize,
+    slots: Vec<Option<usize>>,
+  ) {
+    let mut stack = vec![(pc, slots)];
+    while let Some((pc, mut slots)) = stack.pop() {
+      let empty = (self.repeats..self.slots)
+        .filter(|&slot| slots[slot] == Some(pos))
+        .collect();
+      if !seen.insert((pc, empty)) {
+        continue;
+      }
+      match &self.program[pc] {
+        Inst::Jump(to) => stack.push((*to, slots)),
+        Inst::Split(first, second) => {
+          stack.push((*second, slots.clone()));
+          stack.push((*first, slots));
+        }
+        Inst::Save(slot) => {
+          slots[*slot] = Some(pos);
+          stack.push((pc + 1, slots));
+        }
+        Inst::Assert(node) => {
+          if self.holds(node, pos) {
+            stack.push((pc + 1, slots));
+          }
+        }
+        Inst::Progress(slot) => {
+          if slots[*slot] != Some(pos) {
+            stack.push((pc + 1, slots));
+          }
+        }
+        Inst::Single(_) | Inst::Match => threads.push(Thread { pc, slots }),
+      }
+    }
+  }
+
+  // Whether the anchor or word boundary `node` holds at `pos`.
+  fn holds(&self, node: &Node, pos: usize) -> bool {
+    match node {
+      Node::Start => pos == 0,
+      Node::End => pos == self.chars.len(),
+      Node::WordBoundary(expected) => {
+        let is_word = |index: Option<usize>| {
+          index
+            .and_then(|index| self.chars.get(index))
+            .is_some_and(|&c| is_word_char(c))
+        };
+        let boundary = is_word(pos.checked_sub(1)) != is_word(Some(pos));
+        boundary == *expected
+      }
+      _ => false,
+    }
+  }
+
+  // Whether the single char `node` matches the char at `pos`.
+  fn single(&self, node: &Node, pos: usize) -> bool {
+    let Some(&c) = self.chars.get(pos) else {
+      return false;
+    };
+    match node {
+      Node::Char(expected) => {
+        c == *expected
+          || (self.ignore_case && fold_case(c) == fold_case(*expected))
+      }
+      Node::Any => !matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}'),
+      Node::Class(class) => {
+        let matches = |c| class.items.iter().any(|item| item.matches(c));
+        let matched = matches(c)
+          || (self.ignore_case
+            && (matches(fold_case(c)) || matches(upper_case(c))));
+        matched != class.negated
+      }
+      _ => false,
+    }
+  }
+}
+
+impl ClassItem {
+  fn matches(&self, c: char) -> bool {
+    match *self {
+      ClassItem::Range(from, to) => (from..=to).contains(&c),
+      ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+      ClassItem::Word(negated) => is_word_char(c) != negated,
+      ClassItem::Space(negated) => is_space(c) != negated,
+    }
+  }
+}
+
+struct Parser<'a> {
+  source: &'a str,
+  chars:  Vec<char>,
+  pos:    usize,
+  groups: usize,
+}
+
+impl Parser<'_> {
+  fn alternation(&mut self) -> Result<Node, DawmError> {
+    let mut alternatives = Vec::from([self.concat()?]);
+    while self.eat('|') {
+      alternatives.push(self.concat()?);
+    }
+    Ok(match alternatives.len() {
+      1 => alternatives.remove(0),
+      _ => Node::Alternation(alternatives),
+    })
+  }
+
+  fn concat(&mut self) -> Result<Node, DawmError> {
+    let mut nodes = Vec::new();
+    while let Some(c) = self.peek() {
+      if matches!(c, '|' | ')') {
+        break;
+      }
+      let atom = self.atom()?;
+      nodes.push(self.quantified(atom)?);
+    }
+    Ok(match nodes.len() {
+      1 => nodes.remove(0),
+      _ => Node::Concat(nodes),
+    })
+  }
+
+  fn atom(&mut self) -> Result<Node, DawmError> {
+    let Some(c) = self.next() else {
+      return Err(self.error("unexpected end"));
+    };
+    Ok(match c {
+      '.' => Node::Any,
+      '^' => Node::Start,
+      '$' => Node::End,
+      '[' => Node::Class(self.class()?),
+      '(' => {
+        let index = match self.eat_str("?:") {
+          true => None,
+          false if self.peek() == Some('?') => {
+            return Err(self.error("unsupported group"));
+          }
+          false => {
+            self.groups += 1;
+            Some(self.groups - 1)
+          }
+        };
+        let inner = self.alternation()?;
+        if !self.eat(')') {
+          return Err(self.error("unterminated group"));
+        }
+        Node::Group(Box::new(inner), index)
+      }
+      '*' | '+' | '?' => return Err(self.error("nothing to repeat")),
+      '{' if self.quantifier_bounds(self.pos - 1).is_some() => {
+        return Err(self.error("nothing to repeat"));
+      }
+      '\\' => match self.next() {
+        Some('b') => Node::WordBoundary(true),
+        Some('B') => Node::WordBoundary(false),
+        Some(c @ '1'..='9') => {
+          return Err(self.error(&format!("unsupported backreference \\{c}")));
+        }
+        Some(c) => match class_escape(c) {
+          Some(item) => Node::Class(Class {
+            items:   Vec::from([item]),
+            negated: false,
+          }),
+          None => Node::Char(self.char_escape(c)?),
+        },
+        None => return Err(self.error("trailing backslash")),
+      },
+      c => Node::Char(c),
+    })
+  }
+
+  fn quantified(&mut self, atom: Node) -> Result<Node, DawmError> {
+    let ((min, max), len) = match self.peek() {
+      Some('*') => ((0, None), 1),
+      Some('+') => ((1, None), 1),
+      Some('?') => ((0, Some(1)), 1),
+      Some('{') => match self.quantifier_bounds(self.pos) {
+        Some(quantifier) => quantifier,
+        None => return Ok(atom),
+      },
+      _ => return Ok(atom),
+    };
+    self.pos += len;
+    if matches!(atom, Node::Start | Node::End | Node::WordBoundary(_)) {
+      return Err(self.error("nothing to repeat"));
+    }
+    if max.is_some_and(|max| max < min) {
+      return Err(self.error("numbers out of order in quantifier"));
+    }
+    let greedy = !self.eat('?');
+    Ok(Node::Repeat {
+      node: Box::new(atom),
+      min,
+      max,
+      greedy,
+    })
+  }
+
+  // The bounds of a `{n}`, `{n,}` or `{n,m}` quantifier whose `{` is at
+  // `start`, along with its length. Anything else is taken as a literal
+  // `{`, like JavaScript does.
+  fn quantifier_bounds(
+    &self,
+    start: usize,
+  ) -> Option<((u32, Option<u32>), usize)> {
+    let rest = &self.chars[start + 1..];
+    let end = rest.iter().position(|&c| c == '}')?;
+    let inner: String = rest[..end].iter().collect();
+    let number = |digits: &str| -> Option<u32> {
+      if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+      }
+      digits.parse().ok()
+    };
+    let bounds = match inner.split_once(',') {
+      None => {
+        let exact = number(&inner)?;
+        (exact, Some(exact))
+      }
+      Some((min, "")) => (number(min)?, None),
+      Some((min, max)) => (number(min)?, Some(number(max)?)),
+    };
+    Some((bounds, end + 2))
+  }
+
+  fn class(&mut self) -> Result<Class, DawmError> {
+    let negated = self.eat('^');
+    let mut items = Vec::new();
+    loop {
+      let Some(c) = self.next() else {
+        return Err(self.error("unterminated character class"));
+      };
+      let from = match c {
+        ']' => return Ok(Class { items, negated }),
+        '\\' => {
+          let Some(escaped) = self.next() else {
+            return Err(self.error("trailing backslash"));
+          };
+          if let Some(item) = class_escape(escaped) {
+            items.push(item);
+            continue;
+          }
+          match escaped {
+            'b' => '\u{8}',
+            escaped => self.char_escape(escaped)?,
+          }
+        }
+        c => c,
+      };
+      let is_range = self.peek() == Some('-')
+        && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']');
+      if !is_range {
+        items.push(ClassItem::Range(from, from));
+        continue;
+      }
+      self.pos += 1;
+      let to = match self.next() {
+        Some('\\') => match self.next() {
+          Some('b') => '\u{8}',
+          Some(escaped) if class_escape(escaped).is_none() => {
+            self.char_escape(escaped)?
+          }
+          _ => return Err(self.error("invalid range in character class")),
+        },
+        Some(to) => to,
+        None => return Err(self.error("unterminated character class")),
+      };
+      if to < from {
+        return Err(self.error("range out of order in character class"));
+      }
+      items.push(ClassItem::Range(from, to));
+    }
+  }
+
+  // The char a backslash followed by `c` stands for, outside of the class
+  // escapes.
+  fn char_escape(&mut self, c: char) -> Result<char, DawmError> {
+    Ok(match c {
+      'n' => '\n',
+      'r' => '\r',
+      't' => '\t',
+      'f' => '\u{c}',
+      'v' => '\u{b}',
+      '0' => '\0',
+      'x' => self.hex_escape(2)?,
+      'u' => self.hex_escape(4)?,
+      c if c.is_ascii_alphanumeric() => {
+        return Err(self.error(&format!("unsupported escape \\{c}")));
+      }
+      c => c,
+    })
+  }
+
+  fn hex_escape(&mut self, len: usize) -> Result<char, DawmError> {
+    let digits: String = self.chars.iter().skip(self.pos).take(len).collect();
+    let code = match digits.len() == len {
+      true => u32::from_str_radix(&digits, 16).ok(),
+      false => None,
+    };
+    match code.and_then(char::from_u32) {
+      Some(c) => {
+        self.pos += len;
+        Ok(c)
+      }
+      None => Err(self.error("invalid hexadecimal escape")),
+    }
+  }
+
+  fn next(&mut self) -> Option<char> {
+    let c = self.chars.get(self.pos).copied()?;
+    self.pos += 1;
+    Some(c)
+  }
+
+  fn peek(&self) -> Option<char> {
+    self.chars.get(self.pos).copied()
+  }
+
+  fn eat(&mut self, expected: char) -> bool {
+    let matched = self.peek() == Some(expected);
+    self.pos += usize::from(matched);
+    matched
+  }
+
+  fn eat_str(&mut self, expected: &str) -> bool {
+    let len = expected.chars().count();
+    let matched = self
+      .chars
+      .get(self.pos..self.pos + len)
+      .is_some_and(|chars| chars.iter().copied().eq(expected.chars()));
+    self.pos += if matched { len } else { 0 };
+    matched
+  }
+
+  fn error(&self, reason: &str) -> DawmError {
+    DawmError::Syntax(format!(
+      "{:?} is not a valid pattern: {reason}",
+      self.source
+    ))
+  }
+}
+
+fn class_escape(c: char) -> Option<ClassItem> {
+  Some(match c {
+    'd' => ClassItem::Digit(false),
+    'D' => ClassItem::Digit(true),
+    'w' => ClassItem::Word(false),
+    'W' => ClassItem::Word(true),
+    's' => ClassItem::Space(false),
+    'S' => ClassItem::Space(true),
+    _ => return None,
+  })
+}
+
+fn is_word_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || c == '_'
+}
+
+fn is_space(c: char) -> bool {
+  c.is_whitespace() || c == '\u{feff}'
+}
+
+// Single chars are compared by their simple lowercase mapping when case is
+// ignored, which is close enough to JavaScript's case folding for the
+// patterns attribute values are matched with.
+fn fold_case(c: char) -> char {
+  let mut lower = c.to_lowercase();
+  match (lower.next(), lower.next()) {
+    (Some(lower), None) => lower,
+    _ => c,
+  }
+}
+
+fn upper_case(c: char) -> char {
+  let mut upper = c.to_uppercase();
+  match (upper.next(), upper.next()) {
+    (Some(upper), None) => upper,
+    _ => c,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn find(pattern: &str, text: &str) -> Option<Match> {
+    let chars: Vec<char> = text.chars().collect();
+    Pattern::new(pattern).unwrap().find_at(&chars, 0)
+  }
+
+  #[test]
+  fn repeats_long_input_without_recursing() {
+    let pattern = Pattern::new("(ab)+").unwrap();
+    let text = "ab".repeat(200_000);
+    assert!(pattern.is_match(&text));
+    assert_eq!(pattern.replace_all(&text, "x"), "x");
+  }
+
+  #[test]
+  fn matches_nested_quantifiers_in_linear_time() {
+    let pattern = Pattern::new("^(a+)+$").unwrap();
+    assert!(!pattern.is_match(&format!("{}!", "a".repeat(10_000))));
+    assert!(pattern.is_match(&"a".repeat(10_000)));
+  }
+
+  #[test]
+  fn prefers_matches_like_backtracking() {
+    let found = find("(a|ab)(c|bcd)(d*)", "abcd").unwrap();
+    assert_eq!(found.span, (0, 4));
+    assert_eq!(found.groups, [Some((0, 1)), Some((1, 4)), Some((4, 4))]);
+    assert_eq!(find("x{2,3}?", "xxxx").unwrap().span, (0, 2));
+    assert_eq!(find("(an)+?", "banana").unwrap().groups, [Some((1, 3))]);
+  }
+
+  #[test]
+  fn skips_optional_repetitions_matching_nothing() {
+    assert_eq!(find("(b|)?", "x").unwrap().groups, [None]);
+    let found = find("((a|b)*?)*", "aa").unwrap();
+    assert_eq!(found.span, (0, 2));
+    assert_eq!(found.groups, [Some((1, 2)), Some((1, 2))]);
+  }
+
+  #[test]
+  fn rejects_patterns_with_too_long_programs() {
+    assert!(matches!(
+      Pattern::new("(a{1000}){1000}"),
+      Err(DawmError::Syntax(_))
+    ));
+  }
+}
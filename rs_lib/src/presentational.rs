@@ -0,0 +1,503 @@
+//! Converting presentational attributes to CSS.
+//!
+//! Legacy markup styles itself with attributes that HTML has long
+//! deprecated, and that browsers only still honor as "presentational
+//! hints": the `align` of paragraphs and images, the `bgcolor` and
+//! `cellpadding` of tables, and the `<font face size color>` and `<center>`
+//! elements. [`modernize`] replaces them with the equivalent declarations
+//! of a `style` attribute, which HTML editors, email templates and CSS
+//! tooling all understand:
+//!
+//! | Attribute                         | Declarations                   |
+//! | --------------------------------- | ------------------------------ |
+//! | `align` of blocks and table parts | `text-align`                   |
+//! | `align` of images and embeds      | `float` or `vertical-align`    |
+//! | `align` of tables and `<hr>`      | `float` or `margin-*: auto`    |
+//! | `align` of `<caption>`            | `caption-side` or `text-align` |
+//! | `valign` of table parts           | `vertical-align`               |
+//! | `bgcolor`, and `text` of `<body>` | `background-color`, `color`    |
+//! | `background`                      | `background-image: url()`      |
+//! | `border` of tables and images     | `border-width`, `border-style` |
+//! | `cellpadding` of tables           | `padding` (of their cells)     |
+//! | `cellspacing` of tables           | `border-spacing`               |
+//! | `width` and `height` of tables    | `width`, `height`              |
+//! | `nowrap` of cells                 | `white-space: nowrap`          |
+//! | `clear` of `<br>`                 | `clear`                        |
+//! | `hspace` and `vspace`             | `margin-*`                     |
+//! | `face` of `<font>`                | `font-family`                  |
+//! | `size` of `<font>`                | `font-size`                    |
+//! | `color` of `<font>`               | `color`                        |
+//!
+//! `<font>` elements become `<span>`s and `<center>` elements become
+//! `<div>`s with `text-align: center`. The declarations come before those
+//! of an existing `style` attribute, which keeps overriding them as it did
+//! the attributes. The cells of a table with a `border` get the `1px inset`
+//! border that browsers give them.
+//!
+//! With a `classPrefix`, the attributes with a keyword value (`align`,
+//! `valign`, `clear` and `nowrap`) become classes instead, like
+//! `legacy-align-center` for a prefix of `legacy-`, for a stylesheet to
+//! define. Attributes whose value browsers would ignore, or that could not
+//! be written in CSS safely (like a color with a `;`), are left as they are.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use html5ever::local_name;
+use html5ever::ns;
+use serde::Deserialize;
+
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::rcdom::rename_element;
+
+/// How [`modernize`] converts presentational attributes.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Modernize {
+  /// The prefix of the classes that the attributes with a keyword value
+  /// become, or `None` to convert them to declarations like the others.
+  pub class_prefix: Option<String>,
+}
+
+type Declarations = Vec<(&'static str, String)>;
+
+/// Replaces the presentational attributes of the HTML elements below
+/// `root` (including the contents of templates) with `style` declarations
+/// or classes, and the `<font>` and `<center>` elements with `<span>`s and
+/// `<div>`s, returning how many attributes and elements were converted.
+pub fn modernize(root: &Handle, options: &Modernize) -> usize {
+  let elements = elements(root);
+  // the declarations that tables give to their cells
+  let mut inherited: BTreeMap<*const Node, Declarations> = BTreeMap::new();
+  for table in elements.iter().filter(|element| is_tag(element, "table")) {
+    let mut declarations = Declarations::new();
+    if let Some(padding) = attr(table, "cellpadding").and_then(|v| length(&v)) {
+      declarations.push(("padding", padding));
+    }
+    if attr(table, "border")
+      .and_then(|v| border(&v))
+      .is_some_and(|border| border != "0px")
+    {
+      declarations.push(("border", String::from("1px inset")));
+    }
+    if declarations.is_empty() {
+      continue;
+    }
+    for cell in cells(table) {
+      inherited
+        .entry(Rc::as_ptr(&cell))
+        .or_default()
+        .extend(declarations.iter().cloned());
+    }
+  }
+
+  let mut converted = 0;
+  for element in elements {
+    let inherited = inherited.remove(&Rc::as_ptr(&element));
+    converted += convert(&element, inherited.unwrap_or_default(), options);
+  }
+  converted
+}
+
+// Converts the presentational attributes of `element`, and the element
+// itself if it is a `<font>` or a `<center>`, returning how many were.
+fn convert(
+  element: &Handle,
+  mut declarations: Declarations,
+  options: &Modernize,
+) -> usize {
+  let NodeData::Element { name, attrs, .. } = &element.data else {
+    return 0;
+  };
+  let tag = &*name.local;
+  let mut classes: Vec<String> = Vec::new();
+  let mut converted = 0;
+  let mut rename = match tag {
+    "center" => Some(local_name!("div")),
+    "font" => Some(local_name!("span")),
+    _ => None,
+  };
+  if tag == "center" {
+    match &options.class_prefix {
+      Some(prefix) => classes.push(format!("{prefix}align-center")),
+      None => declarations.push(("text-align", String::from("center"))),
+    }
+  }
+
+  let mut attrs = attrs.borrow_mut();
+  attrs.retain(|attr| {
+    if attr.name.ns != ns!() {
+      return true;
+    }
+    let attribute = &*attr.name.local;
+    let value = attr.value.trim();
+    let Some(hints) = hints(tag, attribute, value) else {
+      // a `<font>` keeps its name while it keeps its attributes
+      if tag == "font" && matches!(attribute, "face" | "size" | "color") {
+        rename = None;
+      }
+      return true;
+    };
+    converted += 1;
+    match &options.class_prefix {
+      Some(prefix) if attribute == "nowrap" => {
+        classes.push(format!("{prefix}nowrap"));
+      }
+      Some(prefix) if matches!(attribute, "align" | "valign" | "clear") => {
+        classes.push(format!("{prefix}{attribute}-{}", keyword(value)));
+      }
+      _ => declarations.extend(hints),
+    }
+    false
+  });
+  if !declarations.is_empty() {
+    let hints: Vec<String> = declarations
+      .iter()
+      .map(|(property, value)| format!("{property}: {value}"))
+      .collect();
+    let hints = hints.join("; ");
+    merge(&mut attrs, "style", |existing| match existing.trim() {
+      "" => hints,
+      existing => format!("{hints}; {existing}"),
+    });
+  }
+  if !classes.is_empty() {
+    let classes = classes.join(" ");
+    merge(&mut attrs, "class", |existing| match existing.trim() {
+      "" => classes,
+      existing => format!("{existing} {classes}"),
+    });
+  }
+  drop(attrs);
+
+  if let Some(local) = rename {
+    rename_element(element, QualName::new(None, ns!(html), local));
+    converted += 1;
+  }
+  converted
+}
+
+// The declarations equivalent to the attribute `attribute` with the value
+// `value` of an element with the tag `tag`, or `None` if it is not
+// presentational there or browsers would ignore its value.
+fn hints(tag: &str, attribute: &str, value: &str) -> Option<Declarations> {
+  let keyword = keyword(value);
+  let one = |property, value: String| Some(vec![(property, value)]);
+  match (attribute, tag) {
+    ("align", "img" | "object" | "embed" | "iframe" | "input" | "applet") => {
+      match keyword.as_str() {
+        "left" | "right" => one("float", keyword),
+        "top" | "middle" | "bottom" | "baseline" => {
+          one("vertical-align", keyword)
+        }
+        "absmiddle" | "center" => one("vertical-align", String::from("middle")),
+        "texttop" => one("vertical-align", String::from("text-top")),
+        "absbottom" => one("vertical-align", String::from("bottom")),
+        _ => None,
+      }
+    }
+    ("align", "table") => match keyword.as_str() {
+      "left" | "right" => one("float", keyword),
+      "center" => Some(margins("auto", "auto")),
+      _ => None,
+    },
+    ("align", "hr") => match keyword.as_str() {
+      "left" => Some(margins("0", "auto")),
+      "right" => Some(margins("auto", "0")),
+      "center" => Some(margins("auto", "auto")),
+      _ => None,
+    },
+    ("align", "caption") => match keyword.as_str() {
+      "top" | "bottom" => one("caption-side", keyword),
+      "left" | "right" | "center" => one("text-align", keyword),
+      _ => None,
+    },
+    (
+      "align",
+      "div" | "p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "td" | "th"
+      | "tr" | "thead" | "tbody" | "tfoot" | "col" | "colgroup",
+    ) => match keyword.as_str() {
+      "left" | "right" | "center" | "justify" => one("text-align", keyword),
+      _ => None,
+    },
+    (
+      "valign",
+      "td" | "th" | "tr" | "thead" | "tbody" | "tfoot" | "col" | "colgroup",
+    ) => match keyword.as_str() {
+      "top" | "middle" | "bottom" | "baseline" => {
+        one("vertical-align", keyword)
+      }
+      _ => None,
+    },
+    (
+      "bgcolor",
+      "body" | "table" | "tr" | "td" | "th" | "thead" | "tbody" | "tfoot",
+    ) => one("background-color", color(value)?),
+    ("text", "body") | ("color", "font") => one("color", color(value)?),
+    ("background", "body" | "table" | "td" | "th") => {
+      one("background-image", url(value)?)
+    }
+    ("border", "table") => Some(vec![
+      ("border-width", border(value)?),
+      ("border-style", String::from("outset")),
+    ]),
+    ("border", "img" | "object") => Some(vec![
+      ("border-width", length(value)?),
+      ("border-style", String::from("solid")),
+    ]),
+    // given to the cells instead, see `modernize`
+    ("cellpadding", "table") => {
+      length(value)?;
+      Some(Vec::new())
+    }
+    ("cellspacing", "table") => one("border-spacing", length(value)?),
+    ("width", "table" | "td" | "th" | "col" | "colgroup" | "hr") => {
+      one("width", length(value)?)
+    }
+    ("height", "table" | "tr" | "td" | "th") => one("height", length(value)?),
+    ("nowrap", "td" | "th") => one("white-space", String::from("nowrap")),
+    ("clear", "br") => match keyword.as_str() {
+      "left" | "right" | "both" => one("clear", keyword),
+      "all" => one("clear", String::from("both")),
+      _ => None,
+    },
+    ("hspace", "img" | "object" | "embed" | "iframe") => {
+      let margin = length(value)?;
+      Some(vec![
+        ("margin-left", margin.clone()),
+        ("margin-right", margin),
+      ])
+    }
+    ("vspace", "img" | "object" | "embed" | "iframe") => {
+      let margin = length(value)?;
+      Some(vec![
+        ("margin-top", margin.clone()),
+        ("margin-bottom", margin),
+      ])
+    }
+    ("face", "font") => one("font-family", font_family(value)?),
+    ("size", "font") => one("font-size", font_size(value)?),
+    _ => None,
+  }
+}
+
+fn margins(left: &str, right: &str) -> Declarations {
+  vec![
+    ("margin-left", String::from(left)),
+    ("margin-right", String::from(right)),
+  ]
+}
+
+// The lowercase keyword of an attribute value, which is case-insensitive.
+fn keyword(value: &str) -> String {
+  value.trim().to_ascii_lowercase()
+}
+
+// A dimension attribute as a CSS length: the number it starts with, in
+// pixels, or a percentage with a `%` after the number.
+fn length(value: &str) -> Option<String> {
+  let value = value.trim();
+  let end = value
+    .find(|c: char| !c.is_ascii_digit() && c != '.')
+    .unwrap_or(value.len());
+  let number: f64 = value[..end].parse().ok()?;
+  if !number.is_finite() {
+    return None;
+  }
+  let number = value[..end].trim_end_matches('.');
+  match value[end..].starts_with('%') {
+    true => Some(format!("{number}%")),
+    false => Some(format!("{number}px")),
+  }
+}
+
+// The `border` of a table as a width, which is `1px` for an empty one.
+fn border(value: &str) -> Option<String> {
+  match value.trim() {
+    "" => Some(String::from("1px")),
+    value => length(value),
+  }
+}
+
+// A color attribute as a CSS color, with the `#` that legacy markup often
+// leaves out of hex colors, or `None` if it could break out of the
+// declaration.
+fn color(value: &str) -> Option<String> {
+  let value = value.trim();
+  let is_safe = value.chars().all(|c| {
+    c.is_ascii_alphanumeric() || matches!(c, '#' | '(' | ')' | ',' | '.' | '%')
+  });
+  if value.is_empty() || !is_safe {
+    return None;
+  }
+  let is_bare_hex = matches!(value.len(), 3 | 6)
+    && value.chars().all(|c| c.is_ascii_hexdigit());
+  match is_bare_hex {
+    true => Some(format!("#{value}")),
+    false => Some(String::from(value)),
+  }
+}
+
+// A `background` URL as a CSS `url()`, or `None` if it has characters
+// that it could not be quoted with.
+fn url(value: &str) -> Option<String> {
+  let value = value.trim();
+  if value.is_empty()
+    || value
+      .chars()
+      .any(|c| matches!(c, '"' | '\\') || c.is_control())
+  {
+    return None;
+  }
+  Some(format!("url(\"{value}\")"))
+}
+
+// A `face` as a `font-family`, quoting the family names that are not a
+// single identifier.
+fn font_family(value: &str) -> Option<String> {
+  let families: Vec<String> = value
+    .split(',')
+    .map(str::trim)
+    .filter(|family| !family.is_empty())
+    .map(|family| {
+      let family = family.trim_matches(['"', '\'']);
+      let is_identifier = family
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !family.starts_with(|c: char| c.is_ascii_digit());
+      match is_identifier {
+        true => family.to_string(),
+        false => format!("\"{family}\""),
+      }
+    })
+    .collect();
+  let is_safe = |family: &String| {
+    !family.trim_matches('"').chars().any(|c| {
+      matches!(c, '"' | '\'' | '\\' | ';' | '{' | '}' | '<' | '>')
+        || c.is_control()
+    })
+  };
+  (!families.is_empty() && families.iter().all(is_safe))
+    .then(|| families.join(", "))
+}
+
+// A `size` of `<font>` as a `font-size`: 1 to 7, or relative to 3 with a
+// sign, clamped to that range.
+fn font_size(value: &str) -> Option<String> {
+  let value = value.trim();
+  let (sign, digits) = match value.chars().next()? {
+    '+' => (1, &value[1..]),
+    '-' => (-1, &value[1..]),
+    _ => (0, value),
+  };
+  let end = digits
+    .find(|c: char| !c.is_ascii_digit())
+    .unwrap_or(digits.len());
+  let number: i64 = digits[..end].parse().ok()?;
+  let size = match sign {
+    0 => number,
+    sign => 3 + sign * number,
+  };
+  let keyword = match size.clamp(1, 7) {
+    1 => "x-small",
+    2 => "small",
+    3 => "medium",
+    4 => "large",
+    5 => "x-large",
+    6 => "xx-large",
+    _ => "xxx-large",
+  };
+  Some(String::from(keyword))
+}
+
+// Sets the attribute `name` to what `value` makes of its current value, or
+// of an empty one if it is missing.
+fn merge(
+  attrs: &mut Vec<Attribute>,
+  name: &str,
+  value: impl FnOnce(&str) -> String,
+) {
+  match attrs
+    .iter_mut()
+    .find(|attr| attr.name.ns == ns!() && &*attr.name.local == name)
+  {
+    Some(attr) => attr.value = value(&attr.value).into(),
+    None => attrs.push(Attribute {
+      name:  QualName::new(None, ns!(), name.into()),
+      value: value("").into(),
+    }),
+  }
+}
+
+// The value of the attribute `name` (without a namespace) of `element`.
+fn attr(element: &Handle, name: &str) -> Option<String> {
+  let NodeData::Element { attrs, .. } = &element.data else {
+    return None;
+  };
+  attrs
+    .borrow()
+    .iter()
+    .find(|attr| attr.name.ns == ns!() && &*attr.name.local == name)
+    .map(|attr| String::from(&*attr.value))
+}
+
+// Whether `node` is the HTML element `tag`.
+fn is_tag(node: &Handle, tag: &str) -> bool {
+  matches!(
+    &node.data,
+    NodeData::Element { name, .. }
+      if name.ns == ns!(html) && &*name.local == tag
+  )
+}
+
+// The cells of `table`, but not those of the tables nested in it.
+fn cells(table: &Handle) -> Vec<Handle> {
+  let mut cells = Vec::new();
+  let mut stack: Vec<Handle> =
+    table.children.borrow().iter().rev().cloned().collect();
+  while let Some(node) = stack.pop() {
+    if is_tag(&node, "td") || is_tag(&node, "th") {
+      cells.push(node);
+    } else if ["thead", "tbody", "tfoot", "tr"]
+      .iter()
+      .any(|tag| is_tag(&node, tag))
+    {
+      stack.extend(node.children.borrow().iter().rev().cloned());
+    }
+  }
+  cells
+}
+
+// The HTML elements below `root`, including those in the contents of
+// templates, in document order.
+fn elements(root: &Handle) -> Vec<Handle> {
+  let mut elements = Vec::new();
+  let mut stack: Vec<Handle> =
+    root.children.borrow().iter().rev().cloned().collect();
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      name,
+      template_contents,
+      ..
+    } = &node.data
+    {
+      if let Some(contents) = template_contents {
+        stack.extend(contents.children.borrow().iter().rev().cloned());
+      }
+      if name.ns == ns!(html) {
+        elements.push(node.clone());
+      }
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  elements
+}
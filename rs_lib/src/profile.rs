@@ -0,0 +1,136 @@
+//! Named option profiles.
+//!
+//! The option surface has grown large enough that most applications only
+//! ever need one of a few combinations of it. Setting `profile` to the name
+//! of a [`Profile`] uses its options as the defaults, and any option given
+//! alongside it still takes precedence:
+//!
+//! | Profile      | Options                                                  |
+//! |--------------|----------------------------------------------------------|
+//! | `"scrape"`   | drops comments and PIs, coalesces text, no scripting     |
+//! | `"sanitize"` | like `"scrape"`, and strips inline event handlers        |
+//! | `"edit"`     | keeps every node, collects errors and lines, echoes      |
+//! | `"archive"`  | keeps every node, collects errors, echoes options        |
+//! | `"minimal"`  | like `"scrape"`, and also drops the doctype              |
+
+#[cfg(feature = "wasm")]
+use js_sys::Object;
+#[cfg(feature = "wasm")]
+use js_sys::Reflect;
+use serde::Deserialize;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsCast;
+#[cfg(feature = "wasm")]
+use wasm_bindgen::JsValue;
+
+/// A named bundle of option defaults.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+  /// For extracting content, with as little noise as possible.
+  Scrape,
+  /// For untrusted markup that is displayed later on.
+  Sanitize,
+  /// For editors and linters, which need every node and position.
+  Edit,
+  /// For keeping a faithful record of what was parsed, and how.
+  Archive,
+  /// For the smallest possible output.
+  Minimal,
+}
+
+impl Profile {
+  /// Every profile.
+  pub const ALL: &[Self] = &[
+    Profile::Scrape,
+    Profile::Sanitize,
+    Profile::Edit,
+    Profile::Archive,
+    Profile::Minimal,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Profile::Scrape => "scrape",
+      Profile::Sanitize => "sanitize",
+      Profile::Edit => "edit",
+      Profile::Archive => "archive",
+      Profile::Minimal => "minimal",
+    }
+  }
+
+  /// Looks up a profile by name.
+  pub fn from_name(name: &str) -> Option<Self> {
+    let profile = match name {
+      "scrape" => Profile::Scrape,
+      "sanitize" => Profile::Sanitize,
+      "edit" => Profile::Edit,
+      "archive" => Profile::Archive,
+      "minimal" => Profile::Minimal,
+      _ => return None,
+    };
+    Some(profile)
+  }
+
+  /// The options set by this profile, by their JS names.
+  pub fn options(&self) -> &'static [(&'static str, bool)] {
+    match self {
+      Profile::Scrape => &[
+        ("allowScripts", false),
+        ("coalesceText", true),
+        ("dropComments", true),
+        ("dropProcessingInstructions", true),
+      ],
+      Profile::Sanitize => &[
+        ("allowScripts", false),
+        ("coalesceText", true),
+        ("dropComments", true),
+        ("dropProcessingInstructions", true),
+        ("stripEventHandlers", true),
+      ],
+      Profile::Edit => &[
+        ("collectErrors", true),
+        ("dropDoctype", false),
+        ("echoOptions", true),
+        ("exactErrors", true),
+        ("lineIndex", true),
+      ],
+      Profile::Archive => &[
+        ("collectErrors", true),
+        ("dropDoctype", false),
+        ("echoOptions", true),
+        ("exactErrors", true),
+      ],
+      Profile::Minimal => &[
+        ("allowScripts", false),
+        ("coalesceText", true),
+        ("dropComments", true),
+        ("dropDoctype", true),
+        ("dropProcessingInstructions", true),
+        ("exactErrors", false),
+      ],
+    }
+  }
+}
+
+/// Resolves the `profile` of a JS options object, returning a new object
+/// with the profile's options as the defaults for those given in `options`.
+/// Anything but an object with a known profile is returned as is.
+#[cfg(feature = "wasm")]
+pub fn apply_profile(options: &JsValue) -> JsValue {
+  if !options.is_object() {
+    return options.clone();
+  }
+  let profile = Reflect::get(options, &JsValue::from_str("profile"))
+    .ok()
+    .and_then(|profile| profile.as_string())
+    .and_then(|name| Profile::from_name(&name));
+  let Some(profile) = profile else {
+    return options.clone();
+  };
+  let merged = Object::new();
+  for (key, value) in profile.options() {
+    let _ = Reflect::set(&merged, &JsValue::from_str(key), &(*value).into());
+  }
+  Object::assign(&merged, options.unchecked_ref()).into()
+}
@@ -1,15 +1,20 @@
+use alloc::string::String;
 use core::fmt::Debug;
 use core::fmt::Display;
 use core::fmt::Formatter;
 use core::fmt::Result as FmtResult;
 use core::str::FromStr;
 
+use derive_more::with_trait::Display as DeriveDisplay;
+use derive_more::with_trait::Error;
 use derive_more::with_trait::From;
 use derive_more::with_trait::IsVariant;
 use derive_more::with_trait::TryFrom;
 use derive_more::with_trait::TryInto;
 use markup5ever::interface::QuirksMode as ServoQuirksMode;
 use serde::Deserialize;
+use serde::Serialize;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 /// Represents the HTML quirks mode to use during parsing.
@@ -30,7 +35,7 @@ use wasm_bindgen::prelude::*;
 #[from(ServoQuirksMode)]
 #[try_into(owned, ref, ref_mut)]
 #[try_from(repr)]
-#[wasm_bindgen]
+#[cfg_attr(feature = "wasm", wasm_bindgen)]
 pub enum QuirksMode {
   Quirks = 0,
   LimitedQuirks = 1,
@@ -38,6 +43,31 @@ pub enum QuirksMode {
   NoQuirks = 2,
 }
 
+/// The error returned by [`QuirksMode::try_from_str`] for a string that is
+/// not the name of a quirks mode.
+#[derive(Debug, Clone, PartialEq, Eq, DeriveDisplay, Error)]
+#[display(
+  "unknown quirks mode {_0:?}, expected \"quirks\", \"limited-quirks\" or \
+   \"no-quirks\""
+)]
+pub struct UnknownQuirksMode(#[error(not(source))] pub String);
+
+impl QuirksMode {
+  /// Parses the name of a quirks mode (`"quirks"`, `"limited-quirks"` or
+  /// `"no-quirks"`, or one of their aliases, in any case), failing for any
+  /// other string. Unlike [`FromStr`], which falls back to
+  /// [`NoQuirks`](QuirksMode::NoQuirks), this catches typos.
+  pub fn try_from_str(s: &str) -> Result<Self, UnknownQuirksMode> {
+    use crate::QuirksMode::*;
+    match s.to_lowercase().trim() {
+      "all" | "yes" | "full" | "quirks" => Ok(Quirks),
+      "some" | "limited" | "partial" | "limited-quirks" => Ok(LimitedQuirks),
+      "none" | "no" | "off" | "no-quirks" => Ok(NoQuirks),
+      _ => Err(UnknownQuirksMode(s.into())),
+    }
+  }
+}
+
 impl Debug for QuirksMode {
   fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
     match self {
@@ -71,12 +101,7 @@ impl FromStr for QuirksMode {
   type Err = ();
 
   fn from_str(s: &str) -> Result<Self, Self::Err> {
-    use crate::QuirksMode::*;
-    match s.to_lowercase().trim() {
-      "all" | "yes" | "full" | "quirks" => Ok(Quirks),
-      "some" | "limited" | "partial" | "limited-quirks" => Ok(LimitedQuirks),
-      _ => Ok(NoQuirks),
-    }
+    Ok(QuirksMode::try_from_str(s).unwrap_or(QuirksMode::NoQuirks))
   }
 }
 
@@ -90,12 +115,33 @@ impl From<QuirksMode> for ServoQuirksMode {
   }
 }
 
+impl From<ServoQuirksMode> for QuirksMode {
+  fn from(q: ServoQuirksMode) -> Self {
+    match q {
+      ServoQuirksMode::Quirks => QuirksMode::Quirks,
+      ServoQuirksMode::LimitedQuirks => QuirksMode::LimitedQuirks,
+      ServoQuirksMode::NoQuirks => QuirksMode::NoQuirks,
+    }
+  }
+}
+
 impl<'de> Deserialize<'de> for QuirksMode {
   fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
   where
     D: serde::Deserializer<'de>,
   {
-    let s: &str = serde::Deserialize::deserialize(deserializer)?;
-    Ok(QuirksMode::from(s))
+    // owned, since not every deserializer (e.g. that of JS values) can lend
+    // out its strings
+    let s: String = serde::Deserialize::deserialize(deserializer)?;
+    QuirksMode::try_from_str(&s).map_err(serde::de::Error::custom)
+  }
+}
+
+impl Serialize for QuirksMode {
+  fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+  where
+    S: serde::Serializer,
+  {
+    serializer.collect_str(self)
   }
 }
@@ -39,9 +39,13 @@
 //! [dom wiki]: https://en.wikipedia.org/wiki/Document_Object_Model
 
 use alloc::borrow::Cow;
+use alloc::collections::BTreeMap;
 use alloc::collections::BTreeSet;
 use alloc::rc::Rc;
 use alloc::rc::Weak;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
 use core::cell::Cell;
 use core::cell::RefCell;
 use core::default::Default;
@@ -52,9 +56,14 @@ use derive_more::with_trait::Debug;
 use derive_more::with_trait::From;
 use derive_more::with_trait::*;
 use html5ever::Attribute;
+use html5ever::LocalName;
 use html5ever::QualName;
+use html5ever::ns;
+#[cfg(feature = "std")]
 use html5ever::serialize::Serialize;
+#[cfg(feature = "std")]
 use html5ever::serialize::Serializer;
+#[cfg(feature = "std")]
 use html5ever::serialize::TraversalScope;
 use html5ever::tendril;
 use html5ever::tree_builder;
@@ -62,7 +71,12 @@ use html5ever::tree_builder::NodeOrText;
 use html5ever::tree_builder::QuirksMode;
 use html5ever::tree_builder::TreeSink;
 use tendril::StrTendril;
-use xml5ever::interface::*;
+use html5ever::interface::ElementFlags;
+
+use crate::diagnostics::ErrorHook;
+use crate::diagnostics::ParseError;
+use crate::diagnostics::Span;
+use crate::sink::InputCursor;
 
 /// The different kinds of nodes in the DOM.
 #[derive(Debug)]
@@ -166,7 +180,7 @@ impl ParentNode for Handle {
       .borrow()
       .iter()
       .enumerate()
-      .find(|&(_, child)| Rc::ptr_eq(&child, &self))
+      .find(|&(_, child)| Rc::ptr_eq(child, self))
     {
       Some((i, _)) => i,
       None => panic!("have parent but couldn't find in parent's children!"),
@@ -207,6 +221,36 @@ impl Appendable for Handle {
   }
 }
 
+/// Bounds on how many parse errors an [`RcDom`] records. Pathological inputs
+/// can produce hundreds of thousands of errors, so these bounds keep memory
+/// and payload sizes in check while [`RcDom::error_count`] still reports the
+/// total number of errors encountered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorBudget {
+  /// The maximum number of errors to record, or `None` for no limit.
+  pub max:         Option<usize>,
+  /// Only every `sample_rate`-th error is recorded; `1` records all of them.
+  pub sample_rate: usize,
+}
+
+impl Default for ErrorBudget {
+  fn default() -> Self {
+    Self {
+      max:         None,
+      sample_rate: 1,
+    }
+  }
+}
+
+impl ErrorBudget {
+  /// Returns `true` if the error with the given (0-based) `index` should be
+  /// recorded, given that `recorded` errors have been recorded so far.
+  pub fn admits(&self, index: usize, recorded: usize) -> bool {
+    self.max.is_none_or(|max| recorded < max)
+      && index.is_multiple_of(self.sample_rate.max(1))
+  }
+}
+
 /// The DOM itself; the result of parsing.
 #[derive(Debug, Clone)]
 pub struct RcDom {
@@ -214,10 +258,90 @@ pub struct RcDom {
   pub document: Handle,
 
   /// Errors that occurred during parsing.
-  pub errors: RefCell<Vec<Cow<'static, str>>>,
+  pub errors: RefCell<Vec<ParseError>>,
 
   /// The document's quirks mode.
   pub quirks_mode: Cell<QuirksMode>,
+
+  /// The line the parser is currently on. This is only updated by parsers
+  /// which report line numbers through [`TreeSink::set_current_line`], and
+  /// remains `0` otherwise.
+  pub current_line: Cell<u64>,
+
+  /// The total number of errors reported by the parser, including the ones
+  /// which were not recorded due to the [`ErrorBudget`].
+  pub error_count: Cell<usize>,
+
+  /// Bounds on how many of the reported errors are recorded in `errors`.
+  pub error_budget: Cell<ErrorBudget>,
+
+  /// A callback invoked with every reported error, which may abort parsing.
+  #[debug(skip)]
+  pub error_hook: Option<ErrorHook>,
+
+  /// The first error reported by the parser, which is kept even if the
+  /// [`ErrorBudget`] does not record it, for fail-fast parsing.
+  pub first_error: RefCell<Option<ParseError>>,
+
+  /// The error for which the [`ErrorHook`] requested parsing to be aborted.
+  /// Once set, the hook is no longer invoked.
+  pub aborted_at: RefCell<Option<ParseError>>,
+
+  /// The input being parsed, if the parser shares it through
+  /// [`ParseSink::track_input`](crate::ParseSink::track_input), which the
+  /// offsets and columns of errors are taken from.
+  pub input: RefCell<Option<Rc<InputCursor>>>,
+}
+
+impl RcDom {
+  /// Creates an empty DOM which starts tracking line numbers at line 1. Use
+  /// this for sinks handed to html5ever, which only reports line changes.
+  pub fn with_line_tracking() -> Self {
+    let dom = Self::default();
+    dom.current_line.set(1);
+    dom
+  }
+
+  /// Where the parser currently is, as far as it told this DOM.
+  pub fn current_span(&self) -> Span {
+    if let Some(input) = &*self.input.borrow() {
+      return input.span();
+    }
+    let line = self.current_line.get();
+    Span {
+      line: (line > 0).then_some(line),
+      ..Span::default()
+    }
+  }
+
+  /// Sets the bounds on how many errors are recorded while parsing.
+  pub fn with_error_budget(self, budget: ErrorBudget) -> Self {
+    self.error_budget.set(budget);
+    self
+  }
+
+  /// Sets a callback to be invoked with every error reported while parsing.
+  /// Callers are responsible for stopping to feed the parser once
+  /// [`RcDom::aborted_at`] is set.
+  pub fn with_error_hook(mut self, hook: Option<ErrorHook>) -> Self {
+    self.error_hook = hook;
+    self
+  }
+
+  /// Applies an error budget after the fact, discarding the recorded errors
+  /// that the budget would not have admitted. This is used for parsers that
+  /// need to inspect the full set of errors before it is trimmed down.
+  pub fn apply_error_budget(&self, budget: ErrorBudget) {
+    let mut index = 0;
+    let mut recorded = 0;
+    self.errors.borrow_mut().retain(|_| {
+      let keep = budget.admits(index, recorded);
+      index += 1;
+      recorded += keep as usize;
+      keep
+    });
+    self.error_budget.set(budget);
+  }
 }
 
 impl TreeSink for RcDom {
@@ -233,7 +357,31 @@ impl TreeSink for RcDom {
   }
 
   fn parse_error(&self, msg: Cow<'static, str>) {
-    self.errors.borrow_mut().push(msg);
+    let index = self.error_count.get();
+    self.error_count.set(index + 1);
+    let recorded = self.errors.borrow().len();
+    let admitted = self.error_budget.get().admits(index, recorded);
+    if !admitted && self.error_hook.is_none() && index > 0 {
+      return;
+    }
+    let error = ParseError::new(msg, self.current_span());
+    if index == 0 {
+      *self.first_error.borrow_mut() = Some(error.clone());
+    }
+    if let Some(hook) = &self.error_hook
+      && self.aborted_at.borrow().is_none()
+      && !hook(&error)
+    {
+      *self.aborted_at.borrow_mut() = Some(error.clone());
+    }
+    if admitted {
+      self.errors.borrow_mut().push(error);
+    }
+  }
+
+  fn set_current_line(&self, line_number: u64) {
+    self.current_line.set(line_number);
+    crate::panic_hook::set_line(line_number);
   }
 
   fn get_document(&self) -> Handle {
@@ -295,13 +443,13 @@ impl TreeSink for RcDom {
   }
 
   fn append(&self, parent: &Handle, child: NodeOrText<Handle>) {
+    crate::panic_hook::set_insertion_point(parent);
     // Append to an existing Text node if we have one.
-    if let NodeOrText::AppendText(ref text) = child {
-      if let Some(h) = parent.children.borrow().last() {
-        if h.append_to_existing_text(&text) {
-          return;
-        }
-      }
+    if let NodeOrText::AppendText(ref text) = child
+      && let Some(h) = parent.children.borrow().last()
+      && h.append_to_existing_text(text)
+    {
+      return;
     }
 
     parent.append(match child {
@@ -407,13 +555,13 @@ impl TreeSink for RcDom {
     let mut new_children = new_parent.children.borrow_mut();
     for child in children.iter() {
       let previous_parent =
-        child.parent.replace(Some(Rc::downgrade(&new_parent)));
+        child.parent.replace(Some(Rc::downgrade(new_parent)));
       assert!(Rc::ptr_eq(
-        &node,
+        node,
         &previous_parent.unwrap().upgrade().expect("dangling weak")
       ))
     }
-    new_children.extend(mem::replace(&mut *children, Vec::new()));
+    new_children.extend(mem::take(&mut *children));
   }
 
   fn is_mathml_annotation_xml_integration_point(
@@ -435,9 +583,16 @@ impl TreeSink for RcDom {
 impl Default for RcDom {
   fn default() -> RcDom {
     RcDom {
-      document:    Node::new(NodeData::Document),
-      errors:      RefCell::new(vec![]),
-      quirks_mode: Cell::new(tree_builder::NoQuirks),
+      document:     Node::new(NodeData::Document),
+      errors:       RefCell::new(vec![]),
+      quirks_mode:  Cell::new(tree_builder::NoQuirks),
+      current_line: Cell::new(0),
+      error_count:  Cell::new(0),
+      error_budget: Cell::new(ErrorBudget::default()),
+      error_hook:   None,
+      first_error:  RefCell::new(None),
+      aborted_at:   RefCell::new(None),
+      input:        RefCell::new(None),
     }
   }
 }
@@ -452,6 +607,7 @@ enum SerializeOp {
 #[derive(Debug, Clone, Deref, DerefMut, From, AsRef, AsMut)]
 pub struct SerializableHandle(Handle);
 
+#[cfg(feature = "std")]
 impl Serialize for SerializableHandle {
   fn serialize<S>(
     &self,
@@ -461,75 +617,632 @@ impl Serialize for SerializableHandle {
   where
     S: Serializer,
   {
-    let mut ops = match traversal_scope {
-      TraversalScope::IncludeNode => vec![SerializeOp::Open(self.0.clone())],
-      TraversalScope::ChildrenOnly(_) => self
-        .0
+    let include_node = matches!(traversal_scope, TraversalScope::IncludeNode);
+    let mut sink = SerializerSink(serializer);
+    serialize_node(&self.0, Some(&self.0), &mut sink, include_node)
+  }
+}
+
+#[cfg(feature = "std")]
+impl Serialize for Node {
+  fn serialize<S>(
+    &self,
+    serializer: &mut S,
+    traversal_scope: TraversalScope,
+  ) -> std::io::Result<()>
+  where
+    S: Serializer,
+  {
+    let include_node = matches!(traversal_scope, TraversalScope::IncludeNode);
+    serialize_node(self, None, &mut SerializerSink(serializer), include_node)
+  }
+}
+
+/// Serializes the node, including itself, as HTML, like [`outer_html`]. For
+/// a document, this is the HTML of all of its children.
+impl fmt::Display for Node {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    serialize_node(self, None, &mut HtmlWriter::new(f), true)
+  }
+}
+
+impl fmt::Display for SerializableHandle {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    serialize_node(&self.0, Some(&self.0), &mut HtmlWriter::new(f), true)
+  }
+}
+
+/// Serializes the document as HTML.
+impl fmt::Display for RcDom {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    fmt::Display::fmt(&*self.document, f)
+  }
+}
+
+// Receives a tree as [`serialize_node`] walks it. This mirrors html5ever's
+// `Serializer`, without tying it to `std::io`.
+trait Sink {
+  type Error;
+
+  fn start_elem(
+    &mut self,
+    name: &QualName,
+    attrs: &[Attribute],
+  ) -> Result<(), Self::Error>;
+
+  fn end_elem(&mut self, name: &QualName) -> Result<(), Self::Error>;
+
+  fn write_text(&mut self, text: &str) -> Result<(), Self::Error>;
+
+  fn write_comment(&mut self, text: &str) -> Result<(), Self::Error>;
+
+  fn write_doctype(&mut self, name: &str) -> Result<(), Self::Error>;
+
+  fn write_processing_instruction(
+    &mut self,
+    target: &str,
+    data: &str,
+  ) -> Result<(), Self::Error>;
+}
+
+// Feeds a walk to one of html5ever's serializers.
+#[cfg(feature = "std")]
+struct SerializerSink<'a, S>(&'a mut S);
+
+#[cfg(feature = "std")]
+impl<S: Serializer> Sink for SerializerSink<'_, S> {
+  type Error = std::io::Error;
+
+  fn start_elem(
+    &mut self,
+    name: &QualName,
+    attrs: &[Attribute],
+  ) -> std::io::Result<()> {
+    let attrs = attrs.iter().map(|at| (&at.name, &at.value[..]));
+    self.0.start_elem(name.clone(), attrs)
+  }
+
+  fn end_elem(&mut self, name: &QualName) -> std::io::Result<()> {
+    self.0.end_elem(name.clone())
+  }
+
+  fn write_text(&mut self, text: &str) -> std::io::Result<()> {
+    self.0.write_text(text)
+  }
+
+  fn write_comment(&mut self, text: &str) -> std::io::Result<()> {
+    self.0.write_comment(text)
+  }
+
+  fn write_doctype(&mut self, name: &str) -> std::io::Result<()> {
+    self.0.write_doctype(name)
+  }
+
+  fn write_processing_instruction(
+    &mut self,
+    target: &str,
+    data: &str,
+  ) -> std::io::Result<()> {
+    self.0.write_processing_instruction(target, data)
+  }
+}
+
+// Writes HTML into anything that implements `fmt::Write`, exactly like
+// html5ever's `HtmlSerializer` (with scripting enabled) writes it into an
+// `io::Write`.
+struct HtmlWriter<W> {
+  out:   W,
+  // the elements that are open, innermost last
+  stack: Vec<OpenElement>,
+}
+
+#[derive(Default)]
+struct OpenElement {
+  // the local name of an HTML element
+  html_name:       Option<LocalName>,
+  // whether the element is void, so its contents are not written
+  ignore_children: bool,
+}
+
+impl<W: fmt::Write> HtmlWriter<W> {
+  fn new(out: W) -> Self {
+    HtmlWriter {
+      out,
+      stack: vec![OpenElement::default()],
+    }
+  }
+
+  fn parent(&self) -> Option<&OpenElement> {
+    self.stack.last()
+  }
+
+  fn write_escaped(&mut self, text: &str, attr_mode: bool) -> fmt::Result {
+    let mut rest = text;
+    while let Some(index) = rest.find(|c: char| match c {
+      '&' | '\u{a0}' => true,
+      '"' => attr_mode,
+      '<' | '>' => !attr_mode,
+      _ => false,
+    }) {
+      self.out.write_str(&rest[..index])?;
+      let c = rest[index..].chars().next().unwrap_or_default();
+      self.out.write_str(match c {
+        '&' => "&amp;",
+        '\u{a0}' => "&nbsp;",
+        '"' => "&quot;",
+        '<' => "&lt;",
+        _ => "&gt;",
+      })?;
+      rest = &rest[index + c.len_utf8()..];
+    }
+    self.out.write_str(rest)
+  }
+}
+
+impl<W: fmt::Write> Sink for HtmlWriter<W> {
+  type Error = fmt::Error;
+
+  fn start_elem(
+    &mut self,
+    name: &QualName,
+    attrs: &[Attribute],
+  ) -> fmt::Result {
+    let html_name = (name.ns == ns!(html)).then(|| name.local.clone());
+    if self.parent().is_some_and(|parent| parent.ignore_children) {
+      self.stack.push(OpenElement {
+        html_name,
+        ignore_children: true,
+      });
+      return Ok(());
+    }
+
+    write!(self.out, "<{}", name.local)?;
+    for attr in attrs {
+      let prefix = match attr.name.ns {
+        ns!() => "",
+        ns!(xml) => "xml:",
+        ns!(xmlns) if &*attr.name.local == "xmlns" => "",
+        ns!(xmlns) => "xmlns:",
+        ns!(xlink) => "xlink:",
+        _ => "unknown_namespace:",
+      };
+      write!(self.out, " {prefix}{}=\"", attr.name.local)?;
+      self.write_escaped(&attr.value, true)?;
+      self.out.write_str("\"")?;
+    }
+    self.out.write_str(">")?;
+
+    let ignore_children = name.ns == ns!(html)
+      && matches!(
+        &*name.local,
+        "area"
+          | "base"
+          | "basefont"
+          | "bgsound"
+          | "br"
+          | "col"
+          | "embed"
+          | "frame"
+          | "hr"
+          | "img"
+          | "input"
+          | "keygen"
+          | "link"
+          | "meta"
+          | "param"
+          | "source"
+          | "track"
+          | "wbr"
+      );
+    self.stack.push(OpenElement {
+      html_name,
+      ignore_children,
+    });
+    Ok(())
+  }
+
+  fn end_elem(&mut self, name: &QualName) -> fmt::Result {
+    let element = self.stack.pop().unwrap_or_default();
+    if element.ignore_children {
+      return Ok(());
+    }
+    write!(self.out, "</{}>", name.local)
+  }
+
+  fn write_text(&mut self, text: &str) -> fmt::Result {
+    let is_raw = self
+      .parent()
+      .and_then(|parent| parent.html_name.as_deref())
+      .is_some_and(|name| {
+        matches!(
+          name,
+          "style"
+            | "script"
+            | "xmp"
+            | "iframe"
+            | "noembed"
+            | "noframes"
+            | "plaintext"
+            | "noscript"
+        )
+      });
+    match is_raw {
+      true => self.out.write_str(text),
+      false => self.write_escaped(text, false),
+    }
+  }
+
+  fn write_comment(&mut self, text: &str) -> fmt::Result {
+    write!(self.out, "<!--{text}-->")
+  }
+
+  fn write_doctype(&mut self, name: &str) -> fmt::Result {
+    write!(self.out, "<!DOCTYPE {name}>")
+  }
+
+  fn write_processing_instruction(
+    &mut self,
+    target: &str,
+    data: &str,
+  ) -> fmt::Result {
+    write!(self.out, "<?{target} {data}>")
+  }
+}
+
+// Serializes `node` (including itself if `include_node` is set) into
+// `sink`. `handle` is the handle of `node`, if the caller has it.
+fn serialize_node<S: Sink>(
+  node: &Node,
+  handle: Option<&Handle>,
+  sink: &mut S,
+  include_node: bool,
+) -> Result<(), S::Error> {
+  let mut ops = Vec::new();
+  match include_node {
+    true => open(node, handle, &mut ops, sink)?,
+    false => ops.extend(
+      node
         .children
         .borrow()
         .iter()
         .rev()
-        .map(|h| SerializeOp::Open(h.clone()))
-        .collect(),
-    };
+        .map(|h| SerializeOp::Open(h.clone())),
+    ),
+  }
 
-    while let Some(op) = ops.pop() {
-      match op {
-        SerializeOp::Open(handle) => match &handle.data {
-          NodeData::Element {
-            name,
-            attrs,
-            template_contents,
-            ..
-          } => {
-            serializer.start_elem(
-              name.clone(),
-              attrs.borrow().iter().map(|at| (&at.name, &at.value[..])),
-            )?;
-
-            ops.push(SerializeOp::Close(name.clone()));
-
-            for child in handle.children.borrow().iter().rev() {
-              ops.push(SerializeOp::Open(child.clone()));
-            }
-
-            if let Some(template_contents) = template_contents {
-              for ref mut child in
-                template_contents.children.borrow().iter().rev()
-              {
-                child.parent.replace(Some(Rc::downgrade(&handle)));
-                ops.push(SerializeOp::Open(child.clone()));
-              }
-            }
-          }
+  while let Some(op) = ops.pop() {
+    match op {
+      SerializeOp::Open(handle) => {
+        open(&handle, Some(&handle), &mut ops, sink)?
+      }
+      SerializeOp::Close(name) => sink.end_elem(&name)?,
+    }
+  }
 
-          NodeData::Doctype { name, .. } => serializer.write_doctype(&name)?,
+  Ok(())
+}
 
-          NodeData::Text { contents } => {
-            serializer.write_text(&contents.borrow())?
-          }
+// Writes the start of `node`, pushing the ops that serialize the rest of it.
+fn open<S: Sink>(
+  node: &Node,
+  handle: Option<&Handle>,
+  ops: &mut Vec<SerializeOp>,
+  sink: &mut S,
+) -> Result<(), S::Error> {
+  match &node.data {
+    NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      ..
+    } => {
+      sink.start_elem(name, &attrs.borrow())?;
 
-          NodeData::Comment { contents } => {
-            serializer.write_comment(&contents)?
-          }
+      ops.push(SerializeOp::Close(name.clone()));
 
-          NodeData::ProcessingInstruction { target, contents } => {
-            serializer.write_processing_instruction(target, contents)?
-          }
+      for child in node.children.borrow().iter().rev() {
+        ops.push(SerializeOp::Open(child.clone()));
+      }
 
-          NodeData::Document => {
-            for child in handle.children.borrow().iter().rev() {
-              ops.push(SerializeOp::Open(child.clone()));
-            }
+      if let Some(template_contents) = template_contents {
+        for ref mut child in template_contents.children.borrow().iter().rev() {
+          if let Some(handle) = handle {
+            child.parent.replace(Some(Rc::downgrade(handle)));
           }
-        },
-
-        SerializeOp::Close(name) => {
-          serializer.end_elem(name)?;
+          ops.push(SerializeOp::Open(child.clone()));
         }
       }
     }
 
-    Ok(())
+    NodeData::Doctype { name, .. } => sink.write_doctype(name)?,
+
+    NodeData::Text { contents } => sink.write_text(&contents.borrow())?,
+
+    NodeData::Comment { contents } => sink.write_comment(contents)?,
+
+    NodeData::ProcessingInstruction { target, contents } => {
+      sink.write_processing_instruction(target, contents)?
+    }
+
+    NodeData::Document => {
+      for child in node.children.borrow().iter().rev() {
+        ops.push(SerializeOp::Open(child.clone()));
+      }
+    }
+  }
+
+  Ok(())
+}
+
+/// Removes every node below `root` (including the contents of templates) for
+/// which `remove` returns `true`, along with its descendants. If
+/// `coalesce_text` is set, the text nodes left adjacent to each other by the
+/// removal are merged into one.
+pub fn remove_nodes(
+  root: &Handle,
+  remove: &impl Fn(&NodeData) -> bool,
+  coalesce_text: bool,
+) {
+  let children = root.children.take();
+  let mut kept: Vec<Handle> = Vec::with_capacity(children.len());
+  for child in children {
+    if remove(&child.data) {
+      child.parent.set(None);
+      continue;
+    }
+    remove_nodes(&child, remove, coalesce_text);
+    if let NodeData::Element {
+      template_contents: Some(contents),
+      ..
+    } = &child.data
+    {
+      remove_nodes(contents, remove, coalesce_text);
+    }
+    match coalesce_text {
+      true => push_merging_text(&mut kept, child),
+      false => kept.push(child),
+    }
+  }
+  *root.children.borrow_mut() = kept;
+}
+
+/// Pushes `node` onto `nodes`, merging it into the last node if both are
+/// text. The merged node is detached from its parent.
+pub(crate) fn push_merging_text(nodes: &mut Vec<Handle>, node: Handle) {
+  if let NodeData::Text { contents } = &node.data
+    && let Some(last) = nodes.last()
+    && let NodeData::Text { contents: last } = &last.data
+  {
+    last.borrow_mut().push_tendril(&contents.borrow());
+    node.parent.set(None);
+    return;
+  }
+  nodes.push(node);
+}
+
+/// Puts `replacement` in the place of `node` in its parent, leaving `node`
+/// detached. Does nothing if `node` has no parent.
+pub(crate) fn replace_node(node: &Handle, replacement: Handle) {
+  let Some((parent, index)) = node.get_parent_and_index() else {
+    return;
+  };
+  replacement.parent.set(Some(Rc::downgrade(&parent)));
+  parent.children.borrow_mut()[index] = replacement;
+  node.parent.set(None);
+}
+
+/// Replaces `element` with an element named `name` that has its attributes
+/// and children, and returns it. Does not carry over template contents.
+pub(crate) fn rename_element(element: &Handle, name: QualName) -> Handle {
+  let attrs = match &element.data {
+    NodeData::Element { attrs, .. } => attrs.take(),
+    _ => Vec::new(),
+  };
+  let renamed = Node::new(NodeData::Element {
+    name,
+    attrs: RefCell::new(attrs),
+    template_contents: None,
+    mathml_annotation_xml_integration_point: false,
+  });
+  let children = element.children.take();
+  for child in &children {
+    child.parent.set(Some(Rc::downgrade(&renamed)));
+  }
+  *renamed.children.borrow_mut() = children;
+  replace_node(element, renamed.clone());
+  renamed
+}
+
+/// Serializes the children of the given node as HTML, like the DOM's
+/// `innerHTML` getter.
+pub fn inner_html(handle: &Handle) -> String {
+  let mut out = String::new();
+  // writing into a `String` cannot fail
+  let mut writer = HtmlWriter::new(&mut out);
+  let _ = serialize_node(handle, Some(handle), &mut writer, false);
+  out
+}
+
+/// Serializes the given node, including itself, as HTML, like the DOM's
+/// `outerHTML` getter.
+pub fn outer_html(handle: &Handle) -> String {
+  let mut out = String::new();
+  // writing into a `String` cannot fail
+  let mut writer = HtmlWriter::new(&mut out);
+  let _ = serialize_node(handle, Some(handle), &mut writer, true);
+  out
+}
+
+/// Compares two nodes like the DOM's `isEqualNode`: they are equal if they
+/// are of the same type with the same data (name, namespace and prefix for
+/// elements, the ids of doctypes, the text of text nodes and comments, the
+/// target and data of processing instructions), have the same attributes
+/// regardless of their order, and have pairwise equal children. As in the
+/// DOM, the contents of templates are not their children, and are not
+/// compared.
+pub fn is_equal_node(a: &Handle, b: &Handle) -> bool {
+  let same_data = match (&a.data, &b.data) {
+    (NodeData::Document, NodeData::Document) => true,
+    (
+      NodeData::Doctype {
+        name: a_name,
+        public_id: a_public,
+        system_id: a_system,
+      },
+      NodeData::Doctype {
+        name: b_name,
+        public_id: b_public,
+        system_id: b_system,
+      },
+    ) => a_name == b_name && a_public == b_public && a_system == b_system,
+    (NodeData::Text { contents: a }, NodeData::Text { contents: b }) => {
+      *a.borrow() == *b.borrow()
+    }
+    (NodeData::Comment { contents: a }, NodeData::Comment { contents: b }) => {
+      a == b
+    }
+    (
+      NodeData::ProcessingInstruction {
+        target: a_target,
+        contents: a_contents,
+      },
+      NodeData::ProcessingInstruction {
+        target: b_target,
+        contents: b_contents,
+      },
+    ) => a_target == b_target && a_contents == b_contents,
+    (
+      NodeData::Element {
+        name: a_name,
+        attrs: a_attrs,
+        ..
+      },
+      NodeData::Element {
+        name: b_name,
+        attrs: b_attrs,
+        ..
+      },
+    ) => {
+      let (a_attrs, b_attrs) = (a_attrs.borrow(), b_attrs.borrow());
+      a_name == b_name
+        && a_attrs.len() == b_attrs.len()
+        && a_attrs.iter().all(|attr| {
+          b_attrs.iter().any(|other| {
+            other.name.ns == attr.name.ns
+              && other.name.local == attr.name.local
+              && other.value == attr.value
+          })
+        })
+    }
+    _ => false,
+  };
+  let (a_children, b_children) = (a.children.borrow(), b.children.borrow());
+  same_data
+    && a_children.len() == b_children.len()
+    && a_children
+      .iter()
+      .zip(b_children.iter())
+      .all(|(a, b)| is_equal_node(a, b))
+}
+
+/// Copies a node like the DOM's `cloneNode`: the copy has no parent, and if
+/// `deep` is set, copies of the children of the node (and of the contents of
+/// a template) are appended to it.
+pub fn clone_node(node: &Handle, deep: bool) -> Handle {
+  let data = match &node.data {
+    NodeData::Document => NodeData::Document,
+    NodeData::Doctype {
+      name,
+      public_id,
+      system_id,
+    } => NodeData::Doctype {
+      name:      name.clone(),
+      public_id: public_id.clone(),
+      system_id: system_id.clone(),
+    },
+    NodeData::Text { contents } => NodeData::Text {
+      contents: RefCell::new(contents.borrow().clone()),
+    },
+    NodeData::Comment { contents } => NodeData::Comment {
+      contents: contents.clone(),
+    },
+    NodeData::ProcessingInstruction { target, contents } => {
+      NodeData::ProcessingInstruction {
+        target:   target.clone(),
+        contents: contents.clone(),
+      }
+    }
+    NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      mathml_annotation_xml_integration_point,
+    } => NodeData::Element {
+      name: name.clone(),
+      attrs: RefCell::new(attrs.borrow().clone()),
+      template_contents: template_contents.as_ref().map(|contents| {
+        match deep {
+          true => clone_node(contents, true),
+          false => Node::new(NodeData::Document),
+        }
+      }),
+      mathml_annotation_xml_integration_point:
+        *mathml_annotation_xml_integration_point,
+    },
+  };
+  let copy = Node::new(data);
+  if deep {
+    for child in node.children.borrow().iter() {
+      copy.append(clone_node(child, true));
+    }
+  }
+  copy
+}
+
+/// Assigns every node the id it gets in the wire output, i.e. in pre-order,
+/// with the contents of a template following the template's children.
+pub(crate) fn number_nodes(
+  node: &Handle,
+  ids: &mut BTreeMap<*const Node, u32>,
+) {
+  ids.insert(Rc::as_ptr(node), ids.len() as u32);
+  for child in node.children.borrow().iter() {
+    number_nodes(child, ids);
+  }
+  if let NodeData::Element {
+    template_contents: Some(contents),
+    ..
+  } = &node.data
+  {
+    number_nodes(contents, ids);
+  }
+}
+
+/// Finds the node with the given id in the wire output of the document rooted
+/// at `root`, numbering the nodes like [`number_nodes`].
+#[cfg(feature = "wasm")]
+pub(crate) fn node_by_id(root: &Handle, id: u32) -> Option<Handle> {
+  let mut next = 0;
+  find_node(root, id, &mut next)
+}
+
+#[cfg(feature = "wasm")]
+fn find_node(node: &Handle, id: u32, next: &mut u32) -> Option<Handle> {
+  if *next == id {
+    return Some(node.clone());
+  }
+  *next += 1;
+  for child in node.children.borrow().iter() {
+    if let Some(found) = find_node(child, id, next) {
+      return Some(found);
+    }
+  }
+  if let NodeData::Element {
+    template_contents: Some(contents),
+    ..
+  } = &node.data
+  {
+    return find_node(contents, id, next);
   }
+  None
 }
@@ -0,0 +1,300 @@
+//! Loading a [`WireDoc`] back into an [`RcDom`].
+//!
+//! [`wire_to_dom`] rebuilds the tree of a document that was serialized
+//! before (and e.g. cached as JSON), so it can be queried, changed and
+//! serialized again without parsing its markup again:
+//!
+//! ```
+//! use dawm::ParseOptions;
+//!
+//! let html = "<p class=x>Hi<svg><rect/></svg>";
+//! let options = ParseOptions::default();
+//! let doc = dawm::parse_wire_doc(html, "text/html", &options)?;
+//! let dom = dawm::wire_to_dom(&doc)?;
+//! assert_eq!(
+//!   dom.to_string(),
+//!   "<html><head></head><body><p class=\"x\">Hi<svg><rect></rect></svg>\
+//!    </p></body></html>"
+//! );
+//! # Ok::<(), dawm::DawmError>(())
+//! ```
+//!
+//! The wire format leaves out some of what the tree had, which is restored
+//! like this:
+//!
+//! - Elements take their prefix from their name, as in `svg:rect`. Those
+//!   without a `namespaceURI` in `text/html` documents (serialized before
+//!   elements had one) are put in the namespace the HTML parser would put them
+//!   in, like the [`build`](crate::build) module does.
+//! - Attributes take the usual prefix for their namespace (`xlink`, `xml` or
+//!   `xmlns`), or that of an element in scope in the same namespace.
+//!
+//! What the options of the parse dropped or changed stays that way: the end
+//! of text cut short by `maxTextLength`, the attributes left out by
+//! `attributeFilter`, names lowercased by `foldNameCase`, and so on. Parse
+//! errors are not loaded, and nor are the documents of `srcdoc`s, since the
+//! iframes still have their `srcdoc` attribute.
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::Namespace;
+use html5ever::Prefix;
+use html5ever::QualName;
+use html5ever::ns;
+
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::QuirksMode;
+use crate::RcDom;
+use crate::WireAttr;
+use crate::WireDoc;
+use crate::WireNode;
+use crate::WireNodeType;
+use crate::build::children_namespace;
+use crate::build::element_namespace;
+use crate::dom::create::new_element;
+use crate::error::DawmError;
+use crate::error::Result;
+
+/// Rebuilds the tree of `doc`, rooted at its node `0`, along with its quirks
+/// mode. Fails if a node or string the tree refers to is missing from `doc`
+/// (as in the documents of a [`ParseSession`](crate::ParseSession) after the
+/// first, whose strings are shipped only once), or if its links do not make
+/// up a tree.
+pub fn wire_to_dom(doc: &WireDoc) -> Result<RcDom> {
+  let dom = RcDom::default();
+  let quirks_mode = QuirksMode::try_from_str(&doc.quirks_mode)
+    .map_err(DawmError::QuirksMode)?;
+  dom.quirks_mode.set(quirks_mode.into());
+
+  let mut loader = Loader {
+    doc,
+    infer: doc.content_type == "text/html",
+    loaded: vec![false; doc.nodes.len()],
+    scope: Vec::new(),
+  };
+  let root = loader.node(0)?;
+  if !matches!(
+    root.node_type,
+    WireNodeType::Document | WireNodeType::DocumentFragment
+  ) {
+    return Err(DawmError::InvalidNodeType(format!(
+      "node 0 is not a document or fragment, but a {:?}",
+      root.node_type
+    )));
+  }
+  loader.children(&dom.document, root, &ns!(html))?;
+  Ok(dom)
+}
+
+struct Loader<'a> {
+  doc:    &'a WireDoc,
+  // whether the namespaces of elements without one are inferred, as for
+  // HTML documents serialized before elements had their namespace
+  infer:  bool,
+  // which nodes are in the tree already, so that no node is loaded twice
+  loaded: Vec<bool>,
+  // the prefixes of the elements in scope, and their namespaces, the
+  // innermost last
+  scope:  Vec<(&'a str, Namespace)>,
+}
+
+impl<'a> Loader<'a> {
+  fn node(&mut self, id: u32) -> Result<&'a WireNode> {
+    let doc = self.doc;
+    let node = doc.node(id).ok_or(DawmError::NodeNotFound(id))?;
+    if core::mem::replace(&mut self.loaded[id as usize], true) {
+      return Err(DawmError::InvalidState(format!(
+        "node {id} is linked into the tree more than once"
+      )));
+    }
+    Ok(node)
+  }
+
+  fn string(&self, index: u32) -> Result<&'a str> {
+    let doc = self.doc;
+    doc
+      .string(index)
+      .ok_or_else(|| DawmError::NotFound(format!("string {index}")))
+  }
+
+  fn value(&self, node: &WireNode) -> Result<&'a str> {
+    node.value.map_or(Ok(""), |index| self.string(index))
+  }
+
+  // Loads the children of `node` into `parent`, in (or as the children in)
+  // the namespace `ns`.
+  fn children(
+    &mut self,
+    parent: &Handle,
+    node: &'a WireNode,
+    ns: &Namespace,
+  ) -> Result<()> {
+    let mut children = Vec::new();
+    let mut next = node.first_child;
+    while let Some(id) = next {
+      let child = self.node(id)?;
+      children.push(self.load(child, ns)?);
+      next = child.next_sibling;
+    }
+    for child in &children {
+      child.parent.set(Some(Rc::downgrade(parent)));
+    }
+    *parent.children.borrow_mut() = children;
+    Ok(())
+  }
+
+  fn load(&mut self, node: &'a WireNode, ns: &Namespace) -> Result<Handle> {
+    let data = match node.node_type {
+      WireNodeType::Element => return self.element(node, ns),
+      WireNodeType::Text | WireNodeType::CData => NodeData::Text {
+        contents: RefCell::new(self.value(node)?.into()),
+      },
+      WireNodeType::Comment => NodeData::Comment {
+        contents: self.value(node)?.into(),
+      },
+      WireNodeType::ProcessingInstruction => NodeData::ProcessingInstruction {
+        target:   self.name(node)?.into(),
+        contents: self.value(node)?.into(),
+      },
+      WireNodeType::DocumentType => NodeData::Doctype {
+        name:      self.name(node)?.into(),
+        public_id: self.doctype_id(node, "publicId")?.into(),
+        system_id: self.doctype_id(node, "systemId")?.into(),
+      },
+      other => {
+        return Err(DawmError::InvalidNodeType(format!(
+          "node {} is a {other:?}, which cannot be a child",
+          node.id
+        )));
+      }
+    };
+    Ok(Node::new(data))
+  }
+
+  fn name(&self, node: &WireNode) -> Result<&'a str> {
+    let index = node.name.ok_or_else(|| {
+      DawmError::InvalidState(format!("node {} has no name", node.id))
+    })?;
+    self.string(index)
+  }
+
+  // The public or system id of a doctype, from its attributes if it has any,
+  // and otherwise from its value: the public id is quoted after a `PUBLIC`
+  // keyword, and the system id either follows it or a `SYSTEM` keyword.
+  fn doctype_id(&self, node: &WireNode, name: &str) -> Result<&'a str> {
+    if let Some(attrs) = &node.attrs {
+      for attr in attrs {
+        if self.string(attr.name)? == name {
+          return self.string(attr.value);
+        }
+      }
+      return Ok("");
+    }
+    let value = self.value(node)?;
+    let quoted = |rest: &'a str| {
+      let rest = rest.trim_start().strip_prefix('"')?;
+      rest.split_once('"')
+    };
+    let (public_id, system_id) = match value.split_once(" PUBLIC ") {
+      Some((_, rest)) => match quoted(rest) {
+        Some((public_id, rest)) => (public_id, quoted(rest).map(|(id, _)| id)),
+        None => ("", None),
+      },
+      None => {
+        let system_id = value.split_once(" SYSTEM ").and_then(|(_, rest)| {
+          quoted(rest).map(|(id, _)| id)
+        });
+        ("", system_id)
+      }
+    };
+    Ok(match name {
+      "publicId" => public_id,
+      _ => system_id.unwrap_or_default(),
+    })
+  }
+
+  fn element(
+    &mut self,
+    node: &'a WireNode,
+    parent: &Namespace,
+  ) -> Result<Handle> {
+    let name = self.name(node)?;
+    let attrs = node.attrs.as_deref().unwrap_or_default();
+    let ns = match node.namespace {
+      Some(ns) => Namespace::from(self.string(ns)?),
+      None if self.infer => element_namespace(name, parent),
+      None => ns!(),
+    };
+    // only names in a namespace have a prefix, like in `patch::qualify`
+    let (prefix, local) = match name.split_once(':') {
+      Some((prefix, local)) if !ns.is_empty() => (Some(prefix), local),
+      _ => (None, name),
+    };
+    let depth = self.scope.len();
+    if let Some(prefix) = prefix {
+      self.scope.push((prefix, ns.clone()));
+    }
+    let children_ns = children_namespace(&ns, local);
+    let element = new_element(QualName::new(
+      prefix.map(Prefix::from),
+      ns,
+      LocalName::from(local),
+    ));
+    let NodeData::Element {
+      attrs: element_attrs,
+      template_contents,
+      ..
+    } = &element.data
+    else {
+      unreachable!("new_element always makes an element");
+    };
+    *element_attrs.borrow_mut() = attrs
+      .iter()
+      .map(|attr| self.attribute(attr))
+      .collect::<Result<_>>()?;
+
+    if let (Some(contents), Some(id)) =
+      (template_contents, node.template_content)
+    {
+      let fragment = self.node(id)?;
+      self.children(contents, fragment, &ns!(html))?;
+    }
+    self.children(&element, node, &children_ns)?;
+    self.scope.truncate(depth);
+    Ok(element)
+  }
+
+  fn attribute(&self, attr: &WireAttr) -> Result<Attribute> {
+    let local = self.string(attr.name)?;
+    let ns = Namespace::from(attr.ns.map_or(Ok(""), |ns| self.string(ns))?);
+    let prefix = match ns {
+      ns!() => None,
+      ns!(xmlns) if local == "xmlns" => None,
+      ns!(xmlns) => Some("xmlns"),
+      ns!(xml) => Some("xml"),
+      ns!(xlink) => Some("xlink"),
+      ref ns => self
+        .scope
+        .iter()
+        .rev()
+        .find(|(_, declared)| declared == ns)
+        .map(|(prefix, _)| *prefix),
+    };
+    Ok(Attribute {
+      name:  QualName::new(
+        prefix.map(Prefix::from),
+        ns,
+        LocalName::from(local),
+      ),
+      value: self.string(attr.value)?.into(),
+    })
+  }
+}
@@ -0,0 +1,219 @@
+//! Rule-based rewriting of attributes.
+//!
+//! A list of [`AttributeRule`]s is applied across a whole tree in one pass,
+//! the way most HTML cleanup pipelines are written down: each rule matches
+//! attributes by the tag name of their element, their own name and a
+//! [`Pattern`] their value has to contain a match of, and then renames,
+//! deletes or sets them, or replaces the matches of a pattern in their
+//! value. Names are compared ignoring ASCII case, and can be `*` for any
+//! name or end with a `*` to match a prefix, e.g. `on*` or `data-*`.
+//!
+//! The rules are applied to every element in document order (including the
+//! contents of templates), in the order they are given, so a rule sees the
+//! attributes as the rules before it left them.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::LocalName;
+use html5ever::QualName;
+use html5ever::ns;
+use serde::Deserialize;
+
+use crate::DawmError;
+use crate::Handle;
+use crate::NodeData;
+use crate::Pattern;
+use crate::diff::qualified_name;
+use crate::dom::create::invalid_name;
+use crate::dom::create::is_valid_attribute_local_name;
+
+/// A rule rewriting the attributes it matches.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeRule {
+  /// Which attributes the rule applies to.
+  #[serde(rename = "match", default)]
+  pub matches: AttributeMatch,
+  /// What to do with them.
+  pub action:  AttributeAction,
+}
+
+/// Which attributes an [`AttributeRule`] applies to. Whatever is left out
+/// matches anything.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct AttributeMatch {
+  /// The tag name of the element, e.g. `img`, `*` or `h*`.
+  pub tag:       Option<String>,
+  /// The (qualified) name of the attribute, e.g. `href`, `*` or `on*`.
+  pub attribute: Option<String>,
+  /// A pattern the value has to contain a match of, e.g. `^javascript:`.
+  pub pattern:   Option<String>,
+}
+
+/// What an [`AttributeRule`] does with the attributes it matches, given as
+/// `"delete"`, `{ "rename": "src" }`, `{ "set": "lazy" }` or
+/// `{ "replace": { "pattern": "^http:", "replacement": "https:" } }`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AttributeAction {
+  /// Rename the attribute, replacing any attribute with the new name.
+  Rename(String),
+  /// Remove the attribute.
+  Delete,
+  /// Set the value of the attribute.
+  Set(String),
+  /// Replace every match of `pattern` in the value with `replacement`,
+  /// where `$&` stands for the match and `$1`, `$2`, ... for its groups.
+  Replace {
+    pattern:     String,
+    replacement: String,
+  },
+}
+
+// A rule with its patterns compiled.
+struct Rule<'a> {
+  rule:    &'a AttributeRule,
+  pattern: Option<Pattern>,
+  replace: Option<Pattern>,
+}
+
+/// Applies `rules` to the attributes of the elements below `root`,
+/// returning how many attributes were changed (or removed). Fails before
+/// changing anything if a pattern or new attribute name is invalid.
+pub fn rewrite_attributes(
+  root: &Handle,
+  rules: &[AttributeRule],
+) -> Result<usize, DawmError> {
+  let rules = rules
+    .iter()
+    .map(|rule| {
+      let pattern = rule.matches.pattern.as_deref().map(Pattern::new);
+      let replace = match &rule.action {
+        AttributeAction::Replace { pattern, .. } => Some(Pattern::new(pattern)),
+        AttributeAction::Rename(name)
+          if !is_valid_attribute_local_name(name) =>
+        {
+          return Err(invalid_name(name));
+        }
+        _ => None,
+      };
+      Ok(Rule {
+        rule,
+        pattern: pattern.transpose()?,
+        replace: replace.transpose()?,
+      })
+    })
+    .collect::<Result<Vec<_>, DawmError>>()?;
+
+  let mut changed = 0;
+  let mut stack = vec![root.clone()];
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      ..
+    } = &node.data
+    {
+      let mut attrs = attrs.borrow_mut();
+      for rule in &rules {
+        if rule
+          .rule
+          .matches
+          .tag
+          .as_deref()
+          .is_none_or(|tag| name_matches(tag, &name.local))
+        {
+          changed += rule.apply(&mut attrs);
+        }
+      }
+      if let Some(contents) = template_contents {
+        stack.push(contents.clone());
+      }
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  Ok(changed)
+}
+
+impl Rule<'_> {
+  // Applies the rule to the attributes of an element, returning how many
+  // were changed.
+  fn apply(&self, attrs: &mut Vec<Attribute>) -> usize {
+    let mut changed = 0;
+    let mut index = 0;
+    while index < attrs.len() {
+      let attr = &attrs[index];
+      let matched = self
+        .rule
+        .matches
+        .attribute
+        .as_deref()
+        .is_none_or(|name| name_matches(name, &qualified_name(&attr.name)))
+        && self
+          .pattern
+          .as_ref()
+          .is_none_or(|pattern| pattern.is_match(&attr.value));
+      if !matched {
+        index += 1;
+        continue;
+      }
+      match &self.rule.action {
+        AttributeAction::Delete => {
+          attrs.remove(index);
+          changed += 1;
+          continue;
+        }
+        AttributeAction::Set(value) => {
+          if *attrs[index].value != **value {
+            attrs[index].value = value.as_str().into();
+            changed += 1;
+          }
+        }
+        AttributeAction::Replace { replacement, .. } => {
+          let pattern = self.replace.as_ref().expect("compiled with the rule");
+          let value = pattern.replace_all(&attrs[index].value, replacement);
+          if *attrs[index].value != *value {
+            attrs[index].value = value.into();
+            changed += 1;
+          }
+        }
+        AttributeAction::Rename(to) => {
+          if qualified_name(&attrs[index].name) != *to {
+            attrs[index].name =
+              QualName::new(None, ns!(), LocalName::from(&**to));
+            changed += 1;
+            // the renamed attribute replaces any other one with its name
+            let name = attrs[index].name.clone();
+            let mut other = 0;
+            while other < attrs.len() {
+              if other != index && attrs[other].name == name {
+                attrs.remove(other);
+                index -= usize::from(other < index);
+              } else {
+                other += 1;
+              }
+            }
+          }
+        }
+      }
+      index += 1;
+    }
+    changed
+  }
+}
+
+// Whether `name` matches `pattern`: `*` for any name, a prefix followed by
+// `*`, or the name itself, ignoring ASCII case.
+fn name_matches(pattern: &str, name: &str) -> bool {
+  match pattern.strip_suffix('*') {
+    Some(prefix) => name
+      .get(..prefix.len())
+      .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+    None => pattern.eq_ignore_ascii_case(name),
+  }
+}
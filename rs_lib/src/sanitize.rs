@@ -0,0 +1,948 @@
+//! HTML sanitization.
+//!
+//! Parsing untrusted markup and sanitizing it in a single pass avoids a
+//! second round-trip through another library (and another parser, with its
+//! own idea of what the markup means). The [`sanitize`] function cleans a
+//! parsed tree in place according to a [`SanitizePolicy`], modeled after the
+//! [ammonia] crate:
+//!
+//! - elements listed in `removeContents` (e.g. `<script>`) are dropped along
+//!   with everything inside of them;
+//! - any other element not listed in `allowedTags` is unwrapped, keeping its
+//!   (sanitized) children in its place;
+//! - elements nested deeper than `maxDepth` are unwrapped as well;
+//! - attributes are only kept if listed in `allowedAttributes` for the tag, or
+//!   for all tags (under the `"*"` key);
+//! - URL-valued attributes (`href`, `src`, `srcset`, ...) are dropped if any of
+//!   their URLs has a scheme not listed in `urlSchemes`; relative URLs are
+//!   always allowed;
+//! - if `dataUris` lists any MIME types, `data:` URLs are only allowed with one
+//!   of those types and within its size limit, regardless of `urlSchemes`, and
+//!   rejected ones are replaced with its `replacement` (if any);
+//! - kept `style` attributes only keep the declarations of the properties
+//!   listed in `allowedStyles` with a [safe value](crate::style), and are
+//!   dropped if none are left;
+//! - comments are dropped unless `allowComments` is set, and processing
+//!   instructions and doctypes are always dropped.
+//!
+//! Any of the policy fields can be omitted to use the defaults, which allow a
+//! conservative set of formatting tags and attributes.
+//!
+//! Most applications can use one of the named [`SanitizePreset`]s instead of
+//! building a policy by hand:
+//!
+//! - `"strict"` keeps the text only, without any markup at all;
+//! - `"basic"` keeps the inline formatting, links, lists and quotes commonly
+//!   allowed in user comments and forum posts;
+//! - `"relaxed"` is the default policy described above;
+//! - `"email"` extends `"relaxed"` with the presentational tags and attributes
+//!   of table-based email layouts, and allows `cid:` image URLs.
+//!
+//! [ammonia]: https://docs.rs/ammonia
+
+use alloc::collections::BTreeMap;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::FragmentParseOptions;
+use crate::Handle;
+use crate::Node;
+use crate::NodeData;
+use crate::RcDom;
+use crate::error;
+use crate::inner_html;
+use crate::parse_html_fragment;
+use crate::rcdom::number_nodes;
+use crate::rcdom::push_merging_text;
+use crate::style::sanitize_style;
+use crate::url_filter::srcset_candidates;
+
+/// Describes what [`sanitize`] keeps. See the [module level documentation]
+/// for details on how each field is applied.
+///
+/// [module level documentation]: crate::sanitize
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SanitizePolicy {
+  /// The tag names of the elements to keep.
+  pub allowed_tags:       Vec<String>,
+  /// The attribute names to keep, per tag name. The `"*"` key applies to
+  /// all tags.
+  pub allowed_attributes: BTreeMap<String, Vec<String>>,
+  /// The URL schemes allowed in URL-valued attributes.
+  pub url_schemes:        Vec<String>,
+  /// The CSS properties kept in `style` attributes, if those are allowed.
+  pub allowed_styles:     Vec<String>,
+  /// The constraints on `data:` URLs.
+  pub data_uris:          DataUriPolicy,
+  /// The tag names of the elements to remove along with their contents.
+  pub remove_contents:    Vec<String>,
+  /// The maximum nesting depth of the kept elements.
+  pub max_depth:          Option<u32>,
+  /// Whether to keep comments.
+  pub allow_comments:     bool,
+}
+
+/// Which `data:` URLs are kept by [`sanitize`].
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DataUriPolicy {
+  /// The allowed MIME types, such as `"image/png"`, or `"image/*"` for all
+  /// subtypes. If empty, `data:` URLs are governed by `urlSchemes` alone.
+  pub mime_types:  Vec<String>,
+  /// The maximum size of the decoded payload, in bytes.
+  pub max_size:    Option<u32>,
+  /// The value used in place of a rejected `data:` URL. If `None`, the
+  /// attribute is dropped instead.
+  pub replacement: Option<String>,
+}
+
+impl DataUriPolicy {
+  /// Returns `true` if `url` is a `data:` URL with an allowed MIME type and
+  /// payload size.
+  pub fn allows(&self, url: &str) -> bool {
+    let Some((mime_type, size)) = parse_data_url(url) else {
+      return false;
+    };
+    let type_allowed =
+      self
+        .mime_types
+        .iter()
+        .any(|allowed| match allowed.strip_suffix("/*") {
+          Some(top_level) => mime_type
+            .split_once('/')
+            .is_some_and(|(top, _)| top.eq_ignore_ascii_case(top_level)),
+          None => allowed.eq_ignore_ascii_case(&mime_type),
+        });
+    type_allowed && self.max_size.is_none_or(|max| size <= max as usize)
+  }
+}
+
+/// A named [`SanitizePolicy`]. See the [module level documentation] for what
+/// each of them keeps.
+///
+/// [module level documentation]: crate::sanitize
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SanitizePreset {
+  Strict,
+  Basic,
+  #[default]
+  Relaxed,
+  Email,
+}
+
+impl SanitizePreset {
+  /// Every preset.
+  pub const ALL: &[Self] = &[
+    SanitizePreset::Strict,
+    SanitizePreset::Basic,
+    SanitizePreset::Relaxed,
+    SanitizePreset::Email,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      SanitizePreset::Strict => "strict",
+      SanitizePreset::Basic => "basic",
+      SanitizePreset::Relaxed => "relaxed",
+      SanitizePreset::Email => "email",
+    }
+  }
+
+  /// Returns the policy of this preset.
+  pub fn policy(&self) -> SanitizePolicy {
+    match self {
+      SanitizePreset::Strict => SanitizePolicy {
+        allowed_tags: Vec::new(),
+        allowed_attributes: BTreeMap::new(),
+        url_schemes: Vec::new(),
+        allowed_styles: Vec::new(),
+        ..SanitizePolicy::default()
+      },
+      SanitizePreset::Basic => SanitizePolicy {
+        allowed_tags: strings(BASIC_TAGS),
+        allowed_attributes: attributes(BASIC_ATTRIBUTES),
+        url_schemes: strings(BASIC_URL_SCHEMES),
+        ..SanitizePolicy::default()
+      },
+      SanitizePreset::Relaxed => SanitizePolicy::default(),
+      SanitizePreset::Email => {
+        let mut policy = SanitizePolicy::default();
+        policy.allowed_tags.extend(strings(EMAIL_TAGS));
+        for (tag, names) in EMAIL_ATTRIBUTES {
+          let allowed = policy.allowed_attributes.entry(tag.to_string());
+          allowed.or_default().extend(strings(names));
+        }
+        policy.url_schemes.push("cid".to_string());
+        policy
+      }
+    }
+  }
+}
+
+impl From<SanitizePreset> for SanitizePolicy {
+  fn from(preset: SanitizePreset) -> Self {
+    preset.policy()
+  }
+}
+
+const DEFAULT_TAGS: &[&str] = &[
+  "a",
+  "abbr",
+  "b",
+  "bdi",
+  "bdo",
+  "blockquote",
+  "br",
+  "caption",
+  "cite",
+  "code",
+  "col",
+  "colgroup",
+  "dd",
+  "del",
+  "details",
+  "dfn",
+  "div",
+  "dl",
+  "dt",
+  "em",
+  "figcaption",
+  "figure",
+  "h1",
+  "h2",
+  "h3",
+  "h4",
+  "h5",
+  "h6",
+  "hr",
+  "i",
+  "img",
+  "ins",
+  "kbd",
+  "li",
+  "mark",
+  "ol",
+  "p",
+  "pre",
+  "q",
+  "rp",
+  "rt",
+  "ruby",
+  "s",
+  "samp",
+  "small",
+  "span",
+  "strike",
+  "strong",
+  "sub",
+  "summary",
+  "sup",
+  "table",
+  "tbody",
+  "td",
+  "tfoot",
+  "th",
+  "thead",
+  "time",
+  "tr",
+  "u",
+  "ul",
+  "var",
+  "wbr",
+];
+
+const DEFAULT_ATTRIBUTES: &[(&str, &[&str])] = &[
+  ("*", &["dir", "lang", "title"]),
+  ("a", &["href", "hreflang"]),
+  ("bdo", &["dir"]),
+  ("blockquote", &["cite"]),
+  ("col", &["align", "char", "charoff", "span"]),
+  ("colgroup", &["align", "char", "charoff", "span"]),
+  ("del", &["cite", "datetime"]),
+  ("img", &["align", "alt", "height", "src", "width"]),
+  ("ins", &["cite", "datetime"]),
+  ("ol", &["start", "reversed", "type"]),
+  ("q", &["cite"]),
+  ("table", &["align", "char", "charoff", "summary"]),
+  (
+    "td",
+    &["align", "char", "charoff", "colspan", "headers", "rowspan"],
+  ),
+  (
+    "th",
+    &[
+      "align", "char", "charoff", "colspan", "headers", "rowspan", "scope",
+    ],
+  ),
+  ("time", &["datetime"]),
+];
+
+const BASIC_TAGS: &[&str] = &[
+  "a",
+  "b",
+  "blockquote",
+  "br",
+  "code",
+  "del",
+  "em",
+  "i",
+  "li",
+  "ol",
+  "p",
+  "pre",
+  "q",
+  "s",
+  "strong",
+  "sub",
+  "sup",
+  "u",
+  "ul",
+];
+
+const BASIC_ATTRIBUTES: &[(&str, &[&str])] = &[
+  ("a", &["href"]),
+  ("blockquote", &["cite"]),
+  ("q", &["cite"]),
+];
+
+const BASIC_URL_SCHEMES: &[&str] = &["http", "https", "mailto"];
+
+const EMAIL_TAGS: &[&str] = &["center", "font"];
+
+const EMAIL_ATTRIBUTES: &[(&str, &[&str])] = &[
+  (
+    "*",
+    &["align", "bgcolor", "height", "style", "valign", "width"],
+  ),
+  ("font", &["color", "face", "size"]),
+  ("img", &["border"]),
+  ("table", &["border", "cellpadding", "cellspacing"]),
+  ("td", &["nowrap"]),
+  ("th", &["nowrap"]),
+];
+
+const DEFAULT_STYLES: &[&str] = &[
+  "background-color",
+  "border",
+  "border-bottom",
+  "border-collapse",
+  "border-color",
+  "border-left",
+  "border-radius",
+  "border-right",
+  "border-style",
+  "border-top",
+  "border-width",
+  "color",
+  "display",
+  "font",
+  "font-family",
+  "font-size",
+  "font-style",
+  "font-variant",
+  "font-weight",
+  "height",
+  "letter-spacing",
+  "line-height",
+  "list-style-type",
+  "margin",
+  "margin-bottom",
+  "margin-left",
+  "margin-right",
+  "margin-top",
+  "max-height",
+  "max-width",
+  "min-height",
+  "min-width",
+  "padding",
+  "padding-bottom",
+  "padding-left",
+  "padding-right",
+  "padding-top",
+  "text-align",
+  "text-decoration",
+  "text-indent",
+  "text-transform",
+  "vertical-align",
+  "white-space",
+  "width",
+  "word-break",
+  "word-spacing",
+];
+
+const DEFAULT_URL_SCHEMES: &[&str] = &["http", "https", "mailto", "tel"];
+
+const DEFAULT_REMOVE_CONTENTS: &[&str] = &[
+  "script", "style", "template", "noscript", "iframe", "object", "embed",
+  "textarea", "select",
+];
+
+// Attributes which hold a URL, regardless of the tag they appear on.
+const URL_ATTRIBUTES: &[&str] = &[
+  "action",
+  "background",
+  "cite",
+  "data",
+  "formaction",
+  "href",
+  "longdesc",
+  "manifest",
+  "poster",
+  "src",
+];
+
+fn strings(values: &[&str]) -> Vec<String> {
+  values.iter().map(|s| s.to_string()).collect()
+}
+
+fn attributes(values: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+  values
+    .iter()
+    .map(|(tag, names)| (tag.to_string(), strings(names)))
+    .collect()
+}
+
+impl Default for SanitizePolicy {
+  fn default() -> Self {
+    Self {
+      allowed_tags:       strings(DEFAULT_TAGS),
+      allowed_attributes: attributes(DEFAULT_ATTRIBUTES),
+      url_schemes:        strings(DEFAULT_URL_SCHEMES),
+      allowed_styles:     strings(DEFAULT_STYLES),
+      data_uris:          DataUriPolicy::default(),
+      remove_contents:    strings(DEFAULT_REMOVE_CONTENTS),
+      max_depth:          None,
+      allow_comments:     false,
+    }
+  }
+}
+
+impl SanitizePolicy {
+  /// Returns `true` if elements named `tag` are kept.
+  pub fn allows_tag(&self, tag: &str) -> bool {
+    contains(&self.allowed_tags, tag)
+  }
+
+  /// Returns `true` if elements named `tag` are removed with their contents.
+  pub fn removes_contents(&self, tag: &str) -> bool {
+    contains(&self.remove_contents, tag)
+  }
+
+  /// Returns `true` if the attribute `name` is kept on elements named `tag`.
+  pub fn allows_attribute(&self, tag: &str, name: &str) -> bool {
+    ["*", tag].iter().any(|key| {
+      self
+        .allowed_attributes
+        .get(*key)
+        .is_some_and(|names| contains(names, name))
+    })
+  }
+
+  /// Returns `true` if `url` is relative, or its scheme is allowed.
+  pub fn allows_url(&self, url: &str) -> bool {
+    match url_scheme(url) {
+      Some(scheme)
+        if scheme == "data" && !self.data_uris.mime_types.is_empty() =>
+      {
+        self.data_uris.allows(url)
+      }
+      Some(scheme) => contains(&self.url_schemes, &scheme),
+      None => true,
+    }
+  }
+
+  /// Returns `true` if the CSS property `name` is kept in `style` attributes.
+  pub fn allows_style(&self, name: &str) -> bool {
+    contains(&self.allowed_styles, name)
+  }
+
+  /// Returns `true` if every URL held by the attribute `name` (if any) is
+  /// allowed, including each of the candidates of a `srcset`.
+  pub fn allows_urls_in(&self, name: &str, value: &str) -> bool {
+    if name.eq_ignore_ascii_case("srcset") {
+      return srcset_candidates(value)
+        .iter()
+        .all(|(url, _)| self.allows_url(url));
+    }
+    !is_url_attribute(name) || self.allows_url(value)
+  }
+}
+
+fn contains(list: &[String], name: &str) -> bool {
+  list.iter().any(|item| item.eq_ignore_ascii_case(name))
+}
+
+/// Returns `true` if the attribute `name` holds a URL.
+pub fn is_url_attribute(name: &str) -> bool {
+  URL_ATTRIBUTES
+    .iter()
+    .any(|attr| attr.eq_ignore_ascii_case(name))
+}
+
+/// Extracts the (lowercase) scheme of `url`, the way browsers do: leading
+/// and trailing whitespace is ignored, as are tabs and newlines anywhere in
+/// the URL. Returns `None` for relative URLs.
+pub fn url_scheme(url: &str) -> Option<String> {
+  let url: String = url
+    .trim_matches(|c: char| c <= ' ')
+    .chars()
+    .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+    .collect();
+  let end = url.find([':', '/', '?', '#'])?;
+  let scheme = &url[..end];
+  let valid = url[end..].starts_with(':')
+    && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+    && scheme
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+  valid.then(|| scheme.to_ascii_lowercase())
+}
+
+/// Parses a `data:` URL, returning its (lowercase) MIME type and the size of
+/// its decoded payload in bytes, or `None` if `url` is not a valid `data:`
+/// URL. The MIME type defaults to `text/plain` if omitted.
+pub fn parse_data_url(url: &str) -> Option<(String, usize)> {
+  let url: String = url
+    .trim_matches(|c: char| c <= ' ')
+    .chars()
+    .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+    .collect();
+  let rest = url
+    .get(..5)?
+    .eq_ignore_ascii_case("data:")
+    .then(|| &url[5..])?;
+  let (header, payload) = rest.split_once(',')?;
+  let mut params = header.split(';');
+  let mime_type = params.next().unwrap_or_default().trim();
+  let mime_type = match mime_type.is_empty() {
+    true => "text/plain".to_string(),
+    false => mime_type.to_ascii_lowercase(),
+  };
+  let base64 = params
+    .next_back()
+    .is_some_and(|param| param.trim().eq_ignore_ascii_case("base64"));
+  let size = if base64 {
+    let digits = payload
+      .bytes()
+      .filter(|b| !b.is_ascii_whitespace() && *b != b'=')
+      .count();
+    digits * 3 / 4
+  } else {
+    // every percent-encoded byte counts as one
+    payload
+      .len()
+      .saturating_sub(payload.matches('%').count() * 2)
+  };
+  Some((mime_type, size))
+}
+
+/// A change made by [`sanitize`] to a node, identified by its id in the
+/// unsanitized document (as in the output of [`parse_frag`]).
+///
+/// [`parse_frag`]: crate::parse_frag
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeChange {
+  pub id:     u32,
+  /// The tag name of an element, or `#comment`, `#doctype`, ...
+  pub name:   String,
+  /// `"removed"` (along with all of its descendants) or `"unwrapped"` (its
+  /// children were kept in its place).
+  pub action: &'static str,
+  /// Why: `"removeContents"`, `"disallowedTag"`, `"maxDepth"` or
+  /// `"disallowedNode"`.
+  pub reason: &'static str,
+}
+
+/// A change made by [`sanitize`] to an attribute of a kept element.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AttributeChange {
+  /// The id of the element, as in [`NodeChange`].
+  pub id:        u32,
+  pub tag:       String,
+  pub name:      String,
+  /// The original attribute value.
+  pub value:     String,
+  /// The new value, if the attribute was rewritten rather than stripped.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub new_value: Option<String>,
+  /// Why: `"disallowedAttribute"`, `"disallowedUrl"` or `"style"`.
+  pub reason:    &'static str,
+}
+
+/// Everything [`sanitize_with_report`] removed or modified, in document
+/// order.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SanitizeReport {
+  pub nodes:      Vec<NodeChange>,
+  pub attributes: Vec<AttributeChange>,
+}
+
+/// Sanitizes the children of `root` in place according to the `policy`.
+pub fn sanitize(root: &Handle, policy: &SanitizePolicy) {
+  let mut sanitizer = Sanitizer {
+    policy,
+    ids: BTreeMap::new(),
+    report: None,
+  };
+  sanitizer.sanitize_children(root, 0);
+}
+
+/// Sanitizes the children of `root` like [`sanitize`], and reports all of the
+/// changes made, with node ids numbered from `document` in the order used by
+/// the wire output.
+pub fn sanitize_with_report(
+  document: &Handle,
+  root: &Handle,
+  policy: &SanitizePolicy,
+) -> SanitizeReport {
+  let mut ids = BTreeMap::new();
+  number_nodes(document, &mut ids);
+  let mut sanitizer = Sanitizer {
+    policy,
+    ids,
+    report: Some(SanitizeReport::default()),
+  };
+  sanitizer.sanitize_children(root, 0);
+  sanitizer.report.unwrap_or_default()
+}
+
+struct Sanitizer<'a> {
+  policy: &'a SanitizePolicy,
+  ids:    BTreeMap<*const Node, u32>,
+  report: Option<SanitizeReport>,
+}
+
+impl Sanitizer<'_> {
+  fn id_of(&self, node: &Handle) -> u32 {
+    self.ids.get(&Rc::as_ptr(node)).copied().unwrap_or(u32::MAX)
+  }
+
+  fn node_changed(
+    &mut self,
+    node: &Handle,
+    action: &'static str,
+    reason: &'static str,
+  ) {
+    let id = self.id_of(node);
+    let Some(report) = self.report.as_mut() else {
+      return;
+    };
+    let name = match &node.data {
+      NodeData::Element { name, .. } => name.local.to_string(),
+      NodeData::Comment { .. } => "#comment".to_string(),
+      NodeData::ProcessingInstruction { target, .. } => target.to_string(),
+      NodeData::Doctype { .. } => "#doctype".to_string(),
+      NodeData::Text { .. } => "#text".to_string(),
+      NodeData::Document => "#document".to_string(),
+    };
+    report.nodes.push(NodeChange {
+      id,
+      name,
+      action,
+      reason,
+    });
+  }
+
+  fn sanitize_children(&mut self, parent: &Handle, depth: u32) {
+    let policy = self.policy;
+    let children = parent.children.take();
+    let mut kept: Vec<Handle> = Vec::with_capacity(children.len());
+    for child in children {
+      child.parent.set(None);
+      match &child.data {
+        NodeData::Element {
+          name,
+          attrs,
+          template_contents,
+          ..
+        } => {
+          let tag = name.local.as_ref();
+          if policy.removes_contents(tag) {
+            self.node_changed(&child, "removed", "removeContents");
+            continue;
+          }
+          let too_deep = policy.max_depth.is_some_and(|max| depth >= max);
+          if !policy.allows_tag(tag) || too_deep {
+            let reason = match too_deep {
+              true => "maxDepth",
+              false => "disallowedTag",
+            };
+            self.node_changed(&child, "unwrapped", reason);
+            self.sanitize_children(&child, depth);
+            for grandchild in child.children.take() {
+              grandchild.parent.set(None);
+              push_merging_text(&mut kept, grandchild);
+            }
+            continue;
+          }
+          let id = self.id_of(&child);
+          let mut changes = Vec::new();
+          attrs.borrow_mut().retain_mut(|attr| {
+            let name = attr.name.local.as_ref();
+            let changed = |new_value: Option<&str>, reason| AttributeChange {
+              id,
+              tag: tag.to_string(),
+              name: name.to_string(),
+              value: attr.value.to_string(),
+              new_value: new_value.map(|v| v.to_string()),
+              reason,
+            };
+            if !policy.allows_attribute(tag, name) {
+              changes.push(changed(None, "disallowedAttribute"));
+              return false;
+            }
+            if !policy.allows_urls_in(name, &attr.value) {
+              let replacement = policy.data_uris.replacement.as_deref();
+              let is_data_url = is_url_attribute(name)
+                && url_scheme(&attr.value).is_some_and(|s| s == "data");
+              let replacement = replacement.filter(|_| is_data_url);
+              changes.push(changed(replacement, "disallowedUrl"));
+              if let Some(replacement) = replacement {
+                attr.value = replacement.into();
+              }
+              return replacement.is_some();
+            }
+            if !name.eq_ignore_ascii_case("style") {
+              return true;
+            }
+            let style = sanitize_style(
+              &attr.value,
+              |property| policy.allows_style(property),
+              |url| policy.allows_url(url),
+            );
+            if style.as_deref() != Some(&*attr.value) {
+              changes.push(changed(style.as_deref(), "style"));
+            }
+            match style {
+              Some(style) => {
+                attr.value = style.into();
+                true
+              }
+              None => false,
+            }
+          });
+          if let Some(report) = self.report.as_mut() {
+            report.attributes.extend(changes);
+          }
+          self.sanitize_children(&child, depth + 1);
+          if let Some(contents) = template_contents {
+            self.sanitize_children(contents, depth + 1);
+          }
+          kept.push(child);
+        }
+        NodeData::Text { .. } => push_merging_text(&mut kept, child),
+        NodeData::Comment { .. } if policy.allow_comments => kept.push(child),
+        NodeData::Comment { .. }
+        | NodeData::ProcessingInstruction { .. }
+        | NodeData::Doctype { .. }
+        | NodeData::Document => {
+          self.node_changed(&child, "removed", "disallowedNode");
+        }
+      }
+    }
+    for child in &kept {
+      child.parent.set(Some(Rc::downgrade(parent)));
+    }
+    *parent.children.borrow_mut() = kept;
+  }
+}
+
+/// Parses `input` as an HTML fragment and sanitizes it according to the
+/// `policy`, returning the DOM. The sanitized nodes are the children of the
+/// document's root element.
+pub fn sanitize_fragment(
+  input: &str,
+  policy: &SanitizePolicy,
+) -> error::Result<RcDom> {
+  let dom = parse_html_fragment(input, &FragmentParseOptions::default())?;
+  if let Some(root) = fragment_root(&dom) {
+    sanitize(&root, policy);
+  }
+  Ok(dom)
+}
+
+/// Parses `input` as an HTML fragment, sanitizes it according to the
+/// `policy` and serializes the result back to HTML.
+pub fn clean(input: &str, policy: &SanitizePolicy) -> error::Result<String> {
+  let dom = sanitize_fragment(input, policy)?;
+  Ok(
+    fragment_root(&dom)
+      .map(|root| inner_html(&root))
+      .unwrap_or_default(),
+  )
+}
+
+/// Like [`clean`], but also returns a report of the changes made, with the
+/// node ids of the unsanitized fragment.
+pub fn clean_with_report(
+  input: &str,
+  policy: &SanitizePolicy,
+) -> error::Result<(String, SanitizeReport)> {
+  let dom = parse_html_fragment(input, &FragmentParseOptions::default())?;
+  let Some(root) = fragment_root(&dom) else {
+    return Ok((String::new(), SanitizeReport::default()));
+  };
+  let report = sanitize_with_report(&dom.document, &root, policy);
+  Ok((inner_html(&root), report))
+}
+
+// html5ever parses fragments into a synthetic `<html>` root element, which
+// may be preceded by a doctype given in the fragment options.
+pub(crate) fn fragment_root(dom: &RcDom) -> Option<Handle> {
+  dom
+    .document
+    .children
+    .borrow()
+    .iter()
+    .find(|node| matches!(node.data, NodeData::Element { .. }))
+    .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const JAVASCRIPT_HREF: &str = r#"<a href="javascript:alert(1)">a</a>"#;
+  const MIXED_CASE_HREF: &str = r#"<a href="JaVaScRiPt:alert(1)">a</a>"#;
+  const ENTITY_HREF: &str = r#"<a href="&#106;avascript&colon;alert(1)">a</a>"#;
+  const TAB_HREF: &str = r#"<a href=" java&#x09;script:alert(1)">a</a>"#;
+  const NEWLINE_HREF: &str = "<a href=\"\njavascript:alert(1)\">a</a>";
+  const HTTPS_HREF: &str = r#"<a href="https://example.com/">a</a>"#;
+  const SVG_SCRIPT: &str = "<svg><script>alert(1)</script></svg>";
+  const SVG_STYLE: &str =
+    "<svg><style>*{color:red}</style><text>t</text></svg>";
+  const MATH_STYLE: &str = "<math><mi><style>x</style></mi></math>";
+  const EVENT_HANDLERS: &str =
+    r#"<p onclick="alert(1)" onmouseover="alert(2)" title="t">p</p>"#;
+  const IMG_ONERROR: &str = r#"<img src="x.png" onerror="alert(1)">"#;
+  const DATA_IMAGE: &str = r#"<img src="data:image/png;base64,AAAA">"#;
+  const DATA_HTML: &str =
+    r#"<img src="data:text/html,<script>alert(1)</script>">"#;
+  const STYLE: &str = r#"<p style="color: red; background: url(javascript:alert(1)); position: fixed">s</p>"#;
+  const FONT: &str = r#"<font color="red" face="Arial" onclick="x">f</font>"#;
+  const CID_IMAGE: &str = r#"<img src="cid:logo@example.com">"#;
+
+  fn assert_cleans(preset: SanitizePreset, cases: &[(&str, &str)]) {
+    let policy = preset.policy();
+    for (input, expected) in cases {
+      let dom = sanitize_fragment(input, &policy).unwrap();
+      let output = fragment_root(&dom).map(|root| inner_html(&root));
+      assert_eq!(
+        output.as_deref(),
+        Some(*expected),
+        "{} preset, input {input:?}",
+        preset.as_str()
+      );
+    }
+  }
+
+  #[test]
+  fn strict_preset() {
+    assert_cleans(
+      SanitizePreset::Strict,
+      &[
+        (JAVASCRIPT_HREF, "a"),
+        (MIXED_CASE_HREF, "a"),
+        (ENTITY_HREF, "a"),
+        (TAB_HREF, "a"),
+        (NEWLINE_HREF, "a"),
+        (HTTPS_HREF, "a"),
+        (SVG_SCRIPT, ""),
+        (SVG_STYLE, "t"),
+        (MATH_STYLE, ""),
+        (EVENT_HANDLERS, "p"),
+        (IMG_ONERROR, ""),
+        (DATA_IMAGE, ""),
+        (DATA_HTML, ""),
+        (STYLE, "s"),
+        (FONT, "f"),
+        (CID_IMAGE, ""),
+      ],
+    );
+  }
+
+  #[test]
+  fn basic_preset() {
+    assert_cleans(
+      SanitizePreset::Basic,
+      &[
+        (JAVASCRIPT_HREF, "<a>a</a>"),
+        (MIXED_CASE_HREF, "<a>a</a>"),
+        (ENTITY_HREF, "<a>a</a>"),
+        (TAB_HREF, "<a>a</a>"),
+        (NEWLINE_HREF, "<a>a</a>"),
+        (HTTPS_HREF, HTTPS_HREF),
+        (SVG_SCRIPT, ""),
+        (SVG_STYLE, "t"),
+        (MATH_STYLE, ""),
+        (EVENT_HANDLERS, "<p>p</p>"),
+        (IMG_ONERROR, ""),
+        (DATA_IMAGE, ""),
+        (DATA_HTML, ""),
+        (STYLE, "<p>s</p>"),
+        (FONT, "f"),
+        (CID_IMAGE, ""),
+      ],
+    );
+  }
+
+  #[test]
+  fn relaxed_preset() {
+    assert_cleans(
+      SanitizePreset::Relaxed,
+      &[
+        (JAVASCRIPT_HREF, "<a>a</a>"),
+        (MIXED_CASE_HREF, "<a>a</a>"),
+        (ENTITY_HREF, "<a>a</a>"),
+        (TAB_HREF, "<a>a</a>"),
+        (NEWLINE_HREF, "<a>a</a>"),
+        (HTTPS_HREF, HTTPS_HREF),
+        (SVG_SCRIPT, ""),
+        (SVG_STYLE, "t"),
+        (MATH_STYLE, ""),
+        (EVENT_HANDLERS, r#"<p title="t">p</p>"#),
+        (IMG_ONERROR, r#"<img src="x.png">"#),
+        (DATA_IMAGE, "<img>"),
+        (DATA_HTML, "<img>"),
+        (STYLE, "<p>s</p>"),
+        (FONT, "f"),
+        (CID_IMAGE, "<img>"),
+      ],
+    );
+  }
+
+  #[test]
+  fn email_preset() {
+    assert_cleans(
+      SanitizePreset::Email,
+      &[
+        (JAVASCRIPT_HREF, "<a>a</a>"),
+        (MIXED_CASE_HREF, "<a>a</a>"),
+        (ENTITY_HREF, "<a>a</a>"),
+        (TAB_HREF, "<a>a</a>"),
+        (NEWLINE_HREF, "<a>a</a>"),
+        (HTTPS_HREF, HTTPS_HREF),
+        (SVG_SCRIPT, ""),
+        (SVG_STYLE, "t"),
+        (MATH_STYLE, ""),
+        (EVENT_HANDLERS, r#"<p title="t">p</p>"#),
+        (IMG_ONERROR, r#"<img src="x.png">"#),
+        (DATA_IMAGE, "<img>"),
+        (DATA_HTML, "<img>"),
+        (STYLE, r#"<p style="color: red">s</p>"#),
+        (FONT, r#"<font color="red" face="Arial">f</font>"#),
+        (CID_IMAGE, CID_IMAGE),
+      ],
+    );
+  }
+}
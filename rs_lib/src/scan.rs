@@ -0,0 +1,215 @@
+//! Attribute-only scanning.
+//!
+//! Link checkers, crawlers and similar tools are usually only interested in a
+//! handful of attribute values (`href`, `src`, ...) and have no use for a DOM
+//! at all. The [`scan_attributes`] function runs the HTML tokenizer on its own,
+//! without any tree building, and reports every start tag carrying at least
+//! one of the requested attributes along with its position in the input.
+//!
+//! Since there is no tree builder, the tokenizer's state is switched by tag
+//! name alone (e.g. the contents of `<script>` and `<textarea>` are treated as
+//! text), which matches the tree builder for all but the most exotic inputs.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use html5ever::local_name;
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::BufferQueue;
+use html5ever::tokenizer::StartTag;
+use html5ever::tokenizer::TagToken;
+use html5ever::tokenizer::Token;
+use html5ever::tokenizer::TokenSink;
+use html5ever::tokenizer::TokenSinkResult;
+use html5ever::tokenizer::Tokenizer;
+use html5ever::tokenizer::TokenizerOpts;
+use html5ever::tokenizer::states::RawKind;
+use serde::Serialize;
+
+use crate::AttributeFilter;
+use crate::DawmError;
+use crate::LineIndex;
+use crate::error::Result;
+
+/// A start tag found by [`scan_attributes`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanMatch {
+  /// The (lowercase) tag name.
+  pub tag_name:   String,
+  /// The byte offset of the tag's `<` in the input. The tokenizer does not
+  /// track offsets itself, so this is located by searching forward from the
+  /// previous start tag, and is `None` if the tag could not be found.
+  pub offset:     Option<usize>,
+  /// The 1-based line on which the tag ends.
+  pub line:       u64,
+  /// The 1-based (byte) column of the tag's `<`, if its offset is known.
+  pub column:     Option<u64>,
+  /// The matching attributes, in source order.
+  pub attributes: Vec<ScanAttribute>,
+}
+
+/// A single attribute of a [`ScanMatch`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScanAttribute {
+  pub name:  String,
+  pub value: String,
+}
+
+struct ScanSink<'a> {
+  input:   &'a str,
+  filter:  &'a AttributeFilter,
+  lines:   LineIndex,
+  cursor:  RefCell<usize>,
+  matches: RefCell<Vec<ScanMatch>>,
+  error:   RefCell<Option<DawmError>>,
+}
+
+impl TokenSink for ScanSink<'_> {
+  type Handle = ();
+
+  fn process_token(&self, token: Token, line: u64) -> TokenSinkResult<()> {
+    let TagToken(tag) = token else {
+      return TokenSinkResult::Continue;
+    };
+    if tag.kind != StartTag || self.error.borrow().is_some() {
+      return TokenSinkResult::Continue;
+    }
+
+    // every start tag advances the cursor, so that tags without matching
+    // attributes are not mistaken for later ones with the same name
+    let mut cursor = self.cursor.borrow_mut();
+    let offset = locate_tag(self.input, *cursor, &tag.name);
+    if let Some(offset) = offset {
+      *cursor = offset + 1;
+    }
+
+    let mut attributes = Vec::new();
+    for a in &tag.attrs {
+      match self.filter.accepts(&a.name.local, &a.value, &tag.name) {
+        Ok(true) => attributes.push(ScanAttribute {
+          name:  a.name.local.to_string(),
+          value: a.value.to_string(),
+        }),
+        Ok(false) => {}
+        Err(error) => {
+          *self.error.borrow_mut() = Some(error);
+          return TokenSinkResult::Continue;
+        }
+      }
+    }
+    if !attributes.is_empty() {
+      self.matches.borrow_mut().push(ScanMatch {
+        tag_name: tag.name.to_string(),
+        offset,
+        line,
+        column: offset.map(|offset| self.lines.locate(offset).1),
+        attributes,
+      });
+    }
+
+    let result = match tag.name {
+      local_name!("script") => TokenSinkResult::RawData(RawKind::ScriptData),
+      local_name!("style")
+      | local_name!("xmp")
+      | local_name!("iframe")
+      | local_name!("noembed")
+      | local_name!("noframes") => TokenSinkResult::RawData(RawKind::Rawtext),
+      local_name!("title") | local_name!("textarea") => {
+        TokenSinkResult::RawData(RawKind::Rcdata)
+      }
+      local_name!("plaintext") => TokenSinkResult::Plaintext,
+      _ => TokenSinkResult::Continue,
+    };
+    // the contents of raw text elements are not markup, so skip past them
+    if let TokenSinkResult::RawData(_) = result
+      && let Some(end) = locate_end_tag(self.input, *cursor, &tag.name)
+    {
+      *cursor = end;
+    }
+    result
+  }
+}
+
+// Finds the next `<name` (case-insensitively, followed by whitespace, `/`,
+// `>` or the end of input) at or after `from`, skipping over comments and
+// other markup declarations.
+fn locate_tag(input: &str, from: usize, name: &str) -> Option<usize> {
+  let bytes = input.as_bytes();
+  let name = name.as_bytes();
+  let mut pos = from;
+  while let Some(i) = bytes.get(pos..)?.iter().position(|&b| b == b'<') {
+    let start = pos + i;
+    let rest = &bytes[start + 1..];
+    if rest.starts_with(b"!--") {
+      pos = find(bytes, start + 4, b"-->").map_or(bytes.len(), |end| end + 3);
+      continue;
+    }
+    if rest.starts_with(b"!") || rest.starts_with(b"?") {
+      pos = find(bytes, start + 1, b">").map_or(bytes.len(), |end| end + 1);
+      continue;
+    }
+    if starts_with_tag_name(rest, name) {
+      return Some(start);
+    }
+    pos = start + 1;
+  }
+  None
+}
+
+// Finds the `</name` which closes a raw text element opened at `from`.
+fn locate_end_tag(input: &str, from: usize, name: &str) -> Option<usize> {
+  let bytes = input.as_bytes();
+  let mut pos = from;
+  while let Some(start) = find(bytes, pos, b"</") {
+    if starts_with_tag_name(&bytes[start + 2..], name.as_bytes()) {
+      return Some(start);
+    }
+    pos = start + 2;
+  }
+  None
+}
+
+fn starts_with_tag_name(rest: &[u8], name: &[u8]) -> bool {
+  rest.len() >= name.len()
+    && rest[..name.len()].eq_ignore_ascii_case(name)
+    && rest
+      .get(name.len())
+      .is_none_or(|&b| b.is_ascii_whitespace() || b == b'/' || b == b'>')
+}
+
+fn find(bytes: &[u8], from: usize, needle: &[u8]) -> Option<usize> {
+  bytes
+    .get(from..)?
+    .windows(needle.len())
+    .position(|w| w == needle)
+    .map(|i| from + i)
+}
+
+/// Tokenizes `input` as HTML and returns every start tag which has at least
+/// one attribute accepted by `filter`, with only the accepted attributes.
+/// Fails if the predicate of `filter` throws.
+pub fn scan_attributes(
+  input: &str,
+  filter: &AttributeFilter,
+) -> Result<Vec<ScanMatch>> {
+  let sink = ScanSink {
+    input,
+    filter,
+    lines: LineIndex::new(input),
+    cursor: RefCell::new(0),
+    matches: RefCell::new(Vec::new()),
+    error: RefCell::new(None),
+  };
+  let tokenizer = Tokenizer::new(sink, TokenizerOpts::default());
+  let queue = BufferQueue::default();
+  queue.push_back(StrTendril::from_slice(input));
+  let _ = tokenizer.feed(&queue);
+  tokenizer.end();
+  match tokenizer.sink.error.take() {
+    Some(error) => Err(error),
+    None => Ok(tokenizer.sink.matches.take()),
+  }
+}
@@ -0,0 +1,202 @@
+//! Prefixing ids and class names.
+//!
+//! A fragment embedded into a page shares the ids and class names of the
+//! page: its `id="title"` may clash with one of the page, and the page's
+//! styles for `.card` apply to its cards. [`scope_names`] prefixes every id
+//! and class name of a fragment, and every reference to one of its ids, so
+//! that the fragment keeps working on its own names:
+//!
+//! - the `id` attribute, and each of the tokens of `class`;
+//! - the references to a single id in `for`, `form`, `list`, `anchor`,
+//!   `popovertarget`, `commandfor`, `aria-activedescendant`,
+//!   `aria-details` and `aria-errormessage`;
+//! - the tokens of the lists of ids in `headers`, `aria-controls`,
+//!   `aria-describedby`, `aria-flowto`, `aria-labelledby` and `aria-owns`;
+//! - the fragment links of `href` and `xlink:href` (`href="#title"`), like
+//!   those of anchors and SVG `<use>` elements;
+//! - the `url(#id)` references of other attributes, like the `fill` and
+//!   `clip-path` of SVG, including those in `style` attributes.
+//!
+//! References to ids that the fragment does not define are left as they
+//! are, as they refer to the page it is embedded into. The contents of
+//! templates are prefixed like the rest, but the selectors of stylesheets
+//! and scripts are not.
+
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::ns;
+
+use crate::Handle;
+use crate::NodeData;
+
+// The attributes referencing a single id.
+const ID_REFERENCES: &[&str] = &[
+  "anchor",
+  "aria-activedescendant",
+  "aria-details",
+  "aria-errormessage",
+  "commandfor",
+  "for",
+  "form",
+  "list",
+  "popovertarget",
+];
+
+// The attributes referencing a space-separated list of ids.
+const ID_LIST_REFERENCES: &[&str] = &[
+  "aria-controls",
+  "aria-describedby",
+  "aria-flowto",
+  "aria-labelledby",
+  "aria-owns",
+  "headers",
+];
+
+/// Prefixes the ids and class names below `root` (and the references to
+/// those ids) with `prefix`, returning how many attributes were changed.
+pub fn scope_names(root: &Handle, prefix: &str) -> usize {
+  if prefix.is_empty() {
+    return 0;
+  }
+  let elements = elements(root);
+  let ids: BTreeSet<String> = elements
+    .iter()
+    .filter_map(|element| {
+      let NodeData::Element { attrs, .. } = &element.data else {
+        return None;
+      };
+      let attrs = attrs.borrow();
+      let id = attrs
+        .iter()
+        .find(|attr| attr.name.ns == ns!() && &*attr.name.local == "id")?;
+      (!id.value.is_empty()).then(|| String::from(&*id.value))
+    })
+    .collect();
+  let scope = |id: &str| match ids.contains(id) {
+    true => Some(format!("{prefix}{id}")),
+    false => None,
+  };
+
+  let mut changed = 0;
+  for element in &elements {
+    let NodeData::Element { attrs, .. } = &element.data else {
+      continue;
+    };
+    for attr in attrs.borrow_mut().iter_mut() {
+      let name = &*attr.name.local;
+      let is_href = &*attr.name.local == "href"
+        && (attr.name.ns == ns!() || attr.name.ns == ns!(xlink));
+      let value = &*attr.value;
+      let scoped = if attr.name.ns != ns!() && !is_href {
+        None
+      } else if name == "id" {
+        scope(value)
+      } else if name == "class" {
+        let classes: Vec<String> = value
+          .split_ascii_whitespace()
+          .map(|class| format!("{prefix}{class}"))
+          .collect();
+        (!classes.is_empty()).then(|| classes.join(" "))
+      } else if ID_REFERENCES.contains(&name) {
+        scope(value)
+      } else if ID_LIST_REFERENCES.contains(&name) {
+        scope_tokens(value, &scope)
+      } else if is_href {
+        value
+          .strip_prefix('#')
+          .and_then(scope)
+          .map(|id| format!("#{id}"))
+      } else {
+        scope_urls(value, &scope)
+      };
+      if let Some(scoped) = scoped
+        && *scoped != *attr.value
+      {
+        attr.value = scoped.into();
+        changed += 1;
+      }
+    }
+  }
+  changed
+}
+
+// Scopes the ids in a space-separated list, or returns `None` if none of
+// them is defined.
+fn scope_tokens(
+  value: &str,
+  scope: &impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+  let mut any = false;
+  let tokens: Vec<String> = value
+    .split_ascii_whitespace()
+    .map(|token| match scope(token) {
+      Some(scoped) => {
+        any = true;
+        scoped
+      }
+      None => String::from(token),
+    })
+    .collect();
+  any.then(|| tokens.join(" "))
+}
+
+// Scopes the ids of the `url(#id)` references in a value, quoted or not,
+// or returns `None` if it has none to scope.
+fn scope_urls(
+  value: &str,
+  scope: &impl Fn(&str) -> Option<String>,
+) -> Option<String> {
+  let mut scoped = String::with_capacity(value.len());
+  let mut rest = value;
+  let mut any = false;
+  while let Some(start) = rest.find("url(") {
+    let (head, tail) = rest.split_at(start + "url(".len());
+    scoped.push_str(head);
+    let quote = tail
+      .trim_start()
+      .chars()
+      .next()
+      .filter(|c| matches!(c, '"' | '\''));
+    let offset = tail.len() - tail.trim_start().len();
+    let reference = &tail[offset + quote.map_or(0, char::len_utf8)..];
+    let end = reference
+      .find(|c: char| Some(c) == quote || c == ')' || c.is_ascii_whitespace())
+      .unwrap_or(reference.len());
+    let id = reference[..end].strip_prefix('#').and_then(scope);
+    match id {
+      Some(id) => {
+        scoped.push_str(&tail[..tail.len() - reference.len()]);
+        scoped.push('#');
+        scoped.push_str(&id);
+        rest = &reference[end..];
+        any = true;
+      }
+      None => rest = tail,
+    }
+  }
+  scoped.push_str(rest);
+  any.then_some(scoped)
+}
+
+// The elements below `root`, including those in the contents of templates.
+fn elements(root: &Handle) -> Vec<Handle> {
+  let mut elements = Vec::new();
+  let mut stack: Vec<Handle> =
+    root.children.borrow().iter().rev().cloned().collect();
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      template_contents, ..
+    } = &node.data
+    {
+      if let Some(contents) = template_contents {
+        stack.extend(contents.children.borrow().iter().rev().cloned());
+      }
+      elements.push(node.clone());
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  elements
+}
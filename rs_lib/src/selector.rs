@@ -0,0 +1,907 @@
+//! A CSS selector engine over the parsed tree.
+//!
+//! Selectors are parsed once into a [`SelectorList`], which is then matched
+//! against elements, right to left like browsers do. The supported syntax
+//! covers what is used to pick out elements in practice:
+//!
+//! - type (`p`), universal (`*`), id (`#main`) and class (`.ad`) selectors;
+//! - attribute selectors (`[href]`, `[rel=nofollow]`, `[class~=a]`,
+//!   `[lang|=en]`, `[src^=https]`, `[src$=".gif"]`, `[id*=ad]`), with the `i`
+//!   and `s` flags;
+//! - the descendant (` `), child (`>`), next sibling (`+`) and subsequent
+//!   sibling (`~`) combinators, and lists of selectors (`a, b`);
+//! - the `:not()`, `:is()` and `:where()` pseudo-classes, taking selector
+//!   lists, and the structural ones: `:root`, `:empty`, `:first-child`,
+//!   `:last-child`, `:only-child`, `:first-of-type`, `:last-of-type`,
+//!   `:only-of-type`, `:nth-child()`, `:nth-last-child()`, `:nth-of-type()` and
+//!   `:nth-last-of-type()`.
+//!
+//! Anything else, including namespace prefixes and pseudo-elements, is a
+//! syntax error, like an invalid selector is for `querySelector`. Type
+//! selectors and attribute names match HTML elements ignoring ASCII case,
+//! and other elements exactly. Like in the DOM, the contents of templates
+//! are not searched. For documents in quirks mode, id and class selectors
+//! ignore ASCII case as well, once the list is [told about
+//! it](SelectorList::with_quirks_mode) or [matched in
+//! it](SelectorList::matches_in).
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use core::str::CharIndices;
+
+use html5ever::QualName;
+use html5ever::ns;
+
+use crate::DawmError;
+use crate::Handle;
+use crate::NodeData;
+use crate::QuirksMode;
+use crate::dom::mutation::detach;
+use crate::dom::parent;
+
+/// A parsed list of selectors, matching the elements any of them match.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectorList {
+  selectors: Vec<Selector>,
+  // whether ids and class names are matched ignoring ASCII case
+  quirks:    bool,
+}
+
+// A complex selector: compound selectors joined by combinators, where
+// `combinators[i]` sits between `compounds[i]` and `compounds[i + 1]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Selector {
+  compounds:   Vec<Vec<Simple>>,
+  combinators: Vec<Combinator>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+  Descendant,
+  Child,
+  NextSibling,
+  SubsequentSibling,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Simple {
+  Type(String),
+  Id(String),
+  Class(String),
+  Attribute {
+    name:  String,
+    value: Option<AttributeValue>,
+  },
+  Not(SelectorList),
+  Is(SelectorList),
+  // like `Is`, but without specificity
+  Where(SelectorList),
+  Root,
+  Empty,
+  Only {
+    of_type: bool,
+  },
+  Nth {
+    nth:      Nth,
+    of_type:  bool,
+    from_end: bool,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct AttributeValue {
+  operator:         Operator,
+  value:            String,
+  case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+  Equals,
+  Includes,
+  DashMatch,
+  Prefix,
+  Suffix,
+  Substring,
+}
+
+// The `an+b` of `:nth-child()` and friends, matching the elements at the
+// (1-based) positions `an + b` for some `n >= 0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Nth {
+  a: i32,
+  b: i32,
+}
+
+impl SelectorList {
+  /// Parses a list of selectors, e.g. `.ad, aside > [data-tracker]`.
+  pub fn parse(input: &str) -> Result<Self, DawmError> {
+    let mut parser = Parser {
+      input,
+      chars: input.char_indices().peekable(),
+    };
+    let list = parser.selector_list()?;
+    match parser.peek() {
+      None => Ok(list),
+      Some(c) => Err(parser.error(&format!("unexpected {c:?}"))),
+    }
+  }
+
+  /// Makes the selectors match like they do in a document of the quirks mode
+  /// `mode`: in [`Quirks`](QuirksMode::Quirks) mode, id and class selectors
+  /// ignore ASCII case, and in the other modes they do not.
+  pub fn with_quirks_mode(mut self, mode: QuirksMode) -> Self {
+    self.quirks = mode.is_quirks();
+    self
+  }
+
+  /// Whether `node` is an element matched by any of the selectors.
+  pub fn matches(&self, node: &Handle) -> bool {
+    self.matches_element(node)
+  }
+
+  /// Whether `node` is an element matched by any of the selectors in a
+  /// document of the quirks mode `mode`, whichever mode the list was made
+  /// for.
+  pub fn matches_in(&self, node: &Handle, mode: QuirksMode) -> bool {
+    self.matches_element_in(node, mode)
+  }
+
+  // Whether `node` is an element matched by any of the selectors, in any of
+  // the trees the engine can match against.
+  pub(crate) fn matches_element<E: Element>(&self, node: &E) -> bool {
+    self.matches_quirky(node, self.quirks)
+  }
+
+  // Like `matches_element`, in a document of the quirks mode `mode`.
+  pub(crate) fn matches_element_in<E: Element>(
+    &self,
+    node: &E,
+    mode: QuirksMode,
+  ) -> bool {
+    self.matches_quirky(node, mode.is_quirks())
+  }
+
+  // Whether `node` is an element matched by any of the selectors, with ids
+  // and class names matched ignoring ASCII case if `quirks` is set.
+  fn matches_quirky<E: Element>(&self, node: &E, quirks: bool) -> bool {
+    node.name().is_some()
+      && self
+        .selectors
+        .iter()
+        .any(|selector| selector.matches_last(node, quirks))
+  }
+
+  // Whether matching needs to know what comes after an element (for
+  // `:empty`, `:only-child`, `:last-child`, `:nth-last-child()` and their
+  // `-of-type` versions), which is not known yet while streaming.
+  #[cfg(feature = "wasm")]
+  pub(crate) fn needs_lookahead(&self) -> bool {
+    self.any_simple(&|simple| match simple {
+      Simple::Empty | Simple::Only { .. } => true,
+      Simple::Nth { from_end, .. } => *from_end,
+      _ => false,
+    })
+  }
+
+  // Whether matching looks at the earlier siblings of an element, through
+  // a sibling combinator or a structural pseudo-class.
+  #[cfg(feature = "wasm")]
+  pub(crate) fn uses_siblings(&self) -> bool {
+    self.selectors.iter().any(|selector| {
+      selector.combinators.iter().any(|combinator| {
+        matches!(
+          combinator,
+          Combinator::NextSibling | Combinator::SubsequentSibling
+        )
+      })
+    }) || self.any_simple(&|simple| {
+      matches!(simple, Simple::Only { .. } | Simple::Nth { .. })
+    }) || self.any_list(&SelectorList::uses_siblings)
+  }
+
+  // Whether any simple selector in the list, including the ones nested in
+  // `:not()` and `:is()`, satisfies `predicate`.
+  #[cfg(feature = "wasm")]
+  fn any_simple(&self, predicate: &impl Fn(&Simple) -> bool) -> bool {
+    self.simples().any(predicate)
+      || self.any_list(&|list| list.any_simple(predicate))
+  }
+
+  // Whether any list nested in `:not()` and `:is()` satisfies `predicate`.
+  #[cfg(feature = "wasm")]
+  fn any_list(&self, predicate: &impl Fn(&SelectorList) -> bool) -> bool {
+    self.simples().any(|simple| match simple {
+      Simple::Not(list) | Simple::Is(list) | Simple::Where(list) => {
+        predicate(list)
+      }
+      _ => false,
+    })
+  }
+
+  // The specificity of the most specific of the selectors matching `node`,
+  // as the numbers of id, class-like and type selectors in it, or `None` if
+  // none of them match.
+  pub(crate) fn specificity_for(
+    &self,
+    node: &Handle,
+  ) -> Option<(u32, u32, u32)> {
+    self
+      .selectors
+      .iter()
+      .filter(|selector| {
+        matches!(node.data, NodeData::Element { .. })
+          && selector.matches_last(node, self.quirks)
+      })
+      .map(Selector::specificity)
+      .max()
+  }
+
+  // The specificity of the most specific of the selectors, which is that of
+  // `:is()` and `:not()` taking them.
+  fn max_specificity(&self) -> (u32, u32, u32) {
+    let specificities = self.selectors.iter().map(Selector::specificity);
+    specificities.max().unwrap_or_default()
+  }
+
+  #[cfg(feature = "wasm")]
+  fn simples(&self) -> impl Iterator<Item = &Simple> {
+    self
+      .selectors
+      .iter()
+      .flat_map(|selector| selector.compounds.iter().flatten())
+  }
+}
+
+/// The descendants of `root` matched by `selectors`, in document order.
+pub fn select_all(root: &Handle, selectors: &SelectorList) -> Vec<Handle> {
+  crate::extract::descendants(root)
+    .filter(|node| selectors.matches(node))
+    .collect()
+}
+
+/// The first descendant of `root` matched by `selectors`, if any.
+pub fn select(root: &Handle, selectors: &SelectorList) -> Option<Handle> {
+  crate::extract::descendants(root).find(|node| selectors.matches(node))
+}
+
+/// Removes the descendants of `root` matched by `selectors`, returning how
+/// many were removed. The descendants of a removed element go with it, and
+/// are not counted even if they match as well.
+pub fn remove_all(root: &Handle, selectors: &SelectorList) -> usize {
+  let mut removed = 0;
+  let mut stack: Vec<Handle> =
+    root.children.borrow().iter().rev().cloned().collect();
+  while let Some(node) = stack.pop() {
+    if selectors.matches(&node) {
+      detach(&node);
+      removed += 1;
+    } else {
+      stack.extend(node.children.borrow().iter().rev().cloned());
+    }
+  }
+  removed
+}
+
+impl Selector {
+  fn specificity(&self) -> (u32, u32, u32) {
+    self
+      .compounds
+      .iter()
+      .flatten()
+      .map(Simple::specificity)
+      .fold((0, 0, 0), |(a, b, c), (d, e, f)| (a + d, b + e, c + f))
+  }
+
+  // Whether `element` is matched by the whole selector.
+  fn matches_last<E: Element>(&self, element: &E, quirks: bool) -> bool {
+    self.matches_at(self.compounds.len() - 1, element, quirks)
+  }
+
+  // Whether `element` is matched by the compounds up to `index`, with the
+  // one at `index` matching `element` itself.
+  fn matches_at<E: Element>(
+    &self,
+    index: usize,
+    element: &E,
+    quirks: bool,
+  ) -> bool {
+    if !self.compounds[index]
+      .iter()
+      .all(|simple| simple.matches(element, quirks))
+    {
+      return false;
+    }
+    let Some(next) = index.checked_sub(1) else {
+      return true;
+    };
+    match self.combinators[next] {
+      Combinator::Descendant => {
+        let mut ancestor = element.parent_element();
+        while let Some(current) = ancestor {
+          if self.matches_at(next, &current, quirks) {
+            return true;
+          }
+          ancestor = current.parent_element();
+        }
+        false
+      }
+      Combinator::Child => element
+        .parent_element()
+        .is_some_and(|parent| self.matches_at(next, &parent, quirks)),
+      Combinator::NextSibling => element
+        .previous_elements()
+        .last()
+        .is_some_and(|sibling| self.matches_at(next, sibling, quirks)),
+      Combinator::SubsequentSibling => element
+        .previous_elements()
+        .iter()
+        .any(|sibling| self.matches_at(next, sibling, quirks)),
+    }
+  }
+}
+
+impl Simple {
+  fn specificity(&self) -> (u32, u32, u32) {
+    match self {
+      Simple::Id(_) => (1, 0, 0),
+      Simple::Type(_) => (0, 0, 1),
+      Simple::Not(list) | Simple::Is(list) => list.max_specificity(),
+      Simple::Where(_) => (0, 0, 0),
+      _ => (0, 1, 0),
+    }
+  }
+
+  fn matches<E: Element>(&self, element: &E, quirks: bool) -> bool {
+    let Some(name) = element.name() else {
+      return false;
+    };
+    let is_html = name.ns == ns!(html);
+    match self {
+      Simple::Type(local) => match is_html {
+        true => name.local.as_ref().eq_ignore_ascii_case(local),
+        false => *name.local == **local,
+      },
+      Simple::Id(id) => element.any_attribute(&mut |attr, value| {
+        is_unqualified(attr, "id", false) && same_name(value, id, quirks)
+      }),
+      Simple::Class(class) => element.any_attribute(&mut |attr, value| {
+        is_unqualified(attr, "class", false)
+          && value
+            .split_ascii_whitespace()
+            .any(|token| same_name(token, class, quirks))
+      }),
+      Simple::Attribute {
+        name,
+        value: expected,
+      } => element.any_attribute(&mut |attr, value| {
+        is_unqualified(attr, name, is_html)
+          && expected
+            .as_ref()
+            .is_none_or(|expected| expected.matches(value))
+      }),
+      Simple::Not(list) => !list.matches_quirky(element, quirks),
+      Simple::Is(list) | Simple::Where(list) => {
+        list.matches_quirky(element, quirks)
+      }
+      Simple::Root => element.is_root(),
+      Simple::Empty => element.is_empty(),
+      Simple::Only { of_type } => {
+        element.position(*of_type, false) == Some(0)
+          && element.position(*of_type, true) == Some(0)
+      }
+      Simple::Nth {
+        nth,
+        of_type,
+        from_end,
+      } => element
+        .position(*of_type, *from_end)
+        .is_some_and(|index| nth.matches(index as i64 + 1)),
+    }
+  }
+}
+
+impl AttributeValue {
+  fn matches(&self, actual: &str) -> bool {
+    let (actual, expected) = match self.case_insensitive {
+      true => (actual.to_ascii_lowercase(), self.value.to_ascii_lowercase()),
+      false => (actual.into(), self.value.clone()),
+    };
+    match self.operator {
+      Operator::Equals => actual == expected,
+      Operator::Includes => {
+        !expected.is_empty()
+          && !expected.contains(|c: char| c.is_ascii_whitespace())
+          && actual
+            .split_ascii_whitespace()
+            .any(|token| token == expected)
+      }
+      Operator::DashMatch => {
+        actual == expected
+          || actual
+            .strip_prefix(&*expected)
+            .is_some_and(|rest| rest.starts_with('-'))
+      }
+      Operator::Prefix => {
+        !expected.is_empty() && actual.starts_with(&*expected)
+      }
+      Operator::Suffix => !expected.is_empty() && actual.ends_with(&*expected),
+      Operator::Substring => {
+        !expected.is_empty() && actual.contains(&*expected)
+      }
+    }
+  }
+}
+
+impl Nth {
+  fn matches(&self, position: i64) -> bool {
+    let (a, b) = (i64::from(self.a), i64::from(self.b));
+    match a {
+      0 => position == b,
+      _ => (position - b) % a == 0 && (position - b) / a >= 0,
+    }
+  }
+
+  // Parses the `an+b` microsyntax, along with `odd` and `even`.
+  fn parse(input: &str) -> Option<Self> {
+    let input: String = input
+      .chars()
+      .filter(|c| !c.is_ascii_whitespace())
+      .collect::<String>()
+      .to_ascii_lowercase();
+    match &*input {
+      "odd" => return Some(Nth { a: 2, b: 1 }),
+      "even" => return Some(Nth { a: 2, b: 0 }),
+      _ => {}
+    }
+    let integer = |digits: &str| -> Option<i32> {
+      let unsigned = digits.strip_prefix(['+', '-']).unwrap_or(digits);
+      if unsigned.is_empty() || !unsigned.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+      }
+      digits.parse().ok()
+    };
+    let Some((a, b)) = input.split_once('n') else {
+      return Some(Nth {
+        a: 0,
+        b: integer(&input)?,
+      });
+    };
+    let a = match a {
+      "" | "+" => 1,
+      "-" => -1,
+      a => integer(a)?,
+    };
+    let b = match b {
+      "" => 0,
+      b if b.starts_with(['+', '-']) => integer(b)?,
+      _ => return None,
+    };
+    Some(Nth { a, b })
+  }
+}
+
+// Whether an id or class name is the one of a selector, ignoring ASCII case
+// in quirks mode.
+fn same_name(actual: &str, expected: &str, quirks: bool) -> bool {
+  match quirks {
+    true => actual.eq_ignore_ascii_case(expected),
+    false => actual == expected,
+  }
+}
+
+// Whether the attribute named `attr` has no namespace and is called
+// `name`, ignoring ASCII case if `ignore_case` is set.
+fn is_unqualified(attr: &QualName, name: &str, ignore_case: bool) -> bool {
+  attr.ns == ns!()
+    && match ignore_case {
+      true => attr.local.as_ref().eq_ignore_ascii_case(name),
+      false => *attr.local == *name,
+    }
+}
+
+// The nodes of a tree the engine can match against, as seen from one of
+// them.
+pub(crate) trait Element: Sized {
+  // The name of the node, if it is an element.
+  fn name(&self) -> Option<&QualName>;
+
+  // Whether any of the attributes, by name and value, satisfies `predicate`.
+  fn any_attribute(
+    &self,
+    predicate: &mut dyn FnMut(&QualName, &str) -> bool,
+  ) -> bool;
+
+  // The parent of the node, if it is an element.
+  fn parent_element(&self) -> Option<Self>;
+
+  // Whether the parent of the node is a document.
+  fn is_root(&self) -> bool;
+
+  // Whether the node has no element children and no text.
+  fn is_empty(&self) -> bool;
+
+  // The (0-based) position of the element among its element siblings, or
+  // the ones of the same type, counted from the end if `from_end` is set.
+  fn position(&self, of_type: bool, from_end: bool) -> Option<usize>;
+
+  // The element siblings before the node, in order.
+  fn previous_elements(&self) -> Vec<Self>;
+}
+
+impl Element for Handle {
+  fn name(&self) -> Option<&QualName> {
+    match &self.data {
+      NodeData::Element { name, .. } => Some(name),
+      _ => None,
+    }
+  }
+
+  fn any_attribute(
+    &self,
+    predicate: &mut dyn FnMut(&QualName, &str) -> bool,
+  ) -> bool {
+    let NodeData::Element { attrs, .. } = &self.data else {
+      return false;
+    };
+    attrs
+      .borrow()
+      .iter()
+      .any(|attr| predicate(&attr.name, &attr.value))
+  }
+
+  fn parent_element(&self) -> Option<Self> {
+    parent(self)
+      .filter(|parent| matches!(parent.data, NodeData::Element { .. }))
+  }
+
+  fn is_root(&self) -> bool {
+    parent(self).is_some_and(|parent| matches!(parent.data, NodeData::Document))
+  }
+
+  fn is_empty(&self) -> bool {
+    self
+      .children
+      .borrow()
+      .iter()
+      .all(|child| match &child.data {
+        NodeData::Element { .. } => false,
+        NodeData::Text { contents } => contents.borrow().is_empty(),
+        _ => true,
+      })
+  }
+
+  fn position(&self, of_type: bool, from_end: bool) -> Option<usize> {
+    let name = Element::name(self)?;
+    let parent = parent(self)?;
+    let siblings = parent.children.borrow();
+    let same_kind = |sibling: &&Handle| match &sibling.data {
+      NodeData::Element { name: other, .. } => {
+        !of_type || (other.ns == name.ns && other.local == name.local)
+      }
+      _ => false,
+    };
+    let mut siblings = siblings.iter().filter(same_kind);
+    let is_element = |sibling: &Handle| Rc::ptr_eq(sibling, self);
+    match from_end {
+      true => siblings.rev().position(is_element),
+      false => siblings.position(is_element),
+    }
+  }
+
+  fn previous_elements(&self) -> Vec<Self> {
+    let Some(parent) = parent(self) else {
+      return Vec::new();
+    };
+    let children = parent.children.borrow();
+    children
+      .iter()
+      .take_while(|child| !Rc::ptr_eq(child, self))
+      .filter(|child| matches!(child.data, NodeData::Element { .. }))
+      .cloned()
+      .collect()
+  }
+}
+
+struct Parser<'a> {
+  input: &'a str,
+  chars: Peekable<CharIndices<'a>>,
+}
+
+impl Parser<'_> {
+  fn selector_list(&mut self) -> Result<SelectorList, DawmError> {
+    let mut selectors = Vec::new();
+    loop {
+      self.skip_whitespace();
+      selectors.push(self.selector()?);
+      self.skip_whitespace();
+      if !self.eat(',') {
+        return Ok(SelectorList {
+          selectors,
+          quirks: false,
+        });
+      }
+    }
+  }
+
+  fn selector(&mut self) -> Result<Selector, DawmError> {
+    let mut compounds = Vec::from([self.compound()?]);
+    let mut combinators = Vec::new();
+    loop {
+      let whitespace = self.skip_whitespace();
+      let combinator = match self.peek() {
+        None | Some(',' | ')') => break,
+        Some('>') => Combinator::Child,
+        Some('+') => Combinator::NextSibling,
+        Some('~') => Combinator::SubsequentSibling,
+        Some(_) if whitespace => Combinator::Descendant,
+        Some(c) => return Err(self.error(&format!("unexpected {c:?}"))),
+      };
+      if combinator != Combinator::Descendant {
+        self.chars.next();
+        self.skip_whitespace();
+      }
+      combinators.push(combinator);
+      compounds.push(self.compound()?);
+    }
+    Ok(Selector {
+      compounds,
+      combinators,
+    })
+  }
+
+  fn compound(&mut self) -> Result<Vec<Simple>, DawmError> {
+    let mut compound = Vec::new();
+    let mut has_type = false;
+    match self.peek() {
+      Some('*') => {
+        self.chars.next();
+        has_type = true;
+      }
+      Some(c) if starts_ident(c) => {
+        compound.push(Simple::Type(self.ident()?));
+        has_type = true;
+      }
+      _ => {}
+    }
+    if self.peek() == Some('|') {
+      return Err(self.error("namespace prefixes are not supported"));
+    }
+    loop {
+      let simple = match self.peek() {
+        Some('#') => {
+          self.chars.next();
+          Simple::Id(self.ident()?)
+        }
+        Some('.') => {
+          self.chars.next();
+          Simple::Class(self.ident()?)
+        }
+        Some('[') => {
+          self.chars.next();
+          self.attribute()?
+        }
+        Some(':') => {
+          self.chars.next();
+          self.pseudo_class()?
+        }
+        _ => break,
+      };
+      compound.push(simple);
+    }
+    if compound.is_empty() && !has_type {
+      return Err(match self.peek() {
+        Some(c) => self.error(&format!("unexpected {c:?}")),
+        None => self.error("expected a selector"),
+      });
+    }
+    Ok(compound)
+  }
+
+  fn attribute(&mut self) -> Result<Simple, DawmError> {
+    self.skip_whitespace();
+    let name = self.ident()?;
+    self.skip_whitespace();
+    if self.eat(']') {
+      return Ok(Simple::Attribute { name, value: None });
+    }
+    let operator = match self.chars.next().map(|(_, c)| c) {
+      Some('=') => Operator::Equals,
+      Some(c @ ('~' | '|' | '^' | '$' | '*')) if self.eat('=') => match c {
+        '~' => Operator::Includes,
+        '|' => Operator::DashMatch,
+        '^' => Operator::Prefix,
+        '$' => Operator::Suffix,
+        _ => Operator::Substring,
+      },
+      _ => return Err(self.error("expected an attribute operator")),
+    };
+    self.skip_whitespace();
+    let value = match self.peek() {
+      Some(quote @ ('"' | '\'')) => {
+        self.chars.next();
+        self.string(quote)?
+      }
+      _ => self.ident()?,
+    };
+    self.skip_whitespace();
+    let mut case_insensitive = false;
+    if let Some(flag @ ('i' | 'I' | 's' | 'S')) = self.peek() {
+      self.chars.next();
+      case_insensitive = flag.eq_ignore_ascii_case(&'i');
+      self.skip_whitespace();
+    }
+    if !self.eat(']') {
+      return Err(self.error("expected \"]\""));
+    }
+    Ok(Simple::Attribute {
+      name,
+      value: Some(AttributeValue {
+        operator,
+        value,
+        case_insensitive,
+      }),
+    })
+  }
+
+  fn pseudo_class(&mut self) -> Result<Simple, DawmError> {
+    if self.peek() == Some(':') {
+      return Err(self.error("pseudo-elements are not supported"));
+    }
+    let name = self.ident()?.to_ascii_lowercase();
+    if !self.eat('(') {
+      let nth = |of_type, from_end| Simple::Nth {
+        nth: Nth { a: 0, b: 1 },
+        of_type,
+        from_end,
+      };
+      return Ok(match &*name {
+        "root" => Simple::Root,
+        "empty" => Simple::Empty,
+        "first-child" => nth(false, false),
+        "last-child" => nth(false, true),
+        "first-of-type" => nth(true, false),
+        "last-of-type" => nth(true, true),
+        "only-child" => Simple::Only { of_type: false },
+        "only-of-type" => Simple::Only { of_type: true },
+        _ => {
+          return Err(self.error(&format!("unknown pseudo-class :{name}")));
+        }
+      });
+    }
+    let simple = match &*name {
+      "not" => Simple::Not(self.selector_list()?),
+      "is" => Simple::Is(self.selector_list()?),
+      "where" => Simple::Where(self.selector_list()?),
+      "nth-child" | "nth-last-child" | "nth-of-type" | "nth-last-of-type" => {
+        let start = self.offset();
+        while self.peek().is_some_and(|c| c != ')') {
+          self.chars.next();
+        }
+        let argument = &self.input[start..self.offset()];
+        let nth = Nth::parse(argument).ok_or_else(|| {
+          self.error(&format!("invalid argument of :{name}(): {argument:?}"))
+        })?;
+        Simple::Nth {
+          nth,
+          of_type: name.ends_with("of-type"),
+          from_end: name.starts_with("nth-last"),
+        }
+      }
+      _ => return Err(self.error(&format!("unknown pseudo-class :{name}()"))),
+    };
+    self.skip_whitespace();
+    if !self.eat(')') {
+      return Err(self.error("expected \")\""));
+    }
+    Ok(simple)
+  }
+
+  // Parses an identifier, resolving escapes.
+  fn ident(&mut self) -> Result<String, DawmError> {
+    let start = self.offset();
+    let mut ident = String::new();
+    while let Some(c) = self.peek() {
+      match c {
+        '\\' => {
+          self.chars.next();
+          ident.push(self.escape()?);
+        }
+        c if is_name_char(c) => {
+          self.chars.next();
+          ident.push(c);
+        }
+        _ => break,
+      }
+    }
+    // an identifier cannot start with a digit, or a `-` and a digit, unless
+    // they are escaped
+    let raw = &self.input[start..self.offset()];
+    let unescaped = raw.strip_prefix('-').unwrap_or(raw);
+    match unescaped.chars().next() {
+      Some(c) if !c.is_ascii_digit() => Ok(ident),
+      _ => Err(self.error("expected an identifier")),
+    }
+  }
+
+  // Parses a string up to the closing `quote`, resolving escapes.
+  fn string(&mut self, quote: char) -> Result<String, DawmError> {
+    let mut string = String::new();
+    loop {
+      match self.chars.next().map(|(_, c)| c) {
+        Some(c) if c == quote => return Ok(string),
+        Some('\\') if self.eat('\n') => {}
+        Some('\\') => string.push(self.escape()?),
+        Some('\n') | None => return Err(self.error("unterminated string")),
+        Some(c) => string.push(c),
+      }
+    }
+  }
+
+  // Parses what follows a backslash: up to six hex digits (and a single
+  // whitespace after them), or any other character as itself.
+  fn escape(&mut self) -> Result<char, DawmError> {
+    let mut code = 0;
+    let mut digits = 0;
+    while digits < 6
+      && let Some(digit) = self.peek().and_then(|c| c.to_digit(16))
+    {
+      self.chars.next();
+      code = code * 16 + digit;
+      digits += 1;
+    }
+    if digits == 0 {
+      return match self.chars.next() {
+        Some((_, '\n')) | None => Err(self.error("invalid escape")),
+        Some((_, c)) => Ok(c),
+      };
+    }
+    if self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+      self.chars.next();
+    }
+    Ok(match char::from_u32(code) {
+      Some('\0') | None => char::REPLACEMENT_CHARACTER,
+      Some(c) => c,
+    })
+  }
+
+  fn skip_whitespace(&mut self) -> bool {
+    let mut skipped = false;
+    while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+      self.chars.next();
+      skipped = true;
+    }
+    skipped
+  }
+
+  fn eat(&mut self, expected: char) -> bool {
+    self.chars.next_if(|&(_, c)| c == expected).is_some()
+  }
+
+  fn peek(&mut self) -> Option<char> {
+    self.chars.peek().map(|&(_, c)| c)
+  }
+
+  fn offset(&mut self) -> usize {
+    self.chars.peek().map_or(self.input.len(), |&(i, _)| i)
+  }
+
+  fn error(&mut self, reason: &str) -> DawmError {
+    let offset = self.offset();
+    DawmError::Syntax(format!(
+      "{:?} is not a valid selector: {reason} at offset {offset}",
+      self.input
+    ))
+  }
+}
+
+fn starts_ident(c: char) -> bool {
+  c.is_ascii_alphabetic() || matches!(c, '_' | '-' | '\\') || !c.is_ascii()
+}
+
+fn is_name_char(c: char) -> bool {
+  c.is_ascii_alphanumeric() || matches!(c, '_' | '-') || !c.is_ascii()
+}
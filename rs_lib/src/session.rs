@@ -0,0 +1,216 @@
+//! Parse sessions with a persistent string table.
+//!
+//! Documents parsed in bulk (e.g. pages from the same site) share most of
+//! their strings: tag and attribute names, class names, boilerplate text and
+//! so on. A [`ParseSession`] keeps one string table alive across parses, and
+//! each result only ships the strings that were newly interned by it, along
+//! with the index of the first one as `stringsOffset`. A long-lived decoder
+//! on the JS side (the `WireDecoder` of the package) appends every `strings`
+//! array it receives to a single table, which all of the session's documents
+//! then index into.
+//!
+//! Results must be decoded in the order they were produced, since each one
+//! assumes that all of the strings shipped before it are already known.
+//!
+//! ## Workers
+//!
+//! Parsed documents are reference-counted trees which never leave the thread
+//! (or worker) that parsed them (see [the `dom` module](crate::dom#workers)),
+//! so a session is not shared between workers either. Instead, it has a single owner at any time: [`transfer`] detaches
+//! the string table (resetting the session), and [`from_strings`] takes
+//! ownership of it on the receiving side, which then continues the stream of
+//! results where the sender left off. Since the table is a plain array of
+//! strings, it can be sent to another worker with `postMessage`.
+//!
+//! ## Restarts
+//!
+//! [`dictionary`] exports the string table along with a version and a hash
+//! of its strings (see [`InternerDictionary`]), which can be stored and
+//! given to [`from_dictionary`] after a restart, so that the session goes on
+//! with the same indices. The JS decoder has to be restored from the same
+//! dictionary (by passing its `strings` to the `WireDecoder`), since the new
+//! session does not ship them again.
+//!
+//! [`transfer`]: ParseSession::transfer
+//! [`from_strings`]: ParseSession::from_strings
+//! [`dictionary`]: ParseSession::dictionary
+//! [`from_dictionary`]: ParseSession::from_dictionary
+//! [`InternerDictionary`]: crate::InternerDictionary
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use js_sys::Object;
+use serde_wasm_bindgen::from_value;
+use serde_wasm_bindgen::to_value;
+use wasm_bindgen::prelude::*;
+
+use crate::DawmError;
+use crate::Interner;
+use crate::InternerDictionary;
+use crate::ParseOptions;
+use crate::RcDom;
+use crate::WireDoc;
+use crate::frag_options_from_js;
+use crate::index_lines;
+use crate::options_from_js;
+use crate::panic_hook;
+use crate::parse_by_mime;
+use crate::parse_html_fragment;
+use crate::serialize_dom_with;
+use crate::to_js;
+
+/// A parsing session whose results share a single, growing string table.
+///
+/// See the [module level documentation](crate::session) for more details.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct ParseSession {
+  interner: Interner,
+  shipped:  usize,
+}
+
+#[wasm_bindgen]
+impl ParseSession {
+  /// Creates a new session, whose first result ships the full string table.
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Creates a session owning a string table detached from another one with
+  /// {@linkcode ParseSession.transfer}. Its results assume that every string
+  /// in the table has already been shipped.
+  pub fn from_strings(strings: JsValue) -> Result<ParseSession, JsError> {
+    let strings: Vec<String> =
+      from_value(strings).map_err(DawmError::Options)?;
+    Ok(Self::with_strings(strings))
+  }
+
+  /// Creates a session owning a string table exported with
+  /// {@linkcode ParseSession.dictionary}, failing if the dictionary is of
+  /// another version or was changed. Its results assume that every string
+  /// in the table has already been shipped.
+  pub fn from_dictionary(dictionary: JsValue) -> Result<ParseSession, JsError> {
+    let dictionary: InternerDictionary =
+      from_value(dictionary).map_err(DawmError::Options)?;
+    Ok(Self::with_dictionary(dictionary)?)
+  }
+
+  /// The string table, with a version and a hash of its strings, to be
+  /// stored and given to {@linkcode ParseSession.from_dictionary} later.
+  /// Unlike {@linkcode ParseSession.transfer}, the session is left as is.
+  pub fn dictionary(&self) -> Result<JsValue, JsError> {
+    let dictionary = self.interner.to_dictionary();
+    let value =
+      to_value(&dictionary).map_err(|source| DawmError::Serialize {
+        context: "dictionary".into(),
+        source,
+      })?;
+    Ok(value)
+  }
+
+  /// The total number of strings in the session's table.
+  #[wasm_bindgen(getter)]
+  pub fn size(&self) -> u32 {
+    self.interner.len().try_into().unwrap_or(u32::MAX)
+  }
+
+  /// Parses a document like {@linkcode parse_doc}, shipping only the strings
+  /// which are new to this session.
+  pub fn parse_doc(
+    &mut self,
+    input: &str,
+    mime: &str,
+    options: Option<Object>,
+  ) -> Result<JsValue, JsError> {
+    panic_hook::install();
+    panic_hook::reset();
+
+    let parse_options = match &options {
+      Some(options) => options_from_js(options)?,
+      None => ParseOptions::default(),
+    };
+    let (dom, content_type) = parse_by_mime(input, mime, &parse_options)?;
+    let mut parsed = self.serialize(dom, content_type, &parse_options)?;
+    index_lines(&mut parsed, input, &parse_options);
+    Ok(to_js(&parsed)?)
+  }
+
+  /// Parses a fragment like {@linkcode parse_frag}, shipping only the strings
+  /// which are new to this session.
+  pub fn parse_frag(
+    &mut self,
+    input: &str,
+    options: JsValue,
+  ) -> Result<JsValue, JsError> {
+    panic_hook::install();
+    panic_hook::reset();
+
+    let frag_options = frag_options_from_js(options)?;
+    let dom = parse_html_fragment(input, &frag_options)?;
+    let mut parsed = self.serialize(dom, "text/html", &frag_options.base)?;
+    index_lines(&mut parsed, input, &frag_options.base);
+    if let Some(effective) = parsed.effective_options.as_mut() {
+      effective.context_element = Some(frag_options.context_element.into());
+      effective.doctype =
+        frag_options.doctype.map(|d| d.name().to_string().into());
+    }
+    Ok(to_js(&parsed)?)
+  }
+
+  /// Detaches the string table, handing its ownership to the caller, and
+  /// resets this session. Pass the result to
+  /// {@linkcode ParseSession.from_strings} to continue in another worker.
+  pub fn transfer(&mut self) -> Result<JsValue, JsError> {
+    let strings = core::mem::take(self).interner.into_strings();
+    Ok(to_value(&strings).map_err(|source| DawmError::Serialize {
+      context: "string table".into(),
+      source,
+    })?)
+  }
+
+  /// Clears the string table. The next result ships the full table again.
+  pub fn reset(&mut self) {
+    *self = Self::default();
+  }
+}
+
+impl ParseSession {
+  /// Creates a session owning the given string table, all of which is
+  /// considered to be already shipped.
+  pub fn with_strings(strings: Vec<String>) -> Self {
+    let interner = Interner::new().extend(strings);
+    let shipped = interner.len();
+    Self { interner, shipped }
+  }
+
+  /// Creates a session owning a string table exported with
+  /// [`Interner::to_dictionary`], all of which is considered to be already
+  /// shipped.
+  pub fn with_dictionary(
+    dictionary: InternerDictionary,
+  ) -> crate::error::Result<Self> {
+    let interner = Interner::from_dictionary(dictionary)?;
+    let shipped = interner.len();
+    Ok(Self { interner, shipped })
+  }
+
+  fn serialize(
+    &mut self,
+    dom: RcDom,
+    content_type: &'static str,
+    options: &ParseOptions,
+  ) -> crate::error::Result<WireDoc> {
+    // on failure, the strings interned so far are shipped with the next result
+    let parsed = serialize_dom_with(
+      dom,
+      content_type,
+      options,
+      &mut self.interner,
+      self.shipped,
+    )?;
+    self.shipped = self.interner.len();
+    Ok(parsed)
+  }
+}
@@ -0,0 +1,139 @@
+//! Pluggable tree sinks for the HTML parsing entry points.
+//!
+//! The `parse_html_*_with` functions accept any [`ParseSink`] as a generic
+//! parameter, so Rust callers can trade the mutable [`RcDom`] for a different
+//! tree representation (or a sink which merely observes the parser) without
+//! needing a separate set of parsing functions for every combination:
+//!
+//! ```ignore
+//! let dom = dawm::parse_html_document_with(MySink::default(), input, &opts)?;
+//! ```
+//!
+//! Sinks that are not [`ParseSink`]s (arenas, or sinks that write straight
+//! into a database) go to [`parse_html_with_sink`], [`parse_xml_with_sink`]
+//! or [`parse_with_sink`] instead, which take any [`TreeSink`] and return
+//! its output, at the cost of always parsing the whole input.
+//!
+//! The WASM entry points always parse into an [`RcDom`], since that is what
+//! the wire output is collected from.
+//!
+//! [`parse_html_with_sink`]: crate::parse_html_with_sink
+//! [`parse_xml_with_sink`]: crate::parse_xml_with_sink
+//! [`parse_with_sink`]: crate::parse_with_sink
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+
+use html5ever::tendril::StrTendril;
+use html5ever::tokenizer::BufferQueue;
+use html5ever::tree_builder::TreeSink;
+
+use crate::DawmError;
+use crate::LineIndex;
+use crate::RcDom;
+use crate::diagnostics::Span;
+use crate::error;
+
+/// A [`TreeSink`] that the parsing entry points can build a document with.
+///
+/// Between chunks of input (and once more after the parser has finished),
+/// [`ParseSink::check_stop`] is consulted to determine whether parsing needs
+/// to be stopped early, which is how the `failFast` and `onError` options are
+/// implemented. Sinks which do not track errors can rely on the default
+/// implementation, which never stops.
+pub trait ParseSink: TreeSink<Output = Self> {
+  /// Returns an error if parsing should be stopped. When `fail_fast` is set,
+  /// this should fail as soon as any parse error has been reported.
+  fn check_stop(&self, fail_fast: bool) -> error::Result<()> {
+    let _ = fail_fast;
+    Ok(())
+  }
+
+  /// Called once before parsing with the input the parser reads from, for
+  /// sinks that report where in the input their errors are. Does nothing by
+  /// default.
+  fn track_input(&self, cursor: Rc<InputCursor>) {
+    let _ = cursor;
+  }
+}
+
+/// The input of an HTML parser, shared between the loop feeding it to the
+/// parser and the sink, so that the sink can tell how far into the input the
+/// parser is when it reports an error.
+#[derive(Debug)]
+pub struct InputCursor {
+  queue: BufferQueue,
+  // the byte offset of the end of what was pushed to `queue` so far
+  end:   Cell<usize>,
+  lines: LineIndex,
+}
+
+impl InputCursor {
+  /// Creates a cursor at the start of `input`.
+  pub fn new(input: &str) -> Self {
+    Self {
+      queue: BufferQueue::default(),
+      end:   Cell::new(0),
+      lines: LineIndex::new(input),
+    }
+  }
+
+  // The queue the tokenizer is fed from.
+  pub(crate) fn queue(&self) -> &BufferQueue {
+    &self.queue
+  }
+
+  // Appends the next chunk of the input to the queue.
+  pub(crate) fn push(&self, chunk: &str) {
+    self.end.set(self.end.get() + chunk.len());
+    self.queue.push_back(StrTendril::from_slice(chunk));
+  }
+
+  /// The byte offset the parser has read the input up to. Since tokens are
+  /// reported once they end, this is usually just past the token an error is
+  /// about.
+  pub fn offset(&self) -> usize {
+    // the queue cannot be measured in place, so its buffers are taken out
+    // and put back in the same order
+    let mut buffers = Vec::new();
+    while let Some(buffer) = self.queue.pop_front() {
+      buffers.push(buffer);
+    }
+    let left: usize = buffers.iter().map(|buffer| buffer.len()).sum();
+    for buffer in buffers {
+      self.queue.push_back(buffer);
+    }
+    self.end.get() - left
+  }
+
+  /// The span of the current [`offset`](Self::offset), with its line and
+  /// column.
+  pub fn span(&self) -> Span {
+    let offset = self.offset();
+    let (line, column) = self.lines.locate(offset);
+    Span {
+      line:   Some(line),
+      column: Some(column),
+      offset: Some(offset),
+    }
+  }
+}
+
+impl ParseSink for RcDom {
+  // Fails if the `onError` callback aborted parsing or, with `fail_fast`, if
+  // any error was reported to the DOM at all.
+  fn check_stop(&self, fail_fast: bool) -> error::Result<()> {
+    if let Some(error) = self.aborted_at.borrow().clone() {
+      return Err(DawmError::Aborted(error));
+    }
+    match self.first_error.borrow().clone() {
+      Some(first) if fail_fast => Err(DawmError::ParseFailed(first)),
+      _ => Ok(()),
+    }
+  }
+
+  fn track_input(&self, cursor: Rc<InputCursor>) {
+    *self.input.borrow_mut() = Some(cursor);
+  }
+}
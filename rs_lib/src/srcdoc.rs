@@ -0,0 +1,116 @@
+//! Nested documents from `<iframe srcdoc>`.
+//!
+//! The `srcdoc` of an `<iframe>` is a whole HTML document, which browsers
+//! parse as the iframe's content document. With the `parseSrcdoc` option,
+//! these documents are parsed as well, and emitted into the
+//! [`WireDoc`](crate::WireDoc) of their host, after the nodes of the host:
+//!
+//! - the nodes of a nested document have the id of its document node as their
+//!   `ownerDocument`, and the document node itself has neither an
+//!   `ownerDocument` nor a `parentNode`;
+//! - the iframe has the id of that document node as its `contentDocument`;
+//! - the `documents` of the output list the ids of all document nodes, that of
+//!   the host first.
+//!
+//! Nested documents are parsed with the options of the host (as
+//! `iframeSrcdoc`, like browsers parse them), and transformed like it, but
+//! their parse errors are not reported, and do not abort parsing with
+//! `failFast`. The `srcdoc`s of their own iframes are parsed as well.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::local_name;
+use html5ever::ns;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::ParseOptions;
+use crate::RcDom;
+use crate::WireDocEmitter;
+use crate::emit;
+use crate::parse_html_document;
+use crate::prepare_dom;
+
+/// Emits the documents of the `srcdoc`s of the iframes in the tree rooted at
+/// `root`, whose wire ids start at `first`, linking them to their iframes.
+/// Returns the nested documents along with the ids of their document nodes,
+/// in the order they were emitted.
+pub(crate) fn emit_nested(
+  root: &Handle,
+  first: u32,
+  options: &ParseOptions,
+  emitter: &mut WireDocEmitter<'_>,
+) -> Vec<(u32, RcDom)> {
+  let mut options = options.clone();
+  options.iframe_srcdoc = true;
+  options.fail_fast = false;
+  #[cfg(feature = "wasm")]
+  {
+    options.on_error = None;
+  }
+  let mut nested = Vec::new();
+  emit_srcdocs(root, first, &options, emitter, &mut nested);
+  nested
+}
+
+fn emit_srcdocs(
+  root: &Handle,
+  first: u32,
+  options: &ParseOptions,
+  emitter: &mut WireDocEmitter<'_>,
+  nested: &mut Vec<(u32, RcDom)>,
+) {
+  let mut srcdocs = Vec::new();
+  let mut next = first;
+  find_srcdocs(root, &mut next, &mut srcdocs);
+  for (iframe, srcdoc) in srcdocs {
+    let Ok(dom) = parse_html_document(&srcdoc, options) else {
+      continue;
+    };
+    prepare_dom(&dom, options);
+    let document = emitter.nodes.len() as u32;
+    emit(&dom.document, emitter);
+    // like in the DOM, a document is not owned by itself
+    for node in &mut emitter.nodes[document as usize + 1..] {
+      node.owner_document = Some(document);
+    }
+    emitter.nodes[iframe as usize].content_document = Some(document);
+    let root = dom.document.clone();
+    nested.push((document, dom));
+    emit_srcdocs(&root, document, options, emitter, nested);
+  }
+}
+
+// Collects the wire ids and `srcdoc`s of the HTML iframes below `node`,
+// numbering the nodes like `number_nodes` does, from `next` on.
+fn find_srcdocs(
+  node: &Handle,
+  next: &mut u32,
+  srcdocs: &mut Vec<(u32, String)>,
+) {
+  let id = *next;
+  *next += 1;
+  if let NodeData::Element { name, attrs, .. } = &node.data
+    && name.ns == ns!(html)
+    && name.local == local_name!("iframe")
+  {
+    let attrs = attrs.borrow();
+    let srcdoc = attrs.iter().find(|attr| {
+      attr.name.ns == ns!() && attr.name.local == local_name!("srcdoc")
+    });
+    if let Some(srcdoc) = srcdoc {
+      srcdocs.push((id, String::from(&*srcdoc.value)));
+    }
+  }
+  for child in node.children.borrow().iter() {
+    find_srcdocs(child, next, srcdocs);
+  }
+  if let NodeData::Element {
+    template_contents: Some(contents),
+    ..
+  } = &node.data
+  {
+    find_srcdocs(contents, next, srcdocs);
+  }
+}
@@ -0,0 +1,166 @@
+//! The `srcset` and `sizes` attribute microsyntaxes.
+//!
+//! A `srcset` lists image candidates, each a URL followed by at most one
+//! width (`480w`) or pixel density (`2x`) descriptor, and optionally a height
+//! (`320h`) along with a width. Candidates with invalid or conflicting
+//! descriptors are dropped, as browsers do. A `sizes` attribute lists the
+//! source sizes selecting the width an image is displayed at, each a media
+//! condition followed by a length, the last one usually without a condition.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use serde::Serialize;
+
+use crate::url_filter::srcset_candidates;
+
+/// An image candidate of a `srcset` attribute.
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct SrcsetCandidate {
+  /// The URL, as given.
+  pub url:          String,
+  /// The absolute URL, when the candidate was resolved against a base URL.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub resolved_url: Option<String>,
+  /// The width descriptor, in pixels.
+  pub width:        Option<u32>,
+  /// The height descriptor, in pixels (only valid along with a width).
+  pub height:       Option<u32>,
+  /// The pixel density descriptor. A candidate without any descriptor has
+  /// an implied density of `1`.
+  pub density:      Option<f64>,
+}
+
+/// A source size of a `sizes` attribute.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceSize {
+  /// The media condition, e.g. `(max-width: 600px)`, if any.
+  pub media:  Option<String>,
+  /// The length, e.g. `100vw` or `calc(50vw - 2rem)`.
+  pub length: String,
+}
+
+/// Parses a `srcset` attribute into its valid image candidates.
+pub fn parse_srcset(value: &str) -> Vec<SrcsetCandidate> {
+  srcset_candidates(value)
+    .into_iter()
+    .filter_map(|(url, descriptors)| {
+      let mut candidate = SrcsetCandidate {
+        url: url.to_string(),
+        ..SrcsetCandidate::default()
+      };
+      for descriptor in descriptors.split_ascii_whitespace() {
+        let last = descriptor.char_indices().last().map_or(0, |(i, _)| i);
+        let (number, kind) = descriptor.split_at(last);
+        match kind {
+          "w" if candidate.width.is_none() && candidate.density.is_none() => {
+            candidate.width = Some(positive_integer(number)?);
+          }
+          "h" if candidate.height.is_none() && candidate.density.is_none() => {
+            candidate.height = Some(positive_integer(number)?);
+          }
+          "x"
+            if candidate.density.is_none()
+              && candidate.width.is_none()
+              && candidate.height.is_none() =>
+          {
+            let density: f64 = number.parse().ok()?;
+            if !density.is_finite() || density < 0.0 || number.starts_with('+')
+            {
+              return None;
+            }
+            candidate.density = Some(density);
+          }
+          _ => return None,
+        }
+      }
+      // a height descriptor is only allowed along with a width
+      if candidate.height.is_some() && candidate.width.is_none() {
+        return None;
+      }
+      Some(candidate)
+    })
+    .collect()
+}
+
+/// Parses a `sizes` attribute into its source sizes. Entries without a
+/// length are skipped.
+pub fn parse_sizes(value: &str) -> Vec<SourceSize> {
+  split_top_level(value)
+    .into_iter()
+    .filter_map(|entry| {
+      let entry = entry.trim();
+      // the length is the last component of the entry, which may itself be
+      // a function with spaces in it
+      let start = if entry.ends_with(')') {
+        function_start(entry)?
+      } else {
+        entry
+          .rfind(|c: char| c.is_ascii_whitespace())
+          .map_or(0, |i| i + 1)
+      };
+      let length = entry[start..].trim();
+      if length.is_empty() {
+        return None;
+      }
+      let media = entry[..start].trim();
+      Some(SourceSize {
+        media:  (!media.is_empty()).then(|| media.to_string()),
+        length: length.to_string(),
+      })
+    })
+    .collect()
+}
+
+fn positive_integer(number: &str) -> Option<u32> {
+  if !number.bytes().all(|b| b.is_ascii_digit()) {
+    return None;
+  }
+  number.parse().ok().filter(|&n| n > 0)
+}
+
+// Splits `value` at every comma outside of parentheses.
+fn split_top_level(value: &str) -> Vec<&str> {
+  let mut parts = Vec::new();
+  let mut depth = 0usize;
+  let mut start = 0;
+  for (i, c) in value.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth = depth.saturating_sub(1),
+      ',' if depth == 0 => {
+        parts.push(&value[start..i]);
+        start = i + 1;
+      }
+      _ => {}
+    }
+  }
+  parts.push(&value[start..]);
+  parts
+}
+
+// Finds where the function call that `entry` ends with starts, including its
+// name, e.g. the `calc(` of `(min-width: 40em) calc(50vw - 2rem)`.
+fn function_start(entry: &str) -> Option<usize> {
+  let mut depth = 0usize;
+  for (i, c) in entry.char_indices().rev() {
+    match c {
+      ')' => depth += 1,
+      '(' => {
+        depth -= 1;
+        if depth == 0 {
+          let name = entry[..i]
+            .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+            .map_or(0, |j| j + 1);
+          // a bare parenthesized expression is a media condition
+          return (name < i).then_some(name);
+        }
+      }
+      _ => {}
+    }
+  }
+  None
+}
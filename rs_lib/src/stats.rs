@@ -0,0 +1,163 @@
+//! Aggregate document statistics.
+//!
+//! For corpus analytics over millions of pages, the full wire output (and
+//! the cost of shipping it to JS) is far more than what's needed. The
+//! [`StatsEmitter`] walks a parsed document like any other [`WireEmitter`],
+//! but only tallies up its structure into a [`DocumentStats`] summary, with
+//! no nodes or strings at all.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use serde::Serialize;
+
+use crate::Handle;
+use crate::WireEmitter;
+use crate::emit;
+
+/// The aggregate structure of a document.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStats {
+  /// The number of elements per tag name.
+  pub tags:        BTreeMap<String, u32>,
+  /// The number of occurrences per attribute name.
+  pub attributes:  BTreeMap<String, u32>,
+  /// The number of elements at each depth, where the root element is at
+  /// depth `0`.
+  pub depths:      Vec<u32>,
+  /// The total number of elements.
+  pub elements:    u32,
+  /// The total number of text nodes.
+  pub text_nodes:  u32,
+  /// The total number of comments.
+  pub comments:    u32,
+  /// The total length of all text nodes, in UTF-8 bytes.
+  pub text_bytes:  u64,
+  /// A hash of the document's element structure (tag names and nesting,
+  /// ignoring attributes and text), as a 16-digit hex string. Documents built
+  /// from the same template typically share a fingerprint.
+  pub fingerprint: String,
+}
+
+/// A [`WireEmitter`] that collects [`DocumentStats`]. Its node ids are the
+/// element depth of the node's children.
+#[derive(Debug, Clone)]
+pub struct StatsEmitter {
+  stats: DocumentStats,
+  hash:  u64,
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+impl Default for StatsEmitter {
+  fn default() -> Self {
+    Self {
+      stats: DocumentStats::default(),
+      hash:  FNV_OFFSET_BASIS,
+    }
+  }
+}
+
+impl StatsEmitter {
+  /// Finishes the collection, returning the statistics.
+  pub fn finish(self) -> DocumentStats {
+    DocumentStats {
+      fingerprint: format!("{:016x}", self.hash),
+      ..self.stats
+    }
+  }
+
+  fn hash_bytes(&mut self, bytes: &[u8]) {
+    for &byte in bytes {
+      self.hash = (self.hash ^ byte as u64).wrapping_mul(FNV_PRIME);
+    }
+  }
+}
+
+fn bump(map: &mut BTreeMap<String, u32>, key: &str) {
+  match map.get_mut(key) {
+    Some(count) => *count += 1,
+    None => {
+      map.insert(key.to_string(), 1);
+    }
+  }
+}
+
+impl WireEmitter for StatsEmitter {
+  type Id = u32;
+
+  fn visit_document(&mut self, _parent: Option<u32>) -> u32 {
+    0
+  }
+
+  fn visit_document_fragment(&mut self) -> u32 {
+    0
+  }
+
+  fn visit_doctype(
+    &mut self,
+    parent: Option<u32>,
+    _name: &str,
+    _public_id: &str,
+    _system_id: &str,
+  ) -> u32 {
+    parent.unwrap_or(0)
+  }
+
+  fn visit_element(
+    &mut self,
+    parent: Option<u32>,
+    name: &QualName,
+    attrs: &[Attribute],
+  ) -> u32 {
+    let depth = parent.unwrap_or(0);
+    let stats = &mut self.stats;
+    stats.elements += 1;
+    bump(&mut stats.tags, &name.local);
+    for attr in attrs {
+      bump(&mut stats.attributes, &attr.name.local);
+    }
+    if stats.depths.len() <= depth as usize {
+      stats.depths.resize(depth as usize + 1, 0);
+    }
+    stats.depths[depth as usize] += 1;
+
+    self.hash_bytes(&depth.to_le_bytes());
+    self.hash_bytes(name.local.as_bytes());
+    depth + 1
+  }
+
+  fn visit_text(&mut self, parent: Option<u32>, text: &str) -> u32 {
+    self.stats.text_nodes += 1;
+    self.stats.text_bytes += text.len() as u64;
+    parent.unwrap_or(0)
+  }
+
+  fn visit_comment(&mut self, parent: Option<u32>, _text: &str) -> u32 {
+    self.stats.comments += 1;
+    parent.unwrap_or(0)
+  }
+
+  fn visit_processing_instruction(
+    &mut self,
+    parent: Option<u32>,
+    _target: &str,
+    _data: &str,
+  ) -> u32 {
+    parent.unwrap_or(0)
+  }
+}
+
+/// Collects the [`DocumentStats`] of the tree rooted at `handle`.
+pub fn document_stats(handle: &Handle) -> DocumentStats {
+  let mut emitter = StatsEmitter::default();
+  emit(handle, &mut emitter);
+  emitter.finish()
+}
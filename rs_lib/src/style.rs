@@ -0,0 +1,250 @@
+//! Inline style (`style` attribute) parsing and sanitization.
+//!
+//! Allowing the `style` attribute through a tag and attribute allowlist also
+//! lets through whatever CSS it holds, such as `expression()` (which runs
+//! script in legacy engines), `url(javascript:...)`, or `position: fixed`
+//! overlays drawn on top of the embedding page. The [`sanitize_style`]
+//! function parses the attribute into its declarations and only keeps those
+//! whose property is allowed, and whose value is safe:
+//!
+//! - values containing CSS escapes (`\`), which are commonly used to hide any
+//!   of the below from naive filters, are dropped;
+//! - values calling `expression()`, or mentioning the `javascript:` or
+//!   `vbscript:` schemes anywhere, are dropped;
+//! - values with a `url()` whose URL is rejected by the caller are dropped;
+//! - `position` is only kept as `static` or `relative`.
+//!
+//! Comments are removed, and the kept declarations are serialized in a
+//! normalized `name: value` form.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// A single `name: value` declaration of a style attribute.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+  /// The (lowercase) property name.
+  pub name:  String,
+  /// The value, including any `!important` flag.
+  pub value: String,
+}
+
+/// Parses the declarations of a `style` attribute. Comments are removed, and
+/// declarations without a name or value are skipped.
+pub fn parse_declarations(style: &str) -> Vec<Declaration> {
+  let style = strip_comments(style);
+  split_outside_parens(&style, ';')
+    .into_iter()
+    .filter_map(|declaration| {
+      let (name, value) = declaration.split_once(':')?;
+      let name = name.trim();
+      let value = value.trim();
+      (!name.is_empty() && !value.is_empty()).then(|| Declaration {
+        name:  name.to_ascii_lowercase(),
+        value: value.to_string(),
+      })
+    })
+    .collect()
+}
+
+/// A top level item of a stylesheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StyleItem {
+  /// A style rule, with the text of its selectors and of its declarations.
+  Rule {
+    selectors:    String,
+    declarations: String,
+  },
+  /// An at-rule, like `@media` or `@import`, as written (including its
+  /// block, if it has one).
+  AtRule(String),
+}
+
+/// Splits a stylesheet into its top level rules and at-rules. Comments (and
+/// the `<!--` and `-->` that may surround the contents of a `<style>`) are
+/// removed, and a block left open at the end of the input is closed.
+pub fn parse_stylesheet(css: &str) -> Vec<StyleItem> {
+  let css = strip_comments(css);
+  let mut items = Vec::new();
+  let mut rest = css.as_str();
+  loop {
+    rest = rest.trim_start();
+    while let Some(after) = rest
+      .strip_prefix("<!--")
+      .or_else(|| rest.strip_prefix("-->"))
+    {
+      rest = after.trim_start();
+    }
+    if rest.is_empty() {
+      return items;
+    }
+    let is_at_rule = rest.starts_with('@');
+    // the prelude ends at a block, or for at-rules, at a semicolon
+    let Some((end, c)) =
+      find_outside_quotes(rest, |c| c == '{' || (is_at_rule && c == ';'))
+    else {
+      if is_at_rule {
+        items.push(StyleItem::AtRule(rest.trim().to_string()));
+      }
+      return items;
+    };
+    if c == ';' {
+      items.push(StyleItem::AtRule(rest[..=end].trim().to_string()));
+      rest = &rest[end + 1..];
+      continue;
+    }
+    let mut depth = 0usize;
+    let close = find_outside_quotes(&rest[end..], |c| {
+      match c {
+        '{' => depth += 1,
+        '}' => depth -= 1,
+        _ => {}
+      }
+      depth == 0
+    })
+    .map(|(i, _)| end + i);
+    let (block_end, next) = match close {
+      Some(close) => (close, close + 1),
+      None => (rest.len(), rest.len()),
+    };
+    items.push(match is_at_rule {
+      true => StyleItem::AtRule(match close {
+        Some(_) => rest[..next].trim().to_string(),
+        None => format!("{}}}", rest.trim()),
+      }),
+      false => StyleItem::Rule {
+        selectors:    rest[..end].trim().to_string(),
+        declarations: rest[end + 1..block_end].trim().to_string(),
+      },
+    });
+    rest = &rest[next..];
+  }
+}
+
+// Finds the first character outside of quotes for which `found` returns
+// `true`, returning its index along with it.
+fn find_outside_quotes(
+  input: &str,
+  mut found: impl FnMut(char) -> bool,
+) -> Option<(usize, char)> {
+  let mut quote = None;
+  let mut escaped = false;
+  input.char_indices().find(|&(_, c)| {
+    match (quote, c) {
+      _ if escaped => escaped = false,
+      (_, '\\') => escaped = true,
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), _) => {}
+      (None, '"' | '\'') => quote = Some(c),
+      (None, c) => return found(c),
+    }
+    false
+  })
+}
+
+/// Filters the declarations of a `style` attribute, keeping the ones whose
+/// property is accepted by `allows_property` and whose value is safe (see the
+/// [module level documentation](crate::style)). URLs in `url()` values are
+/// checked with `allows_url`. Returns `None` if nothing is left.
+pub fn sanitize_style(
+  style: &str,
+  allows_property: impl Fn(&str) -> bool,
+  allows_url: impl Fn(&str) -> bool,
+) -> Option<String> {
+  let kept: Vec<String> = parse_declarations(style)
+    .into_iter()
+    .filter(|d| allows_property(&d.name))
+    .filter(|d| is_safe_value(&d.name, &d.value, &allows_url))
+    .map(|d| format!("{}: {}", d.name, d.value))
+    .collect();
+  (!kept.is_empty()).then(|| kept.join("; "))
+}
+
+fn is_safe_value(
+  name: &str,
+  value: &str,
+  allows_url: impl Fn(&str) -> bool,
+) -> bool {
+  if value.contains('\\') {
+    return false;
+  }
+  let compact: String = value
+    .chars()
+    .filter(|c| !c.is_ascii_whitespace())
+    .map(|c| c.to_ascii_lowercase())
+    .collect();
+  if ["expression(", "javascript:", "vbscript:"]
+    .iter()
+    .any(|needle| compact.contains(needle))
+  {
+    return false;
+  }
+  if name == "position" {
+    let position = value.trim_end_matches("!important").trim();
+    return position.eq_ignore_ascii_case("static")
+      || position.eq_ignore_ascii_case("relative");
+  }
+  url_arguments(value).iter().all(|url| allows_url(url))
+}
+
+// Extracts the (unquoted) arguments of every `url()` in `value`.
+fn url_arguments(value: &str) -> Vec<&str> {
+  let mut urls = Vec::new();
+  let mut rest = value;
+  while let Some(start) = find_ignore_ascii_case(rest, "url(") {
+    let after = &rest[start + 4..];
+    let end = after.find(')').unwrap_or(after.len());
+    let url = after[..end].trim().trim_matches(['"', '\'']).trim();
+    urls.push(url);
+    rest = &after[end..];
+  }
+  urls
+}
+
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+  haystack
+    .as_bytes()
+    .windows(needle.len())
+    .position(|w| w.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+fn strip_comments(style: &str) -> String {
+  let mut out = String::with_capacity(style.len());
+  let mut rest = style;
+  while let Some(start) = rest.find("/*") {
+    out.push_str(&rest[..start]);
+    // a comment separates tokens, much like whitespace does
+    out.push(' ');
+    rest = rest[start + 2..]
+      .find("*/")
+      .map_or("", |end| &rest[start + 2 + end + 2..]);
+  }
+  out.push_str(rest);
+  out
+}
+
+// Splits `input` at every `separator` outside of parentheses and quotes.
+fn split_outside_parens(input: &str, separator: char) -> Vec<&str> {
+  let mut parts = Vec::new();
+  let mut depth = 0usize;
+  let mut quote = None;
+  let mut start = 0;
+  for (i, c) in input.char_indices() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), _) => {}
+      (None, '"' | '\'') => quote = Some(c),
+      (None, '(') => depth += 1,
+      (None, ')') => depth = depth.saturating_sub(1),
+      (None, c) if c == separator && depth == 0 => {
+        parts.push(&input[start..i]);
+        start = i + c.len_utf8();
+      }
+      _ => {}
+    }
+  }
+  parts.push(&input[start..]);
+  parts
+}
@@ -0,0 +1,281 @@
+//! Inlining SVG `<use>` references.
+//!
+//! An SVG `<use href="#icon">` renders a copy of the element with the id
+//! `icon`, which is how icon sprites (a hidden `<svg>` of `<symbol>`s that
+//! the icons of a page all `<use>`) work. Some consumers of SVG never
+//! resolve these references: many email clients, rasterizers and design
+//! tools, and any SVG that is cut out of the document it was part of.
+//! [`inline_uses`] replaces each `<use>` referencing an element of the same
+//! document with the copy it would render, the way the SVG specification
+//! describes the "use-element shadow tree":
+//!
+//! - the `<use>` becomes a `<g>` with its attributes, except for `href`,
+//!   `xlink:href`, `x`, `y`, `width` and `height`, and with the `x` and `y`
+//!   offsets added to its `transform` as a `translate()`;
+//! - a referenced `<symbol>` is copied as an `<svg>` with the attributes of the
+//!   symbol (like its `viewBox`), sized by the `width` and `height` of the
+//!   `<use>`, and so is a referenced `<svg>`;
+//! - any other referenced element is copied as is.
+//!
+//! The copy leaves out the `id` of the referenced element, so that it is
+//! not duplicated, and the `<use>` elements in it are inlined as well.
+//! References to other documents (`href="sprite.svg#icon"`), to elements
+//! that do not exist or are not SVG, and circular references are left as
+//! they are. So are the references left once the copies would total more
+//! than [`MAX_INLINED_NODES`] nodes, which nested references can otherwise
+//! grow exponentially.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::Namespace;
+use html5ever::QualName;
+use html5ever::local_name;
+use html5ever::ns;
+
+use crate::Appendable;
+use crate::Handle;
+use crate::NodeData;
+use crate::ParentNode;
+use crate::dom::create::new_element;
+use crate::extract::descendants;
+use crate::rcdom::clone_node;
+use crate::rcdom::replace_node;
+
+/// The most nodes [`inline_uses`] copies into a document in total.
+pub const MAX_INLINED_NODES: usize = 100_000;
+
+/// Replaces the `<use>` elements below `root` that reference an SVG element
+/// below `root` with a copy of that element, returning how many were
+/// replaced (not counting the ones in the copies).
+pub fn inline_uses(root: &Handle) -> usize {
+  let mut targets: BTreeMap<String, Handle> = BTreeMap::new();
+  let mut uses: Vec<Handle> = Vec::new();
+  for node in descendants(root) {
+    let NodeData::Element { name, .. } = &node.data else {
+      continue;
+    };
+    if name.ns != ns!(svg) {
+      continue;
+    }
+    if let Some(id) = attr(&node, &ns!(), "id") {
+      targets.entry(id).or_insert_with(|| node.clone());
+    }
+    if name.local == local_name!("use") {
+      uses.push(node);
+    }
+  }
+
+  let mut inliner = Inliner {
+    targets,
+    budget: MAX_INLINED_NODES,
+    stack: Vec::new(),
+  };
+  let mut inlined = 0;
+  for node in uses {
+    // a `<use>` inside of what it references renders nothing
+    let Some(target) = inliner.target(&node) else {
+      continue;
+    };
+    if contains(&target, &node) {
+      continue;
+    }
+    if let Some(replacement) = inliner.expand(&node, &target) {
+      replace_node(&node, replacement);
+      inlined += 1;
+    }
+  }
+  inlined
+}
+
+struct Inliner {
+  // the SVG elements by their id, the first one for duplicate ids
+  targets: BTreeMap<String, Handle>,
+  // how many more nodes may be copied
+  budget:  usize,
+  // the ids of the elements being copied, to detect circular references
+  stack:   Vec<String>,
+}
+
+impl Inliner {
+  // The element a `<use>` references, if it is in the document.
+  fn target(&self, node: &Handle) -> Option<Handle> {
+    let href =
+      attr(node, &ns!(), "href").or_else(|| attr(node, &ns!(xlink), "href"))?;
+    let id = href.trim().strip_prefix('#')?;
+    self.targets.get(id).cloned()
+  }
+
+  // Builds the `<g>` that replaces the `<use>` element `node`, which
+  // references `target`, or `None` if the reference is circular or the
+  // budget has run out.
+  fn expand(&mut self, node: &Handle, target: &Handle) -> Option<Handle> {
+    let id = attr(target, &ns!(), "id")?;
+    if self.stack.contains(&id) {
+      return None;
+    }
+    let size = count_nodes(target);
+    if size > self.budget {
+      return None;
+    }
+    self.budget -= size;
+
+    let NodeData::Element { attrs, .. } = &node.data else {
+      return None;
+    };
+    let mut group_attrs: Vec<Attribute> = attrs
+      .borrow()
+      .iter()
+      .filter(|attr| {
+        !is_named(attr, &ns!(xlink), "href")
+          && !["href", "x", "y", "width", "height"]
+            .iter()
+            .any(|name| is_named(attr, &ns!(), name))
+      })
+      .cloned()
+      .collect();
+    let x = offset(attr(node, &ns!(), "x"));
+    let y = offset(attr(node, &ns!(), "y"));
+    if x != "0" || y != "0" {
+      let translate = format!("translate({x} {y})");
+      let transform = group_attrs
+        .iter_mut()
+        .find(|attr| is_named(attr, &ns!(), "transform"));
+      match transform {
+        Some(transform) => {
+          let existing = transform.value.trim();
+          transform.value = match existing.is_empty() {
+            true => translate.into(),
+            false => format!("{existing} {translate}").into(),
+          };
+        }
+        None => group_attrs.push(Attribute {
+          name:  QualName::new(None, ns!(), local_name!("transform")),
+          value: translate.into(),
+        }),
+      }
+    }
+
+    let copy = instantiate(node, target);
+    self.stack.push(id);
+    self.inline_within(&copy);
+    self.stack.pop();
+
+    let group = new_element(QualName::new(None, ns!(svg), local_name!("g")));
+    if let NodeData::Element { attrs, .. } = &group.data {
+      *attrs.borrow_mut() = group_attrs;
+    }
+    group.append(copy);
+    Some(group)
+  }
+
+  // Inlines the `<use>` elements below `root`, a copy being built.
+  fn inline_within(&mut self, root: &Handle) {
+    let uses: Vec<Handle> = descendants(root)
+      .filter(|node| match &node.data {
+        NodeData::Element { name, .. } => {
+          name.ns == ns!(svg) && name.local == local_name!("use")
+        }
+        _ => false,
+      })
+      .collect();
+    for node in uses {
+      if let Some(target) = self.target(&node)
+        && let Some(replacement) = self.expand(&node, &target)
+      {
+        replace_node(&node, replacement);
+      }
+    }
+  }
+}
+
+// Copies `target` for the `<use>` element `node`, as an `<svg>` sized
+// like the `<use>` if it is a `<symbol>` or an `<svg>`.
+fn instantiate(node: &Handle, target: &Handle) -> Handle {
+  let copy = clone_node(target, true);
+  let NodeData::Element { name, attrs, .. } = &copy.data else {
+    return copy;
+  };
+  let is_symbol = name.local == local_name!("symbol");
+  let is_sized = is_symbol || name.local == local_name!("svg");
+  let mut attrs = attrs.take();
+  attrs.retain(|attr| !is_named(attr, &ns!(), "id"));
+  for size in ["width", "height"] {
+    if is_sized && let Some(value) = attr(node, &ns!(), size) {
+      attrs.retain(|attr| !is_named(attr, &ns!(), size));
+      attrs.push(Attribute {
+        name:  QualName::new(None, ns!(), size.into()),
+        value: value.into(),
+      });
+    }
+  }
+  let element = match is_symbol {
+    true => new_element(QualName::new(None, ns!(svg), local_name!("svg"))),
+    false => copy.clone(),
+  };
+  if let NodeData::Element { attrs: slot, .. } = &element.data {
+    *slot.borrow_mut() = attrs;
+  }
+  if is_symbol {
+    let children = copy.children.take();
+    for child in &children {
+      child.parent.set(Some(Rc::downgrade(&element)));
+    }
+    *element.children.borrow_mut() = children;
+  }
+  element
+}
+
+// The value of the attribute of `node` with the namespace `ns` and the local
+// name `name`.
+fn attr(node: &Handle, ns: &Namespace, name: &str) -> Option<String> {
+  let NodeData::Element { attrs, .. } = &node.data else {
+    return None;
+  };
+  attrs
+    .borrow()
+    .iter()
+    .find(|attr| is_named(attr, ns, name))
+    .map(|attr| String::from(&*attr.value))
+}
+
+// Whether `attr` has the namespace `ns` and the local name `name`.
+fn is_named(attr: &Attribute, ns: &Namespace, name: &str) -> bool {
+  attr.name.ns == *ns && &*attr.name.local == name
+}
+
+// The number in an `x` or `y` attribute, as it is written in a `translate()`:
+// without the `px` unit it may have, and `0` if it is missing or is not a
+// number.
+fn offset(value: Option<String>) -> String {
+  let Some(value) = value else {
+    return String::from("0");
+  };
+  let value = value.trim();
+  let number = value.strip_suffix("px").unwrap_or(value);
+  match number.parse::<f64>() {
+    Ok(parsed) if parsed.is_finite() && parsed != 0.0 => String::from(number),
+    _ => String::from("0"),
+  }
+}
+
+// The number of nodes in the subtree of `node`, including itself.
+fn count_nodes(node: &Handle) -> usize {
+  1 + descendants(node).count()
+}
+
+// Whether `node` is `ancestor` or one of its descendants.
+fn contains(ancestor: &Handle, node: &Handle) -> bool {
+  let mut current = Some(node.clone());
+  while let Some(node) = current {
+    if Rc::ptr_eq(&node, ancestor) {
+      return true;
+    }
+    current = node.get_parent_and_index().map(|(parent, _)| parent);
+  }
+  false
+}
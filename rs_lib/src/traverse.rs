@@ -0,0 +1,308 @@
+//! Iterators over the tree of an [`RcDom`](crate::RcDom).
+//!
+//! The [`Traverse`] trait gives every [`Handle`] the iterators of the DOM's
+//! tree walking, so that a pass can be written as a chain of adapters:
+//!
+//! ```
+//! use dawm::RcDom;
+//! use dawm::traverse::NodeIterator;
+//! use dawm::traverse::Traverse;
+//! use html5ever::tendril::TendrilSink;
+//!
+//! let dom = html5ever::parse_document(RcDom::default(), Default::default())
+//!   .one("<p>a <b>b</b> <i>c</i></p>");
+//! let names: Vec<String> = dom
+//!   .document
+//!   .descendants()
+//!   .elements()
+//!   .map(|element| element.local_name().to_string())
+//!   .collect();
+//! assert_eq!(names, ["html", "head", "body", "p", "b", "i"]);
+//! ```
+//!
+//! None of the iterators hold a borrow of the tree between calls to `next`,
+//! so the nodes they yield can be moved or removed while iterating. The
+//! children and siblings of a node are those it had when the iterator was
+//! created, while [`Descendants`] takes the children of each node when it
+//! yields the node, and [`Ancestors`] the parent. None of them enter the
+//! (inert) contents of templates, which can be walked from
+//! [`ElementRef::template_contents`].
+
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Ref;
+use core::iter::Rev;
+use core::ops::Deref;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use html5ever::ns;
+use html5ever::tendril::StrTendril;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::dom::parent;
+
+/// Tree traversal for the nodes of an [`RcDom`](crate::RcDom).
+pub trait Traverse {
+  /// The children of the node, in order.
+  fn children(&self) -> Children;
+
+  /// The descendants of the node in document order, not including the node
+  /// itself.
+  fn descendants(&self) -> Descendants;
+
+  /// The ancestors of the node, starting with its parent.
+  fn ancestors(&self) -> Ancestors;
+
+  /// The other children of the parent of the node, in order.
+  fn siblings(&self) -> Children;
+
+  /// The siblings following the node, starting with the next one.
+  fn following_siblings(&self) -> Children;
+
+  /// The siblings preceding the node, starting with the previous one.
+  fn preceding_siblings(&self) -> Rev<Children>;
+}
+
+impl Traverse for Handle {
+  fn children(&self) -> Children {
+    Children::new(self.children.borrow().clone())
+  }
+
+  fn descendants(&self) -> Descendants {
+    Descendants::new(self)
+  }
+
+  fn ancestors(&self) -> Ancestors {
+    Ancestors { next: parent(self) }
+  }
+
+  fn siblings(&self) -> Children {
+    let Some(parent) = parent(self) else {
+      return Children::new(Vec::new());
+    };
+    let mut siblings = parent.children.borrow().clone();
+    siblings.retain(|sibling| !Rc::ptr_eq(sibling, self));
+    Children::new(siblings)
+  }
+
+  fn following_siblings(&self) -> Children {
+    let siblings = match index_in_parent(self) {
+      Some((parent, index)) => parent.children.borrow()[index + 1..].to_vec(),
+      None => Vec::new(),
+    };
+    Children::new(siblings)
+  }
+
+  fn preceding_siblings(&self) -> Rev<Children> {
+    let siblings = match index_in_parent(self) {
+      Some((parent, index)) => parent.children.borrow()[..index].to_vec(),
+      None => Vec::new(),
+    };
+    Children::new(siblings).rev()
+  }
+}
+
+/// Iterates over the children of a node, or over some of the children of
+/// its parent, as they were when the iterator was created.
+#[derive(Debug, Clone)]
+pub struct Children {
+  nodes: vec::IntoIter<Handle>,
+}
+
+impl Children {
+  fn new(nodes: Vec<Handle>) -> Self {
+    Children {
+      nodes: nodes.into_iter(),
+    }
+  }
+}
+
+impl Iterator for Children {
+  type Item = Handle;
+
+  fn next(&mut self) -> Option<Handle> {
+    self.nodes.next()
+  }
+
+  fn size_hint(&self) -> (usize, Option<usize>) {
+    self.nodes.size_hint()
+  }
+}
+
+impl DoubleEndedIterator for Children {
+  fn next_back(&mut self) -> Option<Handle> {
+    self.nodes.next_back()
+  }
+}
+
+impl ExactSizeIterator for Children {}
+
+/// Iterates over the ancestors of a node, from its parent up to the root.
+#[derive(Debug, Clone)]
+pub struct Ancestors {
+  next: Option<Handle>,
+}
+
+impl Iterator for Ancestors {
+  type Item = Handle;
+
+  fn next(&mut self) -> Option<Handle> {
+    let node = self.next.take()?;
+    self.next = parent(&node);
+    Some(node)
+  }
+}
+
+/// Iterates over the descendants of a node in document order, not including
+/// the node itself or the (inert) contents of templates.
+#[derive(Debug, Clone)]
+pub struct Descendants {
+  stack: Vec<Handle>,
+}
+
+impl Descendants {
+  pub(crate) fn new(root: &Handle) -> Self {
+    Descendants {
+      stack: root.children.borrow().iter().rev().cloned().collect(),
+    }
+  }
+}
+
+impl Iterator for Descendants {
+  type Item = Handle;
+
+  fn next(&mut self) -> Option<Handle> {
+    let node = self.stack.pop()?;
+    self
+      .stack
+      .extend(node.children.borrow().iter().rev().cloned());
+    Some(node)
+  }
+}
+
+/// Adapters for the iterators over nodes.
+pub trait NodeIterator: Iterator<Item = Handle> + Sized {
+  /// Keeps only the elements, as [`ElementRef`]s.
+  fn elements(self) -> Elements<Self> {
+    Elements { nodes: self }
+  }
+}
+
+impl<I: Iterator<Item = Handle>> NodeIterator for I {}
+
+/// Iterates over the elements among the nodes of another iterator.
+#[derive(Debug, Clone)]
+pub struct Elements<I> {
+  nodes: I,
+}
+
+impl<I: Iterator<Item = Handle>> Iterator for Elements<I> {
+  type Item = ElementRef;
+
+  fn next(&mut self) -> Option<ElementRef> {
+    self.nodes.find_map(ElementRef::new)
+  }
+}
+
+/// A [`Handle`] to a node that is known to be an element.
+#[derive(Debug, Clone)]
+pub struct ElementRef(Handle);
+
+impl ElementRef {
+  /// Wraps `node`, or returns `None` if it is not an element.
+  pub fn new(node: Handle) -> Option<Self> {
+    match node.data {
+      NodeData::Element { .. } => Some(ElementRef(node)),
+      _ => None,
+    }
+  }
+
+  /// The handle of the element.
+  pub fn handle(&self) -> &Handle {
+    &self.0
+  }
+
+  /// Unwraps the handle of the element.
+  pub fn into_handle(self) -> Handle {
+    self.0
+  }
+
+  /// The qualified name of the element.
+  pub fn name(&self) -> &QualName {
+    match &self.0.data {
+      NodeData::Element { name, .. } => name,
+      _ => unreachable!("an ElementRef is always an element"),
+    }
+  }
+
+  /// The local name of the element, like `div`.
+  pub fn local_name(&self) -> &str {
+    &self.name().local
+  }
+
+  /// The attributes of the element. The element cannot be changed while
+  /// they are borrowed.
+  pub fn attrs(&self) -> Ref<'_, Vec<Attribute>> {
+    match &self.0.data {
+      NodeData::Element { attrs, .. } => attrs.borrow(),
+      _ => unreachable!("an ElementRef is always an element"),
+    }
+  }
+
+  /// The value of the attribute with the local name `name` and no
+  /// namespace.
+  pub fn attr(&self, name: &str) -> Option<StrTendril> {
+    self
+      .attrs()
+      .iter()
+      .find(|attr| attr.name.ns == ns!() && &*attr.name.local == name)
+      .map(|attr| attr.value.clone())
+  }
+
+  /// Whether the element has an attribute with the local name `name` and no
+  /// namespace.
+  pub fn has_attr(&self, name: &str) -> bool {
+    self
+      .attrs()
+      .iter()
+      .any(|attr| attr.name.ns == ns!() && &*attr.name.local == name)
+  }
+
+  /// The document fragment holding the contents of a `<template>`.
+  pub fn template_contents(&self) -> Option<&Handle> {
+    match &self.0.data {
+      NodeData::Element {
+        template_contents, ..
+      } => template_contents.as_ref(),
+      _ => unreachable!("an ElementRef is always an element"),
+    }
+  }
+}
+
+impl Deref for ElementRef {
+  type Target = Handle;
+
+  fn deref(&self) -> &Handle {
+    &self.0
+  }
+}
+
+impl From<ElementRef> for Handle {
+  fn from(element: ElementRef) -> Handle {
+    element.0
+  }
+}
+
+// The parent of `node` and the index of `node` among its children.
+pub(crate) fn index_in_parent(node: &Handle) -> Option<(Handle, usize)> {
+  let parent = parent(node)?;
+  let index = parent
+    .children
+    .borrow()
+    .iter()
+    .position(|child| Rc::ptr_eq(child, node))?;
+  Some((parent, index))
+}
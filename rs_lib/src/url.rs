@@ -0,0 +1,240 @@
+//! URL reference resolution.
+//!
+//! Extractors report the URLs they find resolved against the base URL of the
+//! document, the way `HTMLAnchorElement.href` does. [`resolve`] implements the
+//! reference resolution algorithm of RFC 3986 (section 5.2), after the same
+//! preprocessing browsers apply to URL attributes: leading and trailing
+//! whitespace is ignored, tabs and newlines are removed, and backslashes are
+//! treated as slashes in the URLs of the special schemes (`http`, `https`,
+//! `ws`, `wss`, `ftp` and `file`). For the special schemes, the host is also
+//! lowercased and an empty path becomes `/`.
+//!
+//! This is not a full WHATWG URL parser: hosts are not IDNA-encoded, and no
+//! percent-encoding is added or removed.
+
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+/// The schemes that WHATWG URLs give special treatment.
+pub const SPECIAL_SCHEMES: &[&str] =
+  &["ftp", "file", "http", "https", "ws", "wss"];
+
+/// Resolves `reference` against `base`, returning the absolute URL, or `None`
+/// if there is none: when `base` is not absolute itself (and `reference` is
+/// not absolute either), or when a relative reference is resolved against a
+/// URL with an opaque path, like `mailto:` or `data:` URLs.
+///
+/// ```ignore
+/// assert_eq!(
+///   resolve("https://example.com/a/b", "../c?d").as_deref(),
+///   Some("https://example.com/c?d"),
+/// );
+/// ```
+pub fn resolve(base: &str, reference: &str) -> Option<String> {
+  let base = clean(base, None);
+  let base = split(&base);
+  let reference = clean(reference, base.scheme);
+  let mut reference = split(&reference);
+
+  // in special URLs, `http:foo` is relative to an `http:` base
+  if let (Some(scheme), Some(base_scheme)) = (reference.scheme, base.scheme)
+    && reference.authority.is_none()
+    && is_special(scheme)
+    && scheme.eq_ignore_ascii_case(base_scheme)
+  {
+    reference.scheme = None;
+  }
+  if reference.scheme.is_some() {
+    let path = remove_dot_segments(&reference.path);
+    return Some(Parts { path, ..reference }.to_string());
+  }
+
+  let scheme = base.scheme?;
+  let opaque = base.authority.is_none() && !base.path.starts_with('/');
+  let fragment_only = reference.authority.is_none()
+    && reference.path.is_empty()
+    && reference.query.is_none();
+  if opaque && !fragment_only {
+    return None;
+  }
+  let target = if reference.authority.is_some() {
+    Parts {
+      scheme: Some(scheme),
+      path: remove_dot_segments(&reference.path),
+      ..reference
+    }
+  } else if reference.path.is_empty() {
+    Parts {
+      query: reference.query.or(base.query),
+      fragment: reference.fragment,
+      ..base
+    }
+  } else {
+    let path = if reference.path.starts_with('/') {
+      reference.path.into_owned()
+    } else if base.authority.is_some() && base.path.is_empty() {
+      format!("/{}", reference.path)
+    } else {
+      let directory = base.path.rfind('/').map_or("", |i| &base.path[..=i]);
+      format!("{directory}{}", reference.path)
+    };
+    Parts {
+      path: remove_dot_segments(&path),
+      query: reference.query,
+      fragment: reference.fragment,
+      ..base
+    }
+  };
+  Some(target.to_string())
+}
+
+/// Returns `true` if `url` has a scheme, i.e. does not need a base URL to be
+/// resolved.
+pub fn is_absolute(url: &str) -> bool {
+  split(&clean(url, None)).scheme.is_some()
+}
+
+/// Returns the (lowercased) host of `url`, without its userinfo and port, or
+/// `None` if the URL has no authority, like relative paths and `mailto:`
+/// URLs do. Protocol-relative URLs (`//host/path`) have one.
+pub fn host(url: &str) -> Option<String> {
+  let url = clean(url, None);
+  let authority = split(&url).authority?;
+  let host = authority
+    .rsplit_once('@')
+    .map_or(authority, |(_, host)| host);
+  // the colon of a port comes after the brackets of an IPv6 address
+  let port = host.rfind(':').filter(|&i| !host[i..].contains(']'));
+  let host = port.map_or(host, |i| &host[..i]);
+  (!host.is_empty()).then(|| host.to_ascii_lowercase())
+}
+
+fn is_special(scheme: &str) -> bool {
+  SPECIAL_SCHEMES
+    .iter()
+    .any(|special| special.eq_ignore_ascii_case(scheme))
+}
+
+// Strips surrounding whitespace and all tabs and newlines, and, in special
+// URLs, replaces backslashes before the query with slashes. Relative URLs are
+// special if `base_scheme` is.
+fn clean(url: &str, base_scheme: Option<&str>) -> String {
+  let mut url: String = url
+    .trim_matches(|c: char| c <= ' ')
+    .chars()
+    .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+    .collect();
+  let special = split(&url).scheme.or(base_scheme).is_some_and(is_special);
+  if special {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let head = url[..end].replace('\\', "/");
+    url.replace_range(..end, &head);
+  }
+  url
+}
+
+#[derive(Debug, Clone)]
+struct Parts<'a> {
+  scheme:    Option<&'a str>,
+  authority: Option<&'a str>,
+  path:      Cow<'a, str>,
+  query:     Option<&'a str>,
+  fragment:  Option<&'a str>,
+}
+
+impl core::fmt::Display for Parts<'_> {
+  fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+    let special = self.scheme.is_some_and(is_special);
+    if let Some(scheme) = self.scheme {
+      write!(f, "{}:", scheme.to_ascii_lowercase())?;
+    }
+    if let Some(authority) = self.authority {
+      if special {
+        // the userinfo keeps its case, the host (and port) do not
+        let host = authority.rfind('@').map_or(0, |i| i + 1);
+        let (userinfo, host) = authority.split_at(host);
+        write!(f, "//{userinfo}{}", host.to_ascii_lowercase())?;
+      } else {
+        write!(f, "//{authority}")?;
+      }
+    }
+    if special && self.authority.is_some() && self.path.is_empty() {
+      f.write_str("/")?;
+    }
+    f.write_str(&self.path)?;
+    if let Some(query) = self.query {
+      write!(f, "?{query}")?;
+    }
+    if let Some(fragment) = self.fragment {
+      write!(f, "#{fragment}")?;
+    }
+    Ok(())
+  }
+}
+
+// Splits a URL into its components (RFC 3986, appendix B).
+fn split(url: &str) -> Parts<'_> {
+  let (rest, fragment) = match url.split_once('#') {
+    Some((rest, fragment)) => (rest, Some(fragment)),
+    None => (url, None),
+  };
+  let (rest, query) = match rest.split_once('?') {
+    Some((rest, query)) => (rest, Some(query)),
+    None => (rest, None),
+  };
+  let scheme = rest.find([':', '/']).filter(|&end| {
+    let scheme = &rest[..end];
+    rest[end..].starts_with(':')
+      && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+      && scheme
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+  });
+  let (scheme, rest) = match scheme {
+    Some(end) => (Some(&rest[..end]), &rest[end + 1..]),
+    None => (None, rest),
+  };
+  let (authority, path) = match rest.strip_prefix("//") {
+    Some(rest) => {
+      let end = rest.find('/').unwrap_or(rest.len());
+      (Some(&rest[..end]), &rest[end..])
+    }
+    None => (None, rest),
+  };
+  Parts {
+    scheme,
+    authority,
+    path: path.into(),
+    query,
+    fragment,
+  }
+}
+
+// Removes the `.` and `..` segments of a hierarchical path (RFC 3986, section
+// 5.2.4). Opaque paths, like that of `mailto:a@b.c`, are returned as is.
+fn remove_dot_segments(path: &str) -> Cow<'static, str> {
+  let Some(rest) = path.strip_prefix('/') else {
+    return String::from(path).into();
+  };
+  let is_any = |segment: &str, dots: &[&str]| {
+    dots.iter().any(|dot| dot.eq_ignore_ascii_case(segment))
+  };
+  let segments: Vec<&str> = rest.split('/').collect();
+  let mut output: Vec<&str> = Vec::with_capacity(segments.len());
+  for (i, &segment) in segments.iter().enumerate() {
+    if is_any(segment, &["..", ".%2e", "%2e.", "%2e%2e"]) {
+      output.pop();
+    } else if !is_any(segment, &[".", "%2e"]) {
+      output.push(segment);
+      continue;
+    }
+    // a path ending in a dot segment still ends in a slash
+    if i + 1 == segments.len() {
+      output.push("");
+    }
+  }
+  format!("/{}", output.join("/")).into()
+}
@@ -0,0 +1,209 @@
+//! URL scheme filtering for URL-valued attributes.
+//!
+//! Independently of full [sanitization](crate::sanitize), [`filter_urls`]
+//! validates every URL-valued attribute (`href`, `src`, `action`, `srcset`,
+//! ...) against an allowlist of schemes, which blocks `javascript:`, `data:`
+//! and friends unless they are explicitly allowed. Offending attributes are
+//! either stripped or have their value replaced, and every violation is
+//! reported back to the caller. In `srcset` attributes, only the offending
+//! image candidates are removed.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::sanitize::is_url_attribute;
+use crate::sanitize::url_scheme;
+
+/// What to do with an attribute holding a disallowed URL.
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlViolationAction {
+  /// Remove the attribute (the default).
+  #[default]
+  Strip,
+  /// Replace the attribute's value with the policy's `replacement`.
+  Rewrite,
+}
+
+impl UrlViolationAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      UrlViolationAction::Strip => "strip",
+      UrlViolationAction::Rewrite => "rewrite",
+    }
+  }
+}
+
+/// The allowed URL schemes, and how violations are handled.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UrlPolicy {
+  /// The allowed schemes. Relative URLs are always allowed.
+  pub schemes:      Vec<String>,
+  /// What to do with attributes holding a disallowed URL.
+  pub on_violation: UrlViolationAction,
+  /// The value used in place of a disallowed URL when rewriting.
+  pub replacement:  String,
+}
+
+impl Default for UrlPolicy {
+  fn default() -> Self {
+    Self {
+      schemes:      ["http", "https", "mailto", "tel"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect(),
+      on_violation: UrlViolationAction::Strip,
+      replacement:  "#".to_string(),
+    }
+  }
+}
+
+impl UrlPolicy {
+  /// Returns `true` if `url` is relative, or its scheme is allowed.
+  pub fn allows(&self, url: &str) -> bool {
+    url_scheme(url).is_none_or(|scheme| {
+      self.schemes.iter().any(|s| s.eq_ignore_ascii_case(&scheme))
+    })
+  }
+}
+
+/// A URL-valued attribute that was stripped or rewritten by [`filter_urls`].
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlViolation {
+  /// The tag name of the element.
+  pub tag:       String,
+  /// The attribute name.
+  pub attribute: String,
+  /// The original attribute value.
+  pub value:     String,
+  /// What was done about it: `"strip"` or `"rewrite"`.
+  pub action:    &'static str,
+}
+
+/// Returns `true` if the attribute `name` holds a URL, or a list of them.
+pub fn is_url_valued(name: &str) -> bool {
+  is_url_attribute(name) || name.eq_ignore_ascii_case("srcset")
+}
+
+/// Splits a `srcset` attribute into its image candidates, returning the URL
+/// and the (possibly empty) descriptors of each one.
+pub fn srcset_candidates(value: &str) -> Vec<(&str, &str)> {
+  let mut candidates = Vec::new();
+  let mut rest = value;
+  loop {
+    rest =
+      rest.trim_start_matches(|c: char| c.is_ascii_whitespace() || c == ',');
+    if rest.is_empty() {
+      return candidates;
+    }
+    let url_end = rest
+      .find(|c: char| c.is_ascii_whitespace())
+      .unwrap_or(rest.len());
+    let url = &rest[..url_end];
+    if let Some(url) = url.strip_suffix(',') {
+      candidates.push((url.trim_end_matches(','), ""));
+      rest = &rest[url_end..];
+      continue;
+    }
+    // descriptors run until the next comma outside of parentheses
+    let after = &rest[url_end..];
+    let mut depth = 0usize;
+    let end = after
+      .char_indices()
+      .find(|&(_, c)| match c {
+        '(' => {
+          depth += 1;
+          false
+        }
+        ')' => {
+          depth = depth.saturating_sub(1);
+          false
+        }
+        ',' => depth == 0,
+        _ => false,
+      })
+      .map_or(after.len(), |(i, _)| i);
+    candidates.push((url, after[..end].trim()));
+    rest = &after[end..];
+  }
+}
+
+/// Validates the URL-valued attributes of every element below `root` against
+/// the `policy`, modifying the tree in place and returning the violations.
+pub fn filter_urls(root: &Handle, policy: &UrlPolicy) -> Vec<UrlViolation> {
+  let mut violations = Vec::new();
+  let mut stack = vec![root.clone()];
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      ..
+    } = &node.data
+    {
+      let tag = name.local.as_ref();
+      attrs.borrow_mut().retain_mut(|attr| {
+        let name = attr.name.local.as_ref();
+        if !is_url_valued(name) {
+          return true;
+        }
+        let is_srcset = name.eq_ignore_ascii_case("srcset");
+        let candidates = if is_srcset {
+          srcset_candidates(&attr.value)
+        } else {
+          vec![(attr.value.as_ref(), "")]
+        };
+        let allowed: Vec<_> = candidates
+          .iter()
+          .filter(|(url, _)| policy.allows(url))
+          .collect();
+        if allowed.len() == candidates.len() {
+          return true;
+        }
+        let mut violation = UrlViolation {
+          tag:       tag.to_string(),
+          attribute: name.to_string(),
+          value:     attr.value.to_string(),
+          action:    policy.on_violation.as_str(),
+        };
+        // a srcset keeps its allowed candidates, and is otherwise stripped
+        if is_srcset && !allowed.is_empty() {
+          let kept: Vec<String> = allowed
+            .iter()
+            .map(|(url, descriptors)| match descriptors.is_empty() {
+              true => url.to_string(),
+              false => format!("{url} {descriptors}"),
+            })
+            .collect();
+          violation.action = UrlViolationAction::Rewrite.as_str();
+          attr.value = kept.join(", ").into();
+          violations.push(violation);
+          return true;
+        }
+        violations.push(violation);
+        match policy.on_violation {
+          UrlViolationAction::Strip => false,
+          UrlViolationAction::Rewrite => {
+            attr.value = policy.replacement.as_str().into();
+            true
+          }
+        }
+      });
+      if let Some(contents) = template_contents {
+        stack.push(contents.clone());
+      }
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  violations
+}
@@ -0,0 +1,354 @@
+//! Rewriting the URLs of a document.
+//!
+//! [`rewrite_urls`] rewrites every URL of a document in one pass: the
+//! URL-valued attributes (`href`, `src`, `poster`, `action`, ...), each
+//! candidate of a `srcset`, and the `url()`s of `style` attributes and
+//! `<style>` elements. Each URL is rewritten by the first of these that
+//! applies:
+//!
+//! 1. a `map` entry for the URL, as written or resolved, replaces it;
+//! 2. a `prefix` is put in front of URLs relative to the site, e.g. for serving
+//!    `/img/a.png` from `https://cdn.example.com/img/a.png`. With a base URL,
+//!    relative paths are resolved first, so the prefix goes in front of the
+//!    full path. URLs with a scheme and protocol-relative ones (`//host/path`)
+//!    are not prefixed;
+//! 3. with a base URL (the `baseUrl`, and the `<base>` of the document),
+//!    relative URLs are made absolute, including protocol-relative ones.
+//!
+//! Fragment-only URLs (`#top`) point into the document itself wherever it
+//! is served from, so they are always kept as they are, as is the `href`
+//! of the `<base>` element.
+//!
+//! [`resolve_base`] bakes the `<base>` of a document into it instead, so
+//! that it renders the same without it: every URL is resolved against the
+//! base URL like above (with the link targets that are only a fragment
+//! too, as those point to the base URL rather than the document in
+//! browsers), the `target` of the `<base>` is given to the links and forms
+//! without one, and the `<base>` elements are removed.
+
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use html5ever::Attribute;
+use html5ever::QualName;
+use html5ever::local_name;
+use html5ever::ns;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::dom::mutation::detach;
+use crate::extract::attribute;
+use crate::extract::base_url;
+use crate::extract::descendants;
+use crate::extract::is_element;
+use crate::url;
+use crate::url_filter::is_url_valued;
+use crate::url_filter::srcset_candidates;
+
+/// How [`rewrite_urls`] rewrites URLs. See the [module level
+/// documentation](crate::url_rewrite) for how the fields apply.
+#[derive(Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(default, rename_all = "camelCase")]
+pub struct UrlRewrite {
+  /// The URL of the document, which relative URLs are resolved against.
+  pub base_url: Option<String>,
+  /// Replacements for specific URLs, as written or resolved.
+  pub map:      BTreeMap<String, String>,
+  /// The prefix put in front of URLs relative to the site.
+  pub prefix:   Option<String>,
+}
+
+impl UrlRewrite {
+  /// Rewrites a single URL, with `base` as the base URL of the document.
+  /// Returns `None` if the URL stays as it is.
+  pub fn rewrite(&self, url: &str, base: Option<&str>) -> Option<String> {
+    let url = url.trim_matches(|c: char| c <= ' ');
+    if url.is_empty() || url.starts_with('#') {
+      return None;
+    }
+    let resolved = base.and_then(|base| url::resolve(base, url));
+    let mapped = self.map.get(url).or_else(|| {
+      let resolved = resolved.as_ref()?;
+      self.map.get(resolved)
+    });
+    let rewritten = match (mapped, &self.prefix) {
+      (Some(mapped), _) => Some(mapped.clone()),
+      (None, Some(prefix)) if is_site_relative(url) => {
+        // with a base, the path is the one the URL resolves to
+        let path = resolved.as_deref().and_then(path_of).unwrap_or(url);
+        let prefix = prefix.trim_end_matches('/');
+        Some(format!("{prefix}/{}", path.trim_start_matches('/')))
+      }
+      _ => resolved,
+    };
+    rewritten.filter(|rewritten| rewritten != url)
+  }
+}
+
+/// Rewrites the URLs of the document (or fragment) `root` with `rewrite`,
+/// returning how many were rewritten.
+pub fn rewrite_urls(root: &Handle, rewrite: &UrlRewrite) -> usize {
+  let base = base_url(root, rewrite.base_url.as_deref());
+  let base = base.as_deref();
+  let mut rewritten = 0;
+  let mut stack = vec![root.clone()];
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      ..
+    } = &node.data
+    {
+      let is_base = &*name.local == "base";
+      for attr in attrs.borrow_mut().iter_mut() {
+        let name = attr.name.local.as_ref();
+        let value = if name.eq_ignore_ascii_case("srcset") {
+          rewrite_srcset(&attr.value, rewrite, base, &mut rewritten)
+        } else if name.eq_ignore_ascii_case("style") {
+          rewrite_css(&attr.value, rewrite, base, &mut rewritten)
+        } else if is_url_valued(name) && !is_base {
+          rewrite
+            .rewrite(&attr.value, base)
+            .inspect(|_| rewritten += 1)
+        } else {
+          None
+        };
+        if let Some(value) = value {
+          attr.value = value.into();
+        }
+      }
+      if &*name.local == "style" {
+        for child in node.children.borrow().iter() {
+          if let NodeData::Text { contents } = &child.data {
+            let css = contents.borrow().to_string();
+            if let Some(css) = rewrite_css(&css, rewrite, base, &mut rewritten)
+            {
+              *contents.borrow_mut() = css.into();
+            }
+          }
+        }
+      }
+      if let Some(contents) = template_contents {
+        stack.push(contents.clone());
+      }
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  rewritten
+}
+
+/// What [`resolve_base`] did.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseResolution {
+  /// The absolute base URL the URLs were resolved against, if any.
+  pub base_url: Option<String>,
+  /// How many URLs were resolved.
+  pub resolved: usize,
+  /// How many links and forms were given the `target` of the `<base>`.
+  pub targeted: usize,
+  /// How many `<base>` elements were removed.
+  pub removed:  usize,
+}
+
+/// Resolves the URLs of the document rooted at `document` against its
+/// `<base>` (relative to `document_url`, the URL of the document itself),
+/// gives its `target` to the links and forms without one, and removes the
+/// `<base>` elements. A `<base>` whose `href` cannot be made absolute (when
+/// it is relative, and no `document_url` is given) is left in place, and
+/// nothing is changed.
+pub fn resolve_base(
+  document: &Handle,
+  document_url: Option<&str>,
+) -> BaseResolution {
+  let bases: Vec<Handle> = descendants(document)
+    .filter(|node| is_element(node, "base"))
+    .collect();
+  let has_href = bases.iter().any(|base| attribute(base, "href").is_some());
+  let base = base_url(document, document_url);
+  if has_href && base.is_none() {
+    return BaseResolution::default();
+  }
+  let target = bases
+    .iter()
+    .find_map(|base| attribute(base, "target"))
+    .filter(|target| !target.trim().is_empty());
+
+  let mut resolution = BaseResolution::default();
+  if has_href && let Some(base) = &base {
+    resolution.resolved = rewrite_urls(
+      document,
+      &UrlRewrite {
+        base_url: document_url.map(String::from),
+        ..Default::default()
+      },
+    );
+    // fragments point to the base URL, unless it is the document itself
+    let is_document = |url: &str| {
+      let without_fragment = |url: &str| {
+        String::from(url.split_once('#').map_or(url, |(url, _)| url))
+      };
+      without_fragment(url) == without_fragment(base)
+    };
+    if document_url.is_none_or(|url| !is_document(url)) {
+      resolution.resolved += resolve_fragments(document, base);
+    }
+  }
+  if let Some(target) = target {
+    for node in descendants(document) {
+      let NodeData::Element { name, attrs, .. } = &node.data else {
+        continue;
+      };
+      if name.ns != ns!(html)
+        || !matches!(&*name.local, "a" | "area" | "form")
+        || attribute(&node, "target").is_some()
+      {
+        continue;
+      }
+      attrs.borrow_mut().push(Attribute {
+        name:  QualName::new(None, ns!(), local_name!("target")),
+        value: target.clone(),
+      });
+      resolution.targeted += 1;
+    }
+  }
+  for base in &bases {
+    detach(base);
+  }
+  resolution.removed = bases.len();
+  resolution.base_url = base.filter(|_| has_href);
+  resolution
+}
+
+// Resolves the `href`s of links (and the `action`s of forms) that are only
+// a fragment against `base`, returning how many there were.
+fn resolve_fragments(root: &Handle, base: &str) -> usize {
+  let mut resolved = 0;
+  let mut stack = vec![root.clone()];
+  while let Some(node) = stack.pop() {
+    if let NodeData::Element {
+      name,
+      attrs,
+      template_contents,
+      ..
+    } = &node.data
+    {
+      let url_attribute = match &*name.local {
+        "a" | "area" => "href",
+        "form" => "action",
+        _ => "",
+      };
+      for attr in attrs.borrow_mut().iter_mut() {
+        if *attr.name.local == *url_attribute
+          && attr.value.trim_start().starts_with('#')
+          && let Some(url) = url::resolve(base, &attr.value)
+        {
+          attr.value = url.into();
+          resolved += 1;
+        }
+      }
+      if let Some(contents) = template_contents {
+        stack.push(contents.clone());
+      }
+    }
+    stack.extend(node.children.borrow().iter().rev().cloned());
+  }
+  resolved
+}
+
+// Whether `url` is relative to the site it is served from, rather than
+// having a scheme or host of its own.
+fn is_site_relative(url: &str) -> bool {
+  !url::is_absolute(url) && !url.starts_with("//") && !url.starts_with("\\\\")
+}
+
+// The path (with query and fragment) of an absolute URL with a host.
+fn path_of(url: &str) -> Option<&str> {
+  let (_, rest) = url.split_once("://")?;
+  Some(rest.find('/').map_or("", |start| &rest[start..]))
+}
+
+// Rewrites the candidates of a `srcset`, returning `None` if none changed.
+fn rewrite_srcset(
+  value: &str,
+  rewrite: &UrlRewrite,
+  base: Option<&str>,
+  rewritten: &mut usize,
+) -> Option<String> {
+  let mut changed = false;
+  let candidates: Vec<String> = srcset_candidates(value)
+    .into_iter()
+    .map(|(url, descriptors)| {
+      let url = match rewrite.rewrite(url, base) {
+        Some(new) => {
+          changed = true;
+          *rewritten += 1;
+          new
+        }
+        None => url.to_string(),
+      };
+      match descriptors.is_empty() {
+        true => url,
+        false => format!("{url} {descriptors}"),
+      }
+    })
+    .collect();
+  changed.then(|| candidates.join(", "))
+}
+
+// Rewrites the `url()`s of a style sheet or `style` attribute, keeping their
+// quotes, and returning `None` if none changed.
+fn rewrite_css(
+  css: &str,
+  rewrite: &UrlRewrite,
+  base: Option<&str>,
+  rewritten: &mut usize,
+) -> Option<String> {
+  let mut output = String::with_capacity(css.len());
+  let mut changed = false;
+  let mut rest = css;
+  while let Some(start) = rest
+    .as_bytes()
+    .windows(4)
+    .position(|window| window.eq_ignore_ascii_case(b"url("))
+  {
+    let (before, after) = rest.split_at(start + 4);
+    output.push_str(before);
+    let end = after.find(')').unwrap_or(after.len());
+    let argument = &after[..end];
+    let trimmed = argument.trim();
+    let quote = trimmed.chars().next().filter(|c| matches!(c, '"' | '\''));
+    let url = match quote {
+      Some(quote) => trimmed.trim_matches(quote),
+      None => trimmed,
+    };
+    match rewrite.rewrite(url, base) {
+      Some(new) => {
+        changed = true;
+        *rewritten += 1;
+        let needs_quotes = new
+          .contains(|c: char| c.is_ascii_whitespace() || "()'\"\\".contains(c));
+        match quote.or(needs_quotes.then_some('"')) {
+          Some(quote) => {
+            let escaped = new
+              .replace('\\', "\\\\")
+              .replace(quote, &format!("\\{quote}"));
+            output.push_str(&format!("{quote}{escaped}{quote}"));
+          }
+          None => output.push_str(&new),
+        }
+      }
+      None => output.push_str(argument),
+    }
+    rest = &after[end..];
+  }
+  output.push_str(rest);
+  changed.then_some(output)
+}
@@ -0,0 +1,202 @@
+//! Integrity checks for [`WireDoc`]s.
+//!
+//! The parser always emits well-formed documents, but a [`WireDoc`] from
+//! anywhere else (a cache, another process, a producer that is not trusted)
+//! may not be, and code walking its nodes would then index out of bounds or
+//! loop forever. [`validate_wire`] checks that:
+//!
+//! - node `0` is a document or a fragment, and every node has its index in
+//!   `nodes` as its id;
+//! - every string index is below `strings_offset` plus the number of `strings`
+//!   (so that documents of a [`ParseSession`](crate::ParseSession) may refer to
+//!   the strings shipped with the ones before);
+//! - every link to a node is to a node of the document, and the
+//!   `templateContent` and `contentDocument` links are to fragments and
+//!   documents;
+//! - the `firstChild` and `nextSibling` links, followed from node `0` and the
+//!   roots it links to, reach every node exactly once, each from the node it
+//!   has as its `parentNode`, and at the `index` it has, if any.
+//!
+//! Together, the last two mean that the links make up a set of trees, with
+//! no cycles. Documents that pass can be walked, and loaded with
+//! [`wire_to_dom`](crate::wire_to_dom), without further checks.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+
+use crate::WireDoc;
+use crate::WireNode;
+use crate::WireNodeType;
+use crate::error::DawmError;
+use crate::error::Result;
+
+/// Checks the integrity of `doc` as described in the [module](self) docs,
+/// failing with a [`DawmError::InvalidWire`] for the first problem found.
+pub fn validate_wire(doc: &WireDoc) -> Result<()> {
+  let invalid = |message: String| Err(DawmError::InvalidWire(message));
+  let Some(root) = doc.node(0) else {
+    return invalid("there is no node 0".into());
+  };
+  if !is_root_type(root.node_type) {
+    return invalid(format!(
+      "node 0 is of the type {:?}, not a document or fragment",
+      root.node_type
+    ));
+  }
+
+  for (id, node) in doc.nodes.iter().enumerate() {
+    if node.id as usize != id {
+      return invalid(format!("node {id} has the id {}", node.id));
+    }
+    check_strings(doc, node)?;
+    check_links(doc, node)?;
+  }
+
+  // walk the trees, from node 0 and the roots it links to
+  let mut reached = vec![false; doc.nodes.len()];
+  let mut pending = vec![0];
+  reached[0] = true;
+  while let Some(id) = pending.pop() {
+    let node = &doc.nodes[id as usize];
+    let mut index = 0;
+    let mut next = node.first_child;
+    while let Some(child_id) = next {
+      let child = &doc.nodes[child_id as usize];
+      if core::mem::replace(&mut reached[child_id as usize], true) {
+        return invalid(format!("node {child_id} is reached more than once"));
+      }
+      if child.parent != Some(id) {
+        return invalid(format!(
+          "node {child_id} is a child of node {id}, but its parent is {}",
+          describe(child.parent)
+        ));
+      }
+      if child.index.is_some_and(|child_index| child_index != index) {
+        return invalid(format!(
+          "node {child_id} is child {index} of node {id}, but its index is {}",
+          describe(child.index)
+        ));
+      }
+      pending.push(child_id);
+      index += 1;
+      next = child.next_sibling;
+    }
+    for root_id in [node.template_content, node.content_document]
+      .into_iter()
+      .flatten()
+    {
+      let root = &doc.nodes[root_id as usize];
+      if core::mem::replace(&mut reached[root_id as usize], true) {
+        return invalid(format!("node {root_id} is reached more than once"));
+      }
+      if root.parent.is_some() || root.next_sibling.is_some() {
+        return invalid(format!(
+          "node {root_id} is the root of node {id}, but has a parent or next \
+           sibling"
+        ));
+      }
+      pending.push(root_id);
+    }
+  }
+  if root.next_sibling.is_some() {
+    return invalid("node 0 has a next sibling".into());
+  }
+  match reached.iter().position(|reached| !reached) {
+    Some(id) => invalid(format!("node {id} is not reached from node 0")),
+    None => Ok(()),
+  }
+}
+
+fn is_root_type(node_type: WireNodeType) -> bool {
+  matches!(
+    node_type,
+    WireNodeType::Document | WireNodeType::DocumentFragment
+  )
+}
+
+fn describe(id: Option<u32>) -> String {
+  match id {
+    Some(id) => format!("{id}"),
+    None => "none".into(),
+  }
+}
+
+fn check_strings(doc: &WireDoc, node: &WireNode) -> Result<()> {
+  let end = doc.strings_offset as usize + doc.strings.len();
+  let attrs = node.attrs.iter().flatten();
+  let attrs =
+    attrs.flat_map(|attr| [attr.ns, Some(attr.name), Some(attr.value)]);
+  let handlers = node.stripped_handlers.iter().flatten().copied().map(Some);
+  let indices = [node.name, node.value, node.namespace]
+    .into_iter()
+    .chain(attrs)
+    .chain(handlers)
+    .flatten();
+  for index in indices {
+    if index as usize >= end {
+      return Err(DawmError::InvalidWire(format!(
+        "node {} refers to string {index}, but there are only {end}",
+        node.id
+      )));
+    }
+  }
+  Ok(())
+}
+
+fn check_links(doc: &WireDoc, node: &WireNode) -> Result<()> {
+  let links = [
+    ("parentNode", node.parent, None),
+    ("firstChild", node.first_child, None),
+    ("nextSibling", node.next_sibling, None),
+    (
+      "templateContent",
+      node.template_content,
+      Some(WireNodeType::DocumentFragment),
+    ),
+    (
+      "contentDocument",
+      node.content_document,
+      Some(WireNodeType::Document),
+    ),
+    (
+      "ownerDocument",
+      node.owner_document,
+      Some(WireNodeType::Document),
+    ),
+  ];
+  for (field, link, node_type) in links {
+    let Some(id) = link else { continue };
+    let Some(target) = doc.node(id) else {
+      return Err(DawmError::InvalidWire(format!(
+        "the {field} of node {} is node {id}, which does not exist",
+        node.id
+      )));
+    };
+    if node_type.is_some_and(|node_type| node_type != target.node_type) {
+      return Err(DawmError::InvalidWire(format!(
+        "the {field} of node {} is node {id}, of the type {:?}",
+        node.id, target.node_type
+      )));
+    }
+  }
+  let has_children = matches!(
+    node.node_type,
+    WireNodeType::Element
+      | WireNodeType::Document
+      | WireNodeType::DocumentFragment
+  );
+  if node.first_child.is_some() && !has_children {
+    return Err(DawmError::InvalidWire(format!(
+      "node {} is of the type {:?}, but has children",
+      node.id, node.node_type
+    )));
+  }
+  if node.parent.is_some() && is_root_type(node.node_type) {
+    return Err(DawmError::InvalidWire(format!(
+      "node {} is of the type {:?}, but has a parent",
+      node.id, node.node_type
+    )));
+  }
+  Ok(())
+}
@@ -0,0 +1,322 @@
+//! Visitors over the tree of an [`RcDom`](crate::RcDom).
+//!
+//! Most passes over a document only care about some kinds of nodes, and
+//! otherwise walk the tree like every other pass does. The [`Visit`] trait
+//! captures the per-node part of such a pass, with a method for each kind of
+//! node that defaults to doing nothing, while [`walk`] owns the traversal.
+//! [`VisitMut`] and [`walk_mut`] do the same for passes that change the tree:
+//! their methods return an [`Action`] that can also remove or replace the
+//! node, which the walk applies once the method has returned.
+//!
+//! ```
+//! use dawm::Handle;
+//! use dawm::RcDom;
+//! use dawm::visit::Action;
+//! use dawm::visit::VisitMut;
+//! use dawm::visit::walk_mut;
+//! use html5ever::tendril::TendrilSink;
+//!
+//! // Unwraps the `<span>`s of a document.
+//! struct Unspan;
+//!
+//! impl VisitMut for Unspan {
+//!   fn enter_element(&mut self, element: &Handle) -> Action {
+//!     match dawm::extract::is_element(element, "span") {
+//!       true => Action::Unwrap,
+//!       false => Action::Continue,
+//!     }
+//!   }
+//! }
+//!
+//! let dom = html5ever::parse_fragment(
+//!   RcDom::default(),
+//!   Default::default(),
+//!   html5ever::QualName::new(None, html5ever::ns!(html), "div".into()),
+//!   Vec::new(),
+//!   false,
+//! )
+//! .one("<p><span>a<span>b</span></span>c</p>");
+//! walk_mut(&dom.document, &mut Unspan);
+//! let html = dawm::inner_html(&dom.document.children.borrow()[0]);
+//! assert_eq!(html, "<p>abc</p>");
+//! ```
+//!
+//! The walks visit the nodes below the root in document order, taking the
+//! children of each node as they are when the node is entered. Changes a
+//! visitor makes to the node it is handed and to its children are seen by
+//! the walk, while the other siblings it changes are not. The (inert)
+//! contents of templates are only visited when the visitor asks for them,
+//! with [`Visit::enter_templates`] or [`VisitMut::enter_templates`], right
+//! after the children of the template.
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::traverse::index_in_parent;
+
+/// What [`walk`] does after visiting a node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Control {
+  /// Goes on with the children of the node, and then its next sibling.
+  #[default]
+  Continue,
+  /// Goes on with the next sibling of the node, leaving out its children
+  /// (but still leaving the element).
+  SkipChildren,
+  /// Ends the walk.
+  Stop,
+}
+
+/// Receives the nodes below the root of a [`walk`], in document order.
+pub trait Visit {
+  /// Called for an element, before its children.
+  fn enter_element(&mut self, element: &Handle) -> Control {
+    let _ = element;
+    Control::Continue
+  }
+
+  /// Called for an element, after its children.
+  fn leave_element(&mut self, element: &Handle) -> Control {
+    let _ = element;
+    Control::Continue
+  }
+
+  /// Called for a text node.
+  fn visit_text(&mut self, text: &Handle) -> Control {
+    let _ = text;
+    Control::Continue
+  }
+
+  /// Called for a comment.
+  fn visit_comment(&mut self, comment: &Handle) -> Control {
+    let _ = comment;
+    Control::Continue
+  }
+
+  /// Called for a doctype.
+  fn visit_doctype(&mut self, doctype: &Handle) -> Control {
+    let _ = doctype;
+    Control::Continue
+  }
+
+  /// Called for a processing instruction.
+  fn visit_processing_instruction(&mut self, instruction: &Handle) -> Control {
+    let _ = instruction;
+    Control::Continue
+  }
+
+  /// Whether to visit the contents of templates.
+  fn enter_templates(&self) -> bool {
+    false
+  }
+}
+
+/// What [`walk_mut`] does after visiting a node.
+#[derive(Debug, Clone, Default)]
+pub enum Action {
+  /// Goes on with the children of the node, and then its next sibling.
+  #[default]
+  Continue,
+  /// Goes on with the next sibling of the node, leaving out its children
+  /// (but still leaving the element).
+  SkipChildren,
+  /// Ends the walk.
+  Stop,
+  /// Removes the node, and goes on with its next sibling.
+  Remove,
+  /// Puts these nodes in the place of the node, and goes on after them. The
+  /// nodes are taken out of wherever they are first, and are not visited.
+  Replace(Vec<Handle>),
+  /// Puts the children of the node in its place, and goes on with them (or
+  /// after them, when leaving an element). Removes nodes without children.
+  Unwrap,
+}
+
+/// Receives the nodes below the root of a [`walk_mut`], in document order,
+/// to change them.
+pub trait VisitMut {
+  /// Called for an element, before its children.
+  fn enter_element(&mut self, element: &Handle) -> Action {
+    let _ = element;
+    Action::Continue
+  }
+
+  /// Called for an element, after its children.
+  fn leave_element(&mut self, element: &Handle) -> Action {
+    let _ = element;
+    Action::Continue
+  }
+
+  /// Called for a text node.
+  fn visit_text(&mut self, text: &Handle) -> Action {
+    let _ = text;
+    Action::Continue
+  }
+
+  /// Called for a comment.
+  fn visit_comment(&mut self, comment: &Handle) -> Action {
+    let _ = comment;
+    Action::Continue
+  }
+
+  /// Called for a doctype.
+  fn visit_doctype(&mut self, doctype: &Handle) -> Action {
+    let _ = doctype;
+    Action::Continue
+  }
+
+  /// Called for a processing instruction.
+  fn visit_processing_instruction(&mut self, instruction: &Handle) -> Action {
+    let _ = instruction;
+    Action::Continue
+  }
+
+  /// Whether to visit the contents of templates.
+  fn enter_templates(&self) -> bool {
+    false
+  }
+}
+
+enum Step {
+  Enter(Handle),
+  Leave(Handle),
+}
+
+/// Feeds the nodes below `root` to `visitor`. Returns [`Control::Stop`] if
+/// the visitor ended the walk, and [`Control::Continue`] otherwise.
+pub fn walk<V: Visit + ?Sized>(root: &Handle, visitor: &mut V) -> Control {
+  let mut stack = Vec::new();
+  push_children(&mut stack, root);
+  while let Some(step) = stack.pop() {
+    let control = match &step {
+      Step::Enter(node) => match &node.data {
+        NodeData::Element { .. } => {
+          let control = visitor.enter_element(node);
+          if control != Control::Stop {
+            stack.push(Step::Leave(node.clone()));
+          }
+          if control == Control::Continue {
+            push_contents(&mut stack, node, visitor.enter_templates());
+          }
+          control
+        }
+        NodeData::Text { .. } => visitor.visit_text(node),
+        NodeData::Comment { .. } => visitor.visit_comment(node),
+        NodeData::Doctype { .. } => visitor.visit_doctype(node),
+        NodeData::ProcessingInstruction { .. } => {
+          visitor.visit_processing_instruction(node)
+        }
+        NodeData::Document => {
+          push_children(&mut stack, node);
+          Control::Continue
+        }
+      },
+      Step::Leave(node) => visitor.leave_element(node),
+    };
+    if control == Control::Stop {
+      return Control::Stop;
+    }
+  }
+  Control::Continue
+}
+
+/// Feeds the nodes below `root` to `visitor`, applying the [`Action`]s it
+/// returns. Returns [`Control::Stop`] if the visitor ended the walk, and
+/// [`Control::Continue`] otherwise.
+pub fn walk_mut<V: VisitMut + ?Sized>(
+  root: &Handle,
+  visitor: &mut V,
+) -> Control {
+  let mut stack = Vec::new();
+  push_children(&mut stack, root);
+  while let Some(step) = stack.pop() {
+    let (node, action) = match step {
+      Step::Enter(node) => {
+        let action = match &node.data {
+          NodeData::Element { .. } => visitor.enter_element(&node),
+          NodeData::Text { .. } => visitor.visit_text(&node),
+          NodeData::Comment { .. } => visitor.visit_comment(&node),
+          NodeData::Doctype { .. } => visitor.visit_doctype(&node),
+          NodeData::ProcessingInstruction { .. } => {
+            visitor.visit_processing_instruction(&node)
+          }
+          NodeData::Document => Action::Continue,
+        };
+        let is_element = matches!(node.data, NodeData::Element { .. });
+        match action {
+          Action::Continue | Action::SkipChildren if is_element => {
+            stack.push(Step::Leave(node.clone()));
+          }
+          _ => {}
+        }
+        match action {
+          Action::Continue => {
+            push_contents(&mut stack, &node, visitor.enter_templates());
+          }
+          Action::Unwrap => push_children(&mut stack, &node),
+          _ => {}
+        }
+        (node, action)
+      }
+      Step::Leave(node) => {
+        let action = visitor.leave_element(&node);
+        (node, action)
+      }
+    };
+    match action {
+      Action::Continue | Action::SkipChildren => {}
+      Action::Stop => return Control::Stop,
+      Action::Remove => replace_with(&node, Vec::new()),
+      Action::Replace(nodes) => replace_with(&node, nodes),
+      Action::Unwrap => {
+        let children = node.children.take();
+        for child in &children {
+          child.parent.set(None);
+        }
+        replace_with(&node, children);
+      }
+    }
+  }
+  Control::Continue
+}
+
+// Pushes the steps that enter the children of `node`, so that they are
+// popped in order.
+fn push_children(stack: &mut Vec<Step>, node: &Handle) {
+  let children = node.children.borrow();
+  stack.extend(children.iter().rev().cloned().map(Step::Enter));
+}
+
+// Pushes the steps that enter the children of `node` and, with `templates`,
+// those of its template contents after them.
+fn push_contents(stack: &mut Vec<Step>, node: &Handle, templates: bool) {
+  if templates
+    && let NodeData::Element {
+      template_contents: Some(contents),
+      ..
+    } = &node.data
+  {
+    push_children(stack, contents);
+  }
+  push_children(stack, node);
+}
+
+// Puts `nodes` in the place of `node` in its parent, leaving `node` detached.
+fn replace_with(node: &Handle, nodes: Vec<Handle>) {
+  for replacement in &nodes {
+    if let Some((parent, index)) = index_in_parent(replacement) {
+      parent.children.borrow_mut().remove(index);
+    }
+    replacement.parent.set(None);
+  }
+  let Some((parent, index)) = index_in_parent(node) else {
+    return;
+  };
+  for replacement in &nodes {
+    replacement.parent.set(Some(Rc::downgrade(&parent)));
+  }
+  parent.children.borrow_mut().splice(index..=index, nodes);
+  node.parent.set(None);
+}
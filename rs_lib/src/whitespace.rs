@@ -0,0 +1,240 @@
+//! Inter-element whitespace collapsing.
+//!
+//! Most of the text nodes of a typical document hold nothing but the
+//! newlines and indentation between its tags, which the renderer throws
+//! away, and which still take a node (and the bytes of their text) each in
+//! the output. [`collapse_whitespace`] removes the whitespace that does not
+//! render, following the white space processing rules of CSS for elements
+//! with the default styles:
+//!
+//! - runs of whitespace in text collapse into a single space;
+//! - whitespace at the start and end of a block (like `<div>` or `<li>`), and
+//!   next to the blocks in it, is removed;
+//! - whitespace right after whitespace is removed, i.e. of `a <!-- x --> b`
+//!   only the first space is kept;
+//! - whitespace between inline content, like in `<b>a</b> <i>b</i>`, is kept as
+//!   a single space.
+//!
+//! The contents of the elements whose whitespace is significant are left
+//! alone: `<pre>`, `<textarea>`, `<listing>` and `<plaintext>`, the raw text
+//! elements (`<script>`, `<style>` and the like), SVG and MathML content,
+//! and the elements with an inline `white-space` style that preserves it.
+//! The contents of templates are collapsed like any other element.
+//!
+//! This runs as part of the parse pipeline with the `collapseWhitespace`
+//! option, right before the document is serialized (so the output itself is
+//! smaller, not just the HTML it serializes to), or on its own with
+//! `collapse_whitespace`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use html5ever::ns;
+
+use crate::Handle;
+use crate::NodeData;
+use crate::extract::attribute;
+use crate::style::parse_declarations;
+
+/// Collapses the insignificant whitespace of the text below `root`, and
+/// removes the text nodes that are left empty, returning how many bytes of
+/// whitespace were removed.
+pub fn collapse_whitespace(root: &Handle) -> usize {
+  let block = match &root.data {
+    NodeData::Element { name, .. } => is_block(&name.local),
+    _ => true,
+  };
+  collapse_children(root, block)
+}
+
+// Collapses the whitespace of the children of `parent`, whose start and end
+// are block boundaries if `block` is set.
+fn collapse_children(parent: &Handle, block: bool) -> usize {
+  let mut removed = 0;
+  let children = parent.children.take();
+  let mut kept: Vec<Handle> = Vec::with_capacity(children.len());
+  // whether whitespace here would follow a boundary or other whitespace
+  let mut after_space = block;
+  for (i, child) in children.iter().enumerate() {
+    match &child.data {
+      NodeData::Text { contents } => {
+        let before_boundary = match next_content(&children[i + 1..]) {
+          Some(next) => is_boundary(next),
+          None => block,
+        };
+        let text = contents.borrow();
+        let collapsed = collapse(&text, after_space, before_boundary);
+        removed += text.len() - collapsed.len();
+        drop(text);
+        if collapsed.is_empty() {
+          child.parent.set(None);
+          continue;
+        }
+        after_space = collapsed.ends_with(' ');
+        *contents.borrow_mut() = collapsed.into();
+      }
+      NodeData::Element {
+        name,
+        template_contents,
+        ..
+      } => {
+        let block = is_boundary(child);
+        if name.ns == ns!(html) && !preserves(child) {
+          removed += collapse_children(child, block);
+          if let Some(contents) = template_contents {
+            removed += collapse_children(contents, true);
+          }
+        }
+        after_space = block;
+      }
+      _ => {}
+    }
+    kept.push(child.clone());
+  }
+  *parent.children.borrow_mut() = kept;
+  removed
+}
+
+// Collapses the whitespace of `text`, dropping the space it would start with
+// if `after_space` is set, and the one it would end with if `before_boundary`
+// is.
+fn collapse(text: &str, after_space: bool, before_boundary: bool) -> String {
+  let mut collapsed = String::with_capacity(text.len());
+  let mut space = after_space;
+  for c in text.chars() {
+    if c.is_ascii_whitespace() {
+      if !space {
+        collapsed.push(' ');
+      }
+      space = true;
+    } else {
+      collapsed.push(c);
+      space = false;
+    }
+  }
+  if before_boundary && collapsed.ends_with(' ') {
+    collapsed.pop();
+  }
+  collapsed
+}
+
+// The first of `nodes` that is rendered, i.e. not a comment or processing
+// instruction.
+fn next_content(nodes: &[Handle]) -> Option<&Handle> {
+  nodes.iter().find(|node| {
+    matches!(node.data, NodeData::Text { .. } | NodeData::Element { .. })
+  })
+}
+
+// Whether whitespace next to `node` does not render: the node is a block, or
+// is not rendered at all.
+fn is_boundary(node: &Handle) -> bool {
+  match &node.data {
+    NodeData::Element { name, .. } => {
+      name.ns == ns!(html) && is_block(&name.local)
+    }
+    _ => false,
+  }
+}
+
+// Whether the whitespace in `element` is significant.
+fn preserves(element: &Handle) -> bool {
+  let NodeData::Element { name, .. } = &element.data else {
+    return false;
+  };
+  if matches!(
+    &*name.local,
+    "pre"
+      | "textarea"
+      | "listing"
+      | "plaintext"
+      | "xmp"
+      | "script"
+      | "style"
+      | "iframe"
+      | "noembed"
+      | "noframes"
+      | "noscript"
+  ) {
+    return true;
+  }
+  attribute(element, "style").is_some_and(|style| {
+    parse_declarations(&style).iter().any(|declaration| {
+      declaration.name == "white-space"
+        && !matches!(&*declaration.value, "normal" | "nowrap")
+    })
+  })
+}
+
+// Whether an HTML element is displayed as a block (or not at all) by
+// default, so that the whitespace around it does not render.
+fn is_block(name: &str) -> bool {
+  matches!(
+    name,
+    "address"
+      | "area"
+      | "article"
+      | "aside"
+      | "base"
+      | "blockquote"
+      | "body"
+      | "caption"
+      | "col"
+      | "colgroup"
+      | "datalist"
+      | "dd"
+      | "details"
+      | "dialog"
+      | "dir"
+      | "div"
+      | "dl"
+      | "dt"
+      | "fieldset"
+      | "figcaption"
+      | "figure"
+      | "footer"
+      | "form"
+      | "frame"
+      | "frameset"
+      | "h1"
+      | "h2"
+      | "h3"
+      | "h4"
+      | "h5"
+      | "h6"
+      | "head"
+      | "header"
+      | "hgroup"
+      | "hr"
+      | "html"
+      | "legend"
+      | "li"
+      | "link"
+      | "main"
+      | "menu"
+      | "meta"
+      | "nav"
+      | "ol"
+      | "optgroup"
+      | "option"
+      | "p"
+      | "param"
+      | "script"
+      | "search"
+      | "section"
+      | "source"
+      | "style"
+      | "summary"
+      | "table"
+      | "tbody"
+      | "td"
+      | "template"
+      | "tfoot"
+      | "th"
+      | "thead"
+      | "title"
+      | "tr"
+      | "track"
+      | "ul"
+  )
+}
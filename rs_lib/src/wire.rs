@@ -1,13 +1,51 @@
+//! The wire format of parsed documents.
+//!
+//! A [`WireDoc`] is a flat representation of a parsed tree: every string is
+//! stored once in `strings`, and the nodes in `nodes` refer to each other and
+//! to their strings by index. It is what the `parse_*` functions hand to JS,
+//! and what [`parse_wire_doc`](crate::parse_wire_doc) returns to Rust.
+//!
+//! The serde representation of these types is the schema other tools can
+//! rely on, for the [`WIRE_VERSION`] they were written against:
+//!
+//! - keys are the camelCase names of the fields, except where noted;
+//! - optional keys are left out rather than set to `null`, as are `false`
+//!   flags and zero counts that say so; readers must treat a missing key as
+//!   its default;
+//! - readers must ignore keys they do not know, which later versions may add
+//!   without bumping [`WIRE_VERSION`];
+//! - node types are the numbers of the DOM's `Node.nodeType`.
+//!
+//! Anything else, like renaming or removing a key or changing what it means,
+//! comes with a new [`WIRE_VERSION`].
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
 use derive_more::with_trait::Debug;
 use derive_more::with_trait::Into;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_repr::Deserialize_repr;
 use serde_repr::Serialize_repr;
+#[cfg(feature = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use moos::CowStr;
 
+use crate::InternerStats;
+use crate::QuirksMode;
+use crate::diagnostics::ParseError;
+use crate::encoding::SniffedEncoding;
+
+/// The version of the wire format of parsed documents, incremented
+/// whenever a change to it would break existing readers.
+pub const WIRE_VERSION: u32 = 1;
+
+/// The type of a [`WireNode`], numbered like the DOM's `Node.nodeType`.
+/// Only elements, text, comments, processing instructions, documents,
+/// document fragments and doctypes are ever emitted.
 #[derive(
   Serialize_repr,
   Deserialize_repr,
@@ -19,7 +57,7 @@ use moos::CowStr;
   Hash,
   Default,
 )]
-#[wasm_bindgen(js_name = NodeType)]
+#[cfg_attr(feature = "wasm", wasm_bindgen(js_name = NodeType))]
 #[repr(u8)]
 pub enum WireNodeType {
   #[default]
@@ -37,47 +75,300 @@ pub enum WireNodeType {
   Notation = 12,
 }
 
+/// An attribute of an element, or of the doctype. The strings are indices
+/// into the `strings` of the [`WireDoc`].
 #[derive(
   Serialize, Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq, Hash,
 )]
 #[serde(default, rename_all = "camelCase")]
-pub(crate) struct WireAttr {
+pub struct WireAttr {
+  /// The string of the namespace URL, for an attribute in a namespace.
   #[serde(skip_serializing_if = "Option::is_none")]
-  pub(crate) ns:    Option<u32>, // namespace url idx
-  pub(crate) name:  u32, // string idx
-  pub(crate) value: u32, // string idx
+  pub ns:    Option<u32>,
+  /// The string of the local name.
+  pub name:  u32,
+  /// The string of the value.
+  pub value: u32,
 }
 
+/// A node of a [`WireDoc`]. Strings are indices into the `strings` of the
+/// document, and nodes are referred to by their ids.
 #[derive(
   Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash,
 )]
 #[serde(default, rename_all = "camelCase")]
-pub(crate) struct WireNode {
-  pub(crate) id:           u32,
-  pub(crate) node_type:    WireNodeType,
+pub struct WireNode {
+  /// The id of the node, which is its index in `nodes`. Nodes are numbered
+  /// in tree order, with the contents of a template following the
+  /// template's descendants.
+  pub id:                u32,
+  pub node_type:         WireNodeType,
+  /// The string of the name (`nodeName`): the qualified name of an element,
+  /// the target of a processing instruction, the name of a doctype, or
+  /// `#text`, `#comment`, `#document` and `#document-fragment`.
   #[serde(rename = "nodeName")]
-  pub(crate) name:         Option<u32>, // Element/PI name (string idx)
+  pub name:              Option<u32>,
+  /// The string of the namespace (`namespaceURI`) of an element, left out
+  /// for elements in no namespace.
+  #[serde(rename = "namespaceURI", skip_serializing_if = "Option::is_none")]
+  pub namespace:         Option<u32>,
+  /// The string of the value (`nodeValue`) of a text node, comment or
+  /// processing instruction, or the serialized doctype without its
+  /// `<!DOCTYPE` and `>` (e.g. `html PUBLIC "..." "..."`).
   #[serde(rename = "nodeValue", skip_serializing_if = "Option::is_none")]
-  pub(crate) value:        Option<u32>, // Text/Comment/PI (string idx)
+  pub value:             Option<u32>,
+  /// The id of the parent (`parentNode`), which is `null` for the roots.
   #[serde(rename = "parentNode")]
-  pub(crate) parent:       Option<u32>,
+  pub parent:            Option<u32>,
+  /// The id of the first child.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub first_child:       Option<u32>,
+  /// The id of the next sibling.
   #[serde(skip_serializing_if = "Option::is_none")]
-  pub(crate) first_child:  Option<u32>,
+  pub next_sibling:      Option<u32>,
+  /// The position of the node among the children of its parent, from `0`
+  /// on, left out for the roots.
   #[serde(skip_serializing_if = "Option::is_none")]
-  pub(crate) next_sibling: Option<u32>,
+  pub index:             Option<u32>,
+  /// The attributes (`attributes`) of an element, or the `name`, `publicId`
+  /// and `systemId` of a doctype, left out if there are none.
   #[serde(rename = "attributes", skip_serializing_if = "Option::is_none")]
-  pub(crate) attrs:        Option<Vec<WireAttr>>,
+  pub attrs:             Option<Vec<WireAttr>>,
+  /// Whether the value was cut short by the `maxTextLength` option.
+  #[serde(skip_serializing_if = "is_false")]
+  pub truncated:         bool,
+  /// The length in bytes of a truncated value before it was cut short.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub full_length:       Option<u32>,
+  /// The id of the document fragment holding the contents of a template.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub template_content:  Option<u32>,
+  /// The id of the document node of the document parsed from the `srcdoc`
+  /// of an iframe, with the `parseSrcdoc` option.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub content_document:  Option<u32>,
+  /// The id of the document node of the nested document the node is part
+  /// of, left out for the nodes of the host document.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub owner_document:    Option<u32>,
+  /// The strings of the names of the event handlers that were dropped, with
+  /// the `reportStrippedHandlers` option.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub stripped_handlers: Option<Vec<u32>>,
+  /// The structural hash of the node, as 16 hex digits, with the
+  /// `nodeHashes` option.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hash:              Option<String>,
+}
+
+/// A parse error, as recorded with the `collectErrors` option.
+#[derive(
+  Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash,
+)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WireError {
+  /// The stable, kebab-case code of the error.
+  #[serde(borrow = "'static")]
+  pub code:     CowStr<'static>,
+  /// `"error"` or `"warning"`.
+  #[serde(borrow = "'static")]
+  pub severity: CowStr<'static>,
+  /// The message of the parser, which may change between versions.
+  #[serde(borrow = "'static")]
+  pub message:  CowStr<'static>,
+  /// The (1-based) line the error was reported on, if known.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub line:     Option<u32>,
+  /// The (1-based) byte column on `line` the error was reported at, if known.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub column:   Option<u32>,
+  /// The byte offset in the input the parser had read up to when the error
+  /// was reported, if known.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub offset:   Option<u32>,
+}
+
+/// The fully resolved options used to produce a [`WireDoc`], after defaults,
+/// mime normalization and any overrides were applied. The keys are those of
+/// the options, with the options that take something other than a flag or a
+/// number echoed as a summary of it, like the kind of the `attributeFilter`
+/// or whether an `onError` callback was given.
+#[derive(
+  Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash,
+)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WireOptions {
+  pub exact_errors:             bool,
+  pub allow_scripts:            bool,
+  pub iframe_srcdoc:            bool,
+  pub drop_doctype:             bool,
+  #[serde(borrow = "'static")]
+  pub quirks_mode:              CowStr<'static>,
+  #[serde(borrow = "'static")]
+  pub content_type:             CowStr<'static>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub context_element:          Option<CowStr<'static>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub doctype:                  Option<CowStr<'static>>, // fragments only
+  #[serde(borrow = "'static")]
+  pub attribute_filter:         CowStr<'static>, // filter kind
+  pub collect_errors:           bool,
+  pub max_text_length:          Option<u32>,
+  #[serde(borrow = "'static")]
+  pub multi_root:               CowStr<'static>,
+  pub max_errors:               Option<u32>,
+  pub error_sample_rate:        u32,
+  pub fail_fast:                bool,
+  pub on_error:                 bool, // whether a callback was given
+  pub strip_event_handlers:     bool,
+  pub report_stripped_handlers: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub encoding:                 Option<CowStr<'static>>,
+  pub detect_encoding:          bool,
+  pub line_index:               bool,
+  pub drop_comments:            bool,
+  #[serde(rename = "dropProcessingInstructions")]
+  pub drop_instructions:        bool,
+  pub coalesce_text:            bool,
+  pub collapse_whitespace:      bool,
+  pub remove_empty:             bool,
+  pub node_hashes:              bool,
+  pub interner_stats:           bool,
+  pub fold_name_case:           bool,
+  #[serde(borrow = "'static")]
+  pub interner_profile:         CowStr<'static>,
+  pub parse_srcdoc:             bool,
+  #[serde(skip_serializing_if = "is_zero")]
+  pub inject_attributes:        u32, // the number of injections
+  pub inline_svg_uses:          bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scope_prefix:             Option<CowStr<'static>>,
+  pub modernize_presentation:   bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub profile:                  Option<CowStr<'static>>,
+}
+
+/// A parsed document in the wire format. See the [module level
+/// documentation](crate::wire) for the guarantees of its schema.
+#[derive(
+  Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash,
+)]
+#[serde(default, rename_all = "camelCase")]
+pub struct WireDoc {
+  /// The normalized MIME type the document was parsed as.
+  #[serde(borrow = "'static")]
+  pub content_type:      CowStr<'static>,
+  /// `"no-quirks"`, `"limited-quirks"` or `"quirks"`.
+  #[serde(borrow = "'static")]
+  pub quirks_mode:       CowStr<'static>,
+  /// The strings the nodes refer to, each of them once.
+  pub strings:           Vec<CowStr<'static>>,
+  /// The index of the first entry of `strings`. Documents parsed in a
+  /// session only ship the strings the earlier ones did not, so that the
+  /// indices below the offset refer to the strings of those.
+  #[serde(skip_serializing_if = "is_zero")]
+  pub strings_offset:    u32,
+  /// The nodes, by their ids.
+  pub nodes:             Vec<WireNode>,
+  /// The parse errors, with the `collectErrors` option.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub errors:            Option<Vec<WireError>>,
+  /// How many parse errors were reported in total, including those that the
+  /// `maxErrors` and `errorSampleRate` options left out of `errors`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub error_count:       Option<u32>,
+  /// The options used, with the `echoOptions` option.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub effective_options: Option<WireOptions>,
+  /// Whether an XML document has several top-level roots.
+  #[serde(skip_serializing_if = "is_false")]
+  pub multi_root:        bool,
+  /// The encoding that byte input was decoded in.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub encoding:          Option<WireEncoding>,
+  /// The byte offsets at which the lines of the input start, with the
+  /// `lineIndex` option.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub line_starts:       Option<Vec<u32>>,
+  /// The ids of the document nodes of every document in the output, with
+  /// the `parseSrcdoc` option: the host's (`0`) first, then those of the
+  /// nested documents. See the [`srcdoc`](crate::srcdoc) module.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub documents:         Option<Vec<u32>>,
+  /// How the string table was built, with the `internerStats` option. In a
+  /// session, the counts are those of all its documents so far.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub interner_stats:    Option<InternerStats>,
 }
 
+impl WireDoc {
+  /// The string with the index `index`, if this document shipped it.
+  pub fn string(&self, index: u32) -> Option<&str> {
+    let index = index.checked_sub(self.strings_offset)?;
+    self.strings.get(index as usize).map(|string| &**string)
+  }
+
+  /// The node with the id `id`.
+  pub fn node(&self, id: u32) -> Option<&WireNode> {
+    self.nodes.get(id as usize)
+  }
+
+  /// Overrides the quirks mode the document reports, e.g. to force
+  /// standards mode for tooling that assumes it. The nodes are left as
+  /// they are, since the tree was already built in the mode it was parsed
+  /// in.
+  pub fn set_quirks_mode(&mut self, mode: QuirksMode) {
+    self.quirks_mode = mode.to_string().into();
+  }
+}
+
+/// The encoding that byte input was decoded in, and how it was found.
 #[derive(
   Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq, Hash,
 )]
 #[serde(default, rename_all = "camelCase")]
-pub(crate) struct WireDoc {
+pub struct WireEncoding {
+  /// The WHATWG name of the encoding, e.g. `"windows-1252"`.
   #[serde(borrow = "'static")]
-  pub(crate) content_type: CowStr<'static>,
+  pub name:       CowStr<'static>,
+  /// Where the encoding came from, e.g. `"bom"` or `"meta"`.
   #[serde(borrow = "'static")]
-  pub(crate) quirks_mode:  CowStr<'static>,
-  pub(crate) strings:      Vec<CowStr<'static>>,
-  pub(crate) nodes:        Vec<WireNode>,
+  pub source:     CowStr<'static>,
+  /// How certain the guess is, in percent.
+  pub confidence: u8,
+}
+
+impl From<&SniffedEncoding> for WireEncoding {
+  fn from(sniffed: &SniffedEncoding) -> Self {
+    Self {
+      name:       sniffed.encoding.as_str().into(),
+      source:     sniffed.source.as_str().into(),
+      confidence: sniffed.confidence,
+    }
+  }
+}
+
+fn is_false(b: &bool) -> bool {
+  !*b
+}
+
+fn is_zero(n: &u32) -> bool {
+  *n == 0
+}
+
+fn saturating_u32(n: impl TryInto<u32>) -> u32 {
+  n.try_into().unwrap_or(u32::MAX)
+}
+
+impl From<&ParseError> for WireError {
+  fn from(error: &ParseError) -> Self {
+    Self {
+      code:     error.code.as_str().into(),
+      severity: error.severity.as_str().into(),
+      message:  error.message.to_string().into(),
+      line:     error.span.line.map(saturating_u32),
+      column:   error.span.column.map(saturating_u32),
+      offset:   error.span.offset.map(saturating_u32),
+    }
+  }
 }
@@ -265,6 +265,12 @@ export function buildDocumentTree(document: WireDoc): Document {
   if (!isWireDoc(document)) {
     throw new TypeError("Expected a serialized wire document.");
   }
+  if (document.stringsOffset) {
+    throw new TypeError(
+      "The wire document only ships part of its string table; " +
+        "decode it with a WireDecoder.",
+    );
+  }
 
   const resolved = resolveStrings(document) as ResolvedWireDoc;
   const lookup = new Map<number, ResolvedWireNode>();
@@ -295,6 +301,70 @@ export function buildDocumentTree(document: WireDoc): Document {
   return context.document;
 }
 
+/**
+ * Decodes the results of a `ParseSession`, which share a single string table:
+ * each result only ships the strings that are new to the session, starting
+ * at its `stringsOffset`. The decoder appends them to the table it keeps, so
+ * results must be decoded in the order they were produced.
+ *
+ * @example
+ * ```ts ignore
+ * const session = new ParseSession();
+ * const decoder = new WireDecoder();
+ * const first = decoder.decode(session.parse_doc(a, "text/html"));
+ * const second = decoder.decode(session.parse_doc(b, "text/html"));
+ * ```
+ */
+export class WireDecoder {
+  #strings: string[];
+
+  /**
+   * Creates a decoder, optionally starting with the string table of a session
+   * that was handed over with `ParseSession.transfer` or restored from a
+   * stored dictionary, since such a session does not ship it again.
+   */
+  constructor(strings: readonly string[] = []) {
+    this.#strings = [...strings];
+  }
+
+  /** The session's string table, as far as it has been received. */
+  get strings(): readonly string[] {
+    return this.#strings;
+  }
+
+  /**
+   * Adds the strings shipped by `document` to the table, returning a copy of
+   * the document whose `strings` are the whole table.
+   */
+  resolve(document: WireDoc): WireDoc {
+    if (!isWireDoc(document)) {
+      throw new TypeError("Expected a serialized wire document.");
+    }
+    const { stringsOffset: offset = 0, ...rest } = document;
+    const table = this.#strings;
+    if (offset > table.length) {
+      throw new RangeError(
+        `Missing the strings ${table.length}..${offset} of the session; ` +
+          "results must be decoded in the order they were produced.",
+      );
+    }
+    // after the session was reset, its strings are shipped from the start
+    table.length = offset;
+    for (const string of document.strings) table.push(string);
+    return { ...rest, strings: table.slice() };
+  }
+
+  /** Builds the {@linkcode Document} of the next result of the session. */
+  decode(document: WireDoc): Document {
+    return buildDocumentTree(this.resolve(document));
+  }
+
+  /** Clears the string table, like `ParseSession.reset` does. */
+  reset(): void {
+    this.#strings = [];
+  }
+}
+
 export function resolveQuirksMode(
   mode: number | string | null | undefined,
 ): QuirksModeType {
@@ -59,6 +59,13 @@ export interface WireDoc {
   contentType: string;
   quirksMode: QuirksMode;
   strings: readonly string[];
+  /**
+   * The index of the first entry of `strings`, if it is not zero. Documents
+   * parsed in a `ParseSession` only ship the strings the session's earlier
+   * results did not, and their nodes index into the session's whole string
+   * table. Such documents are decoded with a {@linkcode WireDecoder}.
+   */
+  stringsOffset?: number;
   nodes: readonly WireNode[];
 }
 